@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS books_created_at_idx;
+DROP INDEX IF EXISTS books_user_id_idx;
+DROP INDEX IF EXISTS books_isbn_trgm_idx;
+DROP INDEX IF EXISTS books_description_trgm_idx;
+DROP INDEX IF EXISTS books_author_trgm_idx;
+DROP INDEX IF EXISTS books_title_trgm_idx;
@@ -0,0 +1,8 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX IF NOT EXISTS books_title_trgm_idx ON books USING GIN (title gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS books_author_trgm_idx ON books USING GIN (author gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS books_description_trgm_idx ON books USING GIN (description gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS books_isbn_trgm_idx ON books USING GIN (isbn gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS books_user_id_idx ON books (user_id);
+CREATE INDEX IF NOT EXISTS books_created_at_idx ON books (created_at);
@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS returned_checkouts_book_id_checked_out_at_idx;
+DROP INDEX IF EXISTS users_created_at_user_id_idx;
+DROP INDEX IF EXISTS books_created_at_book_id_idx;
+CREATE INDEX IF NOT EXISTS books_created_at_idx ON books (created_at);
@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS books_created_at_idx;
+CREATE INDEX IF NOT EXISTS books_created_at_book_id_idx ON books (created_at, book_id);
+CREATE INDEX IF NOT EXISTS users_created_at_user_id_idx ON users (created_at, user_id);
+CREATE INDEX IF NOT EXISTS returned_checkouts_book_id_checked_out_at_idx
+    ON returned_checkouts (book_id, checked_out_at, checkout_id);
@@ -0,0 +1,15 @@
+ALTER TABLE returned_checkouts DROP COLUMN IF EXISTS copy_id;
+
+-- only one checkout per title can survive the UNIQUE constraint
+DELETE FROM checkouts AS c
+USING checkouts AS other
+WHERE c.book_id = other.book_id AND c.checked_out_at > other.checked_out_at;
+
+DROP INDEX IF EXISTS checkouts_book_id_idx;
+ALTER TABLE checkouts
+    DROP CONSTRAINT IF EXISTS checkouts_copy_id_key,
+    DROP COLUMN IF EXISTS copy_id,
+    ADD CONSTRAINT checkouts_book_id_key UNIQUE (book_id);
+
+DROP TRIGGER IF EXISTS book_copies_updated_at_trigger ON book_copies;
+DROP TABLE IF EXISTS book_copies;
@@ -0,0 +1,46 @@
+CREATE TABLE IF NOT EXISTS book_copies (
+    copy_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    book_id UUID NOT NULL,
+    barcode VARCHAR(64) NOT NULL UNIQUE,
+    condition VARCHAR(32) NOT NULL DEFAULT 'Good',
+    shelf_location VARCHAR(255) NOT NULL DEFAULT '',
+    created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+    updated_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+
+    FOREIGN KEY (book_id) REFERENCES books(book_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS book_copies_book_id_idx ON book_copies (book_id);
+
+CREATE TRIGGER book_copies_updated_at_trigger
+    BEFORE UPDATE ON book_copies FOR EACH ROW
+    EXECUTE PROCEDURE set_updated_at();
+
+-- every existing title becomes a single-copy title, whose barcode is derived
+-- from the copy's id like the application derives the one of new copies
+INSERT INTO book_copies (copy_id, book_id, barcode, created_at)
+SELECT copy_id, book_id, UPPER(REPLACE(copy_id::text, '-', '')), created_at
+FROM (SELECT gen_random_uuid() AS copy_id, book_id, created_at FROM books) AS b;
+
+ALTER TABLE checkouts ADD COLUMN copy_id UUID;
+UPDATE checkouts AS c
+SET copy_id = bc.copy_id
+FROM book_copies AS bc
+WHERE bc.book_id = c.book_id;
+ALTER TABLE checkouts
+    ALTER COLUMN copy_id SET NOT NULL,
+    DROP CONSTRAINT IF EXISTS checkouts_book_id_key,
+    ADD CONSTRAINT checkouts_copy_id_key UNIQUE (copy_id),
+    ADD FOREIGN KEY (copy_id) REFERENCES book_copies(copy_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE;
+CREATE INDEX IF NOT EXISTS checkouts_book_id_idx ON checkouts (book_id);
+
+ALTER TABLE returned_checkouts ADD COLUMN copy_id UUID;
+UPDATE returned_checkouts AS rc
+SET copy_id = bc.copy_id
+FROM book_copies AS bc
+WHERE bc.book_id = rc.book_id;
+ALTER TABLE returned_checkouts ALTER COLUMN copy_id SET NOT NULL;
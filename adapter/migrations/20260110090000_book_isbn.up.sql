@@ -0,0 +1,15 @@
+-- stored ISBNs are normalized to 13 digits without hyphens
+UPDATE books SET isbn = REGEXP_REPLACE(isbn, '[- ]', '', 'g');
+
+UPDATE books
+SET isbn = '978' || LEFT(isbn, 9) || (
+    10 - (
+        38 + (
+            SELECT SUM(SUBSTRING(isbn, i, 1)::int * CASE WHEN i % 2 = 1 THEN 3 ELSE 1 END)
+            FROM generate_series(1, 9) AS i
+        )
+    ) % 10
+) % 10
+WHERE isbn ~ '^[0-9]{9}[0-9Xx]$';
+
+CREATE INDEX IF NOT EXISTS books_isbn_idx ON books (isbn);
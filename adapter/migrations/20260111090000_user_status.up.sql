@@ -0,0 +1,8 @@
+-- users who sign up themselves start unverified or pending approval;
+-- only active users can log in
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS status VARCHAR(32) NOT NULL DEFAULT 'active'
+        CHECK (status IN ('active', 'unverified', 'pending_approval', 'rejected'));
+
+CREATE INDEX IF NOT EXISTS users_status_idx ON users (status, created_at)
+    WHERE status <> 'active';
@@ -0,0 +1,26 @@
+-- an authenticator secret per user; it only counts once `enabled_at` is set,
+-- which happens when a first code was entered
+CREATE TABLE IF NOT EXISTS user_totp (
+    user_id UUID PRIMARY KEY,
+    secret VARCHAR(64) NOT NULL,
+    enabled_at TIMESTAMP(3) WITH TIME ZONE,
+    -- time step of the last accepted code, so no code is accepted twice
+    last_used_step BIGINT,
+    created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+
+    FOREIGN KEY (user_id) REFERENCES users(user_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
+
+-- single-use codes for when the authenticator is lost; only hashes are kept
+CREATE TABLE IF NOT EXISTS user_recovery_codes (
+    user_id UUID NOT NULL,
+    code_hash VARCHAR(64) NOT NULL,
+    used_at TIMESTAMP(3) WITH TIME ZONE,
+
+    PRIMARY KEY (user_id, code_hash),
+    FOREIGN KEY (user_id) REFERENCES users(user_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
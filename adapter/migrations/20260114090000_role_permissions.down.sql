@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS role_permissions;
+DELETE FROM roles
+WHERE name = 'Librarian'
+    AND NOT EXISTS (SELECT 1 FROM users WHERE users.role_id = roles.role_id);
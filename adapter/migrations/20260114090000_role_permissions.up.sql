@@ -0,0 +1,33 @@
+-- what each role may do beyond using the library as a member
+CREATE TABLE IF NOT EXISTS role_permissions (
+    role_id UUID NOT NULL,
+    permission VARCHAR(32) NOT NULL
+        CHECK (permission IN ('manage_books', 'manage_checkouts', 'manage_roles', 'manage_users')),
+
+    PRIMARY KEY (role_id, permission),
+    FOREIGN KEY (role_id) REFERENCES roles(role_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
+
+-- the built-in roles; `data/initial_setup.sql` used to be the only place
+-- creating them, which runs after the migrations
+INSERT INTO roles (name)
+VALUES ('Admin'), ('Librarian'), ('User')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission)
+SELECT r.role_id, p.permission
+FROM roles AS r
+CROSS JOIN UNNEST(
+    ARRAY['manage_books', 'manage_checkouts', 'manage_roles', 'manage_users']
+) AS p(permission)
+WHERE r.name = 'Admin'
+ON CONFLICT DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission)
+SELECT r.role_id, p.permission
+FROM roles AS r
+CROSS JOIN UNNEST(ARRAY['manage_books', 'manage_checkouts']) AS p(permission)
+WHERE r.name = 'Librarian'
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,4 @@
+ALTER TABLE roles
+    DROP COLUMN IF EXISTS max_loans,
+    DROP COLUMN IF EXISTS block_when_overdue,
+    DROP COLUMN IF EXISTS block_own_books;
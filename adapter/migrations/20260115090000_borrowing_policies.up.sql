@@ -0,0 +1,8 @@
+-- what members of each role may borrow; NULL max_loans means no limit
+ALTER TABLE roles
+    ADD COLUMN IF NOT EXISTS max_loans INTEGER CHECK (max_loans >= 0),
+    ADD COLUMN IF NOT EXISTS block_when_overdue BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN IF NOT EXISTS block_own_books BOOLEAN NOT NULL DEFAULT FALSE;
+
+UPDATE roles SET max_loans = 5, block_when_overdue = TRUE
+WHERE name = 'User';
@@ -0,0 +1,5 @@
+ALTER TABLE returned_checkouts
+    DROP COLUMN IF EXISTS issued_by,
+    DROP COLUMN IF EXISTS returned_by,
+    DROP COLUMN IF EXISTS return_reason;
+ALTER TABLE checkouts DROP COLUMN IF EXISTS issued_by;
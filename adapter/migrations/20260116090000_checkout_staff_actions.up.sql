@@ -0,0 +1,15 @@
+-- who performed a checkout or a return; the borrower unless staff acted for them
+ALTER TABLE checkouts ADD COLUMN IF NOT EXISTS issued_by UUID;
+UPDATE checkouts SET issued_by = user_id WHERE issued_by IS NULL;
+ALTER TABLE checkouts ALTER COLUMN issued_by SET NOT NULL;
+
+ALTER TABLE returned_checkouts
+    ADD COLUMN IF NOT EXISTS issued_by UUID,
+    ADD COLUMN IF NOT EXISTS returned_by UUID,
+    -- given when staff force a return
+    ADD COLUMN IF NOT EXISTS return_reason TEXT;
+UPDATE returned_checkouts
+SET issued_by = COALESCE(issued_by, user_id), returned_by = COALESCE(returned_by, user_id);
+ALTER TABLE returned_checkouts
+    ALTER COLUMN issued_by SET NOT NULL,
+    ALTER COLUMN returned_by SET NOT NULL;
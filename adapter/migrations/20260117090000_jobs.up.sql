@@ -0,0 +1,16 @@
+-- periodic jobs shared by all instances; an instance runs a job while it holds
+-- its lease, which others take over once `leased_until` passes
+CREATE TABLE IF NOT EXISTS jobs (
+    name VARCHAR(64) PRIMARY KEY,
+    schedule VARCHAR(128) NOT NULL,
+    run_at TIMESTAMP(3) WITH TIME ZONE NOT NULL,
+    -- failed runs since the last successful one
+    attempts INTEGER NOT NULL DEFAULT 0,
+    lease_id UUID,
+    leased_until TIMESTAMP(3) WITH TIME ZONE,
+    last_run_at TIMESTAMP(3) WITH TIME ZONE,
+    last_error TEXT,
+    created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3)
+);
+
+CREATE INDEX IF NOT EXISTS jobs_run_at_idx ON jobs (run_at);
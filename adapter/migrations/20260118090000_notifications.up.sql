@@ -0,0 +1,40 @@
+-- the outbox: notifications are written here in the transaction that causes
+-- them and mailed later, so a failing mail server never loses or blocks one
+CREATE TABLE IF NOT EXISTS notifications (
+    notification_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL,
+    kind VARCHAR(32) NOT NULL,
+    book_id UUID NOT NULL,
+    due_at TIMESTAMP(3) WITH TIME ZONE,
+    -- set for reminders, which must not be queued twice
+    key VARCHAR(128) UNIQUE,
+    created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL,
+    -- failed deliveries so far
+    attempts INTEGER NOT NULL DEFAULT 0,
+    -- NULL once the notification is sent or given up on
+    next_attempt_at TIMESTAMP(3) WITH TIME ZONE,
+    sent_at TIMESTAMP(3) WITH TIME ZONE,
+    last_error TEXT,
+
+    FOREIGN KEY (user_id) REFERENCES users(user_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE,
+    FOREIGN KEY (book_id) REFERENCES books(book_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS notifications_pending_idx ON notifications (next_attempt_at)
+    WHERE next_attempt_at IS NOT NULL;
+
+-- kinds of notifications users changed the setting of; the rest are enabled
+CREATE TABLE IF NOT EXISTS notification_preferences (
+    user_id UUID NOT NULL,
+    kind VARCHAR(32) NOT NULL,
+    enabled BOOLEAN NOT NULL,
+
+    PRIMARY KEY (user_id, kind),
+    FOREIGN KEY (user_id) REFERENCES users(user_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
@@ -0,0 +1,7 @@
+DELETE FROM role_permissions WHERE permission = 'manage_webhooks';
+ALTER TABLE role_permissions DROP CONSTRAINT IF EXISTS role_permissions_permission_check;
+ALTER TABLE role_permissions ADD CONSTRAINT role_permissions_permission_check
+    CHECK (permission IN ('manage_books', 'manage_checkouts', 'manage_roles', 'manage_users'));
+
+DROP TABLE IF EXISTS webhook_deliveries;
+DROP TABLE IF EXISTS webhooks;
@@ -0,0 +1,55 @@
+-- endpoints told about changes to books and checkouts
+CREATE TABLE IF NOT EXISTS webhooks (
+    webhook_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    url VARCHAR(2048) NOT NULL,
+    -- signs the payloads; the subscriber sees it once, when subscribing
+    secret VARCHAR(128) NOT NULL,
+    event_types VARCHAR(32)[] NOT NULL,
+    created_by UUID NOT NULL,
+    created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL,
+
+    FOREIGN KEY (created_by) REFERENCES users(user_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
+
+-- the outbox for webhooks: one row per event and subscription, written in the
+-- transaction of the change and posted later
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    delivery_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    webhook_id UUID NOT NULL,
+    event_type VARCHAR(32) NOT NULL,
+    payload TEXT NOT NULL,
+    status VARCHAR(16) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'delivered', 'dead_letter')),
+    -- failed attempts so far
+    attempts INTEGER NOT NULL DEFAULT 0,
+    -- NULL unless pending
+    next_attempt_at TIMESTAMP(3) WITH TIME ZONE,
+    last_attempt_at TIMESTAMP(3) WITH TIME ZONE,
+    response_status INTEGER,
+    last_error TEXT,
+    created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL,
+    delivered_at TIMESTAMP(3) WITH TIME ZONE,
+
+    FOREIGN KEY (webhook_id) REFERENCES webhooks(webhook_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS webhook_deliveries_pending_idx ON webhook_deliveries (next_attempt_at)
+    WHERE next_attempt_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS webhook_deliveries_log_idx
+    ON webhook_deliveries (webhook_id, created_at DESC, delivery_id DESC);
+
+ALTER TABLE role_permissions DROP CONSTRAINT IF EXISTS role_permissions_permission_check;
+ALTER TABLE role_permissions ADD CONSTRAINT role_permissions_permission_check
+    CHECK (permission IN (
+        'manage_books', 'manage_checkouts', 'manage_roles', 'manage_users', 'manage_webhooks'
+    ));
+
+INSERT INTO role_permissions (role_id, permission)
+SELECT role_id, 'manage_webhooks'
+FROM roles
+WHERE name = 'Admin'
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,10 @@
+DELETE FROM role_permissions WHERE permission = 'view_audit_log';
+ALTER TABLE role_permissions DROP CONSTRAINT IF EXISTS role_permissions_permission_check;
+ALTER TABLE role_permissions ADD CONSTRAINT role_permissions_permission_check
+    CHECK (permission IN (
+        'manage_books', 'manage_checkouts', 'manage_roles', 'manage_users', 'manage_webhooks'
+    ));
+
+DROP TRIGGER IF EXISTS audit_log_append_only_trigger ON audit_log;
+DROP TABLE IF EXISTS audit_log;
+DROP FUNCTION IF EXISTS reject_audit_log_change;
@@ -0,0 +1,47 @@
+-- who changed which book, copy, user, role or checkout; each row is written in
+-- the transaction of the change it records
+CREATE TABLE IF NOT EXISTS audit_log (
+    audit_entry_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- not a foreign key, so entries outlive the users they name; NULL for
+    -- changes the application makes itself, like purging stale sign-ups
+    actor_id UUID,
+    action VARCHAR(32) NOT NULL,
+    target_type VARCHAR(16) NOT NULL,
+    -- the name of roles, the id of everything else
+    target_id VARCHAR(255) NOT NULL,
+    -- JSON of the target; NULL before it was created and after it was deleted
+    before TEXT,
+    after TEXT,
+    request_id VARCHAR(255),
+    occurred_at TIMESTAMP(3) WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS audit_log_occurred_at_idx
+    ON audit_log (occurred_at DESC, audit_entry_id DESC);
+CREATE INDEX IF NOT EXISTS audit_log_actor_idx
+    ON audit_log (actor_id, occurred_at DESC, audit_entry_id DESC);
+CREATE INDEX IF NOT EXISTS audit_log_target_idx
+    ON audit_log (target_type, target_id, occurred_at DESC, audit_entry_id DESC);
+
+CREATE OR REPLACE FUNCTION reject_audit_log_change() RETURNS trigger AS '
+    BEGIN
+        RAISE EXCEPTION ''audit_log is append-only'';
+    END;
+' LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_log_append_only_trigger
+    BEFORE UPDATE OR DELETE ON audit_log FOR EACH ROW
+    EXECUTE PROCEDURE reject_audit_log_change();
+
+ALTER TABLE role_permissions DROP CONSTRAINT IF EXISTS role_permissions_permission_check;
+ALTER TABLE role_permissions ADD CONSTRAINT role_permissions_permission_check
+    CHECK (permission IN (
+        'manage_books', 'manage_checkouts', 'manage_roles', 'manage_users', 'manage_webhooks',
+        'view_audit_log'
+    ));
+
+INSERT INTO role_permissions (role_id, permission)
+SELECT role_id, 'view_audit_log'
+FROM roles
+WHERE name = 'Admin'
+ON CONFLICT DO NOTHING;
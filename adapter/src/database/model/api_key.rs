@@ -0,0 +1,70 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use kernel::model::{
+    api_key::{ApiKey, ApiKeyOwner, ApiKeyScope},
+    id::{ApiKeyId, UserId},
+};
+use shared::error::{AppError, AppResult};
+
+pub struct ApiKeyRow {
+    pub api_key_id: ApiKeyId,
+    pub user_id: UserId,
+    pub name: String,
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<ApiKeyRow> for ApiKey {
+    type Error = AppError;
+
+    fn try_from(value: ApiKeyRow) -> Result<Self, Self::Error> {
+        let ApiKeyRow {
+            api_key_id,
+            user_id,
+            name,
+            prefix,
+            scopes,
+            created_at,
+            last_used_at,
+        } = value;
+        Ok(ApiKey {
+            id: api_key_id,
+            user_id,
+            name,
+            prefix,
+            scopes: parse_scopes(&scopes)?,
+            created_at,
+            last_used_at,
+        })
+    }
+}
+
+pub struct ApiKeyOwnerRow {
+    pub api_key_id: ApiKeyId,
+    pub user_id: UserId,
+    pub scopes: Vec<String>,
+}
+
+impl TryFrom<ApiKeyOwnerRow> for ApiKeyOwner {
+    type Error = AppError;
+
+    fn try_from(value: ApiKeyOwnerRow) -> Result<Self, Self::Error> {
+        Ok(ApiKeyOwner {
+            api_key_id: value.api_key_id,
+            user_id: value.user_id,
+            scopes: parse_scopes(&value.scopes)?,
+        })
+    }
+}
+
+fn parse_scopes(scopes: &[String]) -> AppResult<Vec<ApiKeyScope>> {
+    scopes
+        .iter()
+        .map(|s| {
+            ApiKeyScope::from_str(s).map_err(|e| AppError::ConversionEntityError(e.to_string()))
+        })
+        .collect()
+}
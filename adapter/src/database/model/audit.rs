@@ -0,0 +1,53 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use kernel::model::{
+    audit::{AuditAction, AuditEntry, AuditTarget, AuditTargetKind},
+    id::{AuditEntryId, UserId},
+};
+use shared::error::AppError;
+
+pub struct AuditEntryRow {
+    pub audit_entry_id: AuditEntryId,
+    pub actor_id: Option<UserId>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub request_id: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl TryFrom<AuditEntryRow> for AuditEntry {
+    type Error = AppError;
+
+    fn try_from(value: AuditEntryRow) -> Result<Self, Self::Error> {
+        let AuditEntryRow {
+            audit_entry_id,
+            actor_id,
+            action,
+            target_type,
+            target_id,
+            before,
+            after,
+            request_id,
+            occurred_at,
+        } = value;
+        Ok(AuditEntry {
+            id: audit_entry_id,
+            actor: actor_id,
+            action: AuditAction::from_str(&action)
+                .map_err(|e| AppError::ConversionEntityError(e.to_string()))?,
+            target: AuditTarget {
+                kind: AuditTargetKind::from_str(&target_type)
+                    .map_err(|e| AppError::ConversionEntityError(e.to_string()))?,
+                id: target_id,
+            },
+            before,
+            after,
+            request_id,
+            occurred_at,
+        })
+    }
+}
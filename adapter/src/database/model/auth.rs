@@ -1,9 +1,11 @@
 use std::str::FromStr;
 
+use chrono::{DateTime, Utc};
 use kernel::model::{
-    auth::{AccessToken, event::CreateToken},
-    id::UserId,
+    auth::{AccessToken, RefreshToken, Session},
+    id::{SessionId, UserId},
 };
+use serde::{Deserialize, Serialize};
 use shared::error::AppError;
 
 use crate::redis::model::{RedisKey, RedisValue};
@@ -11,16 +13,60 @@ use crate::redis::model::{RedisKey, RedisValue};
 pub struct UserItem {
     pub user_id: UserId,
     pub password_hash: String,
+    pub status: String,
 }
 
 pub struct AuthorizationKey(String);
-pub struct AuthorizedUserId(pub UserId);
 
-pub fn from(event: CreateToken) -> (AuthorizationKey, AuthorizedUserId) {
-    (
-        AuthorizationKey(event.access_token),
-        AuthorizedUserId(event.user_id),
-    )
+// tokens issued before sessions existed only hold the user id
+pub struct AuthorizedSession {
+    pub user_id: UserId,
+    pub session_id: Option<SessionId>,
+}
+
+// marks a refresh token as issued for a session, and outlives its rotation so
+// that presenting it again can be told apart from presenting an unknown token
+pub struct RefreshTokenKey(String);
+
+// set once a refresh token has been exchanged; a token can only claim it once
+pub struct UsedRefreshTokenKey(String);
+
+pub struct SessionRef(pub SessionId);
+
+pub struct SessionKey(pub SessionId);
+
+// the tokens currently valid for a session, so that all of them can be revoked,
+// along with what is shown when listing sessions
+#[derive(Serialize, Deserialize)]
+pub struct SessionEntry {
+    pub user_id: UserId,
+    pub access_token: String,
+    pub refresh_token: String,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+// kept apart from `SessionEntry`, so that marking a session as used never
+// races with a rotation writing the next tokens
+pub struct SessionLastUsedKey(pub SessionId);
+
+pub struct LastUsedAt(pub DateTime<Utc>);
+
+// indexes the sessions of a user; members may outlive the sessions they name
+pub struct UserSessionsKey(pub UserId);
+
+impl SessionEntry {
+    pub fn into_session(self, id: SessionId, last_used_at: Option<LastUsedAt>) -> Session {
+        Session {
+            id,
+            user_id: self.user_id,
+            user_agent: self.user_agent,
+            ip_address: self.ip_address,
+            created_at: self.created_at,
+            last_used_at: last_used_at.map_or(self.created_at, |l| l.0),
+        }
+    }
 }
 
 impl From<AuthorizationKey> for AccessToken {
@@ -41,30 +87,138 @@ impl From<&AccessToken> for AuthorizationKey {
     }
 }
 
+impl From<&str> for AuthorizationKey {
+    fn from(token: &str) -> Self {
+        AuthorizationKey(token.to_string())
+    }
+}
+
 impl RedisKey for AuthorizationKey {
-    type Value = AuthorizedUserId;
+    type Value = AuthorizedSession;
     fn inner(&self) -> String {
         self.0.clone()
     }
 }
 
-impl RedisValue for AuthorizedUserId {
+impl RedisValue for AuthorizedSession {
+    fn inner(&self) -> String {
+        match self.session_id {
+            Some(session_id) => format!("{}:{}", self.user_id, session_id),
+            None => self.user_id.to_string(),
+        }
+    }
+}
+
+impl TryFrom<String> for AuthorizedSession {
+    type Error = AppError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let convert = |e: AppError| AppError::ConversionEntityError(e.to_string());
+        let (user_id, session_id) = match value.split_once(':') {
+            Some((user_id, session_id)) => (
+                user_id,
+                Some(SessionId::from_str(session_id).map_err(convert)?),
+            ),
+            None => (value.as_str(), None),
+        };
+        Ok(Self {
+            user_id: UserId::from_str(user_id).map_err(convert)?,
+            session_id,
+        })
+    }
+}
+
+impl From<&RefreshToken> for RefreshTokenKey {
+    fn from(token: &RefreshToken) -> Self {
+        RefreshTokenKey(token.0.clone())
+    }
+}
+
+impl From<&str> for RefreshTokenKey {
+    fn from(token: &str) -> Self {
+        RefreshTokenKey(token.to_string())
+    }
+}
+
+impl RedisKey for RefreshTokenKey {
+    type Value = SessionRef;
+    fn inner(&self) -> String {
+        format!("refresh:{}", self.0)
+    }
+}
+
+impl From<&RefreshToken> for UsedRefreshTokenKey {
+    fn from(token: &RefreshToken) -> Self {
+        UsedRefreshTokenKey(token.0.clone())
+    }
+}
+
+impl RedisKey for UsedRefreshTokenKey {
+    type Value = SessionRef;
+    fn inner(&self) -> String {
+        format!("refresh-used:{}", self.0)
+    }
+}
+
+impl RedisValue for SessionRef {
     fn inner(&self) -> String {
         self.0.to_string()
     }
 }
 
-impl TryFrom<String> for AuthorizedUserId {
+impl TryFrom<String> for SessionRef {
     type Error = AppError;
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        Ok(Self(UserId::from_str(&value).map_err(|e| {
+        Ok(Self(SessionId::from_str(&value).map_err(|e| {
             AppError::ConversionEntityError(e.to_string())
         })?))
     }
 }
 
-impl AuthorizedUserId {
-    pub fn into_inner(self) -> UserId {
-        self.0
+impl RedisKey for SessionKey {
+    type Value = SessionEntry;
+    fn inner(&self) -> String {
+        format!("session:{}", self.0)
+    }
+}
+
+impl RedisValue for SessionEntry {
+    fn inner(&self) -> String {
+        serde_json::to_string(self).expect("session entries always serialize")
+    }
+}
+
+impl TryFrom<String> for SessionEntry {
+    type Error = AppError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        serde_json::from_str(&value).map_err(|e| AppError::ConversionEntityError(e.to_string()))
+    }
+}
+
+impl RedisKey for SessionLastUsedKey {
+    type Value = LastUsedAt;
+    fn inner(&self) -> String {
+        format!("session-used:{}", self.0)
+    }
+}
+
+impl RedisValue for LastUsedAt {
+    fn inner(&self) -> String {
+        self.0.to_rfc3339()
+    }
+}
+
+impl TryFrom<String> for LastUsedAt {
+    type Error = AppError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        DateTime::parse_from_rfc3339(&value)
+            .map(|at| Self(at.with_timezone(&Utc)))
+            .map_err(|e| AppError::ConversionEntityError(e.to_string()))
+    }
+}
+
+impl RedisKey for UserSessionsKey {
+    type Value = SessionRef;
+    fn inner(&self) -> String {
+        format!("user-sessions:{}", self.0)
     }
 }
@@ -1,9 +1,12 @@
+use std::str::FromStr;
+
 use chrono::{DateTime, Utc};
 use kernel::model::{
-    book::{Book, Checkout},
-    id::{BookId, CheckoutId, UserId},
+    book::{Book, BookCopy, Checkout, CopyCondition},
+    id::{BookCopyId, BookId, CheckoutId, UserId},
     user::{BookOwner, CheckoutUser},
 };
+use shared::error::AppError;
 
 pub struct BookRow {
     pub book_id: BookId,
@@ -16,7 +19,7 @@ pub struct BookRow {
 }
 
 impl BookRow {
-    pub fn into_book(self, checkout: Option<Checkout>) -> Book {
+    pub fn into_book(self, copies: Vec<BookCopy>) -> Book {
         let BookRow {
             book_id,
             title,
@@ -36,7 +39,7 @@ impl BookRow {
                 id: owned_by,
                 name: owner_name,
             },
-            checkout,
+            copies,
         }
     }
 }
@@ -46,23 +49,59 @@ pub struct PaginatedBookRow {
     pub id: BookId,
 }
 
-pub struct BookCheckoutRow {
-    pub checkout_id: CheckoutId,
+pub struct BookKeysetRow {
+    pub id: BookId,
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct BookOwnerRow {
     pub book_id: BookId,
     pub user_id: UserId,
-    pub user_name: String,
-    pub checked_out_at: DateTime<Utc>,
 }
 
-impl From<BookCheckoutRow> for Checkout {
-    fn from(row: BookCheckoutRow) -> Self {
-        Checkout {
-            checkout_id: row.checkout_id,
-            checked_out_by: CheckoutUser {
-                id: row.user_id,
-                name: row.user_name,
-            },
-            checked_out_at: row.checked_out_at,
-        }
+pub struct BookCopyRow {
+    pub copy_id: BookCopyId,
+    pub book_id: BookId,
+    pub barcode: String,
+    pub condition: String,
+    pub shelf_location: String,
+    pub checkout_id: Option<CheckoutId>,
+    pub user_id: Option<UserId>,
+    pub user_name: Option<String>,
+    pub checked_out_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<BookCopyRow> for BookCopy {
+    type Error = AppError;
+
+    fn try_from(row: BookCopyRow) -> Result<Self, Self::Error> {
+        let BookCopyRow {
+            copy_id,
+            book_id,
+            barcode,
+            condition,
+            shelf_location,
+            checkout_id,
+            user_id,
+            user_name,
+            checked_out_at,
+        } = row;
+        let checkout = match (checkout_id, user_id, user_name, checked_out_at) {
+            (Some(checkout_id), Some(id), Some(name), Some(checked_out_at)) => Some(Checkout {
+                checkout_id,
+                checked_out_by: CheckoutUser { id, name },
+                checked_out_at,
+            }),
+            _ => None,
+        };
+        Ok(BookCopy {
+            id: copy_id,
+            book_id,
+            barcode,
+            condition: CopyCondition::from_str(&condition)
+                .map_err(|e| AppError::ConversionEntityError(e.to_string()))?,
+            shelf_location,
+            checkout,
+        })
     }
 }
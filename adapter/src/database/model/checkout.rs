@@ -1,7 +1,8 @@
 use chrono::{DateTime, Utc};
 use kernel::model::{
     checkout::{Checkout, CheckoutBook},
-    id::{BookId, CheckoutId, UserId},
+    id::{BookCopyId, BookId, CheckoutId, UserId},
+    role::BorrowingPolicy,
 };
 
 pub struct CheckoutStateRow {
@@ -10,11 +11,49 @@ pub struct CheckoutStateRow {
     pub user_id: Option<UserId>,
 }
 
+pub struct CopyStateRow {
+    pub copy_id: BookCopyId,
+    pub checkout_id: Option<CheckoutId>,
+}
+
+pub struct RenewalStateRow {
+    pub checkout_id: CheckoutId,
+    pub book_id: BookId,
+    pub user_id: UserId,
+    pub role_name: String,
+    pub due_at: DateTime<Utc>,
+    pub renewal_count: i32,
+}
+
+pub struct BorrowerStateRow {
+    pub role_name: String,
+    pub max_loans: Option<i32>,
+    pub block_when_overdue: bool,
+    pub block_own_books: bool,
+    pub loan_count: i64,
+    pub has_overdue: bool,
+    pub owns_book: bool,
+}
+
+impl BorrowerStateRow {
+    pub fn policy(&self) -> BorrowingPolicy {
+        BorrowingPolicy {
+            max_loans: self.max_loans,
+            block_when_overdue: self.block_when_overdue,
+            block_own_books: self.block_own_books,
+        }
+    }
+}
+
 pub struct CheckoutRow {
     pub checkout_id: CheckoutId,
     pub book_id: BookId,
+    pub copy_id: BookCopyId,
     pub user_id: UserId,
+    pub issued_by: UserId,
     pub checked_out_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+    pub renewal_count: i32,
     pub title: String,
     pub author: String,
     pub isbn: String,
@@ -25,8 +64,12 @@ impl From<CheckoutRow> for Checkout {
         let CheckoutRow {
             checkout_id,
             book_id,
+            copy_id,
             user_id,
+            issued_by,
             checked_out_at,
+            due_at,
+            renewal_count,
             title,
             author,
             isbn,
@@ -34,10 +77,16 @@ impl From<CheckoutRow> for Checkout {
         Checkout {
             id: checkout_id,
             checked_out_by: user_id,
+            issued_by,
             checked_out_at,
+            due_at,
+            renewal_count,
             returned_at: None,
+            returned_by: None,
+            return_reason: None,
             book: CheckoutBook {
                 book_id,
+                copy_id,
                 title,
                 author,
                 isbn,
@@ -46,25 +95,37 @@ impl From<CheckoutRow> for Checkout {
     }
 }
 
-pub struct ReturnedCheckoutRow {
+pub struct CheckoutHistoryRow {
     pub checkout_id: CheckoutId,
     pub book_id: BookId,
+    pub copy_id: BookCopyId,
     pub user_id: UserId,
+    pub issued_by: UserId,
     pub checked_out_at: DateTime<Utc>,
-    pub returned_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+    pub renewal_count: i32,
+    pub returned_at: Option<DateTime<Utc>>,
+    pub returned_by: Option<UserId>,
+    pub return_reason: Option<String>,
     pub title: String,
     pub author: String,
     pub isbn: String,
 }
 
-impl From<ReturnedCheckoutRow> for Checkout {
-    fn from(value: ReturnedCheckoutRow) -> Self {
-        let ReturnedCheckoutRow {
+impl From<CheckoutHistoryRow> for Checkout {
+    fn from(value: CheckoutHistoryRow) -> Self {
+        let CheckoutHistoryRow {
             checkout_id,
             book_id,
+            copy_id,
             user_id,
+            issued_by,
             checked_out_at,
+            due_at,
+            renewal_count,
             returned_at,
+            returned_by,
+            return_reason,
             title,
             author,
             isbn,
@@ -72,10 +133,16 @@ impl From<ReturnedCheckoutRow> for Checkout {
         Checkout {
             id: checkout_id,
             checked_out_by: user_id,
+            issued_by,
             checked_out_at,
-            returned_at: Some(returned_at),
+            due_at,
+            renewal_count,
+            returned_at,
+            returned_by,
+            return_reason,
             book: CheckoutBook {
                 book_id,
+                copy_id,
                 title,
                 author,
                 isbn,
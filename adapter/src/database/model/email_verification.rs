@@ -0,0 +1,44 @@
+use std::str::FromStr;
+
+use kernel::model::{auth::EmailVerificationToken, id::UserId};
+use shared::error::AppError;
+
+use crate::redis::model::{RedisKey, RedisValue};
+
+pub struct EmailVerificationKey(String);
+
+pub struct VerificationUser(pub UserId);
+
+impl From<&EmailVerificationToken> for EmailVerificationKey {
+    fn from(token: &EmailVerificationToken) -> Self {
+        EmailVerificationKey(token.0.clone())
+    }
+}
+
+impl From<&str> for EmailVerificationKey {
+    fn from(token: &str) -> Self {
+        EmailVerificationKey(token.to_string())
+    }
+}
+
+impl RedisKey for EmailVerificationKey {
+    type Value = VerificationUser;
+    fn inner(&self) -> String {
+        format!("email-verification:{}", self.0)
+    }
+}
+
+impl RedisValue for VerificationUser {
+    fn inner(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl TryFrom<String> for VerificationUser {
+    type Error = AppError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        UserId::from_str(&value)
+            .map(Self)
+            .map_err(|e| AppError::ConversionEntityError(e.to_string()))
+    }
+}
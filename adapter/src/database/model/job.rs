@@ -0,0 +1,22 @@
+use kernel::model::{id::JobLeaseId, job::JobLease};
+
+pub struct JobLeaseRow {
+    pub name: String,
+    pub lease_id: JobLeaseId,
+    pub attempts: i32,
+}
+
+impl From<JobLeaseRow> for JobLease {
+    fn from(value: JobLeaseRow) -> Self {
+        let JobLeaseRow {
+            name,
+            lease_id,
+            attempts,
+        } = value;
+        JobLease {
+            name,
+            lease_id,
+            attempts,
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use kernel::model::{
+    id::BookId,
+    live_event::{BookAvailability, LiveEvent, LiveEventKind},
+};
+use serde::{Deserialize, Serialize};
+use shared::error::AppError;
+
+use crate::redis::model::{RedisKey, RedisValue};
+
+// counts the events of all instances, giving each its id
+pub struct LiveEventSequenceKey;
+
+pub struct LiveEventId(pub u64);
+
+// the latest events, newest first
+pub struct LiveEventHistoryKey;
+
+// where events are published to every instance
+pub struct LiveEventChannel;
+
+// an event as it is kept and published
+pub struct LiveEventMessage(pub LiveEvent);
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LiveEventJson {
+    id: u64,
+    kind: String,
+    book_id: BookId,
+    available_copies: Option<i64>,
+    total_copies: Option<i64>,
+    occurred_at: DateTime<Utc>,
+}
+
+impl RedisKey for LiveEventSequenceKey {
+    type Value = LiveEventId;
+    fn inner(&self) -> String {
+        "live-events:sequence".into()
+    }
+}
+
+impl RedisValue for LiveEventId {
+    fn inner(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl TryFrom<String> for LiveEventId {
+    type Error = AppError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value
+            .parse()
+            .map(Self)
+            .map_err(|_| AppError::ConversionEntityError(format!("invalid event id {value}")))
+    }
+}
+
+impl RedisKey for LiveEventHistoryKey {
+    type Value = LiveEventMessage;
+    fn inner(&self) -> String {
+        "live-events:history".into()
+    }
+}
+
+impl RedisKey for LiveEventChannel {
+    type Value = LiveEventMessage;
+    fn inner(&self) -> String {
+        "live-events".into()
+    }
+}
+
+impl RedisValue for LiveEventMessage {
+    fn inner(&self) -> String {
+        let LiveEvent {
+            id,
+            kind,
+            book_id,
+            availability,
+            occurred_at,
+        } = &self.0;
+        let json = LiveEventJson {
+            id: *id,
+            kind: kind.as_ref().to_string(),
+            book_id: *book_id,
+            available_copies: availability.map(|a| a.available_copies),
+            total_copies: availability.map(|a| a.total_copies),
+            occurred_at: *occurred_at,
+        };
+        serde_json::to_string(&json).expect("events serialize to JSON")
+    }
+}
+
+impl TryFrom<String> for LiveEventMessage {
+    type Error = AppError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let json = serde_json::from_str::<LiveEventJson>(&value)
+            .map_err(|e| AppError::ConversionEntityError(e.to_string()))?;
+        let kind = LiveEventKind::from_str(&json.kind)
+            .map_err(|e| AppError::ConversionEntityError(e.to_string()))?;
+        let availability =
+            json.available_copies
+                .zip(json.total_copies)
+                .map(|(available_copies, total_copies)| BookAvailability {
+                    available_copies,
+                    total_copies,
+                });
+        Ok(Self(LiveEvent {
+            id: json.id,
+            kind,
+            book_id: json.book_id,
+            availability,
+            occurred_at: json.occurred_at,
+        }))
+    }
+}
@@ -0,0 +1,62 @@
+use shared::error::AppError;
+
+use crate::redis::model::{RedisKey, RedisValue};
+
+// failed logins for an email or from an address, forgotten once they stop
+pub enum LoginFailuresKey {
+    Email(String),
+    Ip(String),
+}
+
+// present while an email has to wait before its next attempt
+pub struct LoginBackoffKey(pub String);
+
+// present while an email is locked
+pub struct LoginLockoutKey(pub String);
+
+pub struct FailureCount(pub u64);
+
+// emails differing only in case or surrounding space share their counters
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+impl RedisKey for LoginFailuresKey {
+    type Value = FailureCount;
+    fn inner(&self) -> String {
+        match self {
+            LoginFailuresKey::Email(email) => format!("login-failures:email:{email}"),
+            LoginFailuresKey::Ip(ip) => format!("login-failures:ip:{ip}"),
+        }
+    }
+}
+
+impl RedisKey for LoginBackoffKey {
+    type Value = FailureCount;
+    fn inner(&self) -> String {
+        format!("login-backoff:{}", self.0)
+    }
+}
+
+impl RedisKey for LoginLockoutKey {
+    type Value = FailureCount;
+    fn inner(&self) -> String {
+        format!("login-lockout:{}", self.0)
+    }
+}
+
+impl RedisValue for FailureCount {
+    fn inner(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl TryFrom<String> for FailureCount {
+    type Error = AppError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value
+            .parse()
+            .map(Self)
+            .map_err(|_| AppError::ConversionEntityError(format!("invalid count: {value}")))
+    }
+}
@@ -0,0 +1,44 @@
+use std::str::FromStr;
+
+use kernel::model::{auth::LoginChallengeToken, id::UserId};
+use shared::error::AppError;
+
+use crate::redis::model::{RedisKey, RedisValue};
+
+pub struct LoginChallengeKey(String);
+
+pub struct ChallengedUser(pub UserId);
+
+impl From<&LoginChallengeToken> for LoginChallengeKey {
+    fn from(token: &LoginChallengeToken) -> Self {
+        LoginChallengeKey(token.0.clone())
+    }
+}
+
+impl From<&str> for LoginChallengeKey {
+    fn from(token: &str) -> Self {
+        LoginChallengeKey(token.to_string())
+    }
+}
+
+impl RedisKey for LoginChallengeKey {
+    type Value = ChallengedUser;
+    fn inner(&self) -> String {
+        format!("login-challenge:{}", self.0)
+    }
+}
+
+impl RedisValue for ChallengedUser {
+    fn inner(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl TryFrom<String> for ChallengedUser {
+    type Error = AppError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        UserId::from_str(&value)
+            .map(Self)
+            .map_err(|e| AppError::ConversionEntityError(e.to_string()))
+    }
+}
@@ -1,4 +1,16 @@
+pub mod api_key;
+pub mod audit;
 pub mod auth;
 pub mod book;
 pub mod checkout;
+pub mod email_verification;
+pub mod job;
+pub mod live_event;
+pub mod login_attempt;
+pub mod login_challenge;
+pub mod notification;
+pub mod password_reset;
+pub mod reservation;
+pub mod role;
 pub mod user;
+pub mod webhook;
@@ -0,0 +1,65 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use kernel::model::{
+    id::{BookId, NotificationId, UserId},
+    notification::{Notification, NotificationBook, NotificationKind, NotificationRecipient},
+};
+use shared::error::{AppError, AppResult};
+
+pub struct NotificationRow {
+    pub notification_id: NotificationId,
+    pub kind: String,
+    pub user_id: UserId,
+    pub user_name: String,
+    pub email: String,
+    pub book_id: BookId,
+    pub title: String,
+    pub author: String,
+    pub due_at: Option<DateTime<Utc>>,
+    pub attempts: i32,
+}
+
+impl TryFrom<NotificationRow> for Notification {
+    type Error = AppError;
+
+    fn try_from(value: NotificationRow) -> Result<Self, Self::Error> {
+        let NotificationRow {
+            notification_id,
+            kind,
+            user_id,
+            user_name,
+            email,
+            book_id,
+            title,
+            author,
+            due_at,
+            attempts,
+        } = value;
+        Ok(Notification {
+            id: notification_id,
+            kind: parse_kind(&kind)?,
+            recipient: NotificationRecipient {
+                user_id,
+                name: user_name,
+                email,
+            },
+            book: NotificationBook {
+                book_id,
+                title,
+                author,
+            },
+            due_at,
+            attempts,
+        })
+    }
+}
+
+pub struct NotificationPreferenceRow {
+    pub kind: String,
+    pub enabled: bool,
+}
+
+pub fn parse_kind(kind: &str) -> AppResult<NotificationKind> {
+    NotificationKind::from_str(kind).map_err(|e| AppError::ConversionEntityError(e.to_string()))
+}
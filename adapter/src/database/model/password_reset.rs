@@ -0,0 +1,69 @@
+use std::str::FromStr;
+
+use kernel::model::{auth::PasswordResetToken, id::UserId};
+use shared::error::AppError;
+
+use crate::redis::model::{RedisKey, RedisValue};
+
+pub struct PasswordResetKey(String);
+
+pub struct ResetUser(pub UserId);
+
+// the reset a user has pending, so that a new request can replace it
+pub struct UserPasswordResetKey(pub UserId);
+
+pub struct ResetToken(pub String);
+
+impl From<&PasswordResetToken> for PasswordResetKey {
+    fn from(token: &PasswordResetToken) -> Self {
+        PasswordResetKey(token.0.clone())
+    }
+}
+
+impl From<&str> for PasswordResetKey {
+    fn from(token: &str) -> Self {
+        PasswordResetKey(token.to_string())
+    }
+}
+
+impl RedisKey for PasswordResetKey {
+    type Value = ResetUser;
+    fn inner(&self) -> String {
+        format!("password-reset:{}", self.0)
+    }
+}
+
+impl RedisValue for ResetUser {
+    fn inner(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl TryFrom<String> for ResetUser {
+    type Error = AppError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        UserId::from_str(&value)
+            .map(Self)
+            .map_err(|e| AppError::ConversionEntityError(e.to_string()))
+    }
+}
+
+impl RedisKey for UserPasswordResetKey {
+    type Value = ResetToken;
+    fn inner(&self) -> String {
+        format!("password-reset-user:{}", self.0)
+    }
+}
+
+impl RedisValue for ResetToken {
+    fn inner(&self) -> String {
+        self.0.clone()
+    }
+}
+
+impl TryFrom<String> for ResetToken {
+    type Error = AppError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Ok(Self(value))
+    }
+}
@@ -0,0 +1,32 @@
+use chrono::{DateTime, Utc};
+use kernel::model::{
+    id::{BookId, ReservationId, UserId},
+    reservation::Reservation,
+};
+
+pub struct ReservationRow {
+    pub reservation_id: ReservationId,
+    pub book_id: BookId,
+    pub user_id: UserId,
+    pub reserved_at: DateTime<Utc>,
+    pub claim_expires_at: Option<DateTime<Utc>>,
+}
+
+impl From<ReservationRow> for Reservation {
+    fn from(value: ReservationRow) -> Self {
+        let ReservationRow {
+            reservation_id,
+            book_id,
+            user_id,
+            reserved_at,
+            claim_expires_at,
+        } = value;
+        Reservation {
+            id: reservation_id,
+            book_id,
+            reserved_by: user_id,
+            reserved_at,
+            claim_expires_at,
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use std::str::FromStr;
+
+use kernel::model::role::{BorrowingPolicy, Permission, Role};
+use shared::error::{AppError, AppResult};
+
+pub struct RoleRow {
+    pub name: String,
+    pub permissions: Vec<String>,
+}
+
+impl TryFrom<RoleRow> for Role {
+    type Error = AppError;
+
+    fn try_from(value: RoleRow) -> Result<Self, Self::Error> {
+        let RoleRow { name, permissions } = value;
+        Ok(Role {
+            name,
+            permissions: parse_permissions(&permissions)?,
+        })
+    }
+}
+
+pub(crate) fn parse_permissions(permissions: &[String]) -> AppResult<Vec<Permission>> {
+    permissions
+        .iter()
+        .map(|p| {
+            Permission::from_str(p).map_err(|e| AppError::ConversionEntityError(e.to_string()))
+        })
+        .collect()
+}
+
+pub struct BorrowingPolicyRow {
+    pub max_loans: Option<i32>,
+    pub block_when_overdue: bool,
+    pub block_own_books: bool,
+}
+
+impl From<BorrowingPolicyRow> for BorrowingPolicy {
+    fn from(value: BorrowingPolicyRow) -> Self {
+        let BorrowingPolicyRow {
+            max_loans,
+            block_when_overdue,
+            block_own_books,
+        } = value;
+        BorrowingPolicy {
+            max_loans,
+            block_when_overdue,
+            block_own_books,
+        }
+    }
+}
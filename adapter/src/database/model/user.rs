@@ -1,6 +1,12 @@
 use std::str::FromStr;
 
-use kernel::model::{id::UserId, role::Role, user::User};
+use kernel::model::{
+    id::UserId,
+    role::Role,
+    user::{User, UserStatus},
+};
+
+use super::role::parse_permissions;
 use shared::error::AppError;
 use sqlx::types::chrono::{DateTime, Utc};
 
@@ -9,6 +15,8 @@ pub struct UserRow {
     pub name: String,
     pub email: String,
     pub role_name: String,
+    pub permissions: Vec<String>,
+    pub status: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -22,13 +30,19 @@ impl TryFrom<UserRow> for User {
             name,
             email,
             role_name,
+            permissions,
+            status,
             ..
         } = value;
         Ok(User {
             id: user_id,
             name,
             email,
-            role: Role::from_str(role_name.as_str())
+            role: Role {
+                name: role_name,
+                permissions: parse_permissions(&permissions)?,
+            },
+            status: UserStatus::from_str(status.as_str())
                 .map_err(|e| AppError::ConversionEntityError(e.to_string()))?,
         })
     }
@@ -0,0 +1,128 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use kernel::model::{
+    id::{UserId, WebhookDeliveryId, WebhookId},
+    webhook::{DeliveryStatus, PendingDelivery, Webhook, WebhookDelivery, WebhookEventType},
+};
+use shared::error::{AppError, AppResult};
+
+pub struct WebhookRow {
+    pub webhook_id: WebhookId,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub created_by: UserId,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<WebhookRow> for Webhook {
+    type Error = AppError;
+
+    fn try_from(value: WebhookRow) -> Result<Self, Self::Error> {
+        let WebhookRow {
+            webhook_id,
+            url,
+            event_types,
+            created_by,
+            created_at,
+        } = value;
+        Ok(Webhook {
+            id: webhook_id,
+            url,
+            event_types: event_types
+                .iter()
+                .map(|t| parse_event_type(t))
+                .collect::<AppResult<_>>()?,
+            created_by,
+            created_at,
+        })
+    }
+}
+
+pub struct WebhookDeliveryRow {
+    pub delivery_id: WebhookDeliveryId,
+    pub webhook_id: WebhookId,
+    pub event_type: String,
+    pub payload: String,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<WebhookDeliveryRow> for WebhookDelivery {
+    type Error = AppError;
+
+    fn try_from(value: WebhookDeliveryRow) -> Result<Self, Self::Error> {
+        let WebhookDeliveryRow {
+            delivery_id,
+            webhook_id,
+            event_type,
+            payload,
+            status,
+            attempts,
+            next_attempt_at,
+            last_attempt_at,
+            response_status,
+            last_error,
+            created_at,
+            delivered_at,
+        } = value;
+        Ok(WebhookDelivery {
+            id: delivery_id,
+            webhook_id,
+            event_type: parse_event_type(&event_type)?,
+            payload,
+            status: DeliveryStatus::from_str(&status)
+                .map_err(|e| AppError::ConversionEntityError(e.to_string()))?,
+            attempts,
+            next_attempt_at,
+            last_attempt_at,
+            response_status,
+            last_error,
+            created_at,
+            delivered_at,
+        })
+    }
+}
+
+pub struct PendingDeliveryRow {
+    pub delivery_id: WebhookDeliveryId,
+    pub url: String,
+    pub secret: String,
+    pub event_type: String,
+    pub payload: String,
+    pub attempts: i32,
+}
+
+impl TryFrom<PendingDeliveryRow> for PendingDelivery {
+    type Error = AppError;
+
+    fn try_from(value: PendingDeliveryRow) -> Result<Self, Self::Error> {
+        let PendingDeliveryRow {
+            delivery_id,
+            url,
+            secret,
+            event_type,
+            payload,
+            attempts,
+        } = value;
+        Ok(PendingDelivery {
+            id: delivery_id,
+            url,
+            secret,
+            event_type: parse_event_type(&event_type)?,
+            payload,
+            attempts,
+        })
+    }
+}
+
+pub fn parse_event_type(event_type: &str) -> AppResult<WebhookEventType> {
+    WebhookEventType::from_str(event_type)
+        .map_err(|e| AppError::ConversionEntityError(e.to_string()))
+}
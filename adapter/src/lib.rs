@@ -1,3 +1,7 @@
 pub mod database;
+pub mod live_event;
+pub mod mailer;
+pub mod password;
 pub mod redis;
 pub mod repository;
+pub mod webhook;
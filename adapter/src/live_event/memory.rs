@@ -0,0 +1,111 @@
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, PoisonError},
+};
+
+use async_trait::async_trait;
+use kernel::{
+    live_event::{LiveEventBroker, LiveEventStream},
+    model::live_event::{LiveEvent, NewLiveEvent},
+};
+use shared::error::AppResult;
+use tokio::sync::broadcast;
+use tokio_stream::{StreamExt, wrappers::BroadcastStream};
+
+use super::HISTORY_LEN;
+
+// for a single instance, such as tests and local development
+pub struct InMemoryLiveEventBroker {
+    sender: broadcast::Sender<LiveEvent>,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    last_id: u64,
+    // oldest first
+    history: VecDeque<LiveEvent>,
+}
+
+impl InMemoryLiveEventBroker {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(HISTORY_LEN);
+        Self {
+            sender,
+            state: Mutex::default(),
+        }
+    }
+}
+
+impl Default for InMemoryLiveEventBroker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl LiveEventBroker for InMemoryLiveEventBroker {
+    async fn publish(&self, event: NewLiveEvent) -> AppResult<()> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.last_id += 1;
+        let event = LiveEvent::new(state.last_id, event);
+
+        if state.history.len() == HISTORY_LEN {
+            state.history.pop_front();
+        }
+        state.history.push_back(event.clone());
+        // sent under the lock, so subscribers see events in order; fails only
+        // when nobody is subscribed
+        let _ = self.sender.send(event);
+        Ok(())
+    }
+
+    async fn subscribe(&self, last_event_id: Option<u64>) -> AppResult<LiveEventStream> {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let live = BroadcastStream::new(self.sender.subscribe());
+        let backlog = match last_event_id {
+            Some(last) => state
+                .history
+                .iter()
+                .filter(|event| event.id > last)
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        };
+
+        // a lagging receiver has lost events, so the stream ends there
+        let live = live.map_while(Result::ok);
+        Ok(Box::pin(tokio_stream::iter(backlog).chain(live).map(Ok)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use kernel::model::{id::BookId, live_event::LiveEventKind};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn subscribers_resume_after_the_last_event_seen() -> anyhow::Result<()> {
+        let broker = InMemoryLiveEventBroker::new();
+        let book_id = BookId::new();
+        let event = |kind| NewLiveEvent::new(kind, book_id, None, Utc::now());
+
+        broker.publish(event(LiveEventKind::BookCreated)).await?;
+        broker.publish(event(LiveEventKind::BookUpdated)).await?;
+
+        let mut fresh = broker.subscribe(None).await?;
+        let mut resumed = broker.subscribe(Some(1)).await?;
+        broker.publish(event(LiveEventKind::BookDeleted)).await?;
+
+        let next = fresh.next().await.unwrap()?;
+        assert_eq!((next.id, next.kind), (3, LiveEventKind::BookDeleted));
+
+        let next = resumed.next().await.unwrap()?;
+        assert_eq!((next.id, next.kind), (2, LiveEventKind::BookUpdated));
+        let next = resumed.next().await.unwrap()?;
+        assert_eq!((next.id, next.kind), (3, LiveEventKind::BookDeleted));
+        Ok(())
+    }
+}
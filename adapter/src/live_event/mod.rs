@@ -0,0 +1,5 @@
+pub mod memory;
+pub mod redis;
+
+// how many of the latest events are kept for resuming subscribers
+const HISTORY_LEN: usize = 1000;
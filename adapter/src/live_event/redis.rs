@@ -0,0 +1,67 @@
+use std::{collections::HashSet, sync::Arc};
+
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    live_event::{LiveEventBroker, LiveEventStream},
+    model::live_event::{LiveEvent, NewLiveEvent},
+};
+use shared::error::AppResult;
+use tokio_stream::StreamExt;
+
+use super::HISTORY_LEN;
+use crate::{
+    database::model::live_event::{
+        LiveEventChannel, LiveEventHistoryKey, LiveEventMessage, LiveEventSequenceKey,
+    },
+    redis::RedisClient,
+};
+
+// numbers events with a shared counter and fans them out over pub/sub, so
+// subscribers of any instance see the events of all of them
+#[derive(new)]
+pub struct RedisLiveEventBroker {
+    kv: Arc<RedisClient>,
+}
+
+#[async_trait]
+impl LiveEventBroker for RedisLiveEventBroker {
+    async fn publish(&self, event: NewLiveEvent) -> AppResult<()> {
+        let id = self.kv.next_in_sequence(&LiveEventSequenceKey).await?;
+        let message = LiveEventMessage(LiveEvent::new(id, event));
+        self.kv
+            .push_capped(&LiveEventHistoryKey, &message, HISTORY_LEN)
+            .await?;
+        self.kv.publish(&LiveEventChannel, &message).await
+    }
+
+    async fn subscribe(&self, last_event_id: Option<u64>) -> AppResult<LiveEventStream> {
+        // subscribe before reading the history so nothing published in
+        // between is missed
+        let live = self.kv.subscribe(&LiveEventChannel).await?;
+
+        let mut backlog = match last_event_id {
+            Some(last) => self
+                .kv
+                .list_items(&LiveEventHistoryKey)
+                .await?
+                .into_iter()
+                .map(|LiveEventMessage(event)| event)
+                .filter(|event| event.id > last)
+                .collect(),
+            None => Vec::new(),
+        };
+        backlog.sort_by_key(|event| event.id);
+
+        // events published in between are both in the history and on the channel
+        let replayed = backlog.iter().map(|event| event.id).collect::<HashSet<_>>();
+        let live = live.filter_map(move |message| match message {
+            Ok(LiveEventMessage(event)) if replayed.contains(&event.id) => None,
+            message => Some(message.map(|LiveEventMessage(event)| event)),
+        });
+
+        Ok(Box::pin(
+            tokio_stream::iter(backlog.into_iter().map(Ok)).chain(live),
+        ))
+    }
+}
@@ -0,0 +1,47 @@
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use kernel::mailer::{Mail, Mailer};
+use shared::error::AppResult;
+
+use super::mail_error;
+
+// for development: delivers each mail as an `.eml` file in `dir`, which mail
+// clients open as they are
+pub struct FileMailer {
+    from: String,
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(from: impl Into<String>, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            from: from.into(),
+            dir: dir.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, mail: Mail) -> AppResult<()> {
+        spool(&self.from, &self.dir, mail).await
+    }
+}
+
+// names files after the time they are written, so the spool lists in order
+pub(super) async fn spool(from: &str, dir: &Path, mail: Mail) -> AppResult<()> {
+    let Mail { to, subject, body } = mail;
+    let message = format!("From: {from}\r\nTo: {to}\r\nSubject: {subject}\r\n\r\n{body}\r\n");
+    let file_name = format!(
+        "{}-{}.eml",
+        Utc::now().format("%Y%m%dT%H%M%S%.3fZ"),
+        uuid::Uuid::new_v4().simple()
+    );
+    tokio::fs::create_dir_all(dir).await.map_err(mail_error)?;
+    tokio::fs::write(dir.join(file_name), message)
+        .await
+        .map_err(mail_error)?;
+    Ok(())
+}
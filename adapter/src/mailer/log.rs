@@ -0,0 +1,41 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use kernel::mailer::{Mail, Mailer};
+use shared::error::AppResult;
+
+use super::file::spool;
+
+// for local development: logs that a mail was sent instead of delivering it,
+// and keeps a copy of each in `outbox_dir` when one is given; the body is left
+// out of the log since it carries reset and verification tokens
+pub struct LogMailer {
+    from: String,
+    outbox_dir: Option<PathBuf>,
+}
+
+impl LogMailer {
+    pub fn new(from: impl Into<String>, outbox_dir: Option<PathBuf>) -> Self {
+        Self {
+            from: from.into(),
+            outbox_dir,
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, mail: Mail) -> AppResult<()> {
+        tracing::info!(
+            mail.from = %self.from,
+            mail.to = %mail.to,
+            mail.subject = %mail.subject,
+            "Mail not delivered, the log backend is in use"
+        );
+
+        if let Some(dir) = &self.outbox_dir {
+            spool(&self.from, dir, mail).await?;
+        }
+        Ok(())
+    }
+}
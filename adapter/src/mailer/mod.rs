@@ -0,0 +1,9 @@
+use shared::error::AppError;
+
+pub mod file;
+pub mod log;
+pub mod smtp;
+
+fn mail_error(e: impl std::error::Error + Send + Sync + 'static) -> AppError {
+    AppError::MailError(Box::new(e))
+}
@@ -0,0 +1,123 @@
+use async_trait::async_trait;
+use kernel::mailer::{Mail, Mailer};
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+    message::{Mailbox, header::ContentType},
+    transport::smtp::authentication::Credentials,
+};
+use shared::{config::SmtpConfig, error::AppResult};
+
+use super::mail_error;
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn new(from: &str, config: &SmtpConfig) -> AppResult<Self> {
+        let builder = if config.starttls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)
+                .map_err(mail_error)?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host)
+        };
+        let mut builder = builder.port(config.port);
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from: from.parse().map_err(mail_error)?,
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, mail: Mail) -> AppResult<()> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(mail.to.parse().map_err(mail_error)?)
+            .subject(mail.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(mail.body)
+            .map_err(mail_error)?;
+
+        self.transport.send(message).await.map_err(mail_error)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    // accepts one connection and returns the message it is given, like a
+    // local mail sink; answers every command but DATA with a plain 250
+    async fn smtp_sink(listener: TcpListener) -> anyhow::Result<String> {
+        let (stream, _) = listener.accept().await?;
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        writer.write_all(b"220 sink ESMTP\r\n").await?;
+
+        let mut message = String::new();
+        while let Some(line) = lines.next_line().await? {
+            let command = line.to_ascii_uppercase();
+            if command.starts_with("DATA") {
+                writer.write_all(b"354 go ahead\r\n").await?;
+                while let Some(line) = lines.next_line().await? {
+                    if line == "." {
+                        break;
+                    }
+                    message.push_str(&line);
+                    message.push('\n');
+                }
+                writer.write_all(b"250 queued\r\n").await?;
+            } else if command.starts_with("QUIT") {
+                writer.write_all(b"221 bye\r\n").await?;
+                break;
+            } else {
+                writer.write_all(b"250 ok\r\n").await?;
+            }
+        }
+        Ok(message)
+    }
+
+    #[tokio::test]
+    async fn mails_are_handed_to_the_smtp_server() -> anyhow::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let port = listener.local_addr()?.port();
+        let sink = tokio::spawn(smtp_sink(listener));
+
+        let mailer = SmtpMailer::new(
+            "Library <library@localhost>",
+            &SmtpConfig {
+                host: "127.0.0.1".into(),
+                port,
+                username: None,
+                password: None,
+                starttls: false,
+            },
+        )?;
+        mailer
+            .send(Mail {
+                to: "reader@example.com".into(),
+                subject: "You checked out Dune".into(),
+                body: "Please return it by 2026-02-01.".into(),
+            })
+            .await?;
+
+        let message = tokio::time::timeout(std::time::Duration::from_secs(5), sink).await???;
+        assert!(message.contains("To: reader@example.com"));
+        assert!(message.contains("Subject: You checked out Dune"));
+        assert!(message.contains("Please return it by 2026-02-01."));
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+use argon2::{
+    Algorithm, Argon2, Params, PasswordHash, PasswordVerifier, Version,
+    password_hash::{self, SaltString, rand_core::OsRng},
+};
+use shared::{
+    config::PasswordHashConfig,
+    error::{AppError, AppResult},
+};
+
+pub trait PasswordHasher: Send + Sync {
+    fn hash(&self, password: &str) -> AppResult<String>;
+    fn verify(&self, password: &str, password_hash: &str) -> AppResult<bool>;
+    // whether `password_hash` was made by another algorithm or with other
+    // parameters than `hash` uses now
+    fn needs_rehash(&self, password_hash: &str) -> bool;
+}
+
+// hashes with argon2id and still verifies the bcrypt hashes stored before it;
+// the default has the parameters of `PasswordHashConfig::default`
+#[derive(Default)]
+pub struct Argon2PasswordHasher {
+    argon2: Argon2<'static>,
+}
+
+impl Argon2PasswordHasher {
+    pub fn new(config: &PasswordHashConfig) -> AppResult<Self> {
+        let params = Params::new(
+            config.memory_kib,
+            config.iterations,
+            config.parallelism,
+            None,
+        )
+        .map_err(password_hash_error)?;
+        Ok(Self {
+            argon2: Argon2::new(Algorithm::Argon2id, Version::V0x13, params),
+        })
+    }
+}
+
+impl PasswordHasher for Argon2PasswordHasher {
+    fn hash(&self, password: &str) -> AppResult<String> {
+        let salt = SaltString::generate(&mut OsRng);
+        argon2::PasswordHasher::hash_password(&self.argon2, password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(password_hash_error)
+    }
+
+    fn verify(&self, password: &str, password_hash: &str) -> AppResult<bool> {
+        if is_bcrypt(password_hash) {
+            return Ok(bcrypt::verify(password, password_hash)?);
+        }
+        let parsed = PasswordHash::new(password_hash).map_err(password_hash_error)?;
+        // the parameters come from the hash, so older argon2 hashes still verify
+        match self.argon2.verify_password(password.as_bytes(), &parsed) {
+            Ok(()) => Ok(true),
+            Err(password_hash::Error::Password) => Ok(false),
+            Err(e) => Err(password_hash_error(e)),
+        }
+    }
+
+    fn needs_rehash(&self, password_hash: &str) -> bool {
+        let Ok(parsed) = PasswordHash::new(password_hash) else {
+            return true;
+        };
+        let current = self.argon2.params();
+        parsed.algorithm != Algorithm::Argon2id.ident()
+            || parsed.version != Some(Version::V0x13.into())
+            || Params::try_from(&parsed).map_or(true, |params| {
+                (params.m_cost(), params.t_cost(), params.p_cost())
+                    != (current.m_cost(), current.t_cost(), current.p_cost())
+            })
+    }
+}
+
+fn is_bcrypt(password_hash: &str) -> bool {
+    ["$2a$", "$2b$", "$2x$", "$2y$"]
+        .iter()
+        .any(|prefix| password_hash.starts_with(prefix))
+}
+
+fn password_hash_error(e: impl std::error::Error + Send + Sync + 'static) -> AppError {
+    AppError::PasswordHashError(Box::new(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cheap_hasher() -> Argon2PasswordHasher {
+        Argon2PasswordHasher::new(&PasswordHashConfig {
+            memory_kib: 1024,
+            iterations: 1,
+            parallelism: 1,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn hashes_with_argon2id() -> AppResult<()> {
+        let hasher = cheap_hasher();
+        let hash = hasher.hash("Pa55w0rd")?;
+        assert!(hash.starts_with("$argon2id$v=19$m=1024,t=1,p=1$"));
+        assert!(hasher.verify("Pa55w0rd", &hash)?);
+        assert!(!hasher.verify("wrong", &hash)?);
+        assert!(!hasher.needs_rehash(&hash));
+        Ok(())
+    }
+
+    #[test]
+    fn verifies_legacy_bcrypt_hashes_and_asks_to_rehash_them() -> AppResult<()> {
+        let hasher = cheap_hasher();
+        let hash = bcrypt::hash("Pa55w0rd", 4)?;
+        assert!(hasher.verify("Pa55w0rd", &hash)?);
+        assert!(!hasher.verify("wrong", &hash)?);
+        assert!(hasher.needs_rehash(&hash));
+        Ok(())
+    }
+
+    #[test]
+    fn asks_to_rehash_when_parameters_change() -> AppResult<()> {
+        let hash = cheap_hasher().hash("Pa55w0rd")?;
+        let stronger = Argon2PasswordHasher::new(&PasswordHashConfig {
+            memory_kib: 2048,
+            iterations: 1,
+            parallelism: 1,
+        })?;
+        assert!(stronger.verify("Pa55w0rd", &hash)?);
+        assert!(stronger.needs_rehash(&hash));
+        Ok(())
+    }
+}
@@ -2,6 +2,7 @@ pub mod model;
 
 use redis::{AsyncCommands, Client};
 use shared::{config::RedisConfig, error::AppResult};
+use tokio_stream::{Stream, StreamExt};
 
 use crate::redis::model::{RedisKey, RedisValue};
 
@@ -24,6 +25,25 @@ impl RedisClient {
         Ok(())
     }
 
+    // sets the key only if it does not exist yet; tells whether it was set
+    pub async fn set_nx_ex<T: RedisKey>(
+        &self,
+        key: &T,
+        value: &T::Value,
+        ttl: u64,
+    ) -> AppResult<bool> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let res: Option<String> = redis::cmd("SET")
+            .arg(key.inner())
+            .arg(value.inner())
+            .arg("NX")
+            .arg("EX")
+            .arg(ttl)
+            .query_async(&mut conn)
+            .await?;
+        Ok(res.is_some())
+    }
+
     pub async fn get<T: RedisKey>(&self, key: &T) -> AppResult<Option<T::Value>> {
         let mut conn = self.client.get_multiplexed_async_connection().await?;
         let result: Option<String> = conn.get(key.inner()).await?;
@@ -32,11 +52,120 @@ impl RedisClient {
             None => Ok(None),
         }
     }
+    // gets the value and deletes the key at once, so only one caller can see it
+    pub async fn take<T: RedisKey>(&self, key: &T) -> AppResult<Option<T::Value>> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let result: Option<String> = redis::cmd("GETDEL")
+            .arg(key.inner())
+            .query_async(&mut conn)
+            .await?;
+        result.map(T::Value::try_from).transpose()
+    }
     pub async fn delete<T: RedisKey>(&self, key: &T) -> AppResult<()> {
         let mut conn = self.client.get_multiplexed_async_connection().await?;
         conn.del::<_, ()>(key.inner()).await?;
         Ok(())
     }
+    // counts up from 1; every increment pushes the expiry back to `ttl`
+    pub async fn increment<T: RedisKey>(&self, key: &T, ttl: u64) -> AppResult<u64> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let (count,): (u64,) = redis::pipe()
+            .atomic()
+            .incr(key.inner(), 1)
+            .expire(key.inner(), ttl as i64)
+            .ignore()
+            .query_async(&mut conn)
+            .await?;
+        Ok(count)
+    }
+    // seconds until the key expires; none if it is missing or never expires
+    pub async fn ttl<T: RedisKey>(&self, key: &T) -> AppResult<Option<u64>> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let ttl: i64 = conn.ttl(key.inner()).await?;
+        Ok(u64::try_from(ttl).ok())
+    }
+
+    // for set keys, `T::Value` is the type of the members; every addition
+    // pushes the expiry of the whole set back to `ttl`
+    pub async fn add_to_set<T: RedisKey>(
+        &self,
+        key: &T,
+        member: &T::Value,
+        ttl: u64,
+    ) -> AppResult<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        redis::pipe()
+            .atomic()
+            .sadd(key.inner(), member.inner())
+            .ignore()
+            .expire(key.inner(), ttl as i64)
+            .ignore()
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+        Ok(())
+    }
+    pub async fn set_members<T: RedisKey>(&self, key: &T) -> AppResult<Vec<T::Value>> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let members: Vec<String> = conn.smembers(key.inner()).await?;
+        members.into_iter().map(T::Value::try_from).collect()
+    }
+    pub async fn remove_from_set<T: RedisKey>(&self, key: &T, member: &T::Value) -> AppResult<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        conn.srem::<_, _, ()>(key.inner(), member.inner()).await?;
+        Ok(())
+    }
+    // counts up from 1 and never expires
+    pub async fn next_in_sequence<T: RedisKey>(&self, key: &T) -> AppResult<u64> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let next: u64 = conn.incr(key.inner(), 1).await?;
+        Ok(next)
+    }
+
+    // for list keys, `T::Value` is the type of the items; pushes to the front
+    // and drops the items beyond the first `len`
+    pub async fn push_capped<T: RedisKey>(
+        &self,
+        key: &T,
+        item: &T::Value,
+        len: usize,
+    ) -> AppResult<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        redis::pipe()
+            .atomic()
+            .lpush(key.inner(), item.inner())
+            .ignore()
+            .ltrim(key.inner(), 0, len as isize - 1)
+            .ignore()
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+        Ok(())
+    }
+    pub async fn list_items<T: RedisKey>(&self, key: &T) -> AppResult<Vec<T::Value>> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let items: Vec<String> = conn.lrange(key.inner(), 0, -1).await?;
+        items.into_iter().map(T::Value::try_from).collect()
+    }
+
+    // for channel keys, `T::Value` is the type of the messages
+    pub async fn publish<T: RedisKey>(&self, channel: &T, message: &T::Value) -> AppResult<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        conn.publish::<_, _, ()>(channel.inner(), message.inner())
+            .await?;
+        Ok(())
+    }
+    // holds a connection of its own until the stream is dropped
+    pub async fn subscribe<T: RedisKey>(
+        &self,
+        channel: &T,
+    ) -> AppResult<impl Stream<Item = AppResult<T::Value>> + Send + use<T>> {
+        let mut pubsub = self.client.get_async_pubsub().await?;
+        pubsub.subscribe(channel.inner()).await?;
+        Ok(pubsub.into_on_message().map(|msg| {
+            let payload: String = msg.get_payload()?;
+            T::Value::try_from(payload)
+        }))
+    }
+
     pub async fn try_connect(&self) -> AppResult<()> {
         let _ = self.client.get_multiplexed_async_connection().await?;
         Ok(())
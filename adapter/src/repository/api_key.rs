@@ -0,0 +1,236 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use derive_new::new;
+use kernel::{
+    model::{
+        api_key::{
+            API_KEY_PREFIX, ApiKey, ApiKeyOwner, ApiKeyScope, ApiKeySecret, IssuedApiKey,
+            event::{CreateApiKey, RevokeApiKey, RotateApiKey},
+        },
+        id::{ApiKeyId, UserId},
+    },
+    repository::api_key::ApiKeyRepository,
+};
+use sha2::{Digest, Sha256};
+use shared::error::{AppError, AppResult};
+
+use crate::database::{
+    ConnectionPool,
+    model::api_key::{ApiKeyOwnerRow, ApiKeyRow},
+};
+
+#[derive(new)]
+pub struct ApiKeyRepositoryImpl {
+    db: ConnectionPool,
+}
+
+#[async_trait]
+impl ApiKeyRepository for ApiKeyRepositoryImpl {
+    async fn create(&self, event: CreateApiKey) -> AppResult<IssuedApiKey> {
+        let (prefix, secret) = new_api_key();
+        let row = sqlx::query_as!(
+            ApiKeyRow,
+            r#"
+                INSERT INTO api_keys (api_key_id, user_id, name, prefix, key_hash, scopes)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                RETURNING
+                    api_key_id AS "api_key_id: ApiKeyId",
+                    user_id AS "user_id: UserId",
+                    name, prefix, scopes, created_at, last_used_at
+            "#,
+            ApiKeyId::new() as _,
+            event.user_id as _,
+            event.name,
+            prefix,
+            hash_api_key(&secret),
+            &scope_names(&event.scopes)
+        )
+        .fetch_one(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        Ok(IssuedApiKey {
+            api_key: row.try_into()?,
+            secret,
+        })
+    }
+    async fn find_by_user_id(&self, user_id: UserId) -> AppResult<Vec<ApiKey>> {
+        sqlx::query_as!(
+            ApiKeyRow,
+            r#"
+                SELECT
+                    api_key_id AS "api_key_id: ApiKeyId",
+                    user_id AS "user_id: UserId",
+                    name, prefix, scopes, created_at, last_used_at
+                FROM api_keys
+                WHERE user_id = $1 AND revoked_at IS NULL
+                ORDER BY created_at DESC
+            "#,
+            user_id as _
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .into_iter()
+        .map(ApiKey::try_from)
+        .collect()
+    }
+    async fn rotate(&self, event: RotateApiKey) -> AppResult<IssuedApiKey> {
+        let (prefix, secret) = new_api_key();
+        let row = sqlx::query_as!(
+            ApiKeyRow,
+            r#"
+                UPDATE api_keys SET prefix = $3, key_hash = $4
+                WHERE api_key_id = $1 AND user_id = $2 AND revoked_at IS NULL
+                RETURNING
+                    api_key_id AS "api_key_id: ApiKeyId",
+                    user_id AS "user_id: UserId",
+                    name, prefix, scopes, created_at, last_used_at
+            "#,
+            event.api_key_id as _,
+            event.user_id as _,
+            prefix,
+            hash_api_key(&secret)
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(api_key_not_found)?;
+
+        Ok(IssuedApiKey {
+            api_key: row.try_into()?,
+            secret,
+        })
+    }
+    async fn revoke(&self, event: RevokeApiKey) -> AppResult<()> {
+        let res = sqlx::query!(
+            r#"
+                UPDATE api_keys SET revoked_at = CURRENT_TIMESTAMP(3)
+                WHERE api_key_id = $1 AND user_id = $2 AND revoked_at IS NULL
+            "#,
+            event.api_key_id as _,
+            event.user_id as _
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        if res.rows_affected() < 1 {
+            return Err(api_key_not_found());
+        }
+        Ok(())
+    }
+    async fn authenticate(&self, secret: &ApiKeySecret) -> AppResult<Option<ApiKeyOwner>> {
+        let Some(prefix) = api_key_prefix(secret) else {
+            return Ok(None);
+        };
+        sqlx::query_as!(
+            ApiKeyOwnerRow,
+            r#"
+                UPDATE api_keys SET last_used_at = CURRENT_TIMESTAMP(3)
+                WHERE prefix = $1 AND key_hash = $2 AND revoked_at IS NULL
+                RETURNING
+                    api_key_id AS "api_key_id: ApiKeyId",
+                    user_id AS "user_id: UserId",
+                    scopes
+            "#,
+            prefix,
+            hash_api_key(secret)
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .map(ApiKeyOwner::try_from)
+        .transpose()
+    }
+    async fn delete_revoked(&self, revoked_before: DateTime<Utc>) -> AppResult<u64> {
+        sqlx::query!("DELETE FROM api_keys WHERE revoked_at < $1", revoked_before)
+            .execute(self.db.inner_ref())
+            .await
+            .map(|res| res.rows_affected())
+            .map_err(AppError::SpecificOperationError)
+    }
+}
+
+// `rbm_` and 8 random hex digits make up the prefix, which is stored as is;
+// the key is the prefix followed by 32 more random hex digits
+pub(crate) fn new_api_key() -> (String, ApiKeySecret) {
+    let random = uuid::Uuid::new_v4().simple().to_string();
+    let prefix = format!("{API_KEY_PREFIX}{}", &random[..8]);
+    let secret = format!("{prefix}_{}", uuid::Uuid::new_v4().simple());
+    (prefix, ApiKeySecret(secret))
+}
+
+pub(crate) fn api_key_prefix(secret: &ApiKeySecret) -> Option<&str> {
+    secret
+        .0
+        .rsplit_once('_')
+        .map(|(prefix, _)| prefix)
+        .filter(|prefix| prefix.starts_with(API_KEY_PREFIX))
+}
+
+// keys are random, so a fast hash is enough to keep them from being read
+pub(crate) fn hash_api_key(secret: &ApiKeySecret) -> String {
+    hex::encode(Sha256::digest(secret.0.as_bytes()))
+}
+
+fn scope_names(scopes: &[ApiKeyScope]) -> Vec<String> {
+    scopes.iter().map(|s| s.as_ref().to_string()).collect()
+}
+
+pub(crate) fn api_key_not_found() -> AppError {
+    AppError::EntityNotFound("specified API key not found".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[sqlx::test(fixtures("common"))]
+    async fn keys_authenticate_until_rotated_or_revoked(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let user_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let repo = ApiKeyRepositoryImpl::new(ConnectionPool::new(pool));
+
+        let issued = repo
+            .create(CreateApiKey::new(
+                user_id,
+                "scanner".into(),
+                vec![ApiKeyScope::BooksRead, ApiKeyScope::Checkouts],
+            ))
+            .await?;
+        assert!(issued.secret.0.starts_with(&issued.api_key.prefix));
+        assert_eq!(issued.api_key.last_used_at, None);
+
+        let owner = repo
+            .authenticate(&issued.secret)
+            .await?
+            .expect("a new key authenticates");
+        assert_eq!(owner.user_id, user_id);
+        assert_eq!(
+            owner.scopes,
+            vec![ApiKeyScope::BooksRead, ApiKeyScope::Checkouts]
+        );
+        let keys = repo.find_by_user_id(user_id).await?;
+        assert_eq!(keys.len(), 1);
+        assert!(keys[0].last_used_at.is_some());
+
+        let rotated = repo
+            .rotate(RotateApiKey::new(issued.api_key.id, user_id))
+            .await?;
+        assert_eq!(rotated.api_key.id, issued.api_key.id);
+        assert!(repo.authenticate(&issued.secret).await?.is_none());
+        assert!(repo.authenticate(&rotated.secret).await?.is_some());
+
+        repo.revoke(RevokeApiKey::new(issued.api_key.id, user_id))
+            .await?;
+        assert!(repo.authenticate(&rotated.secret).await?.is_none());
+        assert!(repo.find_by_user_id(user_id).await?.is_empty());
+        assert!(matches!(
+            repo.revoke(RevokeApiKey::new(issued.api_key.id, user_id))
+                .await,
+            Err(AppError::EntityNotFound(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,394 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use derive_new::new;
+use kernel::{
+    model::{
+        audit::{AuditEntry, AuditLogOptions, event::RecordAudit},
+        id::{AuditEntryId, BookCopyId, BookId, UserId},
+        list::{CursorDirection, CursorPaginatedList},
+        role::{BorrowingPolicy, Permission},
+    },
+    repository::audit::AuditRepository,
+};
+use shared::error::{AppError, AppResult};
+
+use crate::database::{ConnectionPool, model::audit::AuditEntryRow};
+
+#[derive(new)]
+pub struct AuditRepositoryImpl {
+    db: ConnectionPool,
+}
+
+#[async_trait]
+impl AuditRepository for AuditRepositoryImpl {
+    async fn find_all(
+        &self,
+        options: AuditLogOptions,
+    ) -> AppResult<CursorPaginatedList<AuditEntry>> {
+        let AuditLogOptions {
+            limit,
+            cursor,
+            actor,
+            target_kind,
+            target_id,
+            since,
+            until,
+        } = options;
+        let (after_at, after_id) = cursor.map(|c| (c.timestamp, c.id)).unzip();
+        let target_type = target_kind.as_ref().map(AsRef::as_ref);
+
+        let rows = match cursor.map(|c| c.direction) {
+            None | Some(CursorDirection::Next) => {
+                sqlx::query_as!(
+                    AuditEntryRow,
+                    r#"
+                        SELECT
+                            audit_entry_id AS "audit_entry_id: AuditEntryId",
+                            actor_id AS "actor_id: UserId",
+                            action,
+                            target_type,
+                            target_id,
+                            before,
+                            after,
+                            request_id,
+                            occurred_at
+                        FROM audit_log
+                        WHERE ($2::uuid IS NULL OR actor_id = $2)
+                            AND ($3::varchar IS NULL OR target_type = $3)
+                            AND ($4::varchar IS NULL OR target_id = $4)
+                            AND ($5::timestamptz IS NULL OR occurred_at >= $5)
+                            AND ($6::timestamptz IS NULL OR occurred_at <= $6)
+                            AND ($7::timestamptz IS NULL
+                                OR (occurred_at, audit_entry_id) < ($7, $8::uuid))
+                        ORDER BY occurred_at DESC, audit_entry_id DESC
+                        LIMIT $1
+                    "#,
+                    limit + 1,
+                    actor as _,
+                    target_type,
+                    target_id,
+                    since,
+                    until,
+                    after_at,
+                    after_id
+                )
+                .fetch_all(self.db.inner_ref())
+                .await
+            }
+            Some(CursorDirection::Prev) => {
+                sqlx::query_as!(
+                    AuditEntryRow,
+                    r#"
+                        SELECT
+                            audit_entry_id AS "audit_entry_id: AuditEntryId",
+                            actor_id AS "actor_id: UserId",
+                            action,
+                            target_type,
+                            target_id,
+                            before,
+                            after,
+                            request_id,
+                            occurred_at
+                        FROM audit_log
+                        WHERE ($2::uuid IS NULL OR actor_id = $2)
+                            AND ($3::varchar IS NULL OR target_type = $3)
+                            AND ($4::varchar IS NULL OR target_id = $4)
+                            AND ($5::timestamptz IS NULL OR occurred_at >= $5)
+                            AND ($6::timestamptz IS NULL OR occurred_at <= $6)
+                            AND (occurred_at, audit_entry_id) > ($7, $8::uuid)
+                        ORDER BY occurred_at ASC, audit_entry_id ASC
+                        LIMIT $1
+                    "#,
+                    limit + 1,
+                    actor as _,
+                    target_type,
+                    target_id,
+                    since,
+                    until,
+                    after_at,
+                    after_id
+                )
+                .fetch_all(self.db.inner_ref())
+                .await
+            }
+        }
+        .map_err(AppError::SpecificOperationError)?
+        .into_iter()
+        .map(AuditEntry::try_from)
+        .collect::<AppResult<Vec<_>>>()?;
+
+        Ok(CursorPaginatedList::from_rows(
+            rows,
+            limit,
+            cursor,
+            |e: &AuditEntry| (e.occurred_at, e.id.raw()),
+        ))
+    }
+}
+
+// adds an entry for the change, in the transaction that makes it
+pub(crate) async fn record_audit(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    event: RecordAudit,
+) -> AppResult<()> {
+    sqlx::query!(
+        r#"
+            INSERT INTO audit_log
+                (audit_entry_id, actor_id, action, target_type, target_id,
+                 before, after, request_id, occurred_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#,
+        AuditEntryId::new() as _,
+        event.actor as _,
+        event.action.as_ref(),
+        event.target.kind.as_ref(),
+        event.target.id,
+        event.before,
+        event.after,
+        shared::request_id::current(),
+        Utc::now()
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+    Ok(())
+}
+
+// the JSON an entry keeps of its target
+pub(crate) fn snapshot(value: serde_json::Value) -> Option<String> {
+    Some(value.to_string())
+}
+
+pub(crate) fn book_snapshot(
+    title: &str,
+    author: &str,
+    isbn: &str,
+    description: &str,
+    owner: UserId,
+) -> Option<String> {
+    snapshot(serde_json::json!({
+        "title": title,
+        "author": author,
+        "isbn": isbn,
+        "description": description,
+        "ownerId": owner,
+    }))
+}
+
+pub(crate) fn copy_snapshot(
+    book_id: BookId,
+    barcode: &str,
+    condition: &str,
+    shelf_location: &str,
+) -> Option<String> {
+    snapshot(serde_json::json!({
+        "bookId": book_id,
+        "barcode": barcode,
+        "condition": condition,
+        "shelfLocation": shelf_location,
+    }))
+}
+
+// leaves out the password hash
+pub(crate) fn user_snapshot(name: &str, email: &str, role: &str, status: &str) -> Option<String> {
+    snapshot(serde_json::json!({
+        "name": name,
+        "email": email,
+        "role": role,
+        "status": status,
+    }))
+}
+
+pub(crate) fn role_snapshot(permissions: &[Permission]) -> Option<String> {
+    let permissions = permissions.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+    snapshot(serde_json::json!({ "permissions": permissions }))
+}
+
+pub(crate) fn borrowing_policy_snapshot(policy: &BorrowingPolicy) -> Option<String> {
+    snapshot(serde_json::json!({
+        "maxLoans": policy.max_loans,
+        "blockWhenOverdue": policy.block_when_overdue,
+        "blockOwnBooks": policy.block_own_books,
+    }))
+}
+
+pub(crate) fn checkout_snapshot(
+    book_id: BookId,
+    copy_id: BookCopyId,
+    user_id: UserId,
+    issued_by: UserId,
+    checked_out_at: DateTime<Utc>,
+    due_at: DateTime<Utc>,
+) -> Option<String> {
+    snapshot(serde_json::json!({
+        "bookId": book_id,
+        "copyId": copy_id,
+        "userId": user_id,
+        "issuedBy": issued_by,
+        "checkedOutAt": checked_out_at,
+        "dueAt": due_at,
+    }))
+}
+
+pub(crate) fn return_snapshot(
+    returned_by: UserId,
+    returned_at: DateTime<Utc>,
+    reason: Option<&str>,
+) -> Option<String> {
+    snapshot(serde_json::json!({
+        "returnedBy": returned_by,
+        "returnedAt": returned_at,
+        "reason": reason,
+    }))
+}
+
+pub(crate) fn renewal_snapshot(due_at: DateTime<Utc>, renewal_count: i32) -> Option<String> {
+    snapshot(serde_json::json!({
+        "dueAt": due_at,
+        "renewalCount": renewal_count,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use kernel::{
+        model::{
+            audit::{AuditAction, AuditTarget, AuditTargetKind},
+            book::event::{CreateBook, DeleteBook},
+            user::{
+                UserStatus,
+                event::{CreateUser, UpdateUserRole},
+            },
+        },
+        repository::{book::BookRepository, user::UserRepository},
+    };
+
+    use super::*;
+    use crate::{
+        password::Argon2PasswordHasher,
+        repository::{book::BookRepositoryImpl, user::UserRepositoryImpl},
+    };
+
+    #[sqlx::test]
+    async fn changes_are_recorded_and_never_altered(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let user_repo = UserRepositoryImpl::new(
+            ConnectionPool::new(pool.clone()),
+            Arc::new(Argon2PasswordHasher::default()),
+        );
+        let book_repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let repo = AuditRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+
+        let user = user_repo
+            .create(CreateUser {
+                user_id: UserId::new(),
+                name: "Test User".into(),
+                email: "test@example.com".into(),
+                password: "test_password".into(),
+                status: UserStatus::Active,
+                created_by: None,
+            })
+            .await?;
+        let book_id = book_repo
+            .create(
+                CreateBook {
+                    title: "The Rust Programming Language".into(),
+                    author: "Steve Klabnik and Carol Nichols".into(),
+                    isbn: "978-1-59327-828-1".parse()?,
+                    description: String::new(),
+                    add_copy: false,
+                },
+                user.id,
+            )
+            .await?;
+        book_repo
+            .delete(DeleteBook {
+                book_id,
+                requested_user: user.id,
+                manages_books: false,
+            })
+            .await?;
+
+        let book_log = repo
+            .find_all(AuditLogOptions {
+                limit: 20,
+                target_kind: Some(AuditTargetKind::Book),
+                target_id: Some(book_id.to_string()),
+                ..Default::default()
+            })
+            .await?;
+        assert_eq!(book_log.items.len(), 2);
+        let deleted = book_log
+            .items
+            .iter()
+            .find(|e| e.action == AuditAction::Delete)
+            .expect("the delete is recorded");
+        assert_eq!(deleted.actor, Some(user.id));
+        assert!(
+            deleted
+                .before
+                .as_deref()
+                .is_some_and(|b| b.contains("The Rust Programming Language"))
+        );
+        assert_eq!(deleted.after, None);
+
+        let admin = UserId::new();
+        user_repo
+            .update_role(UpdateUserRole {
+                user_id: user.id,
+                role: "Librarian".into(),
+                requested_user: admin,
+            })
+            .await?;
+        let admin_log = repo
+            .find_all(AuditLogOptions {
+                limit: 20,
+                actor: Some(admin),
+                ..Default::default()
+            })
+            .await?;
+        assert_eq!(admin_log.items.len(), 1);
+        let changed = &admin_log.items[0];
+        assert_eq!(changed.action, AuditAction::ChangeRole);
+        assert_eq!(changed.before.as_deref(), Some(r#"{"role":"User"}"#));
+        assert_eq!(changed.after.as_deref(), Some(r#"{"role":"Librarian"}"#));
+
+        // the sign-up, the book, its deletion and the role change
+        let page = repo
+            .find_all(AuditLogOptions {
+                limit: 3,
+                ..Default::default()
+            })
+            .await?;
+        assert_eq!(page.items.len(), 3);
+        let rest = repo
+            .find_all(AuditLogOptions {
+                limit: 3,
+                cursor: page.next_cursor,
+                ..Default::default()
+            })
+            .await?;
+        assert_eq!(rest.items.len(), 1);
+        assert!(
+            page.items
+                .iter()
+                .chain(&rest.items)
+                .any(|e| e.target == AuditTarget::user(user.id) && e.actor == Some(user.id))
+        );
+
+        assert!(
+            sqlx::query("DELETE FROM audit_log")
+                .execute(&pool)
+                .await
+                .is_err()
+        );
+        assert!(
+            sqlx::query("UPDATE audit_log SET actor_id = NULL")
+                .execute(&pool)
+                .await
+                .is_err()
+        );
+        Ok(())
+    }
+}
@@ -1,20 +1,31 @@
+use std::{cmp::Reverse, str::FromStr, sync::Arc};
+
 use async_trait::async_trait;
+use chrono::Utc;
 use derive_new::new;
 use kernel::{
     model::{
-        auth::{AccessToken, event::CreateToken},
-        id::UserId,
+        auth::{
+            AccessToken, AuthTokens, RefreshToken, Session, TokenOwner,
+            event::{CreateToken, DeleteSession, RotateToken},
+        },
+        id::{SessionId, UserId},
+        user::UserStatus,
     },
     repository::auth::AuthRepository,
 };
 use shared::error::{AppError, AppResult};
-use std::sync::Arc;
 
 use crate::{
     database::{
         ConnectionPool,
-        model::auth::{AuthorizationKey, AuthorizedUserId, UserItem, from},
+        model::auth::{
+            AuthorizationKey, AuthorizedSession, LastUsedAt, RefreshTokenKey, SessionEntry,
+            SessionKey, SessionLastUsedKey, SessionRef, UsedRefreshTokenKey, UserItem,
+            UserSessionsKey,
+        },
     },
+    password::PasswordHasher,
     redis::RedisClient,
 };
 
@@ -22,47 +33,311 @@ use crate::{
 pub struct AuthRepositoryImpl {
     db: ConnectionPool,
     kv: Arc<RedisClient>,
+    hasher: Arc<dyn PasswordHasher>,
     ttl: u64,
+    refresh_ttl: u64,
 }
 
 #[async_trait]
 impl AuthRepository for AuthRepositoryImpl {
-    async fn fetch_user_id_from_token(
-        &self,
-        access_token: &AccessToken,
-    ) -> AppResult<Option<UserId>> {
+    async fn fetch_token_owner(&self, access_token: &AccessToken) -> AppResult<Option<TokenOwner>> {
         let key: AuthorizationKey = access_token.into();
-        self.kv
-            .get(&key)
-            .await
-            .map(|x| x.map(AuthorizedUserId::into_inner))
+        let Some(AuthorizedSession {
+            user_id,
+            session_id,
+        }) = self.kv.get(&key).await?
+        else {
+            return Ok(None);
+        };
+
+        if let Some(session_id) = session_id {
+            self.kv
+                .set_ex(
+                    &SessionLastUsedKey(session_id),
+                    &LastUsedAt(Utc::now()),
+                    self.refresh_ttl,
+                )
+                .await?;
+        }
+        Ok(Some(TokenOwner {
+            user_id,
+            session_id,
+        }))
     }
     async fn verify_user(&self, email: &str, password: &str) -> AppResult<UserId> {
         let user_item = sqlx::query_as!(
             UserItem,
             r#"
-                SELECT user_id, password_hash FROM users
+                SELECT user_id, password_hash, status FROM users
                 WHERE email = $1
             "#,
             email
         )
-        .fetch_one(self.db.inner_ref())
+        .fetch_optional(self.db.inner_ref())
         .await
-        .map_err(AppError::SpecificOperationError)?;
+        .map_err(AppError::SpecificOperationError)?
+        // an unknown email fails like a wrong password
+        .ok_or(AppError::UnauthorizedError)?;
 
-        let valid = bcrypt::verify(password, &user_item.password_hash)?;
+        let valid = self.hasher.verify(password, &user_item.password_hash)?;
         if !valid {
             return Err(AppError::UnauthorizedError);
         }
+        ensure_active(
+            UserStatus::from_str(&user_item.status)
+                .map_err(|e| AppError::ConversionEntityError(e.to_string()))?,
+        )?;
+
+        if self.hasher.needs_rehash(&user_item.password_hash) {
+            // skipped if the password changed since it was read
+            let rehashed = self.hasher.hash(password)?;
+            sqlx::query!(
+                r#"
+                    UPDATE users SET password_hash = $1
+                    WHERE user_id = $2 AND password_hash = $3
+                "#,
+                rehashed,
+                user_item.user_id as _,
+                user_item.password_hash,
+            )
+            .execute(self.db.inner_ref())
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+        }
         Ok(user_item.user_id)
     }
-    async fn create_token(&self, event: CreateToken) -> AppResult<AccessToken> {
-        let (key, value) = from(event);
-        self.kv.set_ex(&key, &value, self.ttl).await?;
-        Ok(key.into())
+    async fn create_token(&self, event: CreateToken) -> AppResult<AuthTokens> {
+        let CreateToken {
+            user_id,
+            client,
+            access_token,
+            refresh_token,
+        } = event;
+        self.issue(
+            SessionId::new(),
+            SessionEntry {
+                user_id,
+                access_token,
+                refresh_token,
+                user_agent: client.user_agent,
+                ip_address: client.ip_address,
+                created_at: Utc::now(),
+            },
+        )
+        .await
+    }
+    async fn rotate_token(&self, event: RotateToken) -> AppResult<AuthTokens> {
+        let Some(SessionRef(session_id)) = self
+            .kv
+            .get(&RefreshTokenKey::from(&event.refresh_token))
+            .await?
+        else {
+            return Err(AppError::UnauthorizedError);
+        };
+        let Some(session) = self.kv.get(&SessionKey(session_id)).await? else {
+            return Err(AppError::UnauthorizedError);
+        };
+
+        let first_use = self
+            .kv
+            .set_nx_ex(
+                &UsedRefreshTokenKey::from(&event.refresh_token),
+                &SessionRef(session_id),
+                self.refresh_ttl,
+            )
+            .await?;
+        if !first_use || session.refresh_token != event.refresh_token.0 {
+            // an exchanged token is presented again, so it has leaked to someone;
+            // whoever holds the current token loses the session as well
+            self.revoke_session(session_id, &session).await?;
+            return Err(AppError::UnauthorizedError);
+        }
+
+        self.kv
+            .delete(&AuthorizationKey::from(session.access_token.as_str()))
+            .await?;
+        self.issue(
+            session_id,
+            SessionEntry {
+                access_token: event.access_token,
+                refresh_token: event.next_refresh_token,
+                ..session
+            },
+        )
+        .await
     }
     async fn delete_token(&self, access_token: AccessToken) -> AppResult<()> {
         let key: AuthorizationKey = access_token.into();
+        if let Some(AuthorizedSession {
+            session_id: Some(session_id),
+            ..
+        }) = self.kv.get(&key).await?
+            && let Some(session) = self.kv.get(&SessionKey(session_id)).await?
+        {
+            self.revoke_session(session_id, &session).await?;
+        }
         self.kv.delete(&key).await
     }
+    async fn find_sessions(&self, user_id: UserId) -> AppResult<Vec<Session>> {
+        let index = UserSessionsKey(user_id);
+        let mut sessions = Vec::new();
+        for SessionRef(session_id) in self.kv.set_members(&index).await? {
+            match self.kv.get(&SessionKey(session_id)).await? {
+                Some(entry) => {
+                    let last_used_at = self.kv.get(&SessionLastUsedKey(session_id)).await?;
+                    sessions.push(entry.into_session(session_id, last_used_at));
+                }
+                // the session expired after it was indexed
+                None => {
+                    self.kv
+                        .remove_from_set(&index, &SessionRef(session_id))
+                        .await?
+                }
+            }
+        }
+        sessions.sort_by_key(|s| Reverse(s.last_used_at));
+        Ok(sessions)
+    }
+    async fn delete_session(&self, event: DeleteSession) -> AppResult<()> {
+        match self.kv.get(&SessionKey(event.session_id)).await? {
+            Some(session) if session.user_id == event.user_id => {
+                self.revoke_session(event.session_id, &session).await
+            }
+            _ => Err(AppError::EntityNotFound(
+                "specified session not found".into(),
+            )),
+        }
+    }
+    async fn delete_all_sessions(&self, user_id: UserId) -> AppResult<()> {
+        let index = UserSessionsKey(user_id);
+        for SessionRef(session_id) in self.kv.set_members(&index).await? {
+            if let Some(session) = self.kv.get(&SessionKey(session_id)).await? {
+                self.revoke_session(session_id, &session).await?;
+            }
+        }
+        self.kv.delete(&index).await
+    }
+}
+
+impl AuthRepositoryImpl {
+    async fn issue(&self, session_id: SessionId, session: SessionEntry) -> AppResult<AuthTokens> {
+        let user_id = session.user_id;
+        self.kv
+            .set_ex(
+                &AuthorizationKey::from(session.access_token.as_str()),
+                &AuthorizedSession {
+                    user_id,
+                    session_id: Some(session_id),
+                },
+                self.ttl,
+            )
+            .await?;
+        self.kv
+            .set_ex(
+                &RefreshTokenKey::from(session.refresh_token.as_str()),
+                &SessionRef(session_id),
+                self.refresh_ttl,
+            )
+            .await?;
+        self.kv
+            .set_ex(&SessionKey(session_id), &session, self.refresh_ttl)
+            .await?;
+        self.kv
+            .add_to_set(
+                &UserSessionsKey(user_id),
+                &SessionRef(session_id),
+                self.refresh_ttl,
+            )
+            .await?;
+
+        Ok(AuthTokens {
+            user_id,
+            access_token: AccessToken(session.access_token),
+            refresh_token: RefreshToken(session.refresh_token),
+            expires_in: self.ttl,
+        })
+    }
+
+    async fn revoke_session(&self, session_id: SessionId, session: &SessionEntry) -> AppResult<()> {
+        self.kv
+            .delete(&AuthorizationKey::from(session.access_token.as_str()))
+            .await?;
+        self.kv.delete(&SessionKey(session_id)).await?;
+        self.kv.delete(&SessionLastUsedKey(session_id)).await?;
+        self.kv
+            .remove_from_set(&UserSessionsKey(session.user_id), &SessionRef(session_id))
+            .await
+    }
+}
+
+// checked once the password is known to be right, so that only the user
+// learns why they cannot log in
+pub(crate) fn ensure_active(status: UserStatus) -> AppResult<()> {
+    let reason = match status {
+        UserStatus::Active => return Ok(()),
+        UserStatus::Unverified => "the email address is not verified yet",
+        UserStatus::PendingApproval => "the account is waiting for approval by an admin",
+        UserStatus::Rejected => "the account was not approved",
+    };
+    Err(AppError::AccountNotActive(reason.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::config::RedisConfig;
+
+    use crate::password::Argon2PasswordHasher;
+
+    use super::*;
+
+    #[sqlx::test]
+    async fn legacy_hash_is_rehashed_on_login(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let legacy_hash = bcrypt::hash("Pa55w0rd", 4)?;
+        let user_id = sqlx::query_scalar!(
+            r#"
+                INSERT INTO users (name, email, password_hash, role_id)
+                SELECT 'Reader', 'reader@example.com', $1, role_id FROM roles WHERE name = 'User'
+                RETURNING user_id
+            "#,
+            legacy_hash
+        )
+        .fetch_one(&pool)
+        .await?;
+        // verifying a user does not touch Redis, so nothing has to listen there
+        let kv = RedisClient::new(&RedisConfig {
+            host: "localhost".into(),
+            port: 6379,
+        })?;
+        let repo = AuthRepositoryImpl::new(
+            ConnectionPool::new(pool.clone()),
+            Arc::new(kv),
+            Arc::new(Argon2PasswordHasher::default()),
+            60,
+            600,
+        );
+
+        assert!(matches!(
+            repo.verify_user("reader@example.com", "wrong").await,
+            Err(AppError::UnauthorizedError)
+        ));
+        let stored = sqlx::query_scalar!("SELECT password_hash FROM users")
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(stored, legacy_hash);
+
+        assert_eq!(
+            repo.verify_user("reader@example.com", "Pa55w0rd").await?,
+            user_id.into()
+        );
+        let stored = sqlx::query_scalar!("SELECT password_hash FROM users")
+            .fetch_one(&pool)
+            .await?;
+        assert!(stored.starts_with("$argon2id$"));
+        assert_eq!(
+            repo.verify_user("reader@example.com", "Pa55w0rd").await?,
+            user_id.into()
+        );
+
+        Ok(())
+    }
 }
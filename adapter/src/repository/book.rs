@@ -1,23 +1,35 @@
 use std::collections::HashMap;
 
 use async_trait::async_trait;
+use chrono::Utc;
 use derive_new::new;
 use kernel::{
     model::{
+        audit::{AuditAction, AuditTarget, event::RecordAudit},
         book::{
-            Book, BookListOptions, Checkout,
-            event::{CreateBook, DeleteBook, UpdateBook},
+            Book, BookCopy, BookListOptions, BookSort, CopyCondition,
+            event::{
+                CreateBook, CreateBookCopy, DeleteBook, DeleteBookCopy, UpdateBook, UpdateBookCopy,
+            },
         },
-        id::{BookId, UserId},
-        list::PaginatedList,
+        id::{BookCopyId, BookId, CheckoutId, UserId},
+        list::{CursorDirection, CursorPaginatedList, PaginatedList},
+        webhook::WebhookEventType,
     },
     repository::book::BookRepository,
 };
 use shared::error::{AppError, AppResult};
 
-use crate::database::{
-    ConnectionPool,
-    model::book::{BookCheckoutRow, BookRow, PaginatedBookRow},
+use crate::{
+    database::{
+        ConnectionPool,
+        model::book::{BookCopyRow, BookKeysetRow, BookOwnerRow, BookRow, PaginatedBookRow},
+    },
+    repository::{
+        audit::{book_snapshot, copy_snapshot, record_audit},
+        set_transaction_serializable,
+        webhook::queue_webhook_event,
+    },
 };
 
 #[derive(new)]
@@ -27,25 +39,141 @@ pub struct BookRepositoryImpl {
 
 #[async_trait]
 impl BookRepository for BookRepositoryImpl {
-    async fn create(&self, event: CreateBook, user_id: UserId) -> AppResult<()> {
+    async fn create(&self, event: CreateBook, user_id: UserId) -> AppResult<BookId> {
+        let mut tx = self.db.begin().await?;
+        set_transaction_serializable(&mut tx).await?;
+
+        let existing = sqlx::query_as!(
+            BookOwnerRow,
+            r#"
+                SELECT book_id, user_id FROM books
+                WHERE isbn = $1
+                ORDER BY created_at ASC
+                LIMIT 1
+            "#,
+            event.isbn.as_str()
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        if let Some(existing) = existing {
+            if !event.add_copy || existing.user_id != user_id {
+                return Err(AppError::Conflict(format!(
+                    "Book with ISBN {} already exists: {}",
+                    event.isbn, existing.book_id
+                )));
+            }
+
+            let copy_id = BookCopyId::new();
+            let barcode = default_barcode(copy_id);
+            insert_copy(
+                &mut tx,
+                copy_id,
+                existing.book_id,
+                &barcode,
+                CopyCondition::default(),
+                "",
+            )
+            .await?;
+            record_audit(
+                &mut tx,
+                RecordAudit::new(
+                    Some(user_id),
+                    AuditAction::Create,
+                    AuditTarget::book_copy(copy_id),
+                    None,
+                    copy_snapshot(
+                        existing.book_id,
+                        &barcode,
+                        CopyCondition::default().as_ref(),
+                        "",
+                    ),
+                ),
+            )
+            .await?;
+
+            tx.commit().await.map_err(AppError::TransactionError)?;
+
+            return Ok(existing.book_id);
+        }
+
+        let book_id = BookId::new();
         sqlx::query!(
             r#"
-                INSERT INTO books (title, author, isbn, description, user_id)
-                VALUES ($1, $2, $3, $4, $5)
+                INSERT INTO books (book_id, title, author, isbn, description, user_id)
+                VALUES ($1, $2, $3, $4, $5, $6)
             "#,
+            book_id as _,
             event.title,
             event.author,
-            event.isbn,
+            event.isbn.as_str(),
             event.description,
             user_id as _
         )
-        .execute(self.db.inner_ref())
+        .execute(&mut *tx)
         .await
         .map_err(AppError::SpecificOperationError)?;
-        Ok(())
+
+        // a new title starts with a single copy
+        let copy_id = BookCopyId::new();
+        insert_copy(
+            &mut tx,
+            copy_id,
+            book_id,
+            &default_barcode(copy_id),
+            CopyCondition::default(),
+            "",
+        )
+        .await?;
+
+        record_audit(
+            &mut tx,
+            RecordAudit::new(
+                Some(user_id),
+                AuditAction::Create,
+                AuditTarget::book(book_id),
+                None,
+                book_snapshot(
+                    &event.title,
+                    &event.author,
+                    event.isbn.as_str(),
+                    &event.description,
+                    user_id,
+                ),
+            ),
+        )
+        .await?;
+        queue_webhook_event(
+            &mut tx,
+            WebhookEventType::BookCreated,
+            serde_json::json!({
+                "bookId": book_id,
+                "title": event.title,
+                "author": event.author,
+                "isbn": event.isbn.as_str(),
+                "description": event.description,
+                "ownerId": user_id,
+            }),
+            Utc::now(),
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(book_id)
     }
     async fn find_all(&self, options: BookListOptions) -> AppResult<PaginatedList<Book>> {
-        let BookListOptions { limit, offset } = options;
+        let BookListOptions {
+            limit,
+            offset,
+            keyword,
+            owner,
+            checked_out,
+            sort,
+            ..
+        } = options;
+        let pattern = keyword.as_deref().map(like_pattern);
         let rows: Vec<PaginatedBookRow> = sqlx::query_as!(
             PaginatedBookRow,
             r#"
@@ -53,55 +181,41 @@ impl BookRepository for BookRepositoryImpl {
                     COUNT(*) OVER() AS "total!",
                         b.book_id AS id
                 FROM books AS b
-                ORDER BY b.created_at DESC
+                WHERE
+                    ($3::text IS NULL
+                        OR b.title ILIKE $3
+                        OR b.author ILIKE $3
+                        OR b.description ILIKE $3
+                        OR b.isbn ILIKE $3)
+                    AND ($4::uuid IS NULL OR b.user_id = $4)
+                    AND ($5::bool IS NULL
+                        OR NOT EXISTS (
+                            SELECT 1 FROM book_copies AS bc
+                            WHERE bc.book_id = b.book_id
+                                AND NOT EXISTS (SELECT 1 FROM checkouts AS c WHERE c.copy_id = bc.copy_id)
+                        ) = $5)
+                ORDER BY
+                    CASE WHEN $6 = 'title' THEN b.title END ASC,
+                    CASE WHEN $6 = 'author' THEN b.author END ASC,
+                    CASE WHEN $6 = 'oldest' THEN b.created_at END ASC,
+                    b.created_at DESC,
+                    b.book_id
                 LIMIT $1 OFFSET $2
             "#,
             limit as _,
-            offset as _
+            offset as _,
+            pattern,
+            owner as _,
+            checked_out,
+            sort.as_ref()
         )
         .fetch_all(self.db.inner_ref())
         .await
         .map_err(AppError::SpecificOperationError)?;
 
         let total = rows.first().map(|r| r.total).unwrap_or_default();
-        let book_ids = rows
-            .first()
-            .into_iter()
-            .map(|r| r.id)
-            .collect::<Vec<BookId>>();
-
-        let rows: Vec<BookRow> = sqlx::query_as!(
-            BookRow,
-            r#"
-                SELECT 
-                    b.book_id AS book_id,
-                    b.title AS title,
-                    b.author AS author,
-                    b.isbn AS isbn,
-                    b.description AS description,
-                    u.user_id AS owned_by,
-                    u.name AS owner_name
-                FROM books AS b
-                INNER JOIN users AS u USING(user_id)
-                WHERE b.book_id IN (SELECT * FROM UNNEST($1::uuid[]))
-                ORDER BY b.created_at DESC
-            "#,
-            &book_ids as _
-        )
-        .fetch_all(self.db.inner_ref())
-        .await
-        .map_err(AppError::SpecificOperationError)?;
-
-        let book_ids = rows.iter().map(|r| r.book_id).collect::<Vec<BookId>>();
-        let mut checkouts = self.find_checkouts(&book_ids).await?;
-
-        let items = rows
-            .into_iter()
-            .map(|row| {
-                let checkout = checkouts.remove(&row.book_id);
-                row.into_book(checkout)
-            })
-            .collect();
+        let book_ids = rows.iter().map(|r| r.id).collect::<Vec<BookId>>();
+        let items = self.find_by_ids(&book_ids).await?;
 
         Ok(PaginatedList {
             total,
@@ -110,6 +224,116 @@ impl BookRepository for BookRepositoryImpl {
             items,
         })
     }
+    async fn find_all_by_cursor(
+        &self,
+        options: BookListOptions,
+    ) -> AppResult<CursorPaginatedList<Book>> {
+        let BookListOptions {
+            limit,
+            keyword,
+            owner,
+            checked_out,
+            sort,
+            cursor,
+            ..
+        } = options;
+        let descending = match sort {
+            BookSort::Newest => true,
+            BookSort::Oldest => false,
+            BookSort::Title | BookSort::Author => {
+                return Err(AppError::InvalidCursor(
+                    "cursor pagination is only available when sorting by newest or oldest".into(),
+                ));
+            }
+        };
+        // reading the previous page walks the keyset the other way round
+        let descending = descending == cursor.is_none_or(|c| c.direction == CursorDirection::Next);
+        let pattern = keyword.as_deref().map(like_pattern);
+        let (after_at, after_id) = cursor.map(|c| (c.timestamp, c.id)).unzip();
+
+        let rows: Vec<BookKeysetRow> = if descending {
+            sqlx::query_as!(
+                BookKeysetRow,
+                r#"
+                    SELECT b.book_id AS id, b.created_at
+                    FROM books AS b
+                    WHERE
+                        ($2::text IS NULL
+                            OR b.title ILIKE $2
+                            OR b.author ILIKE $2
+                            OR b.description ILIKE $2
+                            OR b.isbn ILIKE $2)
+                        AND ($3::uuid IS NULL OR b.user_id = $3)
+                        AND ($4::bool IS NULL
+                            OR NOT EXISTS (
+                            SELECT 1 FROM book_copies AS bc
+                            WHERE bc.book_id = b.book_id
+                                AND NOT EXISTS (SELECT 1 FROM checkouts AS c WHERE c.copy_id = bc.copy_id)
+                        ) = $4)
+                        AND ($5::timestamptz IS NULL OR (b.created_at, b.book_id) < ($5, $6::uuid))
+                    ORDER BY b.created_at DESC, b.book_id DESC
+                    LIMIT $1
+                "#,
+                limit + 1,
+                pattern,
+                owner as _,
+                checked_out,
+                after_at,
+                after_id
+            )
+            .fetch_all(self.db.inner_ref())
+            .await
+        } else {
+            sqlx::query_as!(
+                BookKeysetRow,
+                r#"
+                    SELECT b.book_id AS id, b.created_at
+                    FROM books AS b
+                    WHERE
+                        ($2::text IS NULL
+                            OR b.title ILIKE $2
+                            OR b.author ILIKE $2
+                            OR b.description ILIKE $2
+                            OR b.isbn ILIKE $2)
+                        AND ($3::uuid IS NULL OR b.user_id = $3)
+                        AND ($4::bool IS NULL
+                            OR NOT EXISTS (
+                            SELECT 1 FROM book_copies AS bc
+                            WHERE bc.book_id = b.book_id
+                                AND NOT EXISTS (SELECT 1 FROM checkouts AS c WHERE c.copy_id = bc.copy_id)
+                        ) = $4)
+                        AND ($5::timestamptz IS NULL OR (b.created_at, b.book_id) > ($5, $6::uuid))
+                    ORDER BY b.created_at ASC, b.book_id ASC
+                    LIMIT $1
+                "#,
+                limit + 1,
+                pattern,
+                owner as _,
+                checked_out,
+                after_at,
+                after_id
+            )
+            .fetch_all(self.db.inner_ref())
+            .await
+        }
+        .map_err(AppError::SpecificOperationError)?;
+
+        let CursorPaginatedList {
+            limit,
+            next_cursor,
+            prev_cursor,
+            items,
+        } = CursorPaginatedList::from_rows(rows, limit, cursor, |r| (r.created_at, r.id.raw()));
+        let book_ids = items.iter().map(|r| r.id).collect::<Vec<BookId>>();
+        let items = self.find_by_ids(&book_ids).await?;
+
+        Ok(CursorPaginatedList {
+            limit,
+            next_cursor,
+            prev_cursor,
+            items,
+        })
+    }
     async fn find_by_id(&self, book_id: BookId) -> AppResult<Option<Book>> {
         let row: Option<BookRow> = sqlx::query_as!(
             BookRow,
@@ -133,75 +357,373 @@ impl BookRepository for BookRepositoryImpl {
         .map_err(AppError::SpecificOperationError)?;
         match row {
             Some(row) => {
-                let checkout = self.find_checkouts(&[book_id]).await?.remove(&row.book_id);
-                Ok(Some(row.into_book(checkout)))
+                let copies = self
+                    .find_copies_by_book_ids(&[book_id])
+                    .await?
+                    .remove(&book_id);
+                Ok(Some(row.into_book(copies.unwrap_or_default())))
             }
             None => Ok(None),
         }
     }
     async fn update(&self, event: UpdateBook) -> AppResult<()> {
-        let res = sqlx::query!(
+        let mut tx = self.db.begin().await?;
+        set_transaction_serializable(&mut tx).await?;
+
+        let duplicate = sqlx::query_scalar!(
             r#"
-                UPDATE books
+                SELECT book_id AS "book_id: BookId" FROM books
+                WHERE isbn = $1 AND book_id <> $2
+                ORDER BY created_at ASC
+                LIMIT 1
+            "#,
+            event.isbn.as_str(),
+            event.book_id as _
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        if let Some(book_id) = duplicate {
+            return Err(AppError::Conflict(format!(
+                "Book with ISBN {} already exists: {}",
+                event.isbn, book_id
+            )));
+        }
+
+        // `old` is read before the update, so it still has the previous values
+        let before = sqlx::query!(
+            r#"
+                UPDATE books AS b
                 SET
                     title = $1,
                     author = $2,
                     isbn = $3,
                     description = $4,
                     updated_at = CURRENT_TIMESTAMP(3)
-                WHERE book_id = $5 AND user_id = $6
+                FROM books AS old
+                WHERE b.book_id = $5 AND old.book_id = b.book_id AND (b.user_id = $6 OR $7)
+                RETURNING
+                    old.title AS "title!",
+                    old.author AS "author!",
+                    old.isbn AS "isbn!",
+                    old.description AS "description!",
+                    old.user_id AS "owner!: UserId"
             "#,
             event.title,
             event.author,
-            event.isbn,
+            event.isbn.as_str(),
             event.description,
             event.book_id as _,
-            event.requested_user as _
+            event.requested_user as _,
+            event.manages_books
         )
-        .execute(self.db.inner_ref())
+        .fetch_optional(&mut *tx)
         .await
-        .map_err(AppError::SpecificOperationError)?;
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| AppError::EntityNotFound("specified book not found".into()))?;
+
+        record_audit(
+            &mut tx,
+            RecordAudit::new(
+                Some(event.requested_user),
+                AuditAction::Update,
+                AuditTarget::book(event.book_id),
+                book_snapshot(
+                    &before.title,
+                    &before.author,
+                    &before.isbn,
+                    &before.description,
+                    before.owner,
+                ),
+                book_snapshot(
+                    &event.title,
+                    &event.author,
+                    event.isbn.as_str(),
+                    &event.description,
+                    before.owner,
+                ),
+            ),
+        )
+        .await?;
+
+        queue_webhook_event(
+            &mut tx,
+            WebhookEventType::BookUpdated,
+            serde_json::json!({
+                "bookId": event.book_id,
+                "title": event.title,
+                "author": event.author,
+                "isbn": event.isbn.as_str(),
+                "description": event.description,
+            }),
+            Utc::now(),
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
 
-        if res.rows_affected() < 1 {
-            return Err(AppError::EntityNotFound("specified book not found".into()));
-        }
         Ok(())
     }
-    async fn delete(&self, _event: DeleteBook) -> AppResult<()> {
-        let res = sqlx::query!(
+    async fn delete(&self, event: DeleteBook) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+        let before = sqlx::query!(
             r#"
                 DELETE FROM books
-                WHERE book_id = $1 AND user_id = $2
+                WHERE book_id = $1 AND (user_id = $2 OR $3)
+                RETURNING title, author, isbn, description, user_id AS "owner: UserId"
+            "#,
+            event.book_id as _,
+            event.requested_user as _,
+            event.manages_books
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| AppError::EntityNotFound("specified book not found".into()))?;
+
+        record_audit(
+            &mut tx,
+            RecordAudit::new(
+                Some(event.requested_user),
+                AuditAction::Delete,
+                AuditTarget::book(event.book_id),
+                book_snapshot(
+                    &before.title,
+                    &before.author,
+                    &before.isbn,
+                    &before.description,
+                    before.owner,
+                ),
+                None,
+            ),
+        )
+        .await?;
+
+        queue_webhook_event(
+            &mut tx,
+            WebhookEventType::BookDeleted,
+            serde_json::json!({ "bookId": event.book_id }),
+            Utc::now(),
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(())
+    }
+    async fn create_copy(&self, event: CreateBookCopy) -> AppResult<BookCopyId> {
+        let mut tx = self.db.begin().await?;
+        self.ensure_owner(
+            &mut tx,
+            event.book_id,
+            event.requested_user,
+            event.manages_books,
+        )
+        .await?;
+
+        let copy_id = BookCopyId::new();
+        let barcode = event.barcode.unwrap_or_else(|| default_barcode(copy_id));
+        insert_copy(
+            &mut tx,
+            copy_id,
+            event.book_id,
+            &barcode,
+            event.condition,
+            &event.shelf_location,
+        )
+        .await?;
+        record_audit(
+            &mut tx,
+            RecordAudit::new(
+                Some(event.requested_user),
+                AuditAction::Create,
+                AuditTarget::book_copy(copy_id),
+                None,
+                copy_snapshot(
+                    event.book_id,
+                    &barcode,
+                    event.condition.as_ref(),
+                    &event.shelf_location,
+                ),
+            ),
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(copy_id)
+    }
+    async fn find_copies(&self, book_id: BookId) -> AppResult<Vec<BookCopy>> {
+        Ok(self
+            .find_copies_by_book_ids(&[book_id])
+            .await?
+            .remove(&book_id)
+            .unwrap_or_default())
+    }
+    async fn update_copy(&self, event: UpdateBookCopy) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+        self.ensure_owner(
+            &mut tx,
+            event.book_id,
+            event.requested_user,
+            event.manages_books,
+        )
+        .await?;
+
+        let barcode_taken = sqlx::query_scalar!(
+            r#"
+                SELECT EXISTS (
+                    SELECT 1 FROM book_copies WHERE barcode = $1 AND copy_id <> $2
+                ) AS "taken!"
             "#,
-            _event.book_id as _,
-            _event.requested_user as _
+            event.barcode,
+            event.copy_id as _
         )
-        .execute(self.db.inner_ref())
+        .fetch_one(&mut *tx)
         .await
         .map_err(AppError::SpecificOperationError)?;
+        if barcode_taken {
+            return Err(AppError::UnprocessableEntity(format!(
+                "Barcode {} is already in use",
+                event.barcode
+            )));
+        }
 
-        if res.rows_affected() < 1 {
-            return Err(AppError::EntityNotFound("specified book not found".into()));
+        let before = sqlx::query!(
+            r#"
+                UPDATE book_copies AS bc
+                SET barcode = $1, condition = $2, shelf_location = $3
+                FROM book_copies AS old
+                WHERE bc.copy_id = $4 AND bc.book_id = $5 AND old.copy_id = bc.copy_id
+                RETURNING
+                    old.barcode AS "barcode!",
+                    old.condition AS "condition!",
+                    old.shelf_location AS "shelf_location!"
+            "#,
+            event.barcode,
+            event.condition.as_ref(),
+            event.shelf_location,
+            event.copy_id as _,
+            event.book_id as _
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| AppError::EntityNotFound("specified book copy not found".into()))?;
+
+        record_audit(
+            &mut tx,
+            RecordAudit::new(
+                Some(event.requested_user),
+                AuditAction::Update,
+                AuditTarget::book_copy(event.copy_id),
+                copy_snapshot(
+                    event.book_id,
+                    &before.barcode,
+                    &before.condition,
+                    &before.shelf_location,
+                ),
+                copy_snapshot(
+                    event.book_id,
+                    &event.barcode,
+                    event.condition.as_ref(),
+                    &event.shelf_location,
+                ),
+            ),
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(())
+    }
+    async fn delete_copy(&self, event: DeleteBookCopy) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+        self.ensure_owner(
+            &mut tx,
+            event.book_id,
+            event.requested_user,
+            event.manages_books,
+        )
+        .await?;
+
+        let checked_out = sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM checkouts WHERE copy_id = $1) AS "exists!""#,
+            event.copy_id as _
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        if checked_out {
+            return Err(AppError::UnprocessableEntity(format!(
+                "Book copy with id {} is checked out",
+                event.copy_id
+            )));
         }
+
+        let before = sqlx::query!(
+            r#"
+                DELETE FROM book_copies
+                WHERE copy_id = $1 AND book_id = $2
+                RETURNING barcode, condition, shelf_location
+            "#,
+            event.copy_id as _,
+            event.book_id as _
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| AppError::EntityNotFound("specified book copy not found".into()))?;
+
+        record_audit(
+            &mut tx,
+            RecordAudit::new(
+                Some(event.requested_user),
+                AuditAction::Delete,
+                AuditTarget::book_copy(event.copy_id),
+                copy_snapshot(
+                    event.book_id,
+                    &before.barcode,
+                    &before.condition,
+                    &before.shelf_location,
+                ),
+                None,
+            ),
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
         Ok(())
     }
 }
 
+// escapes LIKE wildcards so the keyword is matched literally anywhere in a column
+fn like_pattern(keyword: &str) -> String {
+    let escaped = keyword
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
 impl BookRepositoryImpl {
-    async fn find_checkouts(&self, book_ids: &[BookId]) -> AppResult<HashMap<BookId, Checkout>> {
-        let res = sqlx::query_as!(
-            BookCheckoutRow,
+    // loads books in the order of `book_ids`
+    async fn find_by_ids(&self, book_ids: &[BookId]) -> AppResult<Vec<Book>> {
+        let mut rows: HashMap<BookId, BookRow> = sqlx::query_as!(
+            BookRow,
             r#"
                 SELECT 
-                    c.checkout_id,
-                    c.book_id,
-                    u.user_id,
-                    u.name AS user_name,
-                    c.checked_out_at
-                FROM checkouts AS c
+                    b.book_id AS book_id,
+                    b.title AS title,
+                    b.author AS author,
+                    b.isbn AS isbn,
+                    b.description AS description,
+                    u.user_id AS owned_by,
+                    u.name AS owner_name
+                FROM books AS b
                 INNER JOIN users AS u USING(user_id)
-                WHERE book_id = ANY($1)
-                ;
+                WHERE b.book_id IN (SELECT * FROM UNNEST($1::uuid[]))
             "#,
             book_ids as _
         )
@@ -209,50 +731,180 @@ impl BookRepositoryImpl {
         .await
         .map_err(AppError::SpecificOperationError)?
         .into_iter()
-        .map(|checkout| (checkout.book_id, Checkout::from(checkout)))
+        .map(|row| (row.book_id, row))
         .collect();
 
-        Ok(res)
+        let mut copies = self.find_copies_by_book_ids(book_ids).await?;
+
+        Ok(book_ids
+            .iter()
+            .filter_map(|id| rows.remove(id))
+            .map(|row| {
+                let copies = copies.remove(&row.book_id).unwrap_or_default();
+                row.into_book(copies)
+            })
+            .collect())
+    }
+
+    async fn find_copies_by_book_ids(
+        &self,
+        book_ids: &[BookId],
+    ) -> AppResult<HashMap<BookId, Vec<BookCopy>>> {
+        let rows = sqlx::query_as!(
+            BookCopyRow,
+            r#"
+                SELECT
+                    bc.copy_id,
+                    bc.book_id,
+                    bc.barcode,
+                    bc.condition,
+                    bc.shelf_location,
+                    c.checkout_id AS "checkout_id?: CheckoutId",
+                    u.user_id AS "user_id?: UserId",
+                    u.name AS "user_name?",
+                    c.checked_out_at AS "checked_out_at?"
+                FROM book_copies AS bc
+                LEFT OUTER JOIN checkouts AS c USING (copy_id)
+                LEFT OUTER JOIN users AS u ON u.user_id = c.user_id
+                WHERE bc.book_id = ANY($1)
+                ORDER BY bc.created_at ASC, bc.copy_id ASC
+            "#,
+            book_ids as _
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        let mut copies: HashMap<BookId, Vec<BookCopy>> = HashMap::new();
+        for row in rows {
+            let copy = BookCopy::try_from(row)?;
+            copies.entry(copy.book_id).or_default().push(copy);
+        }
+        Ok(copies)
+    }
+
+    // `manages_books` lets the user change books of others too
+    async fn ensure_owner(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        book_id: BookId,
+        user_id: UserId,
+        manages_books: bool,
+    ) -> AppResult<()> {
+        let owned = sqlx::query_scalar!(
+            r#"
+                SELECT EXISTS (
+                    SELECT 1 FROM books WHERE book_id = $1 AND (user_id = $2 OR $3)
+                ) AS "owned!"
+            "#,
+            book_id as _,
+            user_id as _,
+            manages_books
+        )
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        if !owned {
+            return Err(AppError::EntityNotFound("specified book not found".into()));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn default_barcode(copy_id: BookCopyId) -> String {
+    copy_id.to_string().to_uppercase()
+}
+
+async fn insert_copy(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    copy_id: BookCopyId,
+    book_id: BookId,
+    barcode: &str,
+    condition: CopyCondition,
+    shelf_location: &str,
+) -> AppResult<()> {
+    let barcode_taken = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM book_copies WHERE barcode = $1) AS "taken!""#,
+        barcode
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+    if barcode_taken {
+        return Err(AppError::UnprocessableEntity(format!(
+            "Barcode {barcode} is already in use"
+        )));
+    }
+
+    let res = sqlx::query!(
+        r#"
+            INSERT INTO book_copies (copy_id, book_id, barcode, condition, shelf_location)
+            VALUES ($1, $2, $3, $4, $5)
+        "#,
+        copy_id as _,
+        book_id as _,
+        barcode,
+        condition.as_ref(),
+        shelf_location
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    if res.rows_affected() < 1 {
+        return Err(AppError::NoRowsAffectedError(
+            "Failed to create book copy record".into(),
+        ));
     }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr;
+    use std::{str::FromStr, sync::Arc};
 
-    use kernel::{model::user::event::CreateUser, repository::user::UserRepository};
+    use kernel::{
+        model::user::{UserStatus, event::CreateUser},
+        repository::user::UserRepository,
+    };
 
-    use crate::repository::user::UserRepositoryImpl;
+    use crate::{password::Argon2PasswordHasher, repository::user::UserRepositoryImpl};
 
     use super::*;
 
     #[sqlx::test]
     async fn test_register_book(pool: sqlx::PgPool) -> anyhow::Result<()> {
-        sqlx::query!(r#"INSERT INTO roles(name) VALUES ('Admin'), ('User');"#)
-            .execute(&pool)
-            .await?;
-        let user_repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let user_repo = UserRepositoryImpl::new(
+            ConnectionPool::new(pool.clone()),
+            Arc::new(Argon2PasswordHasher::default()),
+        );
         let repo = BookRepositoryImpl::new(ConnectionPool::new(pool));
 
         let user = user_repo
             .create(CreateUser {
+                user_id: UserId::new(),
                 name: "Test User".into(),
                 email: "test@example.com".into(),
                 password: "test_passwod".into(),
+                status: UserStatus::Active,
+                created_by: None,
             })
             .await?;
 
         let book = CreateBook {
             title: "The Rust Programming Language".to_string(),
             author: "Steve Klabnik and Carol Nichols".to_string(),
-            isbn: "9781593278281".to_string(),
+            isbn: "978-1-59327-828-1".parse()?,
             description: "A comprehensive guide to Rust programming.".to_string(),
+            add_copy: false,
         };
         repo.create(book, user.id).await?;
 
         let options = BookListOptions {
             limit: 20,
             offset: 0,
+            ..Default::default()
         };
 
         let res = repo.find_all(options).await?;
@@ -294,9 +946,10 @@ mod tests {
             book_id,
             title: book.title.clone(),
             author: NEW_AUTHOR.to_string(),
-            isbn: book.isbn.clone(),
+            isbn: book.isbn.parse()?,
             description: book.description.clone(),
             requested_user: book.owner.id,
+            manages_books: false,
         };
         repo.update(update_book).await.unwrap();
 
@@ -304,4 +957,145 @@ mod tests {
         assert_eq!(book.author, NEW_AUTHOR);
         Ok(())
     }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_register_duplicate_isbn(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let book_id = BookId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4d")?;
+        let owner = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let duplicate = |add_copy| CreateBook {
+            title: "The Rust Programming Language".to_string(),
+            author: "Steve Klabnik and Carol Nichols".to_string(),
+            isbn: "1593278284".parse().unwrap(),
+            description: "".to_string(),
+            add_copy,
+        };
+
+        let res = repo.create(duplicate(false), owner).await;
+        assert!(matches!(res, Err(AppError::Conflict(m)) if m.contains(&book_id.to_string())));
+
+        repo.create(duplicate(true), owner).await?;
+        assert_eq!(repo.find_copies(book_id).await?.len(), 2);
+
+        let res = repo
+            .find_all(BookListOptions {
+                limit: 20,
+                ..Default::default()
+            })
+            .await?;
+        assert_eq!(res.total, 1);
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_search_books(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let owner = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        repo.create(
+            CreateBook {
+                title: "Programming Rust".to_string(),
+                author: "Jim Blandy".to_string(),
+                isbn: "9781492052593".parse()?,
+                description: "Fast, safe systems development.".to_string(),
+                add_copy: false,
+            },
+            owner,
+        )
+        .await?;
+
+        let search = |keyword: &str| BookListOptions {
+            limit: 20,
+            offset: 0,
+            keyword: Some(keyword.to_string()),
+            sort: BookSort::Title,
+            ..Default::default()
+        };
+
+        let res = repo.find_all(search("rust")).await?;
+        assert_eq!(res.total, 2);
+        assert_eq!(res.items[0].title, "Programming Rust");
+        assert_eq!(res.items[1].title, "The Rust Programming Language");
+
+        let res = repo.find_all(search("klabnik")).await?;
+        assert_eq!(res.total, 1);
+
+        let res = repo.find_all(search("9781492052593")).await?;
+        assert_eq!(res.total, 1);
+
+        let res = repo.find_all(search("100%")).await?;
+        assert_eq!(res.total, 0);
+
+        let res = repo
+            .find_all(BookListOptions {
+                limit: 20,
+                offset: 0,
+                checked_out: Some(true),
+                ..Default::default()
+            })
+            .await?;
+        assert_eq!(res.total, 0);
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_find_books_by_cursor(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let owner = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        for (days, title, isbn) in [
+            (1, "Second", "9781718503106"),
+            (2, "Third", "9781098122539"),
+        ] {
+            repo.create(
+                CreateBook {
+                    title: title.to_string(),
+                    author: "Author".to_string(),
+                    isbn: isbn.parse()?,
+                    description: "".to_string(),
+                    add_copy: false,
+                },
+                owner,
+            )
+            .await?;
+            sqlx::query!(
+                "UPDATE books SET created_at = NOW() + make_interval(days => $1) WHERE title = $2",
+                days,
+                title
+            )
+            .execute(&pool)
+            .await?;
+        }
+
+        let page = |cursor| BookListOptions {
+            limit: 2,
+            cursor,
+            ..Default::default()
+        };
+
+        let first = repo.find_all_by_cursor(page(None)).await?;
+        let titles = first
+            .items
+            .iter()
+            .map(|b| b.title.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(titles, ["Third", "Second"]);
+        assert!(first.prev_cursor.is_none());
+
+        let second = repo.find_all_by_cursor(page(first.next_cursor)).await?;
+        assert_eq!(second.items.len(), 1);
+        assert_eq!(second.items[0].title, "The Rust Programming Language");
+        assert!(second.next_cursor.is_none());
+
+        let back = repo.find_all_by_cursor(page(second.prev_cursor)).await?;
+        let titles = back
+            .items
+            .iter()
+            .map(|b| b.title.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(titles, ["Third", "Second"]);
+        assert!(back.prev_cursor.is_none());
+
+        Ok(())
+    }
 }
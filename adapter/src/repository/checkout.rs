@@ -1,25 +1,47 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
 use derive_new::new;
 use kernel::{
     model::{
+        audit::{AuditAction, AuditTarget, event::RecordAudit},
         checkout::{
             Checkout,
-            event::{CreateCheckout, UpdateReturned},
+            event::{CreateCheckout, RenewCheckout, UpdateReturned},
         },
-        id::{BookId, CheckoutId, UserId},
+        id::{BookCopyId, BookId, CheckoutId, UserId},
+        list::{CursorDirection, CursorListOptions, CursorPaginatedList},
+        notification::{NotificationKind, event::QueueNotification},
+        role::{BorrowingPolicy, BuiltinRole},
+        webhook::WebhookEventType,
     },
     repository::checkout::CheckoutRepository,
 };
-use shared::error::{AppError, AppResult};
+use shared::{
+    config::CheckoutConfig,
+    error::{AppError, AppResult, BorrowingRule},
+};
 
-use crate::database::{
-    ConnectionPool,
-    model::checkout::{CheckoutRow, CheckoutStateRow, ReturnedCheckoutRow},
+use crate::{
+    database::{
+        ConnectionPool,
+        model::checkout::{
+            BorrowerStateRow, CheckoutHistoryRow, CheckoutRow, CheckoutStateRow, CopyStateRow,
+            RenewalStateRow,
+        },
+    },
+    repository::{
+        audit::{checkout_snapshot, record_audit, renewal_snapshot, return_snapshot},
+        notification::queue_notification,
+        reservation::{count_unclaimed_copies, refresh_claims},
+        set_transaction_serializable,
+        webhook::queue_webhook_event,
+    },
 };
 
 #[derive(new)]
 pub struct CheckoutRepositoryImpl {
     db: ConnectionPool,
+    config: CheckoutConfig,
 }
 
 #[async_trait]
@@ -27,7 +49,7 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
     async fn create(&self, event: CreateCheckout) -> AppResult<()> {
         let mut tx = self.db.begin().await?;
 
-        self.set_transaction_serializable(&mut tx).await?;
+        set_transaction_serializable(&mut tx).await?;
         {
             // prerequirement check
             let res = sqlx::query_as!(
@@ -36,12 +58,14 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
                     SELECT
                         b.book_id,
                         c.checkout_id AS "checkout_id?: CheckoutId",
-                        NULL AS "user_id!: UserId"
+                        c.user_id AS "user_id?: UserId"
                     FROM books AS b
-                    LEFT OUTER JOIN checkouts AS c USING (book_id)
+                    LEFT OUTER JOIN checkouts AS c
+                        ON c.book_id = b.book_id AND c.user_id = $2
                     WHERE b.book_id = $1
                     "#,
-                event.book_id as _
+                event.book_id as _,
+                event.checked_out_by as _
             )
             .fetch_optional(&mut *tx)
             .await
@@ -59,7 +83,7 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
                     ..
                 }) => {
                     return Err(AppError::UnprocessableEntity(format!(
-                        "Book with id {} is already exist",
+                        "Book with id {} is already checked out by the requested user",
                         event.book_id
                     )));
                 }
@@ -67,16 +91,95 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
             }
         }
 
+        let borrower = sqlx::query_as!(
+            BorrowerStateRow,
+            r#"
+                SELECT
+                    r.name AS role_name,
+                    r.max_loans,
+                    r.block_when_overdue,
+                    r.block_own_books,
+                    (SELECT COUNT(*) FROM checkouts AS c WHERE c.user_id = u.user_id)
+                        AS "loan_count!",
+                    EXISTS (
+                        SELECT 1 FROM checkouts AS c
+                        WHERE c.user_id = u.user_id AND c.due_at < $3
+                    ) AS "has_overdue!",
+                    EXISTS (
+                        SELECT 1 FROM books AS b
+                        WHERE b.book_id = $2 AND b.user_id = u.user_id
+                    ) AS "owns_book!"
+                FROM users AS u
+                INNER JOIN roles AS r USING (role_id)
+                WHERE u.user_id = $1
+            "#,
+            event.checked_out_by as _,
+            event.book_id as _,
+            event.checked_out_at
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| {
+            AppError::EntityNotFound(format!("User with id {} not found", event.checked_out_by))
+        })?;
+        enforce_borrowing_policy(
+            &borrower.policy(),
+            borrower.loan_count,
+            borrower.has_overdue,
+            borrower.owns_book,
+        )?;
+
+        refresh_claims(
+            &mut tx,
+            event.book_id,
+            event.checked_out_at,
+            claim_period(&self.config),
+        )
+        .await?;
+        let holds_claim = sqlx::query_scalar!(
+            r#"
+                SELECT EXISTS (
+                    SELECT 1 FROM reservations
+                    WHERE book_id = $1 AND user_id = $2 AND claim_expires_at > $3
+                ) AS "exists!"
+            "#,
+            event.book_id as _,
+            event.checked_out_by as _,
+            event.checked_out_at
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        if !holds_claim
+            && count_unclaimed_copies(&mut tx, event.book_id, event.checked_out_at).await? <= 0
+        {
+            return Err(AppError::UnprocessableEntity(format!(
+                "No copy of book with id {} is available for the requested user",
+                event.book_id
+            )));
+        }
+
+        let copy_id = self
+            .find_available_copy(&mut tx, event.book_id, event.copy_id)
+            .await?;
+
+        let due_at = event.checked_out_at + loan_period(&self.config, &borrower.role_name);
+
         let checkout_id = CheckoutId::new();
         let res = sqlx::query!(
             r#"
-                INSERT INTO checkouts (checkout_id, book_id, user_id, checked_out_at)
-                VALUES ($1, $2, $3, $4)
+                INSERT INTO checkouts
+                    (checkout_id, book_id, copy_id, user_id, issued_by, checked_out_at, due_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
             "#,
             checkout_id as _,
             event.book_id as _,
+            copy_id as _,
             event.checked_out_by as _,
-            event.checked_out_at
+            event.issued_by as _,
+            event.checked_out_at,
+            due_at
         )
         .execute(&mut *tx)
         .await
@@ -87,15 +190,74 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
                 "Failed to create checkout record".into(),
             ));
         }
+
+        // the borrower's own place in the queue is fulfilled by this checkout
+        sqlx::query!(
+            r#"
+                DELETE FROM reservations
+                WHERE book_id = $1 AND user_id = $2
+            "#,
+            event.book_id as _,
+            event.checked_out_by as _
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        queue_notification(
+            &mut tx,
+            &QueueNotification::new(
+                event.checked_out_by,
+                NotificationKind::CheckoutCreated,
+                event.book_id,
+                Some(due_at),
+                None,
+                event.checked_out_at,
+            ),
+        )
+        .await?;
+        record_audit(
+            &mut tx,
+            RecordAudit::new(
+                Some(event.issued_by),
+                AuditAction::Create,
+                AuditTarget::checkout(checkout_id),
+                None,
+                checkout_snapshot(
+                    event.book_id,
+                    copy_id,
+                    event.checked_out_by,
+                    event.issued_by,
+                    event.checked_out_at,
+                    due_at,
+                ),
+            ),
+        )
+        .await?;
+        queue_webhook_event(
+            &mut tx,
+            WebhookEventType::CheckoutCreated,
+            serde_json::json!({
+                "checkoutId": checkout_id,
+                "bookId": event.book_id,
+                "copyId": copy_id,
+                "userId": event.checked_out_by,
+                "issuedBy": event.issued_by,
+                "checkedOutAt": event.checked_out_at,
+                "dueAt": due_at,
+            }),
+            event.checked_out_at,
+        )
+        .await?;
         tx.commit().await.map_err(AppError::TransactionError)?;
 
         Ok(())
     }
     async fn update_returned(&self, event: UpdateReturned) -> AppResult<()> {
         let mut tx = self.db.begin().await?;
-        self.set_transaction_serializable(&mut tx).await?;
+        set_transaction_serializable(&mut tx).await?;
 
-        {
+        let borrower = {
             // prerequirement check
             let res = sqlx::query_as!(
                 CheckoutStateRow,
@@ -103,12 +265,14 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
                     SELECT
                         b.book_id,
                         c.checkout_id AS "checkout_id?: CheckoutId",
-                        NULL AS "user_id!: UserId"
+                        c.user_id AS "user_id?: UserId"
                     FROM books AS b
-                    LEFT OUTER JOIN checkouts AS c USING (book_id)
+                    LEFT OUTER JOIN checkouts AS c
+                        ON c.book_id = b.book_id AND c.checkout_id = $2
                     WHERE b.book_id = $1
                     "#,
-                event.book_id as _
+                event.book_id as _,
+                event.checkout_id as _
             )
             .fetch_optional(&mut *tx)
             .await
@@ -122,31 +286,37 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
                     )));
                 }
                 Some(CheckoutStateRow {
-                    checkout_id: Some(c),
+                    checkout_id: Some(_),
                     user_id: Some(u),
                     ..
-                }) if (c, u) != (event.checkout_id, event.returned_by) => {
+                }) if u == event.returned_by || event.manages_checkouts => u,
+                _ => {
                     return Err(AppError::UnprocessableEntity(format!(
                         "Specified checkout record is invalid: checkout_id={}, book_id={}, returned_by={}",
                         event.checkout_id, event.book_id, event.returned_by
                     )));
                 }
-                _ => {}
             }
-        }
+        };
 
         let res = sqlx::query!(
             r#"
-                INSERT INTO returned_checkouts (checkout_id, book_id, user_id, checked_out_at, returned_at)
-                SELECT checkout_id, book_id, user_id, checked_out_at, $1
+                INSERT INTO returned_checkouts
+                    (checkout_id, book_id, copy_id, user_id, issued_by, checked_out_at, due_at,
+                        renewal_count, returned_at, returned_by, return_reason)
+                SELECT checkout_id, book_id, copy_id, user_id, issued_by, checked_out_at, due_at,
+                    renewal_count, $1, $3, $4
                 FROM checkouts
                 WHERE checkout_id = $2;
             "#,
             event.returned_at,
-            event.checkout_id as _
+            event.checkout_id as _,
+            event.returned_by as _,
+            event.reason
         )
         .execute(&mut *tx)
-            .await.map_err(AppError::SpecificOperationError)?;
+        .await
+        .map_err(AppError::SpecificOperationError)?;
 
         if res.rows_affected() < 1 {
             return Err(AppError::NoRowsAffectedError(
@@ -171,6 +341,153 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
             ));
         }
 
+        queue_notification(
+            &mut tx,
+            &QueueNotification::new(
+                borrower,
+                NotificationKind::CheckoutReturned,
+                event.book_id,
+                None,
+                None,
+                event.returned_at,
+            ),
+        )
+        .await?;
+        record_audit(
+            &mut tx,
+            RecordAudit::new(
+                Some(event.returned_by),
+                AuditAction::Return,
+                AuditTarget::checkout(event.checkout_id),
+                None,
+                return_snapshot(
+                    event.returned_by,
+                    event.returned_at,
+                    event.reason.as_deref(),
+                ),
+            ),
+        )
+        .await?;
+        queue_webhook_event(
+            &mut tx,
+            WebhookEventType::CheckoutReturned,
+            serde_json::json!({
+                "checkoutId": event.checkout_id,
+                "bookId": event.book_id,
+                "userId": borrower,
+                "returnedBy": event.returned_by,
+                "returnedAt": event.returned_at,
+                "reason": event.reason,
+            }),
+            event.returned_at,
+        )
+        .await?;
+
+        refresh_claims(
+            &mut tx,
+            event.book_id,
+            event.returned_at,
+            claim_period(&self.config),
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(())
+    }
+    async fn renew(&self, event: RenewCheckout) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+        set_transaction_serializable(&mut tx).await?;
+
+        let row = sqlx::query_as!(
+            RenewalStateRow,
+            r#"
+                SELECT
+                    c.checkout_id,
+                    c.book_id,
+                    c.user_id,
+                    r.name AS role_name,
+                    c.due_at,
+                    c.renewal_count
+                FROM checkouts AS c
+                INNER JOIN users AS u USING (user_id)
+                INNER JOIN roles AS r USING (role_id)
+                WHERE c.checkout_id = $1
+            "#,
+            event.checkout_id as _
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        let row = match row {
+            None => {
+                return Err(AppError::EntityNotFound(format!(
+                    "Checkout with id {} not found",
+                    event.checkout_id
+                )));
+            }
+            Some(r) if (r.book_id, r.user_id) != (event.book_id, event.renewed_by) => {
+                return Err(AppError::UnprocessableEntity(format!(
+                    "Specified checkout record is invalid: checkout_id={}, book_id={}, renewed_by={}",
+                    event.checkout_id, event.book_id, event.renewed_by
+                )));
+            }
+            Some(r) if r.renewal_count >= self.config.max_renewals => {
+                return Err(AppError::UnprocessableEntity(format!(
+                    "Checkout with id {} has reached the renewal limit of {}",
+                    event.checkout_id, self.config.max_renewals
+                )));
+            }
+            Some(r) => r,
+        };
+
+        let reserved = sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM reservations WHERE book_id = $1) AS "exists!""#,
+            event.book_id as _
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        if reserved {
+            return Err(AppError::UnprocessableEntity(format!(
+                "Book with id {} is reserved by another user and cannot be renewed",
+                event.book_id
+            )));
+        }
+
+        let due_at = event.renewed_at + loan_period(&self.config, &row.role_name);
+        let res = sqlx::query!(
+            r#"
+                UPDATE checkouts
+                SET due_at = $1, renewal_count = renewal_count + 1
+                WHERE checkout_id = $2
+            "#,
+            due_at,
+            row.checkout_id as _
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        if res.rows_affected() < 1 {
+            return Err(AppError::NoRowsAffectedError(
+                "Failed to renew checkout record".into(),
+            ));
+        }
+
+        record_audit(
+            &mut tx,
+            RecordAudit::new(
+                Some(event.renewed_by),
+                AuditAction::Renew,
+                AuditTarget::checkout(row.checkout_id),
+                renewal_snapshot(row.due_at, row.renewal_count),
+                renewal_snapshot(due_at, row.renewal_count + 1),
+            ),
+        )
+        .await?;
+
         tx.commit().await.map_err(AppError::TransactionError)?;
 
         Ok(())
@@ -182,8 +499,12 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
                 SELECT 
                     c.checkout_id,
                     c.book_id,
+                    c.copy_id,
                     c.user_id,
+                    c.issued_by,
                     c.checked_out_at,
+                    c.due_at,
+                    c.renewal_count,
                     b.title,
                     b.author,
                     b.isbn
@@ -204,8 +525,12 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
                 SELECT 
                     c.checkout_id,
                     c.book_id,
+                    c.copy_id,
                     c.user_id,
+                    c.issued_by,
                     c.checked_out_at,
+                    c.due_at,
+                    c.renewal_count,
                     b.title,
                     b.author,
                     b.isbn
@@ -221,76 +546,500 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
         .map(|rows| rows.into_iter().map(Checkout::from).collect())
         .map_err(AppError::SpecificOperationError)
     }
-    async fn find_history_by_book_id(&self, book_id: BookId) -> AppResult<Vec<Checkout>> {
-        let checkout: Option<Checkout> = self.find_unreturned_by_book_id(book_id).await?;
-
-        let mut checkout_histories: Vec<Checkout> = sqlx::query_as!(
-            ReturnedCheckoutRow,
+    async fn find_overdue_all(&self, now: DateTime<Utc>) -> AppResult<Vec<Checkout>> {
+        sqlx::query_as!(
+            CheckoutRow,
             r#"
                 SELECT 
-                    rc.checkout_id,
-                    rc.book_id,
-                    rc.user_id,
-                    rc.checked_out_at,
-                    rc.returned_at,
+                    c.checkout_id,
+                    c.book_id,
+                    c.copy_id,
+                    c.user_id,
+                    c.issued_by,
+                    c.checked_out_at,
+                    c.due_at,
+                    c.renewal_count,
                     b.title,
                     b.author,
                     b.isbn
-                FROM returned_checkouts AS rc
+                FROM checkouts AS c
                 INNER JOIN books AS b USING (book_id)
-                WHERE rc.book_id = $1
-                ORDER BY rc.checked_out_at DESC
+                WHERE c.due_at < $1
+                ORDER BY c.due_at ASC
             "#,
-            book_id as _
+            now
         )
         .fetch_all(self.db.inner_ref())
         .await
-        .map_err(AppError::SpecificOperationError)?
-        .into_iter()
-        .map(Checkout::from)
-        .collect();
-        if let Some(c) = checkout {
-            checkout_histories.insert(0, c);
-        }
-        Ok(checkout_histories)
+        .map(|rows| rows.into_iter().map(Checkout::from).collect())
+        .map_err(AppError::SpecificOperationError)
     }
-}
-
-impl CheckoutRepositoryImpl {
-    async fn set_transaction_serializable(
+    async fn find_overdue_by_user_id(
         &self,
-        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
-    ) -> AppResult<()> {
-        sqlx::query!("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE;")
-            .execute(&mut **tx)
-            .await
-            .map_err(AppError::SpecificOperationError)?;
-        Ok(())
-    }
-
-    async fn find_unreturned_by_book_id(&self, book_id: BookId) -> AppResult<Option<Checkout>> {
-        let rows = sqlx::query_as!(
+        user_id: UserId,
+        now: DateTime<Utc>,
+    ) -> AppResult<Vec<Checkout>> {
+        sqlx::query_as!(
             CheckoutRow,
             r#"
                 SELECT 
                     c.checkout_id,
                     c.book_id,
+                    c.copy_id,
                     c.user_id,
+                    c.issued_by,
                     c.checked_out_at,
+                    c.due_at,
+                    c.renewal_count,
                     b.title,
                     b.author,
                     b.isbn
                 FROM checkouts AS c
                 INNER JOIN books AS b USING (book_id)
-                WHERE c.book_id = $1
+                WHERE c.user_id = $1 AND c.due_at < $2
+                ORDER BY c.due_at ASC
             "#,
-            book_id as _
+            user_id as _,
+            now
         )
-        .fetch_optional(self.db.inner_ref())
+        .fetch_all(self.db.inner_ref())
         .await
+        .map(|rows| rows.into_iter().map(Checkout::from).collect())
+        .map_err(AppError::SpecificOperationError)
+    }
+    async fn find_history_by_book_id(
+        &self,
+        book_id: BookId,
+        options: CursorListOptions,
+    ) -> AppResult<CursorPaginatedList<Checkout>> {
+        let CursorListOptions { limit, cursor } = options;
+        let (after_at, after_id) = cursor.map(|c| (c.timestamp, c.id)).unzip();
+
+        let rows = match cursor.map(|c| c.direction) {
+            None | Some(CursorDirection::Next) => {
+                sqlx::query_as!(
+                    CheckoutHistoryRow,
+                    r#"
+                SELECT
+                    h.checkout_id AS "checkout_id!: CheckoutId",
+                    h.book_id AS "book_id!: BookId",
+                    h.copy_id AS "copy_id!: BookCopyId",
+                    h.user_id AS "user_id!: UserId",
+                    h.issued_by AS "issued_by!: UserId",
+                    h.checked_out_at AS "checked_out_at!",
+                    h.due_at AS "due_at!",
+                    h.renewal_count AS "renewal_count!",
+                    h.returned_at,
+                    h.returned_by AS "returned_by: UserId",
+                    h.return_reason,
+                    b.title,
+                    b.author,
+                    b.isbn
+                FROM (
+                    SELECT checkout_id, book_id, copy_id, user_id, issued_by, checked_out_at,
+                        due_at, renewal_count, NULL::timestamptz AS returned_at,
+                        NULL::uuid AS returned_by, NULL::text AS return_reason
+                    FROM checkouts
+                    WHERE book_id = $1
+                    UNION ALL
+                    SELECT checkout_id, book_id, copy_id, user_id, issued_by, checked_out_at,
+                        due_at, renewal_count, returned_at, returned_by, return_reason
+                    FROM returned_checkouts
+                    WHERE book_id = $1
+                ) AS h
+                INNER JOIN books AS b USING (book_id)
+                WHERE $3::timestamptz IS NULL OR (h.checked_out_at, h.checkout_id) < ($3, $4::uuid)
+                ORDER BY h.checked_out_at DESC, h.checkout_id DESC
+                LIMIT $2
+            "#,
+                    book_id as _,
+                    limit + 1,
+                    after_at,
+                    after_id
+                )
+                .fetch_all(self.db.inner_ref())
+                .await
+            }
+            Some(CursorDirection::Prev) => {
+                sqlx::query_as!(
+                    CheckoutHistoryRow,
+                    r#"
+                SELECT
+                    h.checkout_id AS "checkout_id!: CheckoutId",
+                    h.book_id AS "book_id!: BookId",
+                    h.copy_id AS "copy_id!: BookCopyId",
+                    h.user_id AS "user_id!: UserId",
+                    h.issued_by AS "issued_by!: UserId",
+                    h.checked_out_at AS "checked_out_at!",
+                    h.due_at AS "due_at!",
+                    h.renewal_count AS "renewal_count!",
+                    h.returned_at,
+                    h.returned_by AS "returned_by: UserId",
+                    h.return_reason,
+                    b.title,
+                    b.author,
+                    b.isbn
+                FROM (
+                    SELECT checkout_id, book_id, copy_id, user_id, issued_by, checked_out_at,
+                        due_at, renewal_count, NULL::timestamptz AS returned_at,
+                        NULL::uuid AS returned_by, NULL::text AS return_reason
+                    FROM checkouts
+                    WHERE book_id = $1
+                    UNION ALL
+                    SELECT checkout_id, book_id, copy_id, user_id, issued_by, checked_out_at,
+                        due_at, renewal_count, returned_at, returned_by, return_reason
+                    FROM returned_checkouts
+                    WHERE book_id = $1
+                ) AS h
+                INNER JOIN books AS b USING (book_id)
+                WHERE (h.checked_out_at, h.checkout_id) > ($3, $4::uuid)
+                ORDER BY h.checked_out_at ASC, h.checkout_id ASC
+                LIMIT $2
+            "#,
+                    book_id as _,
+                    limit + 1,
+                    after_at,
+                    after_id
+                )
+                .fetch_all(self.db.inner_ref())
+                .await
+            }
+        }
         .map_err(AppError::SpecificOperationError)?
-        .map(Checkout::from);
+        .into_iter()
+        .map(Checkout::from)
+        .collect();
+
+        Ok(CursorPaginatedList::from_rows(
+            rows,
+            limit,
+            cursor,
+            |c: &Checkout| (c.checked_out_at, c.id.raw()),
+        ))
+    }
+}
+
+// librarians and custom roles borrow like members
+pub(crate) fn loan_period(config: &CheckoutConfig, role_name: &str) -> Duration {
+    let days = if role_name == BuiltinRole::Admin.as_ref() {
+        config.admin_loan_days
+    } else {
+        config.user_loan_days
+    };
+    Duration::days(days)
+}
+
+// how long a returned copy is held for the first user in its queue
+pub(crate) fn claim_period(config: &CheckoutConfig) -> Duration {
+    Duration::hours(config.reservation_claim_hours)
+}
 
-        Ok(rows)
+// checked before anything else about the copy, so a refusal names the rule
+pub(crate) fn enforce_borrowing_policy(
+    policy: &BorrowingPolicy,
+    loan_count: i64,
+    has_overdue: bool,
+    owns_book: bool,
+) -> AppResult<()> {
+    if let Some(max_loans) = policy.max_loans
+        && loan_count >= i64::from(max_loans)
+    {
+        return Err(AppError::BorrowingPolicyViolation(
+            BorrowingRule::LoanLimit,
+            format!("The borrower already has the maximum of {max_loans} loans"),
+        ));
+    }
+    if policy.block_when_overdue && has_overdue {
+        return Err(AppError::BorrowingPolicyViolation(
+            BorrowingRule::OverdueLoans,
+            "The borrower has overdue loans to return first".into(),
+        ));
+    }
+    if policy.block_own_books && owns_book {
+        return Err(AppError::BorrowingPolicyViolation(
+            BorrowingRule::OwnBook,
+            "The borrower may not check out a book they registered".into(),
+        ));
+    }
+    Ok(())
+}
+
+impl CheckoutRepositoryImpl {
+    // picks the requested copy, or the longest-held copy on the shelf when none is given
+    async fn find_available_copy(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        book_id: BookId,
+        copy_id: Option<BookCopyId>,
+    ) -> AppResult<BookCopyId> {
+        let rows = sqlx::query_as!(
+            CopyStateRow,
+            r#"
+                SELECT
+                    bc.copy_id,
+                    c.checkout_id AS "checkout_id?: CheckoutId"
+                FROM book_copies AS bc
+                LEFT OUTER JOIN checkouts AS c USING (copy_id)
+                WHERE bc.book_id = $1
+                ORDER BY bc.created_at ASC, bc.copy_id ASC
+            "#,
+            book_id as _
+        )
+        .fetch_all(&mut **tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        match copy_id {
+            Some(copy_id) => match rows.into_iter().find(|r| r.copy_id == copy_id) {
+                None => Err(AppError::EntityNotFound(format!(
+                    "Book copy with id {copy_id} not found"
+                ))),
+                Some(CopyStateRow {
+                    checkout_id: Some(_),
+                    ..
+                }) => Err(AppError::UnprocessableEntity(format!(
+                    "Book copy with id {copy_id} is already checked out"
+                ))),
+                Some(r) => Ok(r.copy_id),
+            },
+            None => rows
+                .into_iter()
+                .find(|r| r.checkout_id.is_none())
+                .map(|r| r.copy_id)
+                .ok_or_else(|| {
+                    AppError::UnprocessableEntity(format!(
+                        "No copy of book with id {book_id} is available"
+                    ))
+                }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, sync::Arc};
+
+    use kernel::{
+        model::{
+            book::{CopyCondition, event::CreateBookCopy},
+            user::{UserStatus, event::CreateUser},
+        },
+        repository::{book::BookRepository, user::UserRepository},
+    };
+
+    use crate::{
+        password::Argon2PasswordHasher,
+        repository::{book::BookRepositoryImpl, user::UserRepositoryImpl},
+    };
+
+    use super::*;
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_renew_checkout(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let config = CheckoutConfig {
+            max_renewals: 1,
+            ..Default::default()
+        };
+        let repo = CheckoutRepositoryImpl::new(ConnectionPool::new(pool), config.clone());
+
+        let book_id = BookId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4d")?;
+        let user_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let checked_out_at = Utc::now() - Duration::days(60);
+        repo.create(CreateCheckout::new(
+            book_id,
+            None,
+            user_id,
+            user_id,
+            checked_out_at,
+        ))
+        .await?;
+
+        let checkout = repo
+            .find_unreturned_by_user_id(user_id)
+            .await?
+            .pop()
+            .expect("Checkout not found");
+        assert_eq!(
+            checkout.due_at,
+            checkout.checked_out_at + Duration::days(config.admin_loan_days)
+        );
+        assert_eq!(checkout.renewal_count, 0);
+
+        let now = Utc::now();
+        assert!(checkout.is_overdue(now));
+        assert_eq!(repo.find_overdue_all(now).await?.len(), 1);
+        assert_eq!(repo.find_overdue_by_user_id(user_id, now).await?.len(), 1);
+
+        repo.renew(RenewCheckout::new(checkout.id, book_id, user_id, now))
+            .await?;
+        assert!(repo.find_overdue_all(now).await?.is_empty());
+
+        let res = repo
+            .renew(RenewCheckout::new(checkout.id, book_id, user_id, now))
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_checkout_multiple_copies(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo =
+            CheckoutRepositoryImpl::new(ConnectionPool::new(pool.clone()), Default::default());
+        let book_repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let user_repo = UserRepositoryImpl::new(
+            ConnectionPool::new(pool),
+            Arc::new(Argon2PasswordHasher::default()),
+        );
+
+        let book_id = BookId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4d")?;
+        let first_copy_id = BookCopyId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4e")?;
+        let owner_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let other_user = user_repo
+            .create(CreateUser {
+                user_id: UserId::new(),
+                name: "Other User".into(),
+                email: "other@example.com".into(),
+                password: "test_password".into(),
+                status: UserStatus::Active,
+                created_by: None,
+            })
+            .await?;
+
+        let second_copy_id = book_repo
+            .create_copy(CreateBookCopy {
+                book_id,
+                barcode: Some("RUSTBOOK-0002".into()),
+                condition: CopyCondition::New,
+                shelf_location: "A-1".into(),
+                requested_user: owner_id,
+                manages_books: false,
+            })
+            .await?;
+
+        repo.create(CreateCheckout::new(
+            book_id,
+            Some(second_copy_id),
+            owner_id,
+            owner_id,
+            Utc::now(),
+        ))
+        .await?;
+        let res = repo
+            .create(CreateCheckout::new(
+                book_id,
+                None,
+                owner_id,
+                owner_id,
+                Utc::now(),
+            ))
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+        let res = repo
+            .create(CreateCheckout::new(
+                book_id,
+                Some(second_copy_id),
+                other_user.id,
+                other_user.id,
+                Utc::now(),
+            ))
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        repo.create(CreateCheckout::new(
+            book_id,
+            None,
+            other_user.id,
+            other_user.id,
+            Utc::now(),
+        ))
+        .await?;
+        let checkouts = repo.find_unreturned_by_user_id(other_user.id).await?;
+        assert_eq!(checkouts[0].book.copy_id, first_copy_id);
+
+        let book = book_repo
+            .find_by_id(book_id)
+            .await?
+            .expect("Book not found");
+        assert_eq!(book.total_copies(), 2);
+        assert_eq!(book.available_copies(), 0);
+
+        Ok(())
+    }
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn staff_actions_are_kept_in_history(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo =
+            CheckoutRepositoryImpl::new(ConnectionPool::new(pool.clone()), Default::default());
+        let user_repo = UserRepositoryImpl::new(
+            ConnectionPool::new(pool),
+            Arc::new(Argon2PasswordHasher::default()),
+        );
+
+        let book_id = BookId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4d")?;
+        let staff_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let borrower = user_repo
+            .create(CreateUser {
+                user_id: UserId::new(),
+                name: "Borrower".into(),
+                email: "borrower@example.com".into(),
+                password: "test_password".into(),
+                status: UserStatus::Active,
+                created_by: None,
+            })
+            .await?;
+
+        repo.create(CreateCheckout::new(
+            book_id,
+            None,
+            borrower.id,
+            staff_id,
+            Utc::now(),
+        ))
+        .await?;
+        let checkout = repo
+            .find_unreturned_by_user_id(borrower.id)
+            .await?
+            .pop()
+            .expect("Checkout not found");
+        assert_eq!(checkout.issued_by, staff_id);
+
+        // only the borrower returns without managing checkouts
+        let res = repo
+            .update_returned(UpdateReturned::new(
+                checkout.id,
+                book_id,
+                staff_id,
+                false,
+                None,
+                Utc::now(),
+            ))
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+        repo.update_returned(UpdateReturned::new(
+            checkout.id,
+            book_id,
+            staff_id,
+            true,
+            Some("left the company".into()),
+            Utc::now(),
+        ))
+        .await?;
+
+        let history = repo
+            .find_history_by_book_id(
+                book_id,
+                CursorListOptions {
+                    limit: 10,
+                    cursor: None,
+                },
+            )
+            .await?;
+        let returned = &history.items[0];
+        assert_eq!(returned.checked_out_by, borrower.id);
+        assert_eq!(returned.issued_by, staff_id);
+        assert_eq!(returned.returned_by, Some(staff_id));
+        assert_eq!(returned.return_reason.as_deref(), Some("left the company"));
+
+        Ok(())
     }
 }
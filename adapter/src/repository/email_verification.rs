@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        auth::{EmailVerification, EmailVerificationToken, event::CreateEmailVerification},
+        id::UserId,
+    },
+    repository::email_verification::EmailVerificationRepository,
+};
+use shared::{config::RegistrationConfig, error::AppResult};
+
+use crate::{
+    database::model::email_verification::{EmailVerificationKey, VerificationUser},
+    redis::RedisClient,
+};
+
+#[derive(new)]
+pub struct EmailVerificationRepositoryImpl {
+    kv: Arc<RedisClient>,
+    config: RegistrationConfig,
+}
+
+#[async_trait]
+impl EmailVerificationRepository for EmailVerificationRepositoryImpl {
+    async fn create(&self, event: CreateEmailVerification) -> AppResult<EmailVerification> {
+        self.kv
+            .set_ex(
+                &EmailVerificationKey::from(event.token.as_str()),
+                &VerificationUser(event.user_id),
+                self.config.verification_ttl,
+            )
+            .await?;
+        Ok(email_verification(&self.config, event))
+    }
+    async fn consume(&self, token: &EmailVerificationToken) -> AppResult<Option<UserId>> {
+        Ok(self
+            .kv
+            .take(&EmailVerificationKey::from(token))
+            .await?
+            .map(|VerificationUser(user_id)| user_id))
+    }
+}
+
+pub(crate) fn email_verification(
+    config: &RegistrationConfig,
+    event: CreateEmailVerification,
+) -> EmailVerification {
+    let link = config.verification_url.as_ref().map(|url| {
+        let separator = if url.contains('?') { '&' } else { '?' };
+        format!("{url}{separator}token={}", event.token)
+    });
+    EmailVerification {
+        user_id: event.user_id,
+        token: EmailVerificationToken(event.token),
+        link,
+        expires_in: config.verification_ttl,
+    }
+}
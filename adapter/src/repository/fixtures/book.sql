@@ -19,4 +19,16 @@ VALUES
     '5b4c96ac-316a-4bee-8e69-cac5eb84ff4c',
     NOW(), 
     NOW()
-    ) ON CONFLICT DO NOTHING;
\ No newline at end of file
+    ) ON CONFLICT DO NOTHING;
+INSERT INTO
+    book_copies (
+        copy_id,
+        book_id,
+        barcode
+    )
+VALUES
+    (
+    '5b4c96ac-316a-4bee-8e69-cac5eb84ff4e',
+    '5b4c96ac-316a-4bee-8e69-cac5eb84ff4d',
+    'RUSTBOOK-0001'
+    ) ON CONFLICT DO NOTHING;
@@ -1,6 +1,4 @@
-INSERT INTO roles(name)
-VALUES ('Admin'), ('User');
-
+-- the roles themselves come with the migrations
 INSERT INTO users(user_id, name, email, password_hash, role_id)
 SELECT
     '5b4c96ac-316a-4bee-8e69-cac5eb84ff4c'
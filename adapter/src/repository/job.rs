@@ -0,0 +1,194 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use derive_new::new;
+use kernel::{
+    model::{
+        id::JobLeaseId,
+        job::{
+            JobLease,
+            event::{CompleteJob, FailJob, ScheduleJob},
+        },
+    },
+    repository::job::JobRepository,
+};
+use shared::error::{AppError, AppResult};
+
+use crate::database::{ConnectionPool, model::job::JobLeaseRow};
+
+#[derive(new)]
+pub struct JobRepositoryImpl {
+    db: ConnectionPool,
+}
+
+#[async_trait]
+impl JobRepository for JobRepositoryImpl {
+    async fn schedule(&self, event: ScheduleJob) -> AppResult<()> {
+        sqlx::query!(
+            r#"
+                INSERT INTO jobs (name, schedule, run_at)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (name) DO UPDATE
+                SET schedule = EXCLUDED.schedule, run_at = EXCLUDED.run_at, attempts = 0
+                WHERE jobs.schedule <> EXCLUDED.schedule
+            "#,
+            event.name,
+            event.schedule,
+            event.next_run_at
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        Ok(())
+    }
+    async fn lease_due(&self, now: DateTime<Utc>, lease: Duration) -> AppResult<Option<JobLease>> {
+        let row = sqlx::query_as!(
+            JobLeaseRow,
+            r#"
+                WITH due AS (
+                    SELECT name FROM jobs
+                    WHERE run_at <= $1 AND (leased_until IS NULL OR leased_until <= $1)
+                    ORDER BY run_at
+                    LIMIT 1
+                    FOR UPDATE SKIP LOCKED
+                )
+                UPDATE jobs AS j SET lease_id = $2, leased_until = $3
+                FROM due
+                WHERE j.name = due.name
+                RETURNING j.name, j.lease_id AS "lease_id!: JobLeaseId", j.attempts
+            "#,
+            now,
+            JobLeaseId::new() as _,
+            now + lease
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        Ok(row.map(JobLease::from))
+    }
+    async fn complete(&self, event: CompleteJob) -> AppResult<()> {
+        let res = sqlx::query!(
+            r#"
+                UPDATE jobs
+                SET run_at = $3, attempts = 0, lease_id = NULL, leased_until = NULL,
+                    last_run_at = $4, last_error = NULL
+                WHERE name = $1 AND lease_id = $2
+            "#,
+            event.name,
+            event.lease_id as _,
+            event.next_run_at,
+            event.finished_at
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        if res.rows_affected() < 1 {
+            return Err(lease_lost(&event.name));
+        }
+        Ok(())
+    }
+    async fn fail(&self, event: FailJob) -> AppResult<()> {
+        let res = sqlx::query!(
+            r#"
+                UPDATE jobs
+                SET run_at = $3, attempts = $4, lease_id = NULL, leased_until = NULL,
+                    last_run_at = $5, last_error = $6
+                WHERE name = $1 AND lease_id = $2
+            "#,
+            event.name,
+            event.lease_id as _,
+            event.next_run_at,
+            event.attempts,
+            event.failed_at,
+            event.error
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        if res.rows_affected() < 1 {
+            return Err(lease_lost(&event.name));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn lease_lost(name: &str) -> AppError {
+    AppError::EntityNotFound(format!("Job {name} is no longer leased by this run"))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::SubsecRound;
+
+    use super::*;
+
+    #[sqlx::test]
+    async fn due_jobs_are_leased_to_one_run_at_a_time(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = JobRepositoryImpl::new(ConnectionPool::new(pool));
+        let now = Utc::now().trunc_subsecs(3);
+        let lease = Duration::minutes(10);
+
+        repo.schedule(ScheduleJob::new(
+            "purge".into(),
+            "0 0 3 * * *".into(),
+            now - Duration::hours(1),
+        ))
+        .await?;
+        repo.schedule(ScheduleJob::new(
+            "reminders".into(),
+            "0 0 8 * * *".into(),
+            now + Duration::hours(1),
+        ))
+        .await?;
+        // scheduling again leaves an unchanged job as it is
+        repo.schedule(ScheduleJob::new("purge".into(), "0 0 3 * * *".into(), now))
+            .await?;
+
+        let first = repo.lease_due(now, lease).await?.expect("purge is due");
+        assert_eq!(first.name, "purge");
+        assert_eq!(first.attempts, 0);
+        assert_eq!(repo.lease_due(now, lease).await?, None);
+
+        // an instance that stopped in the middle of the run loses the job
+        let later = now + lease + Duration::seconds(1);
+        let second = repo.lease_due(later, lease).await?.expect("lease expired");
+        assert_eq!(second.name, "purge");
+        assert!(matches!(
+            repo.complete(CompleteJob::new(
+                first.name.clone(),
+                first.lease_id,
+                later,
+                now + Duration::days(1),
+            ))
+            .await,
+            Err(AppError::EntityNotFound(_))
+        ));
+
+        repo.fail(FailJob::new(
+            second.name.clone(),
+            second.lease_id,
+            later,
+            "mail server down".into(),
+            1,
+            later + Duration::minutes(1),
+        ))
+        .await?;
+        assert_eq!(repo.lease_due(later, lease).await?, None);
+        let retry = later + Duration::minutes(1);
+        let third = repo.lease_due(retry, lease).await?.expect("retry is due");
+        assert_eq!(third.attempts, 1);
+        repo.complete(CompleteJob::new(
+            third.name,
+            third.lease_id,
+            retry,
+            now + Duration::days(1),
+        ))
+        .await?;
+
+        let reminders = repo
+            .lease_due(now + Duration::hours(2), lease)
+            .await?
+            .expect("reminders are due");
+        assert_eq!(reminders.name, "reminders");
+        Ok(())
+    }
+}
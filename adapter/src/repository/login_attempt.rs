@@ -0,0 +1,119 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{model::auth::LoginAttempt, repository::login_attempt::LoginAttemptRepository};
+use shared::{
+    config::LoginThrottleConfig,
+    error::{AppError, AppResult},
+};
+
+use crate::{
+    database::model::login_attempt::{
+        FailureCount, LoginBackoffKey, LoginFailuresKey, LoginLockoutKey, normalize_email,
+    },
+    redis::RedisClient,
+};
+
+#[derive(new)]
+pub struct LoginAttemptRepositoryImpl {
+    kv: Arc<RedisClient>,
+    config: LoginThrottleConfig,
+}
+
+#[async_trait]
+impl LoginAttemptRepository for LoginAttemptRepositoryImpl {
+    async fn check(&self, attempt: &LoginAttempt) -> AppResult<()> {
+        let email = normalize_email(&attempt.email);
+        let lockout = self.kv.ttl(&LoginLockoutKey(email.clone())).await?;
+        if let Some(retry_after) = lockout.or(self.kv.ttl(&LoginBackoffKey(email)).await?) {
+            return Err(AppError::TooManyRequests { retry_after });
+        }
+
+        if let Some(ip) = &attempt.ip_address {
+            let key = LoginFailuresKey::Ip(ip.clone());
+            if let Some(FailureCount(failures)) = self.kv.get(&key).await?
+                && failures >= self.config.max_failures_per_ip
+            {
+                return Err(AppError::TooManyRequests {
+                    retry_after: self.kv.ttl(&key).await?.unwrap_or(self.config.lockout_secs),
+                });
+            }
+        }
+        Ok(())
+    }
+    async fn record_failure(&self, attempt: &LoginAttempt) -> AppResult<()> {
+        let email = normalize_email(&attempt.email);
+        if let Some(ip) = &attempt.ip_address {
+            self.kv
+                .increment(&LoginFailuresKey::Ip(ip.clone()), self.config.lockout_secs)
+                .await?;
+        }
+
+        let failures = self
+            .kv
+            .increment(
+                &LoginFailuresKey::Email(email.clone()),
+                self.config.lockout_secs,
+            )
+            .await?;
+        if failures >= self.config.max_failures {
+            self.kv
+                .set_ex(
+                    &LoginLockoutKey(email.clone()),
+                    &FailureCount(failures),
+                    self.config.lockout_secs,
+                )
+                .await?;
+            // the count starts over once the lockout ends
+            return self.kv.delete(&LoginFailuresKey::Email(email)).await;
+        }
+
+        let backoff = backoff_secs(&self.config, failures);
+        if backoff > 0 {
+            self.kv
+                .set_ex(&LoginBackoffKey(email), &FailureCount(failures), backoff)
+                .await?;
+        }
+        Ok(())
+    }
+    async fn record_success(&self, attempt: &LoginAttempt) -> AppResult<()> {
+        let email = normalize_email(&attempt.email);
+        self.kv.delete(&LoginBackoffKey(email.clone())).await?;
+        self.kv.delete(&LoginFailuresKey::Email(email)).await
+    }
+    async fn unlock(&self, email: &str) -> AppResult<()> {
+        let email = normalize_email(email);
+        self.kv.delete(&LoginLockoutKey(email.clone())).await?;
+        self.kv.delete(&LoginBackoffKey(email.clone())).await?;
+        self.kv.delete(&LoginFailuresKey::Email(email)).await
+    }
+}
+
+// how long an email waits after its `failures`-th failure in a row
+pub(crate) fn backoff_secs(config: &LoginThrottleConfig, failures: u64) -> u64 {
+    let doublings = failures.saturating_sub(1).min(32) as u32;
+    config
+        .backoff_base_secs
+        .saturating_mul(2u64.pow(doublings))
+        .min(config.lockout_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_lockout() {
+        let config = LoginThrottleConfig {
+            backoff_base_secs: 2,
+            lockout_secs: 60,
+            ..Default::default()
+        };
+        let backoffs = (1..=7)
+            .map(|failures| backoff_secs(&config, failures))
+            .collect::<Vec<_>>();
+        assert_eq!(backoffs, vec![2, 4, 8, 16, 32, 60, 60]);
+        assert_eq!(backoff_secs(&config, u64::MAX), 60);
+    }
+}
@@ -0,0 +1,55 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        auth::{LoginChallenge, LoginChallengeToken, event::CreateLoginChallenge},
+        id::UserId,
+    },
+    repository::login_challenge::LoginChallengeRepository,
+};
+use shared::{config::TotpConfig, error::AppResult};
+
+use crate::{
+    database::model::login_challenge::{ChallengedUser, LoginChallengeKey},
+    redis::RedisClient,
+};
+
+#[derive(new)]
+pub struct LoginChallengeRepositoryImpl {
+    kv: Arc<RedisClient>,
+    config: TotpConfig,
+}
+
+#[async_trait]
+impl LoginChallengeRepository for LoginChallengeRepositoryImpl {
+    async fn create(&self, event: CreateLoginChallenge) -> AppResult<LoginChallenge> {
+        self.kv
+            .set_ex(
+                &LoginChallengeKey::from(event.token.as_str()),
+                &ChallengedUser(event.user_id),
+                self.config.challenge_ttl,
+            )
+            .await?;
+        Ok(login_challenge(&self.config, event))
+    }
+    async fn find(&self, token: &LoginChallengeToken) -> AppResult<Option<UserId>> {
+        Ok(self
+            .kv
+            .get(&LoginChallengeKey::from(token))
+            .await?
+            .map(|ChallengedUser(user_id)| user_id))
+    }
+    async fn delete(&self, token: &LoginChallengeToken) -> AppResult<()> {
+        self.kv.delete(&LoginChallengeKey::from(token)).await
+    }
+}
+
+pub(crate) fn login_challenge(config: &TotpConfig, event: CreateLoginChallenge) -> LoginChallenge {
+    LoginChallenge {
+        user_id: event.user_id,
+        token: LoginChallengeToken(event.token),
+        expires_in: config.challenge_ttl,
+    }
+}
@@ -0,0 +1,109 @@
+use std::cmp::Reverse;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use derive_new::new;
+use kernel::{
+    model::{
+        api_key::{
+            ApiKey, ApiKeyOwner, ApiKeySecret, IssuedApiKey,
+            event::{CreateApiKey, RevokeApiKey, RotateApiKey},
+        },
+        id::{ApiKeyId, UserId},
+    },
+    repository::api_key::ApiKeyRepository,
+};
+use shared::error::AppResult;
+
+use super::{ApiKeyRecord, InMemoryStore, stored};
+use crate::repository::api_key::{api_key_not_found, api_key_prefix, hash_api_key, new_api_key};
+
+#[derive(new)]
+pub struct InMemoryApiKeyRepository {
+    store: InMemoryStore,
+}
+
+#[async_trait]
+impl ApiKeyRepository for InMemoryApiKeyRepository {
+    async fn create(&self, event: CreateApiKey) -> AppResult<IssuedApiKey> {
+        let (prefix, secret) = new_api_key();
+        let id = ApiKeyId::new();
+        let record = ApiKeyRecord {
+            user_id: event.user_id,
+            name: event.name,
+            prefix,
+            key_hash: hash_api_key(&secret),
+            scopes: event.scopes,
+            created_at: stored(Utc::now()),
+            last_used_at: None,
+            revoked_at: None,
+        };
+        let api_key = record.to_api_key(id);
+        self.store.write().api_keys.insert(id, record);
+        Ok(IssuedApiKey { api_key, secret })
+    }
+    async fn find_by_user_id(&self, user_id: UserId) -> AppResult<Vec<ApiKey>> {
+        let mut keys = self
+            .store
+            .read()
+            .api_keys
+            .iter()
+            .filter(|(_, k)| k.user_id == user_id && k.revoked_at.is_none())
+            .map(|(id, k)| k.to_api_key(*id))
+            .collect::<Vec<_>>();
+        keys.sort_by_key(|k| Reverse(k.created_at));
+        Ok(keys)
+    }
+    async fn rotate(&self, event: RotateApiKey) -> AppResult<IssuedApiKey> {
+        let (prefix, secret) = new_api_key();
+        let mut tables = self.store.write();
+        let record = tables
+            .api_keys
+            .get_mut(&event.api_key_id)
+            .filter(|k| k.user_id == event.user_id && k.revoked_at.is_none())
+            .ok_or_else(api_key_not_found)?;
+        record.prefix = prefix;
+        record.key_hash = hash_api_key(&secret);
+        Ok(IssuedApiKey {
+            api_key: record.to_api_key(event.api_key_id),
+            secret,
+        })
+    }
+    async fn revoke(&self, event: RevokeApiKey) -> AppResult<()> {
+        let mut tables = self.store.write();
+        let record = tables
+            .api_keys
+            .get_mut(&event.api_key_id)
+            .filter(|k| k.user_id == event.user_id && k.revoked_at.is_none())
+            .ok_or_else(api_key_not_found)?;
+        record.revoked_at = Some(stored(Utc::now()));
+        Ok(())
+    }
+    async fn authenticate(&self, secret: &ApiKeySecret) -> AppResult<Option<ApiKeyOwner>> {
+        let Some(prefix) = api_key_prefix(secret) else {
+            return Ok(None);
+        };
+        let key_hash = hash_api_key(secret);
+        let mut tables = self.store.write();
+        Ok(tables
+            .api_keys
+            .iter_mut()
+            .find(|(_, k)| k.prefix == prefix && k.key_hash == key_hash && k.revoked_at.is_none())
+            .map(|(id, k)| {
+                k.last_used_at = Some(stored(Utc::now()));
+                ApiKeyOwner {
+                    api_key_id: *id,
+                    user_id: k.user_id,
+                    scopes: k.scopes.clone(),
+                }
+            }))
+    }
+    async fn delete_revoked(&self, revoked_before: DateTime<Utc>) -> AppResult<u64> {
+        let mut tables = self.store.write();
+        let before = tables.api_keys.len();
+        tables
+            .api_keys
+            .retain(|_, k| k.revoked_at.is_none_or(|at| at >= revoked_before));
+        Ok((before - tables.api_keys.len()) as u64)
+    }
+}
@@ -0,0 +1,55 @@
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        audit::{AuditEntry, AuditLogOptions},
+        list::{CursorDirection, CursorPaginatedList},
+    },
+    repository::audit::AuditRepository,
+};
+use shared::error::AppResult;
+
+use super::{InMemoryStore, paginate_by_cursor};
+
+#[derive(new)]
+pub struct InMemoryAuditRepository {
+    store: InMemoryStore,
+}
+
+#[async_trait]
+impl AuditRepository for InMemoryAuditRepository {
+    async fn find_all(
+        &self,
+        options: AuditLogOptions,
+    ) -> AppResult<CursorPaginatedList<AuditEntry>> {
+        let AuditLogOptions {
+            limit,
+            cursor,
+            actor,
+            target_kind,
+            target_id,
+            since,
+            until,
+        } = options;
+        let rows = self
+            .store
+            .read()
+            .audit_log
+            .iter()
+            .filter(|e| actor.is_none_or(|actor| e.actor == Some(actor)))
+            .filter(|e| target_kind.is_none_or(|kind| e.target.kind == kind))
+            .filter(|e| target_id.as_ref().is_none_or(|id| &e.target.id == id))
+            .filter(|e| since.is_none_or(|since| e.occurred_at >= since))
+            .filter(|e| until.is_none_or(|until| e.occurred_at <= until))
+            .cloned()
+            .collect();
+        let descending = cursor.is_none_or(|c| c.direction == CursorDirection::Next);
+        Ok(paginate_by_cursor(
+            rows,
+            limit,
+            cursor,
+            descending,
+            |e: &AuditEntry| (e.occurred_at, e.id.raw()),
+        ))
+    }
+}
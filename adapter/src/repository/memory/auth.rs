@@ -0,0 +1,243 @@
+use std::{
+    cmp::Reverse,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use derive_new::new;
+use kernel::{
+    model::{
+        auth::{
+            AccessToken, AuthTokens, RefreshToken, Session, SessionClient, TokenOwner,
+            event::{CreateToken, DeleteSession, RotateToken},
+        },
+        id::{SessionId, UserId},
+    },
+    repository::auth::AuthRepository,
+};
+use shared::error::{AppError, AppResult};
+
+use crate::{password::PasswordHasher, repository::auth::ensure_active};
+
+use super::{InMemoryStore, RefreshTokenRecord, SessionRecord, Tables, TokenRecord, stored};
+
+#[derive(new)]
+pub struct InMemoryAuthRepository {
+    store: InMemoryStore,
+    hasher: Arc<dyn PasswordHasher>,
+    ttl: u64,
+    refresh_ttl: u64,
+}
+
+#[async_trait]
+impl AuthRepository for InMemoryAuthRepository {
+    async fn fetch_token_owner(&self, access_token: &AccessToken) -> AppResult<Option<TokenOwner>> {
+        let mut tables = self.store.write();
+        expire(&mut tables);
+
+        let Some((user_id, session_id)) = tables
+            .tokens
+            .get(&access_token.0)
+            .map(|t| (t.user_id, t.session_id))
+        else {
+            return Ok(None);
+        };
+        if let Some(session) = tables.sessions.get_mut(&session_id) {
+            session.last_used_at = stored(Utc::now());
+        }
+        Ok(Some(TokenOwner {
+            user_id,
+            session_id: Some(session_id),
+        }))
+    }
+    async fn verify_user(&self, email: &str, password: &str) -> AppResult<UserId> {
+        let (user_id, password_hash, status) = self
+            .store
+            .read()
+            .users
+            .values()
+            .find(|u| u.email == email)
+            .map(|u| (u.id, u.password_hash.clone(), u.status))
+            .ok_or(AppError::UnauthorizedError)?;
+
+        let valid = self.hasher.verify(password, &password_hash)?;
+        if !valid {
+            return Err(AppError::UnauthorizedError);
+        }
+        ensure_active(status)?;
+
+        if self.hasher.needs_rehash(&password_hash) {
+            let rehashed = self.hasher.hash(password)?;
+            // skipped if the password changed since it was read
+            if let Some(user) = self.store.write().users.get_mut(&user_id)
+                && user.password_hash == password_hash
+            {
+                user.password_hash = rehashed;
+            }
+        }
+        Ok(user_id)
+    }
+    async fn create_token(&self, event: CreateToken) -> AppResult<AuthTokens> {
+        let CreateToken {
+            user_id,
+            client:
+                SessionClient {
+                    user_agent,
+                    ip_address,
+                },
+            access_token,
+            refresh_token,
+        } = event;
+        let now = stored(Utc::now());
+
+        let mut tables = self.store.write();
+        Ok(self.issue(
+            &mut tables,
+            SessionId::new(),
+            SessionRecord {
+                user_id,
+                access_token,
+                refresh_token,
+                user_agent,
+                ip_address,
+                created_at: now,
+                last_used_at: now,
+                expires_at: Instant::now(),
+            },
+        ))
+    }
+    async fn rotate_token(&self, event: RotateToken) -> AppResult<AuthTokens> {
+        let mut tables = self.store.write();
+        expire(&mut tables);
+
+        let Some(refresh_token) = tables.refresh_tokens.get_mut(&event.refresh_token.0) else {
+            return Err(AppError::UnauthorizedError);
+        };
+        let session_id = refresh_token.session_id;
+        let first_use = !std::mem::replace(&mut refresh_token.used, true);
+        let Some(session) = tables.sessions.remove(&session_id) else {
+            return Err(AppError::UnauthorizedError);
+        };
+
+        tables.tokens.remove(&session.access_token);
+        if !first_use || session.refresh_token != event.refresh_token.0 {
+            // an exchanged token is presented again, so it has leaked to someone;
+            // whoever holds the current token loses the session as well
+            return Err(AppError::UnauthorizedError);
+        }
+
+        Ok(self.issue(
+            &mut tables,
+            session_id,
+            SessionRecord {
+                access_token: event.access_token,
+                refresh_token: event.next_refresh_token,
+                last_used_at: stored(Utc::now()),
+                ..session
+            },
+        ))
+    }
+    async fn delete_token(&self, access_token: AccessToken) -> AppResult<()> {
+        let mut tables = self.store.write();
+        if let Some(token) = tables.tokens.remove(&access_token.0) {
+            revoke_session(&mut tables, token.session_id);
+        }
+        Ok(())
+    }
+    async fn find_sessions(&self, user_id: UserId) -> AppResult<Vec<Session>> {
+        let mut tables = self.store.write();
+        expire(&mut tables);
+
+        let mut sessions = tables
+            .sessions
+            .iter()
+            .filter(|(_, s)| s.user_id == user_id)
+            .map(|(id, s)| s.to_session(*id))
+            .collect::<Vec<_>>();
+        sessions.sort_by_key(|s| Reverse(s.last_used_at));
+        Ok(sessions)
+    }
+    async fn delete_session(&self, event: DeleteSession) -> AppResult<()> {
+        let mut tables = self.store.write();
+        expire(&mut tables);
+
+        let owned = tables
+            .sessions
+            .get(&event.session_id)
+            .is_some_and(|s| s.user_id == event.user_id);
+        if !owned {
+            return Err(AppError::EntityNotFound(
+                "specified session not found".into(),
+            ));
+        }
+        revoke_session(&mut tables, event.session_id);
+        Ok(())
+    }
+    async fn delete_all_sessions(&self, user_id: UserId) -> AppResult<()> {
+        let mut tables = self.store.write();
+        let session_ids = tables
+            .sessions
+            .iter()
+            .filter(|(_, s)| s.user_id == user_id)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for session_id in session_ids {
+            revoke_session(&mut tables, session_id);
+        }
+        Ok(())
+    }
+}
+
+impl InMemoryAuthRepository {
+    // stores the session with fresh expiry times, along with its tokens
+    fn issue(
+        &self,
+        tables: &mut Tables,
+        session_id: SessionId,
+        mut session: SessionRecord,
+    ) -> AuthTokens {
+        let now = Instant::now();
+        session.expires_at = now + Duration::from_secs(self.refresh_ttl);
+        tables.tokens.insert(
+            session.access_token.clone(),
+            TokenRecord {
+                user_id: session.user_id,
+                session_id,
+                expires_at: now + Duration::from_secs(self.ttl),
+            },
+        );
+        tables.refresh_tokens.insert(
+            session.refresh_token.clone(),
+            RefreshTokenRecord {
+                session_id,
+                used: false,
+                expires_at: session.expires_at,
+            },
+        );
+
+        let tokens = AuthTokens {
+            user_id: session.user_id,
+            access_token: AccessToken(session.access_token.clone()),
+            refresh_token: RefreshToken(session.refresh_token.clone()),
+            expires_in: self.ttl,
+        };
+        tables.sessions.insert(session_id, session);
+        tokens
+    }
+}
+
+// drops entries past their TTL, as Redis does with expired keys
+fn expire(tables: &mut Tables) {
+    let now = Instant::now();
+    tables.tokens.retain(|_, t| t.expires_at > now);
+    tables.refresh_tokens.retain(|_, t| t.expires_at > now);
+    tables.sessions.retain(|_, s| s.expires_at > now);
+}
+
+fn revoke_session(tables: &mut Tables, session_id: SessionId) {
+    if let Some(session) = tables.sessions.remove(&session_id) {
+        tables.tokens.remove(&session.access_token);
+    }
+}
@@ -0,0 +1,401 @@
+use std::cmp::Reverse;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use derive_new::new;
+use kernel::{
+    model::{
+        audit::{AuditAction, AuditTarget, event::RecordAudit},
+        book::{
+            Book, BookCopy, BookListOptions, BookSort, CopyCondition,
+            event::{
+                CreateBook, CreateBookCopy, DeleteBook, DeleteBookCopy, UpdateBook, UpdateBookCopy,
+            },
+        },
+        id::{BookCopyId, BookId, UserId},
+        list::{CursorDirection, CursorPaginatedList, PaginatedList},
+        webhook::WebhookEventType,
+    },
+    repository::book::BookRepository,
+};
+use shared::error::{AppError, AppResult};
+
+use super::{BookRecord, InMemoryStore, Tables, paginate_by_cursor, stored};
+use crate::repository::book::default_barcode;
+
+#[derive(new)]
+pub struct InMemoryBookRepository {
+    store: InMemoryStore,
+}
+
+#[async_trait]
+impl BookRepository for InMemoryBookRepository {
+    async fn create(&self, event: CreateBook, user_id: UserId) -> AppResult<BookId> {
+        let mut tables = self.store.write();
+
+        let existing = tables
+            .books
+            .values()
+            .filter(|b| b.isbn == event.isbn.as_str())
+            .min_by_key(|b| b.created_at)
+            .map(|b| (b.id, b.owner));
+
+        if let Some((book_id, owner)) = existing {
+            if !event.add_copy || owner != user_id {
+                return Err(AppError::Conflict(format!(
+                    "Book with ISBN {} already exists: {}",
+                    event.isbn, book_id
+                )));
+            }
+
+            let copy_id = BookCopyId::new();
+            tables.insert_copy(
+                copy_id,
+                book_id,
+                default_barcode(copy_id),
+                CopyCondition::default(),
+                String::new(),
+            )?;
+            tables.record_copy_audit(Some(user_id), AuditAction::Create, copy_id, None);
+            return Ok(book_id);
+        }
+
+        if !tables.users.contains_key(&user_id) {
+            return Err(AppError::EntityNotFound(format!(
+                "User with id {user_id} not found"
+            )));
+        }
+
+        let book_id = BookId::new();
+        let data = serde_json::json!({
+            "bookId": book_id,
+            "title": event.title,
+            "author": event.author,
+            "isbn": event.isbn.as_str(),
+            "description": event.description,
+            "ownerId": user_id,
+        });
+        tables.books.insert(
+            book_id,
+            BookRecord {
+                id: book_id,
+                title: event.title,
+                author: event.author,
+                isbn: event.isbn.into_inner(),
+                description: event.description,
+                owner: user_id,
+                created_at: stored(Utc::now()),
+            },
+        );
+        let after = tables.books[&book_id].snapshot();
+        tables.record_audit(RecordAudit::new(
+            Some(user_id),
+            AuditAction::Create,
+            AuditTarget::book(book_id),
+            None,
+            after,
+        ));
+
+        // a new title starts with a single copy
+        let copy_id = BookCopyId::new();
+        tables.insert_copy(
+            copy_id,
+            book_id,
+            default_barcode(copy_id),
+            CopyCondition::default(),
+            String::new(),
+        )?;
+
+        tables.queue_webhook_event(WebhookEventType::BookCreated, data, Utc::now());
+        Ok(book_id)
+    }
+    async fn find_all(&self, options: BookListOptions) -> AppResult<PaginatedList<Book>> {
+        let BookListOptions {
+            limit,
+            offset,
+            sort,
+            ..
+        } = options;
+        let tables = self.store.read();
+
+        let mut books = filter_books(&tables, &options);
+        books.sort_by(|a, b| {
+            match sort {
+                BookSort::Title => a.title.cmp(&b.title),
+                BookSort::Author => a.author.cmp(&b.author),
+                BookSort::Oldest => a.created_at.cmp(&b.created_at),
+                BookSort::Newest => std::cmp::Ordering::Equal,
+            }
+            .then_with(|| Reverse(a.created_at).cmp(&Reverse(b.created_at)))
+            .then_with(|| a.id.raw().cmp(&b.id.raw()))
+        });
+
+        let total = books.len() as i64;
+        let items = books
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .filter_map(|b| tables.book(b.id))
+            .collect();
+
+        Ok(PaginatedList {
+            total,
+            limit,
+            offset,
+            items,
+        })
+    }
+    async fn find_all_by_cursor(
+        &self,
+        options: BookListOptions,
+    ) -> AppResult<CursorPaginatedList<Book>> {
+        let descending = match options.sort {
+            BookSort::Newest => true,
+            BookSort::Oldest => false,
+            BookSort::Title | BookSort::Author => {
+                return Err(AppError::InvalidCursor(
+                    "cursor pagination is only available when sorting by newest or oldest".into(),
+                ));
+            }
+        };
+        let cursor = options.cursor;
+        // reading the previous page walks the keyset the other way round
+        let descending = descending == cursor.is_none_or(|c| c.direction == CursorDirection::Next);
+        let tables = self.store.read();
+
+        let CursorPaginatedList {
+            limit,
+            next_cursor,
+            prev_cursor,
+            items,
+        } = paginate_by_cursor(
+            filter_books(&tables, &options),
+            options.limit,
+            cursor,
+            descending,
+            |b| (b.created_at, b.id.raw()),
+        );
+
+        Ok(CursorPaginatedList {
+            limit,
+            next_cursor,
+            prev_cursor,
+            items: items
+                .into_iter()
+                .filter_map(|b| tables.book(b.id))
+                .collect(),
+        })
+    }
+    async fn find_by_id(&self, book_id: BookId) -> AppResult<Option<Book>> {
+        Ok(self.store.read().book(book_id))
+    }
+    async fn update(&self, event: UpdateBook) -> AppResult<()> {
+        let mut tables = self.store.write();
+
+        let duplicate = tables
+            .books
+            .values()
+            .filter(|b| b.isbn == event.isbn.as_str() && b.id != event.book_id)
+            .min_by_key(|b| b.created_at)
+            .map(|b| b.id);
+        if let Some(book_id) = duplicate {
+            return Err(AppError::Conflict(format!(
+                "Book with ISBN {} already exists: {}",
+                event.isbn, book_id
+            )));
+        }
+
+        let data = serde_json::json!({
+            "bookId": event.book_id,
+            "title": event.title,
+            "author": event.author,
+            "isbn": event.isbn.as_str(),
+            "description": event.description,
+        });
+        let (before, after) = match tables.books.get_mut(&event.book_id) {
+            Some(book) if book.owner == event.requested_user || event.manages_books => {
+                let before = book.snapshot();
+                book.title = event.title;
+                book.author = event.author;
+                book.isbn = event.isbn.into_inner();
+                book.description = event.description;
+                (before, book.snapshot())
+            }
+            _ => return Err(AppError::EntityNotFound("specified book not found".into())),
+        };
+        tables.record_audit(RecordAudit::new(
+            Some(event.requested_user),
+            AuditAction::Update,
+            AuditTarget::book(event.book_id),
+            before,
+            after,
+        ));
+
+        tables.queue_webhook_event(WebhookEventType::BookUpdated, data, Utc::now());
+        Ok(())
+    }
+    async fn delete(&self, event: DeleteBook) -> AppResult<()> {
+        let mut tables = self.store.write();
+        ensure_owner(
+            &tables,
+            event.book_id,
+            event.requested_user,
+            event.manages_books,
+        )?;
+        let before = tables.books[&event.book_id].snapshot();
+        tables.delete_book(event.book_id);
+        tables.record_audit(RecordAudit::new(
+            Some(event.requested_user),
+            AuditAction::Delete,
+            AuditTarget::book(event.book_id),
+            before,
+            None,
+        ));
+        tables.queue_webhook_event(
+            WebhookEventType::BookDeleted,
+            serde_json::json!({ "bookId": event.book_id }),
+            Utc::now(),
+        );
+        Ok(())
+    }
+    async fn create_copy(&self, event: CreateBookCopy) -> AppResult<BookCopyId> {
+        let mut tables = self.store.write();
+        ensure_owner(
+            &tables,
+            event.book_id,
+            event.requested_user,
+            event.manages_books,
+        )?;
+
+        let copy_id = BookCopyId::new();
+        let barcode = event.barcode.unwrap_or_else(|| default_barcode(copy_id));
+        tables.insert_copy(
+            copy_id,
+            event.book_id,
+            barcode,
+            event.condition,
+            event.shelf_location,
+        )?;
+        tables.record_copy_audit(
+            Some(event.requested_user),
+            AuditAction::Create,
+            copy_id,
+            None,
+        );
+
+        Ok(copy_id)
+    }
+    async fn find_copies(&self, book_id: BookId) -> AppResult<Vec<BookCopy>> {
+        Ok(self.store.read().copies(book_id))
+    }
+    async fn update_copy(&self, event: UpdateBookCopy) -> AppResult<()> {
+        let mut tables = self.store.write();
+        ensure_owner(
+            &tables,
+            event.book_id,
+            event.requested_user,
+            event.manages_books,
+        )?;
+
+        let barcode_taken = tables
+            .copies
+            .values()
+            .any(|c| c.barcode == event.barcode && c.id != event.copy_id);
+        if barcode_taken {
+            return Err(AppError::UnprocessableEntity(format!(
+                "Barcode {} is already in use",
+                event.barcode
+            )));
+        }
+
+        let before = match tables.copies.get_mut(&event.copy_id) {
+            Some(copy) if copy.book_id == event.book_id => {
+                let before = copy.snapshot();
+                copy.barcode = event.barcode;
+                copy.condition = event.condition;
+                copy.shelf_location = event.shelf_location;
+                before
+            }
+            _ => {
+                return Err(AppError::EntityNotFound(
+                    "specified book copy not found".into(),
+                ));
+            }
+        };
+        tables.record_copy_audit(
+            Some(event.requested_user),
+            AuditAction::Update,
+            event.copy_id,
+            before,
+        );
+        Ok(())
+    }
+    async fn delete_copy(&self, event: DeleteBookCopy) -> AppResult<()> {
+        let mut tables = self.store.write();
+        ensure_owner(
+            &tables,
+            event.book_id,
+            event.requested_user,
+            event.manages_books,
+        )?;
+
+        if tables.checkout_of(event.copy_id).is_some() {
+            return Err(AppError::UnprocessableEntity(format!(
+                "Book copy with id {} is checked out",
+                event.copy_id
+            )));
+        }
+
+        match tables.copies.get(&event.copy_id) {
+            Some(copy) if copy.book_id == event.book_id => {
+                let before = copy.snapshot();
+                tables.copies.remove(&event.copy_id);
+                tables.record_audit(RecordAudit::new(
+                    Some(event.requested_user),
+                    AuditAction::Delete,
+                    AuditTarget::book_copy(event.copy_id),
+                    before,
+                    None,
+                ));
+                Ok(())
+            }
+            _ => Err(AppError::EntityNotFound(
+                "specified book copy not found".into(),
+            )),
+        }
+    }
+}
+
+// applies the keyword, owner and availability filters of `BookListOptions`
+fn filter_books<'a>(tables: &'a Tables, options: &BookListOptions) -> Vec<&'a BookRecord> {
+    let keyword = options.keyword.as_deref().map(str::to_lowercase);
+    tables
+        .books
+        .values()
+        .filter(|b| {
+            keyword.as_deref().is_none_or(|k| {
+                [&b.title, &b.author, &b.description, &b.isbn]
+                    .iter()
+                    .any(|column| column.to_lowercase().contains(k))
+            })
+        })
+        .filter(|b| options.owner.is_none_or(|owner| b.owner == owner))
+        .filter(|b| {
+            options
+                .checked_out
+                .is_none_or(|checked_out| (tables.free_copies(b.id) == 0) == checked_out)
+        })
+        .collect()
+}
+
+fn ensure_owner(
+    tables: &Tables,
+    book_id: BookId,
+    user_id: UserId,
+    manages_books: bool,
+) -> AppResult<()> {
+    match tables.books.get(&book_id) {
+        Some(book) if book.owner == user_id || manages_books => Ok(()),
+        _ => Err(AppError::EntityNotFound("specified book not found".into())),
+    }
+}
@@ -0,0 +1,554 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use derive_new::new;
+use kernel::{
+    model::{
+        audit::{AuditAction, AuditTarget, event::RecordAudit},
+        checkout::{
+            Checkout,
+            event::{CreateCheckout, RenewCheckout, UpdateReturned},
+        },
+        id::{BookCopyId, BookId, CheckoutId, UserId},
+        list::{CursorDirection, CursorListOptions, CursorPaginatedList},
+        notification::{NotificationKind, event::QueueNotification},
+        webhook::WebhookEventType,
+    },
+    repository::checkout::CheckoutRepository,
+};
+use shared::{
+    config::CheckoutConfig,
+    error::{AppError, AppResult},
+};
+
+use super::{CheckoutRecord, InMemoryStore, Tables, paginate_by_cursor, stored};
+use crate::repository::{
+    audit::{checkout_snapshot, renewal_snapshot, return_snapshot},
+    checkout::{claim_period, enforce_borrowing_policy, loan_period},
+};
+
+#[derive(new)]
+pub struct InMemoryCheckoutRepository {
+    store: InMemoryStore,
+    config: CheckoutConfig,
+}
+
+#[async_trait]
+impl CheckoutRepository for InMemoryCheckoutRepository {
+    async fn create(&self, event: CreateCheckout) -> AppResult<()> {
+        let mut tables = self.store.write();
+
+        if !tables.books.contains_key(&event.book_id) {
+            return Err(AppError::EntityNotFound(format!(
+                "Book with id {} not found",
+                event.book_id
+            )));
+        }
+        if tables
+            .checkouts
+            .values()
+            .any(|c| c.book_id == event.book_id && c.user_id == event.checked_out_by)
+        {
+            return Err(AppError::UnprocessableEntity(format!(
+                "Book with id {} is already checked out by the requested user",
+                event.book_id
+            )));
+        }
+
+        let role = tables
+            .users
+            .get(&event.checked_out_by)
+            .map(|u| u.role.clone())
+            .ok_or_else(|| {
+                AppError::EntityNotFound(format!("User with id {} not found", event.checked_out_by))
+            })?;
+        let loans = tables
+            .checkouts
+            .values()
+            .filter(|c| c.user_id == event.checked_out_by);
+        let loan_count = loans.clone().count() as i64;
+        let has_overdue = loans.clone().any(|c| c.due_at < event.checked_out_at);
+        let owns_book = tables.books[&event.book_id].owner == event.checked_out_by;
+        enforce_borrowing_policy(
+            &tables
+                .borrowing_policies
+                .get(&role)
+                .copied()
+                .unwrap_or_default(),
+            loan_count,
+            has_overdue,
+            owns_book,
+        )?;
+
+        tables.refresh_claims(
+            event.book_id,
+            event.checked_out_at,
+            claim_period(&self.config),
+        );
+        let holds_claim = tables.reservations.values().any(|r| {
+            r.book_id == event.book_id
+                && r.user_id == event.checked_out_by
+                && r.claim_expires_at
+                    .is_some_and(|at| at > event.checked_out_at)
+        });
+        if !holds_claim && tables.unclaimed_copies(event.book_id, event.checked_out_at) <= 0 {
+            return Err(AppError::UnprocessableEntity(format!(
+                "No copy of book with id {} is available for the requested user",
+                event.book_id
+            )));
+        }
+
+        let copy_id = find_available_copy(&tables, event.book_id, event.copy_id)?;
+
+        let due_at = event.checked_out_at + loan_period(&self.config, &role);
+
+        let checkout_id = CheckoutId::new();
+        tables.checkouts.insert(
+            checkout_id,
+            CheckoutRecord {
+                id: checkout_id,
+                book_id: event.book_id,
+                copy_id,
+                user_id: event.checked_out_by,
+                issued_by: event.issued_by,
+                checked_out_at: stored(event.checked_out_at),
+                due_at: stored(due_at),
+                renewal_count: 0,
+                returned_at: None,
+                returned_by: None,
+                return_reason: None,
+            },
+        );
+
+        // the borrower's own place in the queue is fulfilled by this checkout
+        tables
+            .reservations
+            .retain(|_, r| r.book_id != event.book_id || r.user_id != event.checked_out_by);
+
+        tables.record_audit(RecordAudit::new(
+            Some(event.issued_by),
+            AuditAction::Create,
+            AuditTarget::checkout(checkout_id),
+            None,
+            checkout_snapshot(
+                event.book_id,
+                copy_id,
+                event.checked_out_by,
+                event.issued_by,
+                event.checked_out_at,
+                due_at,
+            ),
+        ));
+        tables.queue_notification(&QueueNotification::new(
+            event.checked_out_by,
+            NotificationKind::CheckoutCreated,
+            event.book_id,
+            Some(due_at),
+            None,
+            event.checked_out_at,
+        ));
+        tables.queue_webhook_event(
+            WebhookEventType::CheckoutCreated,
+            serde_json::json!({
+                "checkoutId": checkout_id,
+                "bookId": event.book_id,
+                "copyId": copy_id,
+                "userId": event.checked_out_by,
+                "issuedBy": event.issued_by,
+                "checkedOutAt": event.checked_out_at,
+                "dueAt": due_at,
+            }),
+            event.checked_out_at,
+        );
+
+        Ok(())
+    }
+    async fn update_returned(&self, event: UpdateReturned) -> AppResult<()> {
+        let mut tables = self.store.write();
+
+        if !tables.books.contains_key(&event.book_id) {
+            return Err(AppError::EntityNotFound(format!(
+                "Book with id {} not found",
+                event.book_id
+            )));
+        }
+        let valid = tables.checkouts.get(&event.checkout_id).is_some_and(|c| {
+            c.book_id == event.book_id
+                && (c.user_id == event.returned_by || event.manages_checkouts)
+        });
+        let Some(mut checkout) = valid
+            .then(|| tables.checkouts.remove(&event.checkout_id))
+            .flatten()
+        else {
+            return Err(AppError::UnprocessableEntity(format!(
+                "Specified checkout record is invalid: checkout_id={}, book_id={}, returned_by={}",
+                event.checkout_id, event.book_id, event.returned_by
+            )));
+        };
+
+        checkout.returned_at = Some(stored(event.returned_at));
+        checkout.returned_by = Some(event.returned_by);
+        checkout.return_reason = event.reason.clone();
+        tables.record_audit(RecordAudit::new(
+            Some(event.returned_by),
+            AuditAction::Return,
+            AuditTarget::checkout(event.checkout_id),
+            None,
+            return_snapshot(
+                event.returned_by,
+                event.returned_at,
+                event.reason.as_deref(),
+            ),
+        ));
+        tables.queue_notification(&QueueNotification::new(
+            checkout.user_id,
+            NotificationKind::CheckoutReturned,
+            event.book_id,
+            None,
+            None,
+            event.returned_at,
+        ));
+        tables.queue_webhook_event(
+            WebhookEventType::CheckoutReturned,
+            serde_json::json!({
+                "checkoutId": event.checkout_id,
+                "bookId": event.book_id,
+                "userId": checkout.user_id,
+                "returnedBy": event.returned_by,
+                "returnedAt": event.returned_at,
+                "reason": event.reason,
+            }),
+            event.returned_at,
+        );
+        tables.returned_checkouts.push(checkout);
+
+        tables.refresh_claims(event.book_id, event.returned_at, claim_period(&self.config));
+
+        Ok(())
+    }
+    async fn renew(&self, event: RenewCheckout) -> AppResult<()> {
+        let mut tables = self.store.write();
+
+        let (book_id, user_id, renewal_count, previous_due_at) = tables
+            .checkouts
+            .get(&event.checkout_id)
+            .map(|c| (c.book_id, c.user_id, c.renewal_count, c.due_at))
+            .ok_or_else(|| {
+                AppError::EntityNotFound(format!(
+                    "Checkout with id {} not found",
+                    event.checkout_id
+                ))
+            })?;
+        if (book_id, user_id) != (event.book_id, event.renewed_by) {
+            return Err(AppError::UnprocessableEntity(format!(
+                "Specified checkout record is invalid: checkout_id={}, book_id={}, renewed_by={}",
+                event.checkout_id, event.book_id, event.renewed_by
+            )));
+        }
+        if renewal_count >= self.config.max_renewals {
+            return Err(AppError::UnprocessableEntity(format!(
+                "Checkout with id {} has reached the renewal limit of {}",
+                event.checkout_id, self.config.max_renewals
+            )));
+        }
+
+        if tables
+            .reservations
+            .values()
+            .any(|r| r.book_id == event.book_id)
+        {
+            return Err(AppError::UnprocessableEntity(format!(
+                "Book with id {} is reserved by another user and cannot be renewed",
+                event.book_id
+            )));
+        }
+
+        let role = tables
+            .users
+            .get(&user_id)
+            .map(|u| u.role.clone())
+            .ok_or_else(|| AppError::EntityNotFound(format!("User with id {user_id} not found")))?;
+        let due_at = stored(event.renewed_at + loan_period(&self.config, &role));
+        if let Some(checkout) = tables.checkouts.get_mut(&event.checkout_id) {
+            checkout.due_at = due_at;
+            checkout.renewal_count += 1;
+        }
+        tables.record_audit(RecordAudit::new(
+            Some(event.renewed_by),
+            AuditAction::Renew,
+            AuditTarget::checkout(event.checkout_id),
+            renewal_snapshot(previous_due_at, renewal_count),
+            renewal_snapshot(due_at, renewal_count + 1),
+        ));
+
+        Ok(())
+    }
+    async fn find_unreturned_all(&self) -> AppResult<Vec<Checkout>> {
+        let tables = self.store.read();
+        Ok(find_open(
+            &tables,
+            |_| true,
+            |a, b| b.checked_out_at.cmp(&a.checked_out_at),
+        ))
+    }
+    async fn find_unreturned_by_user_id(&self, user_id: UserId) -> AppResult<Vec<Checkout>> {
+        let tables = self.store.read();
+        Ok(find_open(
+            &tables,
+            |c| c.user_id == user_id,
+            |a, b| b.checked_out_at.cmp(&a.checked_out_at),
+        ))
+    }
+    async fn find_overdue_all(&self, now: DateTime<Utc>) -> AppResult<Vec<Checkout>> {
+        let tables = self.store.read();
+        Ok(find_open(
+            &tables,
+            |c| c.due_at < now,
+            |a, b| a.due_at.cmp(&b.due_at),
+        ))
+    }
+    async fn find_overdue_by_user_id(
+        &self,
+        user_id: UserId,
+        now: DateTime<Utc>,
+    ) -> AppResult<Vec<Checkout>> {
+        let tables = self.store.read();
+        Ok(find_open(
+            &tables,
+            |c| c.user_id == user_id && c.due_at < now,
+            |a, b| a.due_at.cmp(&b.due_at),
+        ))
+    }
+    async fn find_history_by_book_id(
+        &self,
+        book_id: BookId,
+        options: CursorListOptions,
+    ) -> AppResult<CursorPaginatedList<Checkout>> {
+        let CursorListOptions { limit, cursor } = options;
+        let tables = self.store.read();
+        let descending = cursor.is_none_or(|c| c.direction == CursorDirection::Next);
+
+        let rows = tables
+            .checkouts
+            .values()
+            .chain(tables.returned_checkouts.iter())
+            .filter(|c| c.book_id == book_id)
+            .filter_map(|c| tables.checkout(c))
+            .collect();
+
+        Ok(paginate_by_cursor(
+            rows,
+            limit,
+            cursor,
+            descending,
+            |c: &Checkout| (c.checked_out_at, c.id.raw()),
+        ))
+    }
+}
+
+fn find_open(
+    tables: &Tables,
+    filter: impl Fn(&CheckoutRecord) -> bool,
+    order: impl Fn(&CheckoutRecord, &CheckoutRecord) -> std::cmp::Ordering,
+) -> Vec<Checkout> {
+    let mut rows = tables
+        .checkouts
+        .values()
+        .filter(|c| filter(c))
+        .collect::<Vec<_>>();
+    rows.sort_by(|a, b| order(a, b));
+    rows.into_iter()
+        .filter_map(|c| tables.checkout(c))
+        .collect()
+}
+
+// picks the requested copy, or the longest-held copy on the shelf when none is given
+fn find_available_copy(
+    tables: &Tables,
+    book_id: BookId,
+    copy_id: Option<BookCopyId>,
+) -> AppResult<BookCopyId> {
+    let copies = tables.copy_records(book_id);
+
+    match copy_id {
+        Some(copy_id) => match copies.into_iter().find(|c| c.id == copy_id) {
+            None => Err(AppError::EntityNotFound(format!(
+                "Book copy with id {copy_id} not found"
+            ))),
+            Some(c) if tables.checkout_of(c.id).is_some() => Err(AppError::UnprocessableEntity(
+                format!("Book copy with id {copy_id} is already checked out"),
+            )),
+            Some(c) => Ok(c.id),
+        },
+        None => copies
+            .into_iter()
+            .find(|c| tables.checkout_of(c.id).is_none())
+            .map(|c| c.id)
+            .ok_or_else(|| {
+                AppError::UnprocessableEntity(format!(
+                    "No copy of book with id {book_id} is available"
+                ))
+            }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kernel::{
+        model::{
+            book::{
+                BookListOptions, CopyCondition,
+                event::{CreateBook, CreateBookCopy},
+            },
+            checkout::event::UpdateReturned,
+            reservation::event::CreateReservation,
+            role::BuiltinRole,
+        },
+        repository::{book::BookRepository, reservation::ReservationRepository},
+    };
+
+    use crate::repository::memory::{
+        book::InMemoryBookRepository, reservation::InMemoryReservationRepository,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_checkout_conflicts() -> anyhow::Result<()> {
+        let store = InMemoryStore::new();
+        let repo = InMemoryCheckoutRepository::new(store.clone(), Default::default());
+        let book_repo = InMemoryBookRepository::new(store.clone());
+        let reservation_repo =
+            InMemoryReservationRepository::new(store.clone(), Default::default());
+
+        let owner_id = store.insert_user(
+            "Owner",
+            "owner@example.com",
+            "test_password",
+            BuiltinRole::Admin,
+        )?;
+        let other_id = store.insert_user(
+            "Other",
+            "other@example.com",
+            "test_password",
+            BuiltinRole::User,
+        )?;
+        let waiting_id = store.insert_user(
+            "Waiting",
+            "waiting@example.com",
+            "test_password",
+            BuiltinRole::User,
+        )?;
+
+        book_repo
+            .create(
+                CreateBook {
+                    title: "The Rust Programming Language".into(),
+                    author: "Steve Klabnik and Carol Nichols".into(),
+                    isbn: "9781593278281".parse()?,
+                    description: "".into(),
+                    add_copy: false,
+                },
+                owner_id,
+            )
+            .await?;
+        let book_id = book_repo
+            .find_all(BookListOptions {
+                limit: 20,
+                ..Default::default()
+            })
+            .await?
+            .items[0]
+            .id;
+        let first_copy_id = book_repo.find_copies(book_id).await?[0].id;
+        let second_copy_id = book_repo
+            .create_copy(CreateBookCopy {
+                book_id,
+                barcode: Some("RUSTBOOK-0002".into()),
+                condition: CopyCondition::New,
+                shelf_location: "A-1".into(),
+                requested_user: owner_id,
+                manages_books: false,
+            })
+            .await?;
+
+        repo.create(CreateCheckout::new(
+            book_id,
+            Some(second_copy_id),
+            owner_id,
+            owner_id,
+            Utc::now(),
+        ))
+        .await?;
+        let res = repo
+            .create(CreateCheckout::new(
+                book_id,
+                None,
+                owner_id,
+                owner_id,
+                Utc::now(),
+            ))
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+        let res = repo
+            .create(CreateCheckout::new(
+                book_id,
+                Some(second_copy_id),
+                other_id,
+                other_id,
+                Utc::now(),
+            ))
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        repo.create(CreateCheckout::new(
+            book_id,
+            None,
+            other_id,
+            other_id,
+            Utc::now(),
+        ))
+        .await?;
+        let checkouts = repo.find_unreturned_by_user_id(other_id).await?;
+        assert_eq!(checkouts[0].book.copy_id, first_copy_id);
+
+        // a returned copy is held for the oldest reservation
+        reservation_repo
+            .create(CreateReservation::new(book_id, waiting_id, Utc::now()))
+            .await?;
+        repo.update_returned(UpdateReturned::new(
+            checkouts[0].id,
+            book_id,
+            other_id,
+            false,
+            None,
+            Utc::now(),
+        ))
+        .await?;
+        let res = repo
+            .create(CreateCheckout::new(
+                book_id,
+                None,
+                other_id,
+                other_id,
+                Utc::now(),
+            ))
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+        repo.create(CreateCheckout::new(
+            book_id,
+            None,
+            waiting_id,
+            waiting_id,
+            Utc::now(),
+        ))
+        .await?;
+        assert!(
+            reservation_repo
+                .find_by_user_id(waiting_id)
+                .await?
+                .is_empty()
+        );
+
+        Ok(())
+    }
+}
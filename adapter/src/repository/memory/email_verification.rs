@@ -0,0 +1,44 @@
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        auth::{EmailVerification, EmailVerificationToken, event::CreateEmailVerification},
+        id::UserId,
+    },
+    repository::email_verification::EmailVerificationRepository,
+};
+use shared::{config::RegistrationConfig, error::AppResult};
+
+use super::{InMemoryStore, OneTimeTokenRecord};
+use crate::repository::email_verification::email_verification;
+
+#[derive(new)]
+pub struct InMemoryEmailVerificationRepository {
+    store: InMemoryStore,
+    config: RegistrationConfig,
+}
+
+#[async_trait]
+impl EmailVerificationRepository for InMemoryEmailVerificationRepository {
+    async fn create(&self, event: CreateEmailVerification) -> AppResult<EmailVerification> {
+        self.store.write().email_verifications.insert(
+            event.token.clone(),
+            OneTimeTokenRecord {
+                user_id: event.user_id,
+                expires_at: Instant::now() + Duration::from_secs(self.config.verification_ttl),
+            },
+        );
+        Ok(email_verification(&self.config, event))
+    }
+    async fn consume(&self, token: &EmailVerificationToken) -> AppResult<Option<UserId>> {
+        Ok(self
+            .store
+            .write()
+            .email_verifications
+            .remove(&token.0)
+            .filter(|r| r.expires_at > Instant::now())
+            .map(|r| r.user_id))
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use derive_new::new;
+use kernel::{
+    model::{
+        id::JobLeaseId,
+        job::{
+            JobLease,
+            event::{CompleteJob, FailJob, ScheduleJob},
+        },
+    },
+    repository::job::JobRepository,
+};
+use shared::error::AppResult;
+
+use super::{InMemoryStore, JobRecord, stored};
+use crate::repository::job::lease_lost;
+
+#[derive(new)]
+pub struct InMemoryJobRepository {
+    store: InMemoryStore,
+}
+
+#[async_trait]
+impl JobRepository for InMemoryJobRepository {
+    async fn schedule(&self, event: ScheduleJob) -> AppResult<()> {
+        let mut tables = self.store.write();
+        let unchanged = tables
+            .jobs
+            .get(&event.name)
+            .is_some_and(|j| j.schedule == event.schedule);
+        if !unchanged {
+            let lease = tables.jobs.remove(&event.name).and_then(|j| j.lease);
+            tables.jobs.insert(
+                event.name,
+                JobRecord {
+                    schedule: event.schedule,
+                    run_at: stored(event.next_run_at),
+                    attempts: 0,
+                    lease,
+                },
+            );
+        }
+        Ok(())
+    }
+    async fn lease_due(&self, now: DateTime<Utc>, lease: Duration) -> AppResult<Option<JobLease>> {
+        let mut tables = self.store.write();
+        let due = tables
+            .jobs
+            .iter_mut()
+            .filter(|(_, j)| j.run_at <= now && j.lease.is_none_or(|(_, until)| until <= now))
+            .min_by_key(|(_, j)| j.run_at);
+        Ok(due.map(|(name, job)| {
+            let lease_id = JobLeaseId::new();
+            job.lease = Some((lease_id, stored(now + lease)));
+            JobLease {
+                name: name.clone(),
+                lease_id,
+                attempts: job.attempts,
+            }
+        }))
+    }
+    async fn complete(&self, event: CompleteJob) -> AppResult<()> {
+        let mut tables = self.store.write();
+        let job = leased(&mut tables.jobs, &event.name, event.lease_id)?;
+        job.run_at = stored(event.next_run_at);
+        job.attempts = 0;
+        job.lease = None;
+        Ok(())
+    }
+    async fn fail(&self, event: FailJob) -> AppResult<()> {
+        let mut tables = self.store.write();
+        let job = leased(&mut tables.jobs, &event.name, event.lease_id)?;
+        job.run_at = stored(event.next_run_at);
+        job.attempts = event.attempts;
+        job.lease = None;
+        Ok(())
+    }
+}
+
+fn leased<'a>(
+    jobs: &'a mut HashMap<String, JobRecord>,
+    name: &str,
+    lease_id: JobLeaseId,
+) -> AppResult<&'a mut JobRecord> {
+    jobs.get_mut(name)
+        .filter(|j| j.lease.is_some_and(|(id, _)| id == lease_id))
+        .ok_or_else(|| lease_lost(name))
+}
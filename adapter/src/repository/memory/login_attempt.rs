@@ -0,0 +1,137 @@
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{model::auth::LoginAttempt, repository::login_attempt::LoginAttemptRepository};
+use shared::{
+    config::LoginThrottleConfig,
+    error::{AppError, AppResult},
+};
+
+use super::{CounterRecord, InMemoryStore, Tables};
+use crate::{
+    database::model::login_attempt::{
+        LoginBackoffKey, LoginFailuresKey, LoginLockoutKey, normalize_email,
+    },
+    redis::model::RedisKey,
+    repository::login_attempt::backoff_secs,
+};
+
+#[derive(new)]
+pub struct InMemoryLoginAttemptRepository {
+    store: InMemoryStore,
+    config: LoginThrottleConfig,
+}
+
+#[async_trait]
+impl LoginAttemptRepository for InMemoryLoginAttemptRepository {
+    async fn check(&self, attempt: &LoginAttempt) -> AppResult<()> {
+        let mut tables = self.store.write();
+        expire(&mut tables);
+        let counters = &tables.login_counters;
+
+        let email = normalize_email(&attempt.email);
+        for key in [
+            LoginLockoutKey(email.clone()).inner(),
+            LoginBackoffKey(email).inner(),
+        ] {
+            if let Some(counter) = counters.get(&key) {
+                return Err(too_many_requests(counter));
+            }
+        }
+
+        if let Some(ip) = &attempt.ip_address
+            && let Some(counter) = counters.get(&LoginFailuresKey::Ip(ip.clone()).inner())
+            && counter.count >= self.config.max_failures_per_ip
+        {
+            return Err(too_many_requests(counter));
+        }
+        Ok(())
+    }
+    async fn record_failure(&self, attempt: &LoginAttempt) -> AppResult<()> {
+        let mut tables = self.store.write();
+        expire(&mut tables);
+        let lockout = self.config.lockout_secs;
+
+        let email = normalize_email(&attempt.email);
+        if let Some(ip) = &attempt.ip_address {
+            increment(&mut tables, LoginFailuresKey::Ip(ip.clone()), lockout);
+        }
+
+        let failures = increment(&mut tables, LoginFailuresKey::Email(email.clone()), lockout);
+        if failures >= self.config.max_failures {
+            set(
+                &mut tables,
+                LoginLockoutKey(email.clone()),
+                failures,
+                lockout,
+            );
+            // the count starts over once the lockout ends
+            tables
+                .login_counters
+                .remove(&LoginFailuresKey::Email(email).inner());
+            return Ok(());
+        }
+
+        let backoff = backoff_secs(&self.config, failures);
+        if backoff > 0 {
+            set(&mut tables, LoginBackoffKey(email), failures, backoff);
+        }
+        Ok(())
+    }
+    async fn record_success(&self, attempt: &LoginAttempt) -> AppResult<()> {
+        let email = normalize_email(&attempt.email);
+        let mut tables = self.store.write();
+        tables
+            .login_counters
+            .remove(&LoginBackoffKey(email.clone()).inner());
+        tables
+            .login_counters
+            .remove(&LoginFailuresKey::Email(email).inner());
+        Ok(())
+    }
+    async fn unlock(&self, email: &str) -> AppResult<()> {
+        let email = normalize_email(email);
+        let mut tables = self.store.write();
+        for key in [
+            LoginLockoutKey(email.clone()).inner(),
+            LoginBackoffKey(email.clone()).inner(),
+            LoginFailuresKey::Email(email).inner(),
+        ] {
+            tables.login_counters.remove(&key);
+        }
+        Ok(())
+    }
+}
+
+fn expire(tables: &mut Tables) {
+    let now = Instant::now();
+    tables.login_counters.retain(|_, c| c.expires_at > now);
+}
+
+fn increment(tables: &mut Tables, key: impl RedisKey, ttl: u64) -> u64 {
+    let count = tables
+        .login_counters
+        .get(&key.inner())
+        .map_or(0, |c| c.count)
+        + 1;
+    set(tables, key, count, ttl);
+    count
+}
+
+fn set(tables: &mut Tables, key: impl RedisKey, count: u64, ttl: u64) {
+    tables.login_counters.insert(
+        key.inner(),
+        CounterRecord {
+            count,
+            expires_at: Instant::now() + Duration::from_secs(ttl),
+        },
+    );
+}
+
+fn too_many_requests(counter: &CounterRecord) -> AppError {
+    let remaining = counter.expires_at.saturating_duration_since(Instant::now());
+    AppError::TooManyRequests {
+        retry_after: remaining.as_secs_f64().ceil() as u64,
+    }
+}
@@ -0,0 +1,48 @@
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        auth::{LoginChallenge, LoginChallengeToken, event::CreateLoginChallenge},
+        id::UserId,
+    },
+    repository::login_challenge::LoginChallengeRepository,
+};
+use shared::{config::TotpConfig, error::AppResult};
+
+use super::{InMemoryStore, OneTimeTokenRecord};
+use crate::repository::login_challenge::login_challenge;
+
+#[derive(new)]
+pub struct InMemoryLoginChallengeRepository {
+    store: InMemoryStore,
+    config: TotpConfig,
+}
+
+#[async_trait]
+impl LoginChallengeRepository for InMemoryLoginChallengeRepository {
+    async fn create(&self, event: CreateLoginChallenge) -> AppResult<LoginChallenge> {
+        self.store.write().login_challenges.insert(
+            event.token.clone(),
+            OneTimeTokenRecord {
+                user_id: event.user_id,
+                expires_at: Instant::now() + Duration::from_secs(self.config.challenge_ttl),
+            },
+        );
+        Ok(login_challenge(&self.config, event))
+    }
+    async fn find(&self, token: &LoginChallengeToken) -> AppResult<Option<UserId>> {
+        Ok(self
+            .store
+            .read()
+            .login_challenges
+            .get(&token.0)
+            .filter(|r| r.expires_at > Instant::now())
+            .map(|r| r.user_id))
+    }
+    async fn delete(&self, token: &LoginChallengeToken) -> AppResult<()> {
+        self.store.write().login_challenges.remove(&token.0);
+        Ok(())
+    }
+}
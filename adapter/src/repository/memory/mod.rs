@@ -0,0 +1,785 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    time::Instant,
+};
+
+use chrono::{DateTime, Duration, SubsecRound, Utc};
+use kernel::model::{
+    api_key::{ApiKey, ApiKeyScope},
+    audit::{AuditAction, AuditEntry, AuditTarget, event::RecordAudit},
+    auth::Session,
+    book::{Book, BookCopy, Checkout as CopyCheckout, CopyCondition},
+    checkout::{Checkout, CheckoutBook},
+    id::{
+        ApiKeyId, AuditEntryId, BookCopyId, BookId, CheckoutId, JobLeaseId, NotificationId,
+        ReservationId, SessionId, UserId, WebhookDeliveryId, WebhookId,
+    },
+    list::{Cursor, CursorPaginatedList},
+    notification::{NotificationKind, event::QueueNotification},
+    reservation::Reservation,
+    role::{BorrowingPolicy, BuiltinRole, Permission, Role},
+    user::{BookOwner, CheckoutUser, User, UserStatus},
+    webhook::{DeliveryStatus, Webhook, WebhookDelivery, WebhookEventType},
+};
+use shared::error::{AppError, AppResult};
+use strum::IntoEnumIterator;
+
+use crate::{
+    password::{Argon2PasswordHasher, PasswordHasher},
+    repository::{
+        audit::{book_snapshot, copy_snapshot, user_snapshot},
+        webhook::webhook_payload,
+    },
+};
+
+pub mod api_key;
+pub mod audit;
+pub mod auth;
+pub mod book;
+pub mod checkout;
+pub mod email_verification;
+pub mod health;
+pub mod job;
+pub mod login_attempt;
+pub mod login_challenge;
+pub mod notification;
+pub mod password_reset;
+pub mod reservation;
+pub mod role;
+pub mod totp;
+pub mod user;
+pub mod webhook;
+
+// the administrator `data/initial_setup.sql` seeds into a fresh database
+const INITIAL_ADMIN_NAME: &str = "Eleazar Fig";
+const INITIAL_ADMIN_EMAIL: &str = "eleazar.fig@example.com";
+const INITIAL_ADMIN_PASSWORD_HASH: &str = "$argon2id$v=19$m=19456,t=2,p=1$liG3Pqxc+NgWtnKuWTMXiA$suCFu/0jYlKbJuFwofAv7gGVgOoszQNLQaixhF5iq/I";
+
+// tables backing the in-memory repositories; repositories built from clones of
+// the same store see each other's writes, like repositories sharing a pool
+#[derive(Clone)]
+pub struct InMemoryStore {
+    tables: Arc<RwLock<Tables>>,
+}
+
+// holds the built-in roles, which come with the migrations
+impl Default for InMemoryStore {
+    fn default() -> Self {
+        let tables = Tables {
+            roles: BuiltinRole::iter()
+                .map(|role| (role.as_ref().to_string(), role.permissions()))
+                .collect(),
+            borrowing_policies: BuiltinRole::iter()
+                .map(|role| (role.as_ref().to_string(), role.borrowing_policy()))
+                .collect(),
+            ..Default::default()
+        };
+        Self {
+            tables: Arc::new(RwLock::new(tables)),
+        }
+    }
+}
+
+impl InMemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // a store holding what `data/initial_setup.sql` inserts
+    pub fn with_initial_setup() -> Self {
+        let store = Self::new();
+        store.write().insert_user(
+            UserId::new(),
+            INITIAL_ADMIN_NAME,
+            INITIAL_ADMIN_EMAIL,
+            INITIAL_ADMIN_PASSWORD_HASH.into(),
+            BuiltinRole::Admin.as_ref().into(),
+            UserStatus::Active,
+        );
+        store
+    }
+
+    // registers a user with any role, which the `UserRepository` cannot do on its own
+    pub fn insert_user(
+        &self,
+        name: &str,
+        email: &str,
+        password: &str,
+        role: BuiltinRole,
+    ) -> AppResult<UserId> {
+        let password_hash = Argon2PasswordHasher::default().hash(password)?;
+        Ok(self.write().insert_user(
+            UserId::new(),
+            name,
+            email,
+            password_hash,
+            role.as_ref().into(),
+            UserStatus::Active,
+        ))
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Tables> {
+        self.tables.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Tables> {
+        self.tables.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[derive(Default)]
+struct Tables {
+    // permissions by role name
+    roles: BTreeMap<String, Vec<Permission>>,
+    // by role name, for every role in `roles`
+    borrowing_policies: HashMap<String, BorrowingPolicy>,
+    users: HashMap<UserId, UserRecord>,
+    books: HashMap<BookId, BookRecord>,
+    copies: HashMap<BookCopyId, CopyRecord>,
+    checkouts: HashMap<CheckoutId, CheckoutRecord>,
+    returned_checkouts: Vec<CheckoutRecord>,
+    reservations: HashMap<ReservationId, ReservationRecord>,
+    tokens: HashMap<String, TokenRecord>,
+    refresh_tokens: HashMap<String, RefreshTokenRecord>,
+    sessions: HashMap<SessionId, SessionRecord>,
+    // login throttling counters, keyed like their Redis counterparts
+    login_counters: HashMap<String, CounterRecord>,
+    password_resets: HashMap<String, OneTimeTokenRecord>,
+    email_verifications: HashMap<String, OneTimeTokenRecord>,
+    login_challenges: HashMap<String, OneTimeTokenRecord>,
+    totp: HashMap<UserId, TotpRecord>,
+    api_keys: HashMap<ApiKeyId, ApiKeyRecord>,
+    jobs: HashMap<String, JobRecord>,
+    notifications: HashMap<NotificationId, NotificationRecord>,
+    // only the kinds users changed the setting of
+    notification_preferences: HashMap<(UserId, NotificationKind), bool>,
+    webhooks: HashMap<WebhookId, WebhookRecord>,
+    webhook_deliveries: HashMap<WebhookDeliveryId, WebhookDeliveryRecord>,
+    // in the order the entries were recorded
+    audit_log: Vec<AuditEntry>,
+}
+
+struct UserRecord {
+    id: UserId,
+    name: String,
+    email: String,
+    password_hash: String,
+    // the name of the role
+    role: String,
+    status: UserStatus,
+    created_at: DateTime<Utc>,
+}
+
+impl UserRecord {
+    fn snapshot(&self) -> Option<String> {
+        user_snapshot(&self.name, &self.email, &self.role, self.status.as_ref())
+    }
+}
+
+struct BookRecord {
+    id: BookId,
+    title: String,
+    author: String,
+    isbn: String,
+    description: String,
+    owner: UserId,
+    created_at: DateTime<Utc>,
+}
+
+impl BookRecord {
+    fn snapshot(&self) -> Option<String> {
+        book_snapshot(
+            &self.title,
+            &self.author,
+            &self.isbn,
+            &self.description,
+            self.owner,
+        )
+    }
+}
+
+struct CopyRecord {
+    id: BookCopyId,
+    book_id: BookId,
+    barcode: String,
+    condition: CopyCondition,
+    shelf_location: String,
+    created_at: DateTime<Utc>,
+}
+
+impl CopyRecord {
+    fn snapshot(&self) -> Option<String> {
+        copy_snapshot(
+            self.book_id,
+            &self.barcode,
+            self.condition.as_ref(),
+            &self.shelf_location,
+        )
+    }
+}
+
+#[derive(Clone)]
+struct CheckoutRecord {
+    id: CheckoutId,
+    book_id: BookId,
+    copy_id: BookCopyId,
+    user_id: UserId,
+    issued_by: UserId,
+    checked_out_at: DateTime<Utc>,
+    due_at: DateTime<Utc>,
+    renewal_count: i32,
+    returned_at: Option<DateTime<Utc>>,
+    returned_by: Option<UserId>,
+    return_reason: Option<String>,
+}
+
+struct ReservationRecord {
+    id: ReservationId,
+    book_id: BookId,
+    user_id: UserId,
+    reserved_at: DateTime<Utc>,
+    claim_expires_at: Option<DateTime<Utc>>,
+}
+
+impl ReservationRecord {
+    fn to_reservation(&self) -> Reservation {
+        Reservation {
+            id: self.id,
+            book_id: self.book_id,
+            reserved_by: self.user_id,
+            reserved_at: self.reserved_at,
+            claim_expires_at: self.claim_expires_at,
+        }
+    }
+}
+
+struct TokenRecord {
+    user_id: UserId,
+    session_id: SessionId,
+    expires_at: Instant,
+}
+
+struct RefreshTokenRecord {
+    session_id: SessionId,
+    used: bool,
+    expires_at: Instant,
+}
+
+// a mailed token that resolves to its user once, such as a password reset
+struct OneTimeTokenRecord {
+    user_id: UserId,
+    expires_at: Instant,
+}
+
+struct TotpRecord {
+    secret: String,
+    enabled: bool,
+    last_used_step: Option<i64>,
+    // hashes of the recovery codes, and whether each was used
+    recovery_codes: Vec<(String, bool)>,
+}
+
+struct JobRecord {
+    schedule: String,
+    run_at: DateTime<Utc>,
+    attempts: i32,
+    lease: Option<(JobLeaseId, DateTime<Utc>)>,
+}
+
+struct NotificationRecord {
+    user_id: UserId,
+    kind: NotificationKind,
+    book_id: BookId,
+    due_at: Option<DateTime<Utc>>,
+    key: Option<String>,
+    created_at: DateTime<Utc>,
+    attempts: i32,
+    next_attempt_at: Option<DateTime<Utc>>,
+    sent_at: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+}
+
+struct WebhookRecord {
+    url: String,
+    secret: String,
+    event_types: Vec<WebhookEventType>,
+    created_by: UserId,
+    created_at: DateTime<Utc>,
+}
+
+impl WebhookRecord {
+    fn to_webhook(&self, id: WebhookId) -> Webhook {
+        Webhook {
+            id,
+            url: self.url.clone(),
+            event_types: self.event_types.clone(),
+            created_by: self.created_by,
+            created_at: self.created_at,
+        }
+    }
+}
+
+struct WebhookDeliveryRecord {
+    webhook_id: WebhookId,
+    event_type: WebhookEventType,
+    payload: String,
+    status: DeliveryStatus,
+    attempts: i32,
+    next_attempt_at: Option<DateTime<Utc>>,
+    last_attempt_at: Option<DateTime<Utc>>,
+    response_status: Option<i32>,
+    last_error: Option<String>,
+    created_at: DateTime<Utc>,
+    delivered_at: Option<DateTime<Utc>>,
+}
+
+impl WebhookDeliveryRecord {
+    fn to_delivery(&self, id: WebhookDeliveryId) -> WebhookDelivery {
+        WebhookDelivery {
+            id,
+            webhook_id: self.webhook_id,
+            event_type: self.event_type,
+            payload: self.payload.clone(),
+            status: self.status,
+            attempts: self.attempts,
+            next_attempt_at: self.next_attempt_at,
+            last_attempt_at: self.last_attempt_at,
+            response_status: self.response_status,
+            last_error: self.last_error.clone(),
+            created_at: self.created_at,
+            delivered_at: self.delivered_at,
+        }
+    }
+}
+
+struct ApiKeyRecord {
+    user_id: UserId,
+    name: String,
+    prefix: String,
+    key_hash: String,
+    scopes: Vec<ApiKeyScope>,
+    created_at: DateTime<Utc>,
+    last_used_at: Option<DateTime<Utc>>,
+    revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKeyRecord {
+    fn to_api_key(&self, id: ApiKeyId) -> ApiKey {
+        ApiKey {
+            id,
+            user_id: self.user_id,
+            name: self.name.clone(),
+            prefix: self.prefix.clone(),
+            scopes: self.scopes.clone(),
+            created_at: self.created_at,
+            last_used_at: self.last_used_at,
+        }
+    }
+}
+
+struct CounterRecord {
+    count: u64,
+    expires_at: Instant,
+}
+
+struct SessionRecord {
+    user_id: UserId,
+    access_token: String,
+    refresh_token: String,
+    user_agent: Option<String>,
+    ip_address: Option<String>,
+    created_at: DateTime<Utc>,
+    last_used_at: DateTime<Utc>,
+    expires_at: Instant,
+}
+
+impl SessionRecord {
+    fn to_session(&self, id: SessionId) -> Session {
+        Session {
+            id,
+            user_id: self.user_id,
+            user_agent: self.user_agent.clone(),
+            ip_address: self.ip_address.clone(),
+            created_at: self.created_at,
+            last_used_at: self.last_used_at,
+        }
+    }
+}
+
+impl Tables {
+    fn insert_user(
+        &mut self,
+        id: UserId,
+        name: &str,
+        email: &str,
+        password_hash: String,
+        role: String,
+        status: UserStatus,
+    ) -> UserId {
+        self.users.insert(
+            id,
+            UserRecord {
+                id,
+                name: name.into(),
+                email: email.into(),
+                password_hash,
+                role,
+                status,
+                created_at: stored(Utc::now()),
+            },
+        );
+        id
+    }
+
+    fn user(&self, record: &UserRecord) -> User {
+        User {
+            id: record.id,
+            name: record.name.clone(),
+            email: record.email.clone(),
+            // roles are never deleted, so the user's one is there
+            role: Role {
+                name: record.role.clone(),
+                permissions: self.roles.get(&record.role).cloned().unwrap_or_default(),
+            },
+            status: record.status,
+        }
+    }
+
+    fn book(&self, book_id: BookId) -> Option<Book> {
+        let record = self.books.get(&book_id)?;
+        let owner = self.users.get(&record.owner)?;
+        Some(Book {
+            id: record.id,
+            title: record.title.clone(),
+            author: record.author.clone(),
+            isbn: record.isbn.clone(),
+            description: record.description.clone(),
+            owner: BookOwner {
+                id: owner.id,
+                name: owner.name.clone(),
+            },
+            copies: self.copies(book_id),
+        })
+    }
+
+    fn copies(&self, book_id: BookId) -> Vec<BookCopy> {
+        self.copy_records(book_id)
+            .into_iter()
+            .map(|copy| BookCopy {
+                id: copy.id,
+                book_id: copy.book_id,
+                barcode: copy.barcode.clone(),
+                condition: copy.condition,
+                shelf_location: copy.shelf_location.clone(),
+                checkout: self.checkout_of(copy.id).and_then(|c| {
+                    let user = self.users.get(&c.user_id)?;
+                    Some(CopyCheckout {
+                        checkout_id: c.id,
+                        checked_out_by: CheckoutUser {
+                            id: user.id,
+                            name: user.name.clone(),
+                        },
+                        checked_out_at: c.checked_out_at,
+                    })
+                }),
+            })
+            .collect()
+    }
+
+    fn insert_copy(
+        &mut self,
+        copy_id: BookCopyId,
+        book_id: BookId,
+        barcode: String,
+        condition: CopyCondition,
+        shelf_location: String,
+    ) -> AppResult<()> {
+        if self.copies.values().any(|c| c.barcode == barcode) {
+            return Err(AppError::UnprocessableEntity(format!(
+                "Barcode {barcode} is already in use"
+            )));
+        }
+        self.copies.insert(
+            copy_id,
+            CopyRecord {
+                id: copy_id,
+                book_id,
+                barcode,
+                condition,
+                shelf_location,
+                created_at: stored(Utc::now()),
+            },
+        );
+        Ok(())
+    }
+
+    // copies of a title, longest held first
+    fn copy_records(&self, book_id: BookId) -> Vec<&CopyRecord> {
+        let mut copies = self
+            .copies
+            .values()
+            .filter(|c| c.book_id == book_id)
+            .collect::<Vec<_>>();
+        copies.sort_by_key(|c| (c.created_at, c.id.raw()));
+        copies
+    }
+
+    fn checkout_of(&self, copy_id: BookCopyId) -> Option<&CheckoutRecord> {
+        self.checkouts.values().find(|c| c.copy_id == copy_id)
+    }
+
+    fn free_copies(&self, book_id: BookId) -> i64 {
+        self.copy_records(book_id)
+            .into_iter()
+            .filter(|c| self.checkout_of(c.id).is_none())
+            .count() as i64
+    }
+
+    fn active_claims(&self, book_id: BookId, now: DateTime<Utc>) -> i64 {
+        self.reservations
+            .values()
+            .filter(|r| r.book_id == book_id && r.claim_expires_at.is_some_and(|at| at > now))
+            .count() as i64
+    }
+
+    // counts copies on the shelf that are not held for a reservation
+    fn unclaimed_copies(&self, book_id: BookId, now: DateTime<Utc>) -> i64 {
+        self.free_copies(book_id) - self.active_claims(book_id, now)
+    }
+
+    // same as `repository::reservation::refresh_claims`
+    fn refresh_claims(&mut self, book_id: BookId, now: DateTime<Utc>, claim_period: Duration) {
+        self.reservations
+            .retain(|_, r| r.book_id != book_id || r.claim_expires_at.is_none_or(|at| at > now));
+
+        let grants = self.unclaimed_copies(book_id, now).max(0) as usize;
+        let mut waiting = self
+            .reservations
+            .values_mut()
+            .filter(|r| r.book_id == book_id && r.claim_expires_at.is_none())
+            .collect::<Vec<_>>();
+        waiting.sort_by_key(|r| r.reserved_at);
+        let mut claims = Vec::new();
+        for reservation in waiting.into_iter().take(grants) {
+            reservation.claim_expires_at = Some(stored(now + claim_period));
+            claims.push((reservation.user_id, reservation.claim_expires_at));
+        }
+
+        // everyone whose reservation now holds a copy hears of it
+        for (user_id, claim_expires_at) in claims {
+            self.queue_notification(&QueueNotification::new(
+                user_id,
+                NotificationKind::ReservationAvailable,
+                book_id,
+                claim_expires_at,
+                None,
+                now,
+            ));
+        }
+    }
+
+    // like `queue_notification` of the PostgreSQL repository
+    fn queue_notification(&mut self, event: &QueueNotification) {
+        let opted_out = self
+            .notification_preferences
+            .get(&(event.user_id, event.kind))
+            == Some(&false);
+        let queued_before =
+            event.key.is_some() && self.notifications.values().any(|n| n.key == event.key);
+        if opted_out || queued_before {
+            return;
+        }
+        self.notifications.insert(
+            NotificationId::new(),
+            NotificationRecord {
+                user_id: event.user_id,
+                kind: event.kind,
+                book_id: event.book_id,
+                due_at: event.due_at.map(stored),
+                key: event.key.clone(),
+                created_at: stored(event.queued_at),
+                attempts: 0,
+                next_attempt_at: Some(stored(event.queued_at)),
+                sent_at: None,
+                last_error: None,
+            },
+        );
+    }
+
+    // like `queue_webhook_event` of the PostgreSQL repository
+    fn queue_webhook_event(
+        &mut self,
+        event_type: WebhookEventType,
+        data: serde_json::Value,
+        occurred_at: DateTime<Utc>,
+    ) {
+        let payload = webhook_payload(event_type, data, occurred_at);
+        let subscribers = self
+            .webhooks
+            .iter()
+            .filter(|(_, w)| w.event_types.contains(&event_type))
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for webhook_id in subscribers {
+            self.webhook_deliveries.insert(
+                WebhookDeliveryId::new(),
+                WebhookDeliveryRecord {
+                    webhook_id,
+                    event_type,
+                    payload: payload.clone(),
+                    status: DeliveryStatus::Pending,
+                    attempts: 0,
+                    next_attempt_at: Some(stored(occurred_at)),
+                    last_attempt_at: None,
+                    response_status: None,
+                    last_error: None,
+                    created_at: stored(occurred_at),
+                    delivered_at: None,
+                },
+            );
+        }
+    }
+
+    // like `record_audit` of the PostgreSQL repository
+    fn record_audit(&mut self, event: RecordAudit) {
+        self.audit_log.push(AuditEntry {
+            id: AuditEntryId::new(),
+            actor: event.actor,
+            action: event.action,
+            target: event.target,
+            before: event.before,
+            after: event.after,
+            request_id: shared::request_id::current(),
+            occurred_at: stored(Utc::now()),
+        });
+    }
+
+    // records a change of a copy with its current state as `after`
+    fn record_copy_audit(
+        &mut self,
+        actor: Option<UserId>,
+        action: AuditAction,
+        copy_id: BookCopyId,
+        before: Option<String>,
+    ) {
+        let after = self.copies.get(&copy_id).and_then(CopyRecord::snapshot);
+        self.record_audit(RecordAudit::new(
+            actor,
+            action,
+            AuditTarget::book_copy(copy_id),
+            before,
+            after,
+        ));
+    }
+
+    fn queue(&self, book_id: BookId) -> Vec<&ReservationRecord> {
+        let mut queue = self
+            .reservations
+            .values()
+            .filter(|r| r.book_id == book_id)
+            .collect::<Vec<_>>();
+        queue.sort_by_key(|r| r.reserved_at);
+        queue
+    }
+
+    fn checkout(&self, record: &CheckoutRecord) -> Option<Checkout> {
+        let book = self.books.get(&record.book_id)?;
+        Some(Checkout {
+            id: record.id,
+            checked_out_by: record.user_id,
+            issued_by: record.issued_by,
+            checked_out_at: record.checked_out_at,
+            due_at: record.due_at,
+            renewal_count: record.renewal_count,
+            returned_at: record.returned_at,
+            returned_by: record.returned_by,
+            return_reason: record.return_reason.clone(),
+            book: CheckoutBook {
+                book_id: book.id,
+                copy_id: record.copy_id,
+                title: book.title.clone(),
+                author: book.author.clone(),
+                isbn: book.isbn.clone(),
+            },
+        })
+    }
+
+    // follows the `ON DELETE CASCADE` foreign keys of `books`
+    fn delete_book(&mut self, book_id: BookId) {
+        self.books.remove(&book_id);
+        self.copies.retain(|_, c| c.book_id != book_id);
+        self.checkouts.retain(|_, c| c.book_id != book_id);
+        self.reservations.retain(|_, r| r.book_id != book_id);
+        self.notifications.retain(|_, n| n.book_id != book_id);
+    }
+
+    // follows the `ON DELETE CASCADE` foreign keys of `users`
+    fn delete_user(&mut self, user_id: UserId) {
+        self.users.remove(&user_id);
+        let owned = self
+            .books
+            .values()
+            .filter(|b| b.owner == user_id)
+            .map(|b| b.id)
+            .collect::<Vec<_>>();
+        for book_id in owned {
+            self.delete_book(book_id);
+        }
+        self.checkouts.retain(|_, c| c.user_id != user_id);
+        self.reservations.retain(|_, r| r.user_id != user_id);
+        self.totp.remove(&user_id);
+        self.api_keys.retain(|_, k| k.user_id != user_id);
+        self.notifications.retain(|_, n| n.user_id != user_id);
+        self.notification_preferences
+            .retain(|(id, _), _| *id != user_id);
+        let subscriptions = self
+            .webhooks
+            .iter()
+            .filter(|(_, w)| w.created_by == user_id)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for webhook_id in subscriptions {
+            self.delete_webhook(webhook_id);
+        }
+    }
+
+    // follows the `ON DELETE CASCADE` foreign key of `webhook_deliveries`
+    fn delete_webhook(&mut self, webhook_id: WebhookId) -> bool {
+        self.webhook_deliveries
+            .retain(|_, d| d.webhook_id != webhook_id);
+        self.webhooks.remove(&webhook_id).is_some()
+    }
+}
+
+// timestamp columns are `TIMESTAMP(3)`, so stored times keep millisecond precision
+fn stored(at: DateTime<Utc>) -> DateTime<Utc> {
+    at.round_subsecs(3)
+}
+
+// reads the keyset the way the `(timestamp, id) < cursor` queries do: `descending`
+// is the order rows are read in, which is already reversed for a `Prev` cursor
+fn paginate_by_cursor<T>(
+    mut rows: Vec<T>,
+    limit: i64,
+    cursor: Option<Cursor>,
+    descending: bool,
+    key: impl Fn(&T) -> (DateTime<Utc>, uuid::Uuid),
+) -> CursorPaginatedList<T> {
+    rows.sort_by_key(&key);
+    if descending {
+        rows.reverse();
+    }
+    if let Some(cursor) = cursor {
+        let position = (cursor.timestamp, cursor.id);
+        rows.retain(|r| {
+            if descending {
+                key(r) < position
+            } else {
+                key(r) > position
+            }
+        });
+    }
+    rows.truncate((limit + 1).max(0) as usize);
+    CursorPaginatedList::from_rows(rows, limit, cursor, key)
+}
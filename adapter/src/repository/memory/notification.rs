@@ -0,0 +1,113 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use derive_new::new;
+use kernel::{
+    model::{
+        id::UserId,
+        notification::{
+            Notification, NotificationBook, NotificationPreference, NotificationRecipient,
+            event::{
+                MarkNotificationFailed, MarkNotificationSent, QueueNotification,
+                UpdateNotificationPreferences,
+            },
+        },
+    },
+    repository::notification::NotificationRepository,
+};
+use shared::error::AppResult;
+
+use super::{InMemoryStore, stored};
+use crate::repository::notification::{all_preferences, notification_not_found};
+
+#[derive(new)]
+pub struct InMemoryNotificationRepository {
+    store: InMemoryStore,
+}
+
+#[async_trait]
+impl NotificationRepository for InMemoryNotificationRepository {
+    async fn queue(&self, event: QueueNotification) -> AppResult<()> {
+        self.store.write().queue_notification(&event);
+        Ok(())
+    }
+    async fn find_pending(&self, now: DateTime<Utc>, limit: i64) -> AppResult<Vec<Notification>> {
+        let tables = self.store.read();
+        let mut pending = tables
+            .notifications
+            .iter()
+            .filter(|(_, n)| n.next_attempt_at.is_some_and(|at| at <= now))
+            .filter_map(|(id, n)| {
+                let user = tables.users.get(&n.user_id)?;
+                let book = tables.books.get(&n.book_id)?;
+                Some(Notification {
+                    id: *id,
+                    kind: n.kind,
+                    recipient: NotificationRecipient {
+                        user_id: user.id,
+                        name: user.name.clone(),
+                        email: user.email.clone(),
+                    },
+                    book: NotificationBook {
+                        book_id: book.id,
+                        title: book.title.clone(),
+                        author: book.author.clone(),
+                    },
+                    due_at: n.due_at,
+                    attempts: n.attempts,
+                })
+            })
+            .collect::<Vec<_>>();
+        pending.sort_by_key(|n| tables.notifications[&n.id].next_attempt_at);
+        pending.truncate(limit.max(0) as usize);
+        Ok(pending)
+    }
+    async fn mark_sent(&self, event: MarkNotificationSent) -> AppResult<()> {
+        let mut tables = self.store.write();
+        let notification = tables
+            .notifications
+            .get_mut(&event.id)
+            .ok_or_else(|| notification_not_found(event.id))?;
+        notification.sent_at = Some(stored(event.sent_at));
+        notification.next_attempt_at = None;
+        notification.last_error = None;
+        Ok(())
+    }
+    async fn mark_failed(&self, event: MarkNotificationFailed) -> AppResult<()> {
+        let mut tables = self.store.write();
+        let notification = tables
+            .notifications
+            .get_mut(&event.id)
+            .ok_or_else(|| notification_not_found(event.id))?;
+        notification.attempts += 1;
+        notification.next_attempt_at = event.next_attempt_at.map(stored);
+        notification.last_error = Some(event.error);
+        Ok(())
+    }
+    async fn delete_finished(&self, before: DateTime<Utc>) -> AppResult<u64> {
+        let mut tables = self.store.write();
+        let count = tables.notifications.len();
+        tables
+            .notifications
+            .retain(|_, n| n.next_attempt_at.is_some() || n.created_at >= before);
+        Ok((count - tables.notifications.len()) as u64)
+    }
+    async fn find_preferences(&self, user_id: UserId) -> AppResult<Vec<NotificationPreference>> {
+        let tables = self.store.read();
+        Ok(all_preferences(
+            tables
+                .notification_preferences
+                .iter()
+                .filter(|((id, _), _)| *id == user_id)
+                .map(|((_, kind), enabled)| (*kind, *enabled)),
+        ))
+    }
+    async fn update_preferences(&self, event: UpdateNotificationPreferences) -> AppResult<()> {
+        let mut tables = self.store.write();
+        for preference in event.preferences {
+            tables
+                .notification_preferences
+                .insert((event.user_id, preference.kind), preference.enabled);
+        }
+        Ok(())
+    }
+}
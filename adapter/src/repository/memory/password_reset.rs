@@ -0,0 +1,48 @@
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        auth::{PasswordReset, PasswordResetToken, event::CreatePasswordReset},
+        id::UserId,
+    },
+    repository::password_reset::PasswordResetRepository,
+};
+use shared::{config::PasswordResetConfig, error::AppResult};
+
+use super::{InMemoryStore, OneTimeTokenRecord};
+use crate::repository::password_reset::password_reset;
+
+#[derive(new)]
+pub struct InMemoryPasswordResetRepository {
+    store: InMemoryStore,
+    config: PasswordResetConfig,
+}
+
+#[async_trait]
+impl PasswordResetRepository for InMemoryPasswordResetRepository {
+    async fn create(&self, event: CreatePasswordReset) -> AppResult<PasswordReset> {
+        let mut tables = self.store.write();
+        tables
+            .password_resets
+            .retain(|_, r| r.user_id != event.user_id);
+        tables.password_resets.insert(
+            event.token.clone(),
+            OneTimeTokenRecord {
+                user_id: event.user_id,
+                expires_at: Instant::now() + Duration::from_secs(self.config.ttl),
+            },
+        );
+        Ok(password_reset(&self.config, event))
+    }
+    async fn consume(&self, token: &PasswordResetToken) -> AppResult<Option<UserId>> {
+        Ok(self
+            .store
+            .write()
+            .password_resets
+            .remove(&token.0)
+            .filter(|r| r.expires_at > Instant::now())
+            .map(|r| r.user_id))
+    }
+}
@@ -0,0 +1,127 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use derive_new::new;
+use kernel::{
+    model::{
+        id::{BookId, ReservationId, UserId},
+        reservation::{
+            Reservation,
+            event::{CreateReservation, DeleteReservation},
+        },
+    },
+    repository::reservation::ReservationRepository,
+};
+use shared::{
+    config::CheckoutConfig,
+    error::{AppError, AppResult},
+};
+
+use super::{InMemoryStore, ReservationRecord, stored};
+use crate::repository::checkout::claim_period;
+
+#[derive(new)]
+pub struct InMemoryReservationRepository {
+    store: InMemoryStore,
+    config: CheckoutConfig,
+}
+
+#[async_trait]
+impl ReservationRepository for InMemoryReservationRepository {
+    async fn create(&self, event: CreateReservation) -> AppResult<Reservation> {
+        let mut tables = self.store.write();
+
+        if !tables.books.contains_key(&event.book_id) {
+            return Err(AppError::EntityNotFound(format!(
+                "Book with id {} not found",
+                event.book_id
+            )));
+        }
+        if tables
+            .checkouts
+            .values()
+            .any(|c| c.book_id == event.book_id && c.user_id == event.reserved_by)
+        {
+            return Err(AppError::UnprocessableEntity(format!(
+                "Book with id {} is already checked out by the requested user",
+                event.book_id
+            )));
+        }
+
+        tables.refresh_claims(event.book_id, event.reserved_at, claim_period(&self.config));
+
+        if tables.unclaimed_copies(event.book_id, event.reserved_at) > 0 {
+            return Err(AppError::UnprocessableEntity(format!(
+                "Book with id {} is available and cannot be reserved",
+                event.book_id
+            )));
+        }
+        if tables
+            .queue(event.book_id)
+            .iter()
+            .any(|r| r.user_id == event.reserved_by)
+        {
+            return Err(AppError::UnprocessableEntity(format!(
+                "Book with id {} is already reserved by the requested user",
+                event.book_id
+            )));
+        }
+
+        let reservation = ReservationRecord {
+            id: ReservationId::new(),
+            book_id: event.book_id,
+            user_id: event.reserved_by,
+            reserved_at: stored(event.reserved_at),
+            claim_expires_at: None,
+        };
+        let created = reservation.to_reservation();
+        tables.reservations.insert(reservation.id, reservation);
+
+        Ok(created)
+    }
+    async fn find_by_book_id(&self, book_id: BookId) -> AppResult<Vec<Reservation>> {
+        let mut tables = self.store.write();
+        tables.refresh_claims(book_id, Utc::now(), claim_period(&self.config));
+
+        Ok(tables
+            .queue(book_id)
+            .into_iter()
+            .map(ReservationRecord::to_reservation)
+            .collect())
+    }
+    async fn find_by_user_id(&self, user_id: UserId) -> AppResult<Vec<Reservation>> {
+        let tables = self.store.read();
+        let mut reservations = tables
+            .reservations
+            .values()
+            .filter(|r| r.user_id == user_id)
+            .collect::<Vec<_>>();
+        reservations.sort_by_key(|r| r.reserved_at);
+
+        Ok(reservations
+            .into_iter()
+            .map(ReservationRecord::to_reservation)
+            .collect())
+    }
+    async fn delete(&self, event: DeleteReservation) -> AppResult<()> {
+        let mut tables = self.store.write();
+
+        let found = tables
+            .reservations
+            .get(&event.reservation_id)
+            .is_some_and(|r| {
+                r.book_id == event.book_id
+                    && (r.user_id == event.requested_user || event.manages_checkouts)
+            });
+        if !found {
+            return Err(AppError::EntityNotFound(
+                "specified reservation not found".into(),
+            ));
+        }
+        tables.reservations.remove(&event.reservation_id);
+
+        // a cancelled claim is passed on to the next user in the queue
+        tables.refresh_claims(event.book_id, Utc::now(), claim_period(&self.config));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        audit::{AuditAction, AuditTarget, event::RecordAudit},
+        role::{
+            BorrowingPolicy, Role,
+            event::{CreateRole, UpdateBorrowingPolicy},
+        },
+    },
+    repository::role::RoleRepository,
+};
+use shared::error::AppResult;
+
+use super::InMemoryStore;
+use crate::repository::{
+    audit::{borrowing_policy_snapshot, role_snapshot},
+    role::{role_exists, sorted, unknown_role},
+};
+
+#[derive(new)]
+pub struct InMemoryRoleRepository {
+    store: InMemoryStore,
+}
+
+#[async_trait]
+impl RoleRepository for InMemoryRoleRepository {
+    async fn find_all(&self) -> AppResult<Vec<Role>> {
+        Ok(self
+            .store
+            .read()
+            .roles
+            .iter()
+            .map(|(name, permissions)| Role {
+                name: name.clone(),
+                permissions: permissions.clone(),
+            })
+            .collect())
+    }
+    async fn create(&self, event: CreateRole) -> AppResult<Role> {
+        let mut tables = self.store.write();
+        if tables.roles.contains_key(&event.name) {
+            return Err(role_exists(&event.name));
+        }
+        let permissions = sorted(event.permissions);
+        tables.roles.insert(event.name.clone(), permissions.clone());
+        tables
+            .borrowing_policies
+            .insert(event.name.clone(), BorrowingPolicy::default());
+        tables.record_audit(RecordAudit::new(
+            Some(event.requested_user),
+            AuditAction::Create,
+            AuditTarget::role(&event.name),
+            None,
+            role_snapshot(&permissions),
+        ));
+        Ok(Role {
+            name: event.name,
+            permissions,
+        })
+    }
+    async fn find_borrowing_policy(&self, role_name: &str) -> AppResult<BorrowingPolicy> {
+        self.store
+            .read()
+            .borrowing_policies
+            .get(role_name)
+            .copied()
+            .ok_or_else(|| unknown_role(role_name))
+    }
+    async fn update_borrowing_policy(
+        &self,
+        event: UpdateBorrowingPolicy,
+    ) -> AppResult<BorrowingPolicy> {
+        let mut tables = self.store.write();
+        let policy = tables
+            .borrowing_policies
+            .get_mut(&event.role_name)
+            .ok_or_else(|| unknown_role(&event.role_name))?;
+        let before = std::mem::replace(policy, event.policy);
+        tables.record_audit(RecordAudit::new(
+            Some(event.requested_user),
+            AuditAction::Update,
+            AuditTarget::role(&event.role_name),
+            borrowing_policy_snapshot(&before),
+            borrowing_policy_snapshot(&event.policy),
+        ));
+        Ok(event.policy)
+    }
+}
@@ -0,0 +1,117 @@
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        id::UserId,
+        totp::{
+            RecoveryCode, TotpEnrollment,
+            event::{BeginTotpEnrollment, ConfirmTotpEnrollment, VerifyTotp},
+        },
+    },
+    repository::totp::TotpRepository,
+};
+use shared::{config::TotpConfig, error::AppResult};
+
+use super::{InMemoryStore, TotpRecord};
+use crate::repository::totp::{
+    hash_recovery_code, matching_step, new_recovery_codes, new_secret, totp_already_enabled,
+    totp_enrollment,
+};
+
+#[derive(new)]
+pub struct InMemoryTotpRepository {
+    store: InMemoryStore,
+    config: TotpConfig,
+}
+
+impl InMemoryTotpRepository {
+    fn recovery_codes(&self) -> (Vec<RecoveryCode>, Vec<(String, bool)>) {
+        let codes = new_recovery_codes(self.config.recovery_codes);
+        let hashes = codes
+            .iter()
+            .map(|c| (hash_recovery_code(&c.0), false))
+            .collect();
+        (codes, hashes)
+    }
+}
+
+#[async_trait]
+impl TotpRepository for InMemoryTotpRepository {
+    async fn is_enabled(&self, user_id: UserId) -> AppResult<bool> {
+        Ok(self
+            .store
+            .read()
+            .totp
+            .get(&user_id)
+            .is_some_and(|t| t.enabled))
+    }
+    async fn begin_enrollment(&self, event: BeginTotpEnrollment) -> AppResult<TotpEnrollment> {
+        let secret = new_secret();
+        let enrollment = totp_enrollment(&self.config, &secret, event.account_name)?;
+
+        let mut tables = self.store.write();
+        if tables.totp.get(&event.user_id).is_some_and(|t| t.enabled) {
+            return Err(totp_already_enabled());
+        }
+        tables.totp.insert(
+            event.user_id,
+            TotpRecord {
+                secret,
+                enabled: false,
+                last_used_step: None,
+                recovery_codes: vec![],
+            },
+        );
+        Ok(enrollment)
+    }
+    async fn confirm_enrollment(
+        &self,
+        event: ConfirmTotpEnrollment,
+    ) -> AppResult<Option<Vec<RecoveryCode>>> {
+        let mut tables = self.store.write();
+        let Some(record) = tables.totp.get_mut(&event.user_id).filter(|t| !t.enabled) else {
+            return Ok(None);
+        };
+        let Some(step) = matching_step(&record.secret, &event.code, None)? else {
+            return Ok(None);
+        };
+        let (codes, hashes) = self.recovery_codes();
+        record.enabled = true;
+        record.last_used_step = Some(step);
+        record.recovery_codes = hashes;
+        Ok(Some(codes))
+    }
+    async fn verify(&self, event: VerifyTotp) -> AppResult<bool> {
+        let mut tables = self.store.write();
+        let Some(record) = tables.totp.get_mut(&event.user_id).filter(|t| t.enabled) else {
+            return Ok(false);
+        };
+        if let Some(step) = matching_step(&record.secret, &event.code, record.last_used_step)? {
+            record.last_used_step = Some(step);
+            return Ok(true);
+        }
+        let hash = hash_recovery_code(&event.code);
+        match record
+            .recovery_codes
+            .iter_mut()
+            .find(|(h, used)| *h == hash && !*used)
+        {
+            Some((_, used)) => {
+                *used = true;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+    async fn regenerate_recovery_codes(&self, user_id: UserId) -> AppResult<Vec<RecoveryCode>> {
+        let (codes, hashes) = self.recovery_codes();
+        if let Some(record) = self.store.write().totp.get_mut(&user_id) {
+            record.recovery_codes = hashes;
+        }
+        Ok(codes)
+    }
+    async fn disable(&self, user_id: UserId) -> AppResult<()> {
+        self.store.write().totp.remove(&user_id);
+        Ok(())
+    }
+}
@@ -0,0 +1,254 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use derive_new::new;
+use kernel::{
+    model::{
+        audit::{AuditAction, AuditTarget, event::RecordAudit},
+        id::UserId,
+        list::{CursorDirection, CursorListOptions, CursorPaginatedList},
+        role::{BuiltinRole, Role},
+        user::{
+            User, UserStatus,
+            event::{
+                CreateUser, DeleteUser, ResetUserPassword, UpdateUserPassword, UpdateUserRole,
+                UpdateUserStatus,
+            },
+        },
+    },
+    repository::user::UserRepository,
+};
+use shared::error::{AppError, AppResult};
+
+use super::{InMemoryStore, paginate_by_cursor};
+use crate::{
+    password::PasswordHasher,
+    repository::{
+        audit::snapshot,
+        role::role_not_found,
+        user::{status_mismatch, verify_password},
+    },
+};
+
+#[derive(new)]
+pub struct InMemoryUserRepository {
+    store: InMemoryStore,
+    hasher: Arc<dyn PasswordHasher>,
+}
+
+#[async_trait]
+impl UserRepository for InMemoryUserRepository {
+    async fn find_current_user(&self, current_user_id: UserId) -> AppResult<Option<User>> {
+        let tables = self.store.read();
+        Ok(tables.users.get(&current_user_id).map(|u| tables.user(u)))
+    }
+    async fn find_by_email(&self, email: &str) -> AppResult<Option<User>> {
+        let tables = self.store.read();
+        Ok(tables
+            .users
+            .values()
+            .find(|u| u.email == email)
+            .map(|u| tables.user(u)))
+    }
+    async fn find_by_status(&self, status: UserStatus) -> AppResult<Vec<User>> {
+        let tables = self.store.read();
+        let mut users = tables
+            .users
+            .values()
+            .filter(|u| u.status == status)
+            .collect::<Vec<_>>();
+        users.sort_by_key(|u| (u.created_at, u.id.raw()));
+        Ok(users.into_iter().map(|u| tables.user(u)).collect())
+    }
+    async fn find_all(&self, options: CursorListOptions) -> AppResult<CursorPaginatedList<User>> {
+        let CursorListOptions { limit, cursor } = options;
+        let tables = self.store.read();
+        let descending = cursor.is_none_or(|c| c.direction == CursorDirection::Next);
+
+        let CursorPaginatedList {
+            limit,
+            next_cursor,
+            prev_cursor,
+            items,
+        } = paginate_by_cursor(
+            tables.users.values().collect(),
+            limit,
+            cursor,
+            descending,
+            |u| (u.created_at, u.id.raw()),
+        );
+
+        Ok(CursorPaginatedList {
+            limit,
+            next_cursor,
+            prev_cursor,
+            items: items.into_iter().map(|u| tables.user(u)).collect(),
+        })
+    }
+    async fn create(&self, event: CreateUser) -> AppResult<User> {
+        let hashed_password = self.hasher.hash(&event.password)?;
+        let role = Role::from(BuiltinRole::User);
+
+        let mut tables = self.store.write();
+        // `users.email` is unique
+        if tables.users.values().any(|u| u.email == event.email) {
+            return Err(AppError::Conflict(format!(
+                "User with email {} already exists",
+                event.email
+            )));
+        }
+        let user_id = tables.insert_user(
+            event.user_id,
+            &event.name,
+            &event.email,
+            hashed_password,
+            role.name.clone(),
+            event.status,
+        );
+        let after = tables.users[&user_id].snapshot();
+        tables.record_audit(RecordAudit::new(
+            Some(event.created_by.unwrap_or(user_id)),
+            AuditAction::Create,
+            AuditTarget::user(user_id),
+            None,
+            after,
+        ));
+
+        Ok(User {
+            id: user_id,
+            name: event.name,
+            email: event.email,
+            role,
+            status: event.status,
+        })
+    }
+    async fn update_password(&self, event: UpdateUserPassword) -> AppResult<()> {
+        let original_password_hash = self
+            .store
+            .read()
+            .users
+            .get(&event.user_id)
+            .map(|u| u.password_hash.clone())
+            .ok_or_else(|| AppError::EntityNotFound("Specified user not found.".into()))?;
+
+        verify_password(
+            self.hasher.as_ref(),
+            &event.current_password,
+            &original_password_hash,
+        )?;
+
+        let new_password_hash = self.hasher.hash(&event.new_password)?;
+        let mut tables = self.store.write();
+        if let Some(user) = tables.users.get_mut(&event.user_id) {
+            user.password_hash = new_password_hash;
+            tables.record_audit(RecordAudit::new(
+                Some(event.user_id),
+                AuditAction::ChangePassword,
+                AuditTarget::user(event.user_id),
+                None,
+                None,
+            ));
+        }
+
+        Ok(())
+    }
+    async fn reset_password(&self, event: ResetUserPassword) -> AppResult<()> {
+        let password_hash = self.hasher.hash(&event.new_password)?;
+        let mut tables = self.store.write();
+        match tables.users.get_mut(&event.user_id) {
+            Some(user) => {
+                user.password_hash = password_hash;
+                tables.record_audit(RecordAudit::new(
+                    Some(event.user_id),
+                    AuditAction::ResetPassword,
+                    AuditTarget::user(event.user_id),
+                    None,
+                    None,
+                ));
+                Ok(())
+            }
+            None => Err(AppError::NoRowsAffectedError(
+                "Specified user not found.".into(),
+            )),
+        }
+    }
+    async fn update_role(&self, event: UpdateUserRole) -> AppResult<()> {
+        let mut tables = self.store.write();
+        if !tables.roles.contains_key(&event.role) {
+            return Err(role_not_found(&event.role));
+        }
+        match tables.users.get_mut(&event.user_id) {
+            Some(user) => {
+                let previous_role = std::mem::replace(&mut user.role, event.role.clone());
+                tables.record_audit(RecordAudit::new(
+                    Some(event.requested_user),
+                    AuditAction::ChangeRole,
+                    AuditTarget::user(event.user_id),
+                    snapshot(serde_json::json!({ "role": previous_role })),
+                    snapshot(serde_json::json!({ "role": event.role })),
+                ));
+                Ok(())
+            }
+            None => Err(AppError::NoRowsAffectedError(
+                "Specified user not found.".into(),
+            )),
+        }
+    }
+    async fn update_status(&self, event: UpdateUserStatus) -> AppResult<()> {
+        let mut tables = self.store.write();
+        match tables.users.get_mut(&event.user_id) {
+            Some(user) if user.status == event.from => {
+                user.status = event.to;
+                tables.record_audit(RecordAudit::new(
+                    Some(event.requested_user),
+                    AuditAction::ChangeStatus,
+                    AuditTarget::user(event.user_id),
+                    snapshot(serde_json::json!({ "status": event.from.as_ref() })),
+                    snapshot(serde_json::json!({ "status": event.to.as_ref() })),
+                ));
+                Ok(())
+            }
+            _ => Err(status_mismatch(&event)),
+        }
+    }
+    async fn delete(&self, event: DeleteUser) -> AppResult<()> {
+        let mut tables = self.store.write();
+        let Some(before) = tables.users.get(&event.user_id).map(|u| u.snapshot()) else {
+            return Err(AppError::NoRowsAffectedError(
+                "Specified user not found.".into(),
+            ));
+        };
+        tables.delete_user(event.user_id);
+        tables.record_audit(RecordAudit::new(
+            Some(event.requested_user),
+            AuditAction::Delete,
+            AuditTarget::user(event.user_id),
+            before,
+            None,
+        ));
+        Ok(())
+    }
+    async fn delete_stale_sign_ups(&self, created_before: DateTime<Utc>) -> AppResult<u64> {
+        let mut tables = self.store.write();
+        let stale = tables
+            .users
+            .values()
+            .filter(|u| matches!(u.status, UserStatus::Unverified | UserStatus::Rejected))
+            .filter(|u| u.created_at < created_before)
+            .map(|u| (u.id, u.snapshot()))
+            .collect::<Vec<_>>();
+        // the purge job deletes them, not a user
+        for (user_id, before) in &stale {
+            tables.delete_user(*user_id);
+            tables.record_audit(RecordAudit::new(
+                None,
+                AuditAction::Delete,
+                AuditTarget::user(*user_id),
+                before.clone(),
+                None,
+            ));
+        }
+        Ok(stale.len() as u64)
+    }
+}
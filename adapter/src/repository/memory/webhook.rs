@@ -0,0 +1,176 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use derive_new::new;
+use kernel::{
+    model::{
+        id::WebhookId,
+        list::{CursorDirection, CursorListOptions, CursorPaginatedList},
+        webhook::{
+            CreatedWebhook, DeliveryStatus, PendingDelivery, Webhook, WebhookDelivery,
+            event::{
+                CreateWebhook, DeleteWebhook, MarkDeliveryDelivered, MarkDeliveryFailed,
+                RetryDelivery,
+            },
+        },
+    },
+    repository::webhook::WebhookRepository,
+};
+use shared::error::{AppError, AppResult};
+
+use super::{InMemoryStore, WebhookRecord, paginate_by_cursor, stored};
+use crate::repository::webhook::{
+    dead_letter_not_found, delivery_not_found, generate_secret, webhook_not_found,
+};
+
+#[derive(new)]
+pub struct InMemoryWebhookRepository {
+    store: InMemoryStore,
+}
+
+#[async_trait]
+impl WebhookRepository for InMemoryWebhookRepository {
+    async fn create(&self, event: CreateWebhook) -> AppResult<CreatedWebhook> {
+        let mut tables = self.store.write();
+        if !tables.users.contains_key(&event.created_by) {
+            return Err(AppError::EntityNotFound(format!(
+                "User with id {} not found",
+                event.created_by
+            )));
+        }
+        let id = WebhookId::new();
+        let record = WebhookRecord {
+            url: event.url,
+            secret: generate_secret(),
+            event_types: event.event_types,
+            created_by: event.created_by,
+            created_at: stored(Utc::now()),
+        };
+        let created = CreatedWebhook {
+            webhook: record.to_webhook(id),
+            secret: record.secret.clone(),
+        };
+        tables.webhooks.insert(id, record);
+        Ok(created)
+    }
+    async fn find_all(&self) -> AppResult<Vec<Webhook>> {
+        let tables = self.store.read();
+        let mut webhooks = tables
+            .webhooks
+            .iter()
+            .map(|(id, w)| w.to_webhook(*id))
+            .collect::<Vec<_>>();
+        webhooks.sort_by_key(|w| (w.created_at, w.id.raw()));
+        Ok(webhooks)
+    }
+    async fn delete(&self, event: DeleteWebhook) -> AppResult<()> {
+        if !self.store.write().delete_webhook(event.webhook_id) {
+            return Err(webhook_not_found(event.webhook_id));
+        }
+        Ok(())
+    }
+    async fn find_deliveries(
+        &self,
+        webhook_id: WebhookId,
+        options: CursorListOptions,
+    ) -> AppResult<CursorPaginatedList<WebhookDelivery>> {
+        let tables = self.store.read();
+        if !tables.webhooks.contains_key(&webhook_id) {
+            return Err(webhook_not_found(webhook_id));
+        }
+        let CursorListOptions { limit, cursor } = options;
+        let rows = tables
+            .webhook_deliveries
+            .iter()
+            .filter(|(_, d)| d.webhook_id == webhook_id)
+            .map(|(id, d)| d.to_delivery(*id))
+            .collect();
+        let descending = cursor.is_none_or(|c| c.direction == CursorDirection::Next);
+        Ok(paginate_by_cursor(
+            rows,
+            limit,
+            cursor,
+            descending,
+            |d: &WebhookDelivery| (d.created_at, d.id.raw()),
+        ))
+    }
+    async fn find_pending_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> AppResult<Vec<PendingDelivery>> {
+        let tables = self.store.read();
+        let mut pending = tables
+            .webhook_deliveries
+            .iter()
+            .filter(|(_, d)| d.next_attempt_at.is_some_and(|at| at <= now))
+            .filter_map(|(id, d)| {
+                let webhook = tables.webhooks.get(&d.webhook_id)?;
+                Some((
+                    (d.next_attempt_at, d.created_at),
+                    PendingDelivery {
+                        id: *id,
+                        url: webhook.url.clone(),
+                        secret: webhook.secret.clone(),
+                        event_type: d.event_type,
+                        payload: d.payload.clone(),
+                        attempts: d.attempts,
+                    },
+                ))
+            })
+            .collect::<Vec<_>>();
+        pending.sort_by_key(|(key, _)| *key);
+        pending.truncate(limit.max(0) as usize);
+        Ok(pending.into_iter().map(|(_, d)| d).collect())
+    }
+    async fn mark_delivered(&self, event: MarkDeliveryDelivered) -> AppResult<()> {
+        let mut tables = self.store.write();
+        let delivery = tables
+            .webhook_deliveries
+            .get_mut(&event.delivery_id)
+            .ok_or_else(|| delivery_not_found(event.delivery_id))?;
+        delivery.status = DeliveryStatus::Delivered;
+        delivery.next_attempt_at = None;
+        delivery.last_attempt_at = Some(stored(event.delivered_at));
+        delivery.delivered_at = Some(stored(event.delivered_at));
+        delivery.response_status = Some(event.response_status);
+        delivery.last_error = None;
+        Ok(())
+    }
+    async fn mark_failed(&self, event: MarkDeliveryFailed) -> AppResult<()> {
+        let mut tables = self.store.write();
+        let delivery = tables
+            .webhook_deliveries
+            .get_mut(&event.delivery_id)
+            .ok_or_else(|| delivery_not_found(event.delivery_id))?;
+        delivery.status = match event.next_attempt_at {
+            Some(_) => DeliveryStatus::Pending,
+            None => DeliveryStatus::DeadLetter,
+        };
+        delivery.attempts += 1;
+        delivery.next_attempt_at = event.next_attempt_at.map(stored);
+        delivery.last_attempt_at = Some(stored(event.failed_at));
+        delivery.response_status = event.response_status;
+        delivery.last_error = Some(event.error);
+        Ok(())
+    }
+    async fn retry_delivery(&self, event: RetryDelivery) -> AppResult<()> {
+        let mut tables = self.store.write();
+        let delivery = tables
+            .webhook_deliveries
+            .get_mut(&event.delivery_id)
+            .filter(|d| d.webhook_id == event.webhook_id && d.status == DeliveryStatus::DeadLetter)
+            .ok_or_else(|| dead_letter_not_found(event.delivery_id))?;
+        delivery.status = DeliveryStatus::Pending;
+        delivery.attempts = 0;
+        delivery.next_attempt_at = Some(stored(event.retry_at));
+        Ok(())
+    }
+    async fn delete_delivered(&self, before: DateTime<Utc>) -> AppResult<u64> {
+        let mut tables = self.store.write();
+        let count = tables.webhook_deliveries.len();
+        tables
+            .webhook_deliveries
+            .retain(|_, d| d.delivered_at.is_none_or(|at| at >= before));
+        Ok((count - tables.webhook_deliveries.len()) as u64)
+    }
+}
@@ -1,5 +1,30 @@
+pub mod api_key;
+pub mod audit;
 pub mod auth;
 pub mod book;
 pub mod checkout;
+pub mod email_verification;
 pub mod health;
+pub mod job;
+pub mod login_attempt;
+pub mod login_challenge;
+pub mod memory;
+pub mod notification;
+pub mod password_reset;
+pub mod reservation;
+pub mod role;
+pub mod totp;
 pub mod user;
+pub mod webhook;
+
+use shared::error::{AppError, AppResult};
+
+pub(crate) async fn set_transaction_serializable(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> AppResult<()> {
+    sqlx::query!("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE;")
+        .execute(&mut **tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+    Ok(())
+}
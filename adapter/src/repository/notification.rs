@@ -0,0 +1,322 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use derive_new::new;
+use kernel::{
+    model::{
+        id::{NotificationId, UserId},
+        notification::{
+            Notification, NotificationKind, NotificationPreference,
+            event::{
+                MarkNotificationFailed, MarkNotificationSent, QueueNotification,
+                UpdateNotificationPreferences,
+            },
+        },
+    },
+    repository::notification::NotificationRepository,
+};
+use shared::error::{AppError, AppResult};
+use strum::IntoEnumIterator;
+
+use crate::database::{
+    ConnectionPool,
+    model::notification::{NotificationPreferenceRow, NotificationRow, parse_kind},
+};
+
+#[derive(new)]
+pub struct NotificationRepositoryImpl {
+    db: ConnectionPool,
+}
+
+#[async_trait]
+impl NotificationRepository for NotificationRepositoryImpl {
+    async fn queue(&self, event: QueueNotification) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+        queue_notification(&mut tx, &event).await?;
+        tx.commit().await.map_err(AppError::TransactionError)?;
+        Ok(())
+    }
+    async fn find_pending(&self, now: DateTime<Utc>, limit: i64) -> AppResult<Vec<Notification>> {
+        sqlx::query_as!(
+            NotificationRow,
+            r#"
+                SELECT
+                    n.notification_id AS "notification_id: NotificationId",
+                    n.kind,
+                    n.user_id AS "user_id: UserId",
+                    u.name AS user_name,
+                    u.email,
+                    n.book_id,
+                    b.title,
+                    b.author,
+                    n.due_at,
+                    n.attempts
+                FROM notifications AS n
+                INNER JOIN users AS u ON u.user_id = n.user_id
+                INNER JOIN books AS b ON b.book_id = n.book_id
+                WHERE n.next_attempt_at <= $1
+                ORDER BY n.next_attempt_at
+                LIMIT $2
+            "#,
+            now,
+            limit
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .into_iter()
+        .map(Notification::try_from)
+        .collect()
+    }
+    async fn mark_sent(&self, event: MarkNotificationSent) -> AppResult<()> {
+        let res = sqlx::query!(
+            r#"
+                UPDATE notifications
+                SET sent_at = $2, next_attempt_at = NULL, last_error = NULL
+                WHERE notification_id = $1
+            "#,
+            event.id as _,
+            event.sent_at
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        if res.rows_affected() < 1 {
+            return Err(notification_not_found(event.id));
+        }
+        Ok(())
+    }
+    async fn mark_failed(&self, event: MarkNotificationFailed) -> AppResult<()> {
+        let res = sqlx::query!(
+            r#"
+                UPDATE notifications
+                SET attempts = attempts + 1, next_attempt_at = $2, last_error = $3
+                WHERE notification_id = $1
+            "#,
+            event.id as _,
+            event.next_attempt_at,
+            event.error
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        if res.rows_affected() < 1 {
+            return Err(notification_not_found(event.id));
+        }
+        Ok(())
+    }
+    async fn delete_finished(&self, before: DateTime<Utc>) -> AppResult<u64> {
+        let res = sqlx::query!(
+            r#"
+                DELETE FROM notifications
+                WHERE next_attempt_at IS NULL AND created_at < $1
+            "#,
+            before
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        Ok(res.rows_affected())
+    }
+    async fn find_preferences(&self, user_id: UserId) -> AppResult<Vec<NotificationPreference>> {
+        let rows = sqlx::query_as!(
+            NotificationPreferenceRow,
+            r#"
+                SELECT kind, enabled FROM notification_preferences
+                WHERE user_id = $1
+            "#,
+            user_id as _
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        let stored = rows
+            .into_iter()
+            .map(|row| Ok((parse_kind(&row.kind)?, row.enabled)))
+            .collect::<AppResult<Vec<_>>>()?;
+        Ok(all_preferences(stored))
+    }
+    async fn update_preferences(&self, event: UpdateNotificationPreferences) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+        for preference in event.preferences {
+            sqlx::query!(
+                r#"
+                    INSERT INTO notification_preferences (user_id, kind, enabled)
+                    VALUES ($1, $2, $3)
+                    ON CONFLICT (user_id, kind) DO UPDATE SET enabled = EXCLUDED.enabled
+                "#,
+                event.user_id as _,
+                preference.kind.as_ref(),
+                preference.enabled
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+        }
+        tx.commit().await.map_err(AppError::TransactionError)?;
+        Ok(())
+    }
+}
+
+// writes the notification in the transaction of the change it is about, unless
+// the user opted out of its kind or one with the same key was queued before
+pub(crate) async fn queue_notification(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    event: &QueueNotification,
+) -> AppResult<()> {
+    sqlx::query!(
+        r#"
+            INSERT INTO notifications
+                (notification_id, user_id, kind, book_id, due_at, key, created_at, next_attempt_at)
+            SELECT $1, $2, $3::VARCHAR, $4, $5, $6, $7, $7
+            WHERE NOT EXISTS (
+                SELECT 1 FROM notification_preferences
+                WHERE user_id = $2 AND kind = $3::VARCHAR AND NOT enabled
+            )
+            ON CONFLICT (key) DO NOTHING
+        "#,
+        NotificationId::new() as _,
+        event.user_id as _,
+        event.kind.as_ref(),
+        event.book_id as _,
+        event.due_at,
+        event.key,
+        event.queued_at
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+    Ok(())
+}
+
+// every kind, enabled unless `stored` says otherwise
+pub(crate) fn all_preferences(
+    stored: impl IntoIterator<Item = (NotificationKind, bool)>,
+) -> Vec<NotificationPreference> {
+    let stored = stored.into_iter().collect::<Vec<_>>();
+    NotificationKind::iter()
+        .map(|kind| NotificationPreference {
+            kind,
+            enabled: stored
+                .iter()
+                .find(|(k, _)| *k == kind)
+                .is_none_or(|(_, enabled)| *enabled),
+        })
+        .collect()
+}
+
+pub(crate) fn notification_not_found(id: NotificationId) -> AppError {
+    AppError::EntityNotFound(format!("Notification with id {id} not found"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chrono::{Duration, SubsecRound};
+    use kernel::{
+        model::{
+            checkout::event::{CreateCheckout, UpdateReturned},
+            id::BookId,
+        },
+        repository::checkout::CheckoutRepository,
+    };
+
+    use super::*;
+    use crate::repository::checkout::CheckoutRepositoryImpl;
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn checkouts_queue_notifications_users_did_not_opt_out_of(
+        pool: sqlx::PgPool,
+    ) -> anyhow::Result<()> {
+        let repo = NotificationRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let checkouts = CheckoutRepositoryImpl::new(ConnectionPool::new(pool), Default::default());
+        let book_id = BookId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4d")?;
+        let user_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let now = Utc::now().trunc_subsecs(3);
+
+        repo.update_preferences(UpdateNotificationPreferences::new(
+            user_id,
+            vec![NotificationPreference {
+                kind: NotificationKind::CheckoutReturned,
+                enabled: false,
+            }],
+        ))
+        .await?;
+        let preferences = repo.find_preferences(user_id).await?;
+        assert_eq!(preferences.len(), 5);
+        assert!(
+            preferences
+                .iter()
+                .all(|p| p.enabled == (p.kind != NotificationKind::CheckoutReturned))
+        );
+
+        checkouts
+            .create(CreateCheckout::new(book_id, None, user_id, user_id, now))
+            .await?;
+        let checkout = checkouts
+            .find_unreturned_by_user_id(user_id)
+            .await?
+            .pop()
+            .expect("Checkout not found");
+        checkouts
+            .update_returned(UpdateReturned::new(
+                checkout.id,
+                book_id,
+                user_id,
+                false,
+                None,
+                now,
+            ))
+            .await?;
+
+        // the return went unnoticed, as the user opted out of it
+        let pending = repo.find_pending(now, 10).await?;
+        assert_eq!(pending.len(), 1);
+        let created = &pending[0];
+        assert_eq!(created.kind, NotificationKind::CheckoutCreated);
+        assert_eq!(created.recipient.email, "eleazar.fig@example.com");
+        assert_eq!(created.book.title, "The Rust Programming Language");
+        assert_eq!(created.due_at, Some(checkout.due_at));
+        assert!(
+            created
+                .render()
+                .body
+                .contains(&checkout.due_at.format("%Y-%m-%d").to_string())
+        );
+
+        // a reminder queued again is dropped
+        for _ in 0..2 {
+            repo.queue(QueueNotification::new(
+                user_id,
+                NotificationKind::Overdue,
+                book_id,
+                Some(now),
+                Some("overdue:test".into()),
+                now,
+            ))
+            .await?;
+        }
+        let pending = repo.find_pending(now, 10).await?;
+        assert_eq!(pending.len(), 2);
+
+        let retry = now + Duration::minutes(5);
+        repo.mark_failed(MarkNotificationFailed::new(
+            pending[0].id,
+            now,
+            "mail server down".into(),
+            Some(retry),
+        ))
+        .await?;
+        repo.mark_sent(MarkNotificationSent::new(pending[1].id, now))
+            .await?;
+        assert!(repo.find_pending(now, 10).await?.is_empty());
+        let retried = repo.find_pending(retry, 10).await?;
+        assert_eq!(retried.len(), 1);
+        assert_eq!(retried[0].attempts, 1);
+
+        // only the sent one is finished
+        assert_eq!(repo.delete_finished(retry).await?, 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        auth::{PasswordReset, PasswordResetToken, event::CreatePasswordReset},
+        id::UserId,
+    },
+    repository::password_reset::PasswordResetRepository,
+};
+use shared::{config::PasswordResetConfig, error::AppResult};
+
+use crate::{
+    database::model::password_reset::{
+        PasswordResetKey, ResetToken, ResetUser, UserPasswordResetKey,
+    },
+    redis::RedisClient,
+};
+
+#[derive(new)]
+pub struct PasswordResetRepositoryImpl {
+    kv: Arc<RedisClient>,
+    config: PasswordResetConfig,
+}
+
+#[async_trait]
+impl PasswordResetRepository for PasswordResetRepositoryImpl {
+    async fn create(&self, event: CreatePasswordReset) -> AppResult<PasswordReset> {
+        let pending = UserPasswordResetKey(event.user_id);
+        if let Some(ResetToken(previous)) = self.kv.get(&pending).await? {
+            self.kv
+                .delete(&PasswordResetKey::from(previous.as_str()))
+                .await?;
+        }
+
+        self.kv
+            .set_ex(
+                &PasswordResetKey::from(event.token.as_str()),
+                &ResetUser(event.user_id),
+                self.config.ttl,
+            )
+            .await?;
+        self.kv
+            .set_ex(&pending, &ResetToken(event.token.clone()), self.config.ttl)
+            .await?;
+
+        Ok(password_reset(&self.config, event))
+    }
+    async fn consume(&self, token: &PasswordResetToken) -> AppResult<Option<UserId>> {
+        let Some(ResetUser(user_id)) = self.kv.take(&PasswordResetKey::from(token)).await? else {
+            return Ok(None);
+        };
+        self.kv.delete(&UserPasswordResetKey(user_id)).await?;
+        Ok(Some(user_id))
+    }
+}
+
+pub(crate) fn password_reset(
+    config: &PasswordResetConfig,
+    event: CreatePasswordReset,
+) -> PasswordReset {
+    let link = config.url.as_ref().map(|url| {
+        let separator = if url.contains('?') { '&' } else { '?' };
+        format!("{url}{separator}token={}", event.token)
+    });
+    PasswordReset {
+        user_id: event.user_id,
+        token: PasswordResetToken(event.token),
+        link,
+        expires_in: config.ttl,
+    }
+}
@@ -0,0 +1,425 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use derive_new::new;
+use kernel::{
+    model::{
+        id::{BookId, CheckoutId, ReservationId, UserId},
+        notification::{NotificationKind, event::QueueNotification},
+        reservation::{
+            Reservation,
+            event::{CreateReservation, DeleteReservation},
+        },
+    },
+    repository::reservation::ReservationRepository,
+};
+use shared::{
+    config::CheckoutConfig,
+    error::{AppError, AppResult},
+};
+
+use crate::{
+    database::{
+        ConnectionPool,
+        model::{checkout::CheckoutStateRow, reservation::ReservationRow},
+    },
+    repository::{
+        checkout::claim_period, notification::queue_notification, set_transaction_serializable,
+    },
+};
+
+#[derive(new)]
+pub struct ReservationRepositoryImpl {
+    db: ConnectionPool,
+    config: CheckoutConfig,
+}
+
+#[async_trait]
+impl ReservationRepository for ReservationRepositoryImpl {
+    async fn create(&self, event: CreateReservation) -> AppResult<Reservation> {
+        let mut tx = self.db.begin().await?;
+        set_transaction_serializable(&mut tx).await?;
+
+        {
+            // prerequirement check
+            let res = sqlx::query_as!(
+                CheckoutStateRow,
+                r#"
+                    SELECT
+                        b.book_id,
+                        c.checkout_id AS "checkout_id?: CheckoutId",
+                        c.user_id AS "user_id?: UserId"
+                    FROM books AS b
+                    LEFT OUTER JOIN checkouts AS c
+                        ON c.book_id = b.book_id AND c.user_id = $2
+                    WHERE b.book_id = $1
+                "#,
+                event.book_id as _,
+                event.reserved_by as _
+            )
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+
+            match res {
+                None => {
+                    return Err(AppError::EntityNotFound(format!(
+                        "Book with id {} not found",
+                        event.book_id
+                    )));
+                }
+                Some(CheckoutStateRow {
+                    checkout_id: Some(_),
+                    ..
+                }) => {
+                    return Err(AppError::UnprocessableEntity(format!(
+                        "Book with id {} is already checked out by the requested user",
+                        event.book_id
+                    )));
+                }
+                _ => {}
+            }
+        }
+
+        refresh_claims(
+            &mut tx,
+            event.book_id,
+            event.reserved_at,
+            claim_period(&self.config),
+        )
+        .await?;
+
+        if count_unclaimed_copies(&mut tx, event.book_id, event.reserved_at).await? > 0 {
+            return Err(AppError::UnprocessableEntity(format!(
+                "Book with id {} is available and cannot be reserved",
+                event.book_id
+            )));
+        }
+        let queue = find_queue(&mut tx, event.book_id).await?;
+        if queue.iter().any(|r| r.user_id == event.reserved_by) {
+            return Err(AppError::UnprocessableEntity(format!(
+                "Book with id {} is already reserved by the requested user",
+                event.book_id
+            )));
+        }
+
+        let reservation_id = ReservationId::new();
+        let res = sqlx::query!(
+            r#"
+                INSERT INTO reservations (reservation_id, book_id, user_id, reserved_at)
+                VALUES ($1, $2, $3, $4)
+            "#,
+            reservation_id as _,
+            event.book_id as _,
+            event.reserved_by as _,
+            event.reserved_at
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        if res.rows_affected() < 1 {
+            return Err(AppError::NoRowsAffectedError(
+                "Failed to create reservation record".into(),
+            ));
+        }
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(Reservation {
+            id: reservation_id,
+            book_id: event.book_id,
+            reserved_by: event.reserved_by,
+            reserved_at: event.reserved_at,
+            claim_expires_at: None,
+        })
+    }
+    async fn find_by_book_id(&self, book_id: BookId) -> AppResult<Vec<Reservation>> {
+        let mut tx = self.db.begin().await?;
+        refresh_claims(&mut tx, book_id, Utc::now(), claim_period(&self.config)).await?;
+        let rows = find_queue(&mut tx, book_id).await?;
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(rows.into_iter().map(Reservation::from).collect())
+    }
+    async fn find_by_user_id(&self, user_id: UserId) -> AppResult<Vec<Reservation>> {
+        sqlx::query_as!(
+            ReservationRow,
+            r#"
+                SELECT
+                    reservation_id,
+                    book_id,
+                    user_id,
+                    reserved_at,
+                    claim_expires_at
+                FROM reservations
+                WHERE user_id = $1
+                ORDER BY reserved_at ASC
+            "#,
+            user_id as _
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map(|rows| rows.into_iter().map(Reservation::from).collect())
+        .map_err(AppError::SpecificOperationError)
+    }
+    async fn delete(&self, event: DeleteReservation) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+        set_transaction_serializable(&mut tx).await?;
+
+        let res = sqlx::query!(
+            r#"
+                DELETE FROM reservations
+                WHERE reservation_id = $1
+                    AND book_id = $2
+                    AND (user_id = $3 OR $4)
+            "#,
+            event.reservation_id as _,
+            event.book_id as _,
+            event.requested_user as _,
+            event.manages_checkouts
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        if res.rows_affected() < 1 {
+            return Err(AppError::EntityNotFound(
+                "specified reservation not found".into(),
+            ));
+        }
+
+        // a cancelled claim is passed on to the next user in the queue
+        refresh_claims(
+            &mut tx,
+            event.book_id,
+            Utc::now(),
+            claim_period(&self.config),
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(())
+    }
+}
+
+async fn find_queue(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    book_id: BookId,
+) -> AppResult<Vec<ReservationRow>> {
+    sqlx::query_as!(
+        ReservationRow,
+        r#"
+            SELECT
+                reservation_id,
+                book_id,
+                user_id,
+                reserved_at,
+                claim_expires_at
+            FROM reservations
+            WHERE book_id = $1
+            ORDER BY reserved_at ASC
+        "#,
+        book_id as _
+    )
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)
+}
+
+// counts copies on the shelf that are not held for a reservation
+pub(crate) async fn count_unclaimed_copies(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    book_id: BookId,
+    now: DateTime<Utc>,
+) -> AppResult<i64> {
+    sqlx::query_scalar!(
+        r#"
+            SELECT (
+                SELECT COUNT(*) FROM book_copies AS bc
+                WHERE bc.book_id = $1
+                    AND NOT EXISTS (SELECT 1 FROM checkouts AS c WHERE c.copy_id = bc.copy_id)
+            ) - (
+                SELECT COUNT(*) FROM reservations
+                WHERE book_id = $1 AND claim_expires_at > $2
+            ) AS "count!"
+        "#,
+        book_id as _,
+        now
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)
+}
+
+// drops reservations whose claim has lapsed and hands every copy on the shelf
+// that nobody holds a claim for to the oldest waiting reservation
+pub(crate) async fn refresh_claims(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    book_id: BookId,
+    now: DateTime<Utc>,
+    claim_period: Duration,
+) -> AppResult<()> {
+    sqlx::query!(
+        r#"
+            DELETE FROM reservations
+            WHERE book_id = $1 AND claim_expires_at <= $2
+        "#,
+        book_id as _,
+        now
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    let claims = sqlx::query!(
+        r#"
+            UPDATE reservations
+            SET claim_expires_at = $3
+            WHERE reservation_id IN (
+                SELECT reservation_id FROM reservations
+                WHERE book_id = $1 AND claim_expires_at IS NULL
+                ORDER BY reserved_at ASC
+                LIMIT GREATEST(
+                    (
+                        SELECT COUNT(*) FROM book_copies AS bc
+                        WHERE bc.book_id = $1
+                            AND NOT EXISTS (
+                                SELECT 1 FROM checkouts AS c WHERE c.copy_id = bc.copy_id
+                            )
+                    ) - (
+                        SELECT COUNT(*) FROM reservations
+                        WHERE book_id = $1 AND claim_expires_at > $2
+                    ),
+                    0
+                )
+            )
+            RETURNING user_id AS "user_id: UserId", claim_expires_at
+        "#,
+        book_id as _,
+        now,
+        now + claim_period
+    )
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    // everyone whose reservation now holds a copy hears of it
+    for claim in claims {
+        queue_notification(
+            tx,
+            &QueueNotification::new(
+                claim.user_id,
+                NotificationKind::ReservationAvailable,
+                book_id,
+                claim.claim_expires_at,
+                None,
+                now,
+            ),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, sync::Arc};
+
+    use kernel::{
+        model::{
+            checkout::event::{CreateCheckout, UpdateReturned},
+            user::{UserStatus, event::CreateUser},
+        },
+        repository::{checkout::CheckoutRepository, user::UserRepository},
+    };
+
+    use crate::{
+        password::Argon2PasswordHasher,
+        repository::{checkout::CheckoutRepositoryImpl, user::UserRepositoryImpl},
+    };
+
+    use super::*;
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_claim_on_return(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let config = CheckoutConfig::default();
+        let user_repo = UserRepositoryImpl::new(
+            ConnectionPool::new(pool.clone()),
+            Arc::new(Argon2PasswordHasher::default()),
+        );
+        let checkout_repo =
+            CheckoutRepositoryImpl::new(ConnectionPool::new(pool.clone()), config.clone());
+        let repo = ReservationRepositoryImpl::new(ConnectionPool::new(pool), config);
+
+        let book_id = BookId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4d")?;
+        let owner_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let waiting_user = user_repo
+            .create(CreateUser {
+                user_id: UserId::new(),
+                name: "Waiting User".into(),
+                email: "waiting@example.com".into(),
+                password: "test_password".into(),
+                status: UserStatus::Active,
+                created_by: None,
+            })
+            .await?;
+
+        let now = Utc::now();
+        let res = repo
+            .create(CreateReservation::new(book_id, waiting_user.id, now))
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        checkout_repo
+            .create(CreateCheckout::new(book_id, None, owner_id, owner_id, now))
+            .await?;
+        repo.create(CreateReservation::new(book_id, waiting_user.id, now))
+            .await?;
+
+        let checkout = checkout_repo
+            .find_unreturned_by_user_id(owner_id)
+            .await?
+            .pop()
+            .expect("Checkout not found");
+        checkout_repo
+            .update_returned(UpdateReturned::new(
+                checkout.id,
+                book_id,
+                owner_id,
+                false,
+                None,
+                now,
+            ))
+            .await?;
+
+        let queue = repo.find_by_book_id(book_id).await?;
+        assert_eq!(queue.len(), 1);
+        assert!(queue[0].claim_expires_at.is_some());
+
+        let res = checkout_repo
+            .create(CreateCheckout::new(
+                book_id,
+                None,
+                owner_id,
+                owner_id,
+                Utc::now(),
+            ))
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        checkout_repo
+            .create(CreateCheckout::new(
+                book_id,
+                None,
+                waiting_user.id,
+                waiting_user.id,
+                Utc::now(),
+            ))
+            .await?;
+        assert!(repo.find_by_book_id(book_id).await?.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,282 @@
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        audit::{AuditAction, AuditTarget, event::RecordAudit},
+        role::{
+            BorrowingPolicy, Permission, Role,
+            event::{CreateRole, UpdateBorrowingPolicy},
+        },
+    },
+    repository::role::RoleRepository,
+};
+use shared::error::{AppError, AppResult};
+
+use crate::{
+    database::{
+        ConnectionPool,
+        model::role::{BorrowingPolicyRow, RoleRow},
+    },
+    repository::audit::{borrowing_policy_snapshot, record_audit, role_snapshot},
+};
+
+#[derive(new)]
+pub struct RoleRepositoryImpl {
+    db: ConnectionPool,
+}
+
+#[async_trait]
+impl RoleRepository for RoleRepositoryImpl {
+    async fn find_all(&self) -> AppResult<Vec<Role>> {
+        sqlx::query_as!(
+            RoleRow,
+            r#"
+                SELECT
+                    r.name,
+                    ARRAY(
+                        SELECT p.permission FROM role_permissions AS p
+                        WHERE p.role_id = r.role_id ORDER BY p.permission
+                    ) AS "permissions!"
+                FROM roles AS r
+                ORDER BY r.name
+            "#
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .into_iter()
+        .map(Role::try_from)
+        .collect()
+    }
+    async fn create(&self, event: CreateRole) -> AppResult<Role> {
+        let mut tx = self.db.begin().await?;
+        let role_id = sqlx::query_scalar!(
+            "INSERT INTO roles (name) VALUES ($1) RETURNING role_id",
+            event.name
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_unique_violation() => role_exists(&event.name),
+            e => AppError::SpecificOperationError(e),
+        })?;
+
+        let permissions = sorted(event.permissions);
+        sqlx::query!(
+            r#"
+                INSERT INTO role_permissions (role_id, permission)
+                SELECT $1, UNNEST($2::VARCHAR[])
+            "#,
+            role_id,
+            &permissions
+                .iter()
+                .map(|p| p.as_ref().to_string())
+                .collect::<Vec<_>>()
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        record_audit(
+            &mut tx,
+            RecordAudit::new(
+                Some(event.requested_user),
+                AuditAction::Create,
+                AuditTarget::role(&event.name),
+                None,
+                role_snapshot(&permissions),
+            ),
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+        Ok(Role {
+            name: event.name,
+            permissions,
+        })
+    }
+    async fn find_borrowing_policy(&self, role_name: &str) -> AppResult<BorrowingPolicy> {
+        sqlx::query_as!(
+            BorrowingPolicyRow,
+            r#"
+                SELECT max_loans, block_when_overdue, block_own_books
+                FROM roles
+                WHERE name = $1
+            "#,
+            role_name
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .map(BorrowingPolicy::from)
+        .ok_or_else(|| unknown_role(role_name))
+    }
+    async fn update_borrowing_policy(
+        &self,
+        event: UpdateBorrowingPolicy,
+    ) -> AppResult<BorrowingPolicy> {
+        let UpdateBorrowingPolicy {
+            role_name,
+            policy,
+            requested_user,
+        } = event;
+        let mut tx = self.db.begin().await?;
+        // `old` is read before the update, so it still has the previous values
+        let before = sqlx::query_as!(
+            BorrowingPolicyRow,
+            r#"
+                UPDATE roles AS r
+                SET max_loans = $2, block_when_overdue = $3, block_own_books = $4
+                FROM roles AS old
+                WHERE r.name = $1 AND old.role_id = r.role_id
+                RETURNING
+                    old.max_loans,
+                    old.block_when_overdue AS "block_when_overdue!",
+                    old.block_own_books AS "block_own_books!"
+            "#,
+            role_name,
+            policy.max_loans,
+            policy.block_when_overdue,
+            policy.block_own_books
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .map(BorrowingPolicy::from)
+        .ok_or_else(|| unknown_role(&role_name))?;
+
+        record_audit(
+            &mut tx,
+            RecordAudit::new(
+                Some(requested_user),
+                AuditAction::Update,
+                AuditTarget::role(&role_name),
+                borrowing_policy_snapshot(&before),
+                borrowing_policy_snapshot(&policy),
+            ),
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+        Ok(policy)
+    }
+}
+
+// without duplicates, in the order roles list their permissions
+pub(crate) fn sorted(mut permissions: Vec<Permission>) -> Vec<Permission> {
+    permissions.sort();
+    permissions.dedup();
+    permissions
+}
+
+pub(crate) fn role_exists(name: &str) -> AppError {
+    AppError::Conflict(format!("Role {name} already exists"))
+}
+
+pub(crate) fn role_not_found(name: &str) -> AppError {
+    AppError::UnprocessableEntity(format!("Role {name} does not exist"))
+}
+
+// for a role named in the path rather than in the request body
+pub(crate) fn unknown_role(name: &str) -> AppError {
+    AppError::EntityNotFound(format!("Role {name} not found"))
+}
+
+#[cfg(test)]
+mod tests {
+    use kernel::model::{id::UserId, role::BuiltinRole};
+
+    use super::*;
+
+    #[sqlx::test]
+    async fn custom_roles_are_listed_with_the_built_in_ones(
+        pool: sqlx::PgPool,
+    ) -> anyhow::Result<()> {
+        let repo = RoleRepositoryImpl::new(ConnectionPool::new(pool));
+        // the audit log does not check that the actor exists
+        let admin = UserId::new();
+
+        let role = repo
+            .create(CreateRole::new(
+                "Front desk".into(),
+                vec![
+                    Permission::ManageUsers,
+                    Permission::ManageCheckouts,
+                    Permission::ManageUsers,
+                ],
+                admin,
+            ))
+            .await?;
+        assert_eq!(
+            role.permissions,
+            vec![Permission::ManageCheckouts, Permission::ManageUsers]
+        );
+
+        let roles = repo.find_all().await?;
+        let names = roles.iter().map(|r| r.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["Admin", "Front desk", "Librarian", "User"]);
+        assert!(roles.contains(&role));
+        assert!(roles.contains(&BuiltinRole::Admin.into()));
+        assert!(roles.contains(&BuiltinRole::Librarian.into()));
+
+        assert!(matches!(
+            repo.create(CreateRole::new("Front desk".into(), Vec::new(), admin))
+                .await,
+            Err(AppError::Conflict(_))
+        ));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn borrowing_policies_are_kept_per_role(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = RoleRepositoryImpl::new(ConnectionPool::new(pool));
+        let admin = UserId::new();
+
+        for role in [
+            BuiltinRole::Admin,
+            BuiltinRole::Librarian,
+            BuiltinRole::User,
+        ] {
+            assert_eq!(
+                repo.find_borrowing_policy(role.as_ref()).await?,
+                role.borrowing_policy()
+            );
+        }
+        repo.create(CreateRole::new("Guest".into(), Vec::new(), admin))
+            .await?;
+        assert_eq!(
+            repo.find_borrowing_policy("Guest").await?,
+            BorrowingPolicy::default()
+        );
+
+        let policy = BorrowingPolicy {
+            max_loans: Some(1),
+            block_when_overdue: true,
+            block_own_books: true,
+        };
+        let updated = repo
+            .update_borrowing_policy(UpdateBorrowingPolicy::new("Guest".into(), policy, admin))
+            .await?;
+        assert_eq!(updated, policy);
+        assert_eq!(repo.find_borrowing_policy("Guest").await?, policy);
+        assert_eq!(
+            repo.find_borrowing_policy("User").await?,
+            BuiltinRole::User.borrowing_policy()
+        );
+
+        assert!(matches!(
+            repo.find_borrowing_policy("Nobody").await,
+            Err(AppError::EntityNotFound(_))
+        ));
+        assert!(matches!(
+            repo.update_borrowing_policy(UpdateBorrowingPolicy::new(
+                "Nobody".into(),
+                policy,
+                admin
+            ))
+            .await,
+            Err(AppError::EntityNotFound(_))
+        ));
+        Ok(())
+    }
+}
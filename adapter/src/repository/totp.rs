@@ -0,0 +1,360 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use derive_new::new;
+use kernel::{
+    model::{
+        id::UserId,
+        totp::{
+            RecoveryCode, TotpEnrollment,
+            event::{BeginTotpEnrollment, ConfirmTotpEnrollment, VerifyTotp},
+        },
+    },
+    repository::totp::TotpRepository,
+};
+use sha2::{Digest, Sha256};
+use shared::{
+    config::TotpConfig,
+    error::{AppError, AppResult},
+};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use crate::database::ConnectionPool;
+
+#[derive(new)]
+pub struct TotpRepositoryImpl {
+    db: ConnectionPool,
+    config: TotpConfig,
+}
+
+#[async_trait]
+impl TotpRepository for TotpRepositoryImpl {
+    async fn is_enabled(&self, user_id: UserId) -> AppResult<bool> {
+        let enabled = sqlx::query_scalar!(
+            r#"
+                SELECT EXISTS (
+                    SELECT 1 FROM user_totp
+                    WHERE user_id = $1 AND enabled_at IS NOT NULL
+                ) AS "enabled!"
+            "#,
+            user_id as _
+        )
+        .fetch_one(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        Ok(enabled)
+    }
+    async fn begin_enrollment(&self, event: BeginTotpEnrollment) -> AppResult<TotpEnrollment> {
+        let secret = new_secret();
+        let enrollment = totp_enrollment(&self.config, &secret, event.account_name)?;
+
+        let res = sqlx::query!(
+            r#"
+                INSERT INTO user_totp (user_id, secret)
+                VALUES ($1, $2)
+                ON CONFLICT (user_id) DO UPDATE
+                SET secret = EXCLUDED.secret, created_at = CURRENT_TIMESTAMP(3)
+                WHERE user_totp.enabled_at IS NULL
+            "#,
+            event.user_id as _,
+            secret
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        if res.rows_affected() < 1 {
+            return Err(totp_already_enabled());
+        }
+        Ok(enrollment)
+    }
+    async fn confirm_enrollment(
+        &self,
+        event: ConfirmTotpEnrollment,
+    ) -> AppResult<Option<Vec<RecoveryCode>>> {
+        let mut tx = self.db.begin().await?;
+        let Some(secret) = sqlx::query_scalar!(
+            r#"
+                SELECT secret FROM user_totp
+                WHERE user_id = $1 AND enabled_at IS NULL
+                FOR UPDATE
+            "#,
+            event.user_id as _
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        else {
+            return Ok(None);
+        };
+        let Some(step) = matching_step(&secret, &event.code, None)? else {
+            return Ok(None);
+        };
+
+        sqlx::query!(
+            r#"
+                UPDATE user_totp
+                SET enabled_at = CURRENT_TIMESTAMP(3), last_used_step = $2
+                WHERE user_id = $1
+            "#,
+            event.user_id as _,
+            step
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        let codes =
+            replace_recovery_codes(&mut tx, event.user_id, self.config.recovery_codes).await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+        Ok(Some(codes))
+    }
+    async fn verify(&self, event: VerifyTotp) -> AppResult<bool> {
+        let mut tx = self.db.begin().await?;
+        let Some(row) = sqlx::query!(
+            r#"
+                SELECT secret, last_used_step FROM user_totp
+                WHERE user_id = $1 AND enabled_at IS NOT NULL
+                FOR UPDATE
+            "#,
+            event.user_id as _
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        else {
+            return Ok(false);
+        };
+
+        if let Some(step) = matching_step(&row.secret, &event.code, row.last_used_step)? {
+            sqlx::query!(
+                "UPDATE user_totp SET last_used_step = $2 WHERE user_id = $1",
+                event.user_id as _,
+                step
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+        } else {
+            let res = sqlx::query!(
+                r#"
+                    UPDATE user_recovery_codes SET used_at = CURRENT_TIMESTAMP(3)
+                    WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+                "#,
+                event.user_id as _,
+                hash_recovery_code(&event.code)
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+            if res.rows_affected() < 1 {
+                return Ok(false);
+            }
+        }
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+        Ok(true)
+    }
+    async fn regenerate_recovery_codes(&self, user_id: UserId) -> AppResult<Vec<RecoveryCode>> {
+        let mut tx = self.db.begin().await?;
+        let codes = replace_recovery_codes(&mut tx, user_id, self.config.recovery_codes).await?;
+        tx.commit().await.map_err(AppError::TransactionError)?;
+        Ok(codes)
+    }
+    async fn disable(&self, user_id: UserId) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+        sqlx::query!(
+            "DELETE FROM user_recovery_codes WHERE user_id = $1",
+            user_id as _
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        sqlx::query!("DELETE FROM user_totp WHERE user_id = $1", user_id as _)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+        tx.commit().await.map_err(AppError::TransactionError)?;
+        Ok(())
+    }
+}
+
+async fn replace_recovery_codes(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: UserId,
+    count: usize,
+) -> AppResult<Vec<RecoveryCode>> {
+    let codes = new_recovery_codes(count);
+    let hashes = codes
+        .iter()
+        .map(|c| hash_recovery_code(&c.0))
+        .collect::<Vec<_>>();
+
+    sqlx::query!(
+        "DELETE FROM user_recovery_codes WHERE user_id = $1",
+        user_id as _
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+    sqlx::query!(
+        r#"
+            INSERT INTO user_recovery_codes (user_id, code_hash)
+            SELECT $1, UNNEST($2::VARCHAR[])
+        "#,
+        user_id as _,
+        &hashes
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+    Ok(codes)
+}
+
+const STEP_SECS: u64 = 30;
+
+// base32, as authenticator apps expect it
+pub(crate) fn new_secret() -> String {
+    match Secret::generate_secret().to_encoded() {
+        Secret::Encoded(secret) => secret,
+        Secret::Raw(_) => unreachable!("to_encoded returns an encoded secret"),
+    }
+}
+
+fn totp(secret: &str, account_name: String, issuer: Option<String>) -> AppResult<TOTP> {
+    let secret = Secret::Encoded(secret.into())
+        .to_bytes()
+        .map_err(|e| AppError::ConversionEntityError(e.to_string()))?;
+    TOTP::new(
+        Algorithm::SHA1,
+        6,
+        1,
+        STEP_SECS,
+        secret,
+        issuer,
+        account_name,
+    )
+    .map_err(|e| AppError::ConversionEntityError(e.to_string()))
+}
+
+pub(crate) fn totp_enrollment(
+    config: &TotpConfig,
+    secret: &str,
+    account_name: String,
+) -> AppResult<TotpEnrollment> {
+    let totp = totp(secret, account_name, Some(config.issuer.clone()))?;
+    Ok(TotpEnrollment {
+        secret: secret.into(),
+        provisioning_uri: totp.get_url(),
+    })
+}
+
+// the time step `code` belongs to, allowing one step of clock drift either way;
+// steps up to `last_used_step` are spent, so a code cannot be replayed
+pub(crate) fn matching_step(
+    secret: &str,
+    code: &str,
+    last_used_step: Option<i64>,
+) -> AppResult<Option<i64>> {
+    let totp = totp(secret, String::new(), None)?;
+    let code = code.trim();
+    let current = Utc::now().timestamp() / STEP_SECS as i64;
+    Ok((current - 1..=current + 1)
+        .filter(|step| last_used_step.is_none_or(|last| *step > last))
+        .find(|step| totp.generate(*step as u64 * STEP_SECS) == code))
+}
+
+pub(crate) fn new_recovery_codes(count: usize) -> Vec<RecoveryCode> {
+    (0..count)
+        .map(|_| {
+            let random = uuid::Uuid::new_v4().simple().to_string();
+            RecoveryCode(format!("{}-{}", &random[..5], &random[5..10]))
+        })
+        .collect()
+}
+
+// recovery codes are random, so a fast hash is enough to keep them from being read
+pub(crate) fn hash_recovery_code(code: &str) -> String {
+    let normalized = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase();
+    hex::encode(Sha256::digest(normalized.as_bytes()))
+}
+
+pub(crate) fn totp_already_enabled() -> AppError {
+    AppError::Conflict("two-factor authentication is already enabled".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[sqlx::test(fixtures("common"))]
+    async fn enrollment_enables_codes_and_recovery_codes(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let user_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let repo = TotpRepositoryImpl::new(ConnectionPool::new(pool), TotpConfig::default());
+
+        let enrollment = repo
+            .begin_enrollment(BeginTotpEnrollment::new(
+                user_id,
+                "eleazar.fig@example.com".into(),
+            ))
+            .await?;
+        assert!(!repo.is_enabled(user_id).await?);
+        let code =
+            totp(&enrollment.secret, String::new(), None)?.generate(Utc::now().timestamp() as u64);
+        let codes = repo
+            .confirm_enrollment(ConfirmTotpEnrollment::new(user_id, code.clone()))
+            .await?
+            .expect("the code of the new secret confirms it");
+        assert!(repo.is_enabled(user_id).await?);
+        assert!(matches!(
+            repo.begin_enrollment(BeginTotpEnrollment::new(
+                user_id,
+                "eleazar.fig@example.com".into()
+            ))
+            .await,
+            Err(AppError::Conflict(_))
+        ));
+
+        assert!(!repo.verify(VerifyTotp::new(user_id, code)).await?);
+        let RecoveryCode(recovery_code) = &codes[0];
+        assert!(
+            repo.verify(VerifyTotp::new(user_id, recovery_code.clone()))
+                .await?
+        );
+        assert!(
+            !repo
+                .verify(VerifyTotp::new(user_id, recovery_code.clone()))
+                .await?
+        );
+
+        repo.disable(user_id).await?;
+        assert!(!repo.is_enabled(user_id).await?);
+        Ok(())
+    }
+
+    #[test]
+    fn codes_are_accepted_once() -> AppResult<()> {
+        let secret = new_secret();
+        let totp = totp(&secret, String::new(), None)?;
+        let code = totp.generate(Utc::now().timestamp() as u64);
+
+        let step = matching_step(&secret, &code, None)?.expect("current code matches");
+        assert_eq!(matching_step(&secret, &code, Some(step))?, None);
+        assert_eq!(matching_step(&secret, "000000x", None)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn recovery_codes_are_hashed_regardless_of_formatting() {
+        let RecoveryCode(code) = new_recovery_codes(1).remove(0);
+        assert_eq!(
+            hash_recovery_code(&code),
+            hash_recovery_code(&code.to_uppercase().replace('-', " "))
+        );
+        assert_ne!(hash_recovery_code(&code), hash_recovery_code("other"));
+    }
+}
@@ -1,23 +1,39 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use derive_new::new;
 use kernel::{
     model::{
+        audit::{AuditAction, AuditTarget, event::RecordAudit},
         id::UserId,
-        role::Role,
+        list::{CursorDirection, CursorListOptions, CursorPaginatedList},
+        role::{BuiltinRole, Role},
         user::{
-            User,
-            event::{CreateUser, DeleteUser, UpdateUserPassword, UpdateUserRole},
+            User, UserStatus,
+            event::{
+                CreateUser, DeleteUser, ResetUserPassword, UpdateUserPassword, UpdateUserRole,
+                UpdateUserStatus,
+            },
         },
     },
     repository::user::UserRepository,
 };
 use shared::error::{AppError, AppResult};
 
-use crate::database::{ConnectionPool, model::user::UserRow};
+use crate::{
+    database::{ConnectionPool, model::user::UserRow},
+    password::PasswordHasher,
+    repository::{
+        audit::{record_audit, snapshot, user_snapshot},
+        role::role_not_found,
+    },
+};
 
 #[derive(new)]
 pub struct UserRepositoryImpl {
     db: ConnectionPool,
+    hasher: Arc<dyn PasswordHasher>,
 }
 
 #[async_trait]
@@ -31,6 +47,11 @@ impl UserRepository for UserRepositoryImpl {
                     u.name,
                     u.email,
                     r.name as role_name,
+                    ARRAY(
+                        SELECT p.permission FROM role_permissions AS p
+                        WHERE p.role_id = u.role_id ORDER BY p.permission
+                    ) AS "permissions!",
+                    u.status,
                     u.created_at,
                     u.updated_at
                 FROM users AS u
@@ -47,62 +68,201 @@ impl UserRepository for UserRepositoryImpl {
             None => Ok(None),
         }
     }
-    async fn find_all(&self) -> AppResult<Vec<User>> {
-        let rows = sqlx::query_as!(
+    async fn find_by_email(&self, email: &str) -> AppResult<Option<User>> {
+        let row = sqlx::query_as!(
             UserRow,
             r#"
-                SELECT 
+                SELECT
+                    u.user_id,
+                    u.name,
+                    u.email,
+                    r.name as role_name,
+                    ARRAY(
+                        SELECT p.permission FROM role_permissions AS p
+                        WHERE p.role_id = u.role_id ORDER BY p.permission
+                    ) AS "permissions!",
+                    u.status,
+                    u.created_at,
+                    u.updated_at
+                FROM users AS u
+                INNER JOIN roles AS r USING (role_id)
+                WHERE u.email = $1
+            "#,
+            email
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        row.map(User::try_from).transpose()
+    }
+    async fn find_by_status(&self, status: UserStatus) -> AppResult<Vec<User>> {
+        sqlx::query_as!(
+            UserRow,
+            r#"
+                SELECT
                     u.user_id,
                     u.name,
                     u.email,
                     r.name as role_name,
+                    ARRAY(
+                        SELECT p.permission FROM role_permissions AS p
+                        WHERE p.role_id = u.role_id ORDER BY p.permission
+                    ) AS "permissions!",
+                    u.status,
                     u.created_at,
                     u.updated_at
                 FROM users AS u
                 INNER JOIN roles AS r USING (role_id)
-                ORDER BY u.created_at DESC
-            "#
+                WHERE u.status = $1
+                ORDER BY u.created_at ASC, u.user_id ASC
+            "#,
+            status.as_ref()
         )
         .fetch_all(self.db.inner_ref())
         .await
         .map_err(AppError::SpecificOperationError)?
         .into_iter()
-        .filter_map(|r| User::try_from(r).ok())
-        .collect();
+        .map(User::try_from)
+        .collect()
+    }
+    async fn find_all(&self, options: CursorListOptions) -> AppResult<CursorPaginatedList<User>> {
+        let CursorListOptions { limit, cursor } = options;
+        let (after_at, after_id) = cursor.map(|c| (c.timestamp, c.id)).unzip();
+
+        let rows = match cursor.map(|c| c.direction) {
+            None | Some(CursorDirection::Next) => {
+                sqlx::query_as!(
+                    UserRow,
+                    r#"
+                        SELECT 
+                            u.user_id,
+                            u.name,
+                            u.email,
+                            r.name as role_name,
+                            ARRAY(
+                                SELECT p.permission FROM role_permissions AS p
+                                WHERE p.role_id = u.role_id ORDER BY p.permission
+                            ) AS "permissions!",
+                            u.status,
+                            u.created_at,
+                            u.updated_at
+                        FROM users AS u
+                        INNER JOIN roles AS r USING (role_id)
+                        WHERE $2::timestamptz IS NULL OR (u.created_at, u.user_id) < ($2, $3::uuid)
+                        ORDER BY u.created_at DESC, u.user_id DESC
+                        LIMIT $1
+                    "#,
+                    limit + 1,
+                    after_at,
+                    after_id
+                )
+                .fetch_all(self.db.inner_ref())
+                .await
+            }
+            Some(CursorDirection::Prev) => {
+                sqlx::query_as!(
+                    UserRow,
+                    r#"
+                        SELECT 
+                            u.user_id,
+                            u.name,
+                            u.email,
+                            r.name as role_name,
+                            ARRAY(
+                                SELECT p.permission FROM role_permissions AS p
+                                WHERE p.role_id = u.role_id ORDER BY p.permission
+                            ) AS "permissions!",
+                            u.status,
+                            u.created_at,
+                            u.updated_at
+                        FROM users AS u
+                        INNER JOIN roles AS r USING (role_id)
+                        WHERE (u.created_at, u.user_id) > ($2, $3::uuid)
+                        ORDER BY u.created_at ASC, u.user_id ASC
+                        LIMIT $1
+                    "#,
+                    limit + 1,
+                    after_at,
+                    after_id
+                )
+                .fetch_all(self.db.inner_ref())
+                .await
+            }
+        }
+        .map_err(AppError::SpecificOperationError)?;
 
-        Ok(rows)
+        let CursorPaginatedList {
+            limit,
+            next_cursor,
+            prev_cursor,
+            items,
+        } = CursorPaginatedList::from_rows(rows, limit, cursor, |r| {
+            (r.created_at, r.user_id.raw())
+        });
+
+        Ok(CursorPaginatedList {
+            limit,
+            next_cursor,
+            prev_cursor,
+            items: items
+                .into_iter()
+                .filter_map(|r| User::try_from(r).ok())
+                .collect(),
+        })
     }
     async fn create(&self, event: CreateUser) -> AppResult<User> {
-        let user_id = UserId::new();
-        let hashed_password = hash_password(&event.password)?;
+        let user_id = event.user_id;
+        let hashed_password = self.hasher.hash(&event.password)?;
 
-        let role = Role::User;
+        let role = Role::from(BuiltinRole::User);
 
+        let mut tx = self.db.begin().await?;
         let res = sqlx::query!(
             r#"
-                INSERT INTO users (user_id, name, email, password_hash, role_id)
-                SELECT $1, $2, $3, $4, role_id FROM roles WHERE name = $5
+                INSERT INTO users (user_id, name, email, password_hash, role_id, status)
+                SELECT $1, $2, $3, $4, role_id, $6 FROM roles WHERE name = $5
             "#,
             user_id as _,
             event.name,
             event.email,
             hashed_password,
-            role.as_ref(),
+            role.name,
+            event.status.as_ref(),
         )
-        .execute(self.db.inner_ref())
+        .execute(&mut *tx)
         .await
-        .map_err(AppError::SpecificOperationError)?;
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                AppError::Conflict(format!("User with email {} already exists", event.email))
+            }
+            e => AppError::SpecificOperationError(e),
+        })?;
 
         if res.rows_affected() < 1 {
             return Err(AppError::NoRowsAffectedError(
                 "No user has been created.".into(),
             ));
         }
+
+        record_audit(
+            &mut tx,
+            RecordAudit::new(
+                Some(event.created_by.unwrap_or(user_id)),
+                AuditAction::Create,
+                AuditTarget::user(user_id),
+                None,
+                user_snapshot(&event.name, &event.email, &role.name, event.status.as_ref()),
+            ),
+        )
+        .await?;
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
         Ok(User {
             id: user_id,
             name: event.name,
             email: event.email,
             role,
+            status: event.status,
         })
     }
     async fn update_password(&self, event: UpdateUserPassword) -> AppResult<()> {
@@ -119,9 +279,13 @@ impl UserRepository for UserRepositoryImpl {
         .map_err(AppError::SpecificOperationError)?
         .password_hash;
 
-        verify_password(&event.current_password, &original_password_hash)?;
+        verify_password(
+            self.hasher.as_ref(),
+            &event.current_password,
+            &original_password_hash,
+        )?;
 
-        let new_password_hash = hash_password(&event.new_password)?;
+        let new_password_hash = self.hasher.hash(&event.new_password)?;
         sqlx::query!(
             r#"
                 UPDATE users
@@ -135,23 +299,36 @@ impl UserRepository for UserRepositoryImpl {
         .await
         .map_err(AppError::SpecificOperationError)?;
 
+        // the hashes are left out, so the entry only tells the password changed
+        record_audit(
+            &mut tx,
+            RecordAudit::new(
+                Some(event.user_id),
+                AuditAction::ChangePassword,
+                AuditTarget::user(event.user_id),
+                None,
+                None,
+            ),
+        )
+        .await?;
+
         tx.commit().await.map_err(AppError::TransactionError)?;
 
         Ok(())
     }
-    async fn update_role(&self, event: UpdateUserRole) -> AppResult<()> {
+    async fn reset_password(&self, event: ResetUserPassword) -> AppResult<()> {
+        let password_hash = self.hasher.hash(&event.new_password)?;
+        let mut tx = self.db.begin().await?;
         let res = sqlx::query!(
             r#"
                 UPDATE users
-                SET role_id = (
-                    SELECT role_id FROM roles WHERE name = $2
-                )
-                WHERE user_id = $1
+                SET password_hash = $1, updated_at = NOW()
+                WHERE user_id = $2
             "#,
-            event.user_id as _,
-            event.role.as_ref(),
+            password_hash,
+            event.user_id as _
         )
-        .execute(self.db.inner_ref())
+        .execute(&mut *tx)
         .await
         .map_err(AppError::SpecificOperationError)?;
         if res.rows_affected() < 1 {
@@ -159,35 +336,179 @@ impl UserRepository for UserRepositoryImpl {
                 "Specified user not found.".into(),
             ));
         }
+
+        record_audit(
+            &mut tx,
+            RecordAudit::new(
+                Some(event.user_id),
+                AuditAction::ResetPassword,
+                AuditTarget::user(event.user_id),
+                None,
+                None,
+            ),
+        )
+        .await?;
+        tx.commit().await.map_err(AppError::TransactionError)?;
         Ok(())
     }
-    async fn delete(&self, event: DeleteUser) -> AppResult<()> {
+    async fn update_role(&self, event: UpdateUserRole) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+        let role_id = sqlx::query_scalar!("SELECT role_id FROM roles WHERE name = $1", event.role)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(AppError::SpecificOperationError)?
+            .ok_or_else(|| role_not_found(&event.role))?;
+
+        // `old` is read before the update, so it still has the previous role
+        let previous_role = sqlx::query_scalar!(
+            r#"
+                UPDATE users AS u
+                SET role_id = $2
+                FROM users AS old
+                INNER JOIN roles AS r USING (role_id)
+                WHERE u.user_id = $1 AND old.user_id = u.user_id
+                RETURNING r.name AS "name!"
+            "#,
+            event.user_id as _,
+            role_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| AppError::NoRowsAffectedError("Specified user not found.".into()))?;
+
+        record_audit(
+            &mut tx,
+            RecordAudit::new(
+                Some(event.requested_user),
+                AuditAction::ChangeRole,
+                AuditTarget::user(event.user_id),
+                snapshot(serde_json::json!({ "role": previous_role })),
+                snapshot(serde_json::json!({ "role": event.role })),
+            ),
+        )
+        .await?;
+        tx.commit().await.map_err(AppError::TransactionError)?;
+        Ok(())
+    }
+    async fn update_status(&self, event: UpdateUserStatus) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
         let res = sqlx::query!(
             r#"
-                DELETE FROM users
-                WHERE user_id = $1
+                UPDATE users
+                SET status = $3
+                WHERE user_id = $1 AND status = $2
             "#,
-            event.user_id as _
+            event.user_id as _,
+            event.from.as_ref(),
+            event.to.as_ref(),
         )
-        .execute(self.db.inner_ref())
+        .execute(&mut *tx)
         .await
         .map_err(AppError::SpecificOperationError)?;
         if res.rows_affected() < 1 {
-            return Err(AppError::NoRowsAffectedError(
-                "Specified user not found.".into(),
-            ));
+            return Err(status_mismatch(&event));
         }
+
+        record_audit(
+            &mut tx,
+            RecordAudit::new(
+                Some(event.requested_user),
+                AuditAction::ChangeStatus,
+                AuditTarget::user(event.user_id),
+                snapshot(serde_json::json!({ "status": event.from.as_ref() })),
+                snapshot(serde_json::json!({ "status": event.to.as_ref() })),
+            ),
+        )
+        .await?;
+        tx.commit().await.map_err(AppError::TransactionError)?;
         Ok(())
     }
+    async fn delete(&self, event: DeleteUser) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+        let before = sqlx::query!(
+            r#"
+                DELETE FROM users AS u
+                WHERE user_id = $1
+                RETURNING
+                    name,
+                    email,
+                    (SELECT r.name FROM roles AS r WHERE r.role_id = u.role_id) AS "role!",
+                    status
+            "#,
+            event.user_id as _
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| AppError::NoRowsAffectedError("Specified user not found.".into()))?;
+
+        record_audit(
+            &mut tx,
+            RecordAudit::new(
+                Some(event.requested_user),
+                AuditAction::Delete,
+                AuditTarget::user(event.user_id),
+                user_snapshot(&before.name, &before.email, &before.role, &before.status),
+                None,
+            ),
+        )
+        .await?;
+        tx.commit().await.map_err(AppError::TransactionError)?;
+        Ok(())
+    }
+    async fn delete_stale_sign_ups(&self, created_before: DateTime<Utc>) -> AppResult<u64> {
+        let mut tx = self.db.begin().await?;
+        let deleted = sqlx::query!(
+            r#"
+                DELETE FROM users AS u
+                WHERE status IN ('unverified', 'rejected') AND created_at < $1
+                RETURNING
+                    user_id AS "user_id: UserId",
+                    name,
+                    email,
+                    (SELECT r.name FROM roles AS r WHERE r.role_id = u.role_id) AS "role!",
+                    status
+            "#,
+            created_before
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        // the purge job deletes them, not a user
+        for user in &deleted {
+            record_audit(
+                &mut tx,
+                RecordAudit::new(
+                    None,
+                    AuditAction::Delete,
+                    AuditTarget::user(user.user_id),
+                    user_snapshot(&user.name, &user.email, &user.role, &user.status),
+                    None,
+                ),
+            )
+            .await?;
+        }
+        tx.commit().await.map_err(AppError::TransactionError)?;
+        Ok(deleted.len() as u64)
+    }
 }
 
-fn hash_password(password: &str) -> AppResult<String> {
-    let hashed = bcrypt::hash(password, bcrypt::DEFAULT_COST)?;
-    Ok(hashed)
+pub(crate) fn status_mismatch(event: &UpdateUserStatus) -> AppError {
+    AppError::UnprocessableEntity(format!(
+        "User with id {} is not {}",
+        event.user_id,
+        event.from.as_ref()
+    ))
 }
 
-fn verify_password(password: &str, password_hash: &str) -> AppResult<()> {
-    let valid = bcrypt::verify(password, password_hash)?;
+pub(crate) fn verify_password(
+    hasher: &dyn PasswordHasher,
+    password: &str,
+    password_hash: &str,
+) -> AppResult<()> {
+    let valid = hasher.verify(password, password_hash)?;
     if !valid {
         return Err(AppError::UnauthenticatedError);
     }
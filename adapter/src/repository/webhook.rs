@@ -0,0 +1,523 @@
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use derive_new::new;
+use kernel::{
+    model::{
+        id::{WebhookDeliveryId, WebhookId},
+        list::{CursorDirection, CursorListOptions, CursorPaginatedList},
+        webhook::{
+            CreatedWebhook, PendingDelivery, Webhook, WebhookDelivery, WebhookEventType,
+            event::{
+                CreateWebhook, DeleteWebhook, MarkDeliveryDelivered, MarkDeliveryFailed,
+                RetryDelivery,
+            },
+        },
+    },
+    repository::webhook::WebhookRepository,
+};
+use shared::error::{AppError, AppResult};
+
+use crate::database::{
+    ConnectionPool,
+    model::webhook::{PendingDeliveryRow, WebhookDeliveryRow, WebhookRow},
+};
+
+// signing secrets are told apart from other credentials by this
+const WEBHOOK_SECRET_PREFIX: &str = "whsec_";
+
+#[derive(new)]
+pub struct WebhookRepositoryImpl {
+    db: ConnectionPool,
+}
+
+#[async_trait]
+impl WebhookRepository for WebhookRepositoryImpl {
+    async fn create(&self, event: CreateWebhook) -> AppResult<CreatedWebhook> {
+        let secret = generate_secret();
+        let event_types = event
+            .event_types
+            .iter()
+            .map(|t| t.as_ref().to_string())
+            .collect::<Vec<_>>();
+        let row = sqlx::query_as!(
+            WebhookRow,
+            r#"
+                INSERT INTO webhooks (webhook_id, url, secret, event_types, created_by, created_at)
+                VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP(3))
+                RETURNING
+                    webhook_id AS "webhook_id: WebhookId",
+                    url,
+                    event_types,
+                    created_by,
+                    created_at
+            "#,
+            WebhookId::new() as _,
+            event.url,
+            secret,
+            &event_types,
+            event.created_by as _
+        )
+        .fetch_one(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        Ok(CreatedWebhook {
+            webhook: row.try_into()?,
+            secret,
+        })
+    }
+    async fn find_all(&self) -> AppResult<Vec<Webhook>> {
+        sqlx::query_as!(
+            WebhookRow,
+            r#"
+                SELECT
+                    webhook_id AS "webhook_id: WebhookId",
+                    url,
+                    event_types,
+                    created_by,
+                    created_at
+                FROM webhooks
+                ORDER BY created_at, webhook_id
+            "#
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .into_iter()
+        .map(Webhook::try_from)
+        .collect()
+    }
+    async fn delete(&self, event: DeleteWebhook) -> AppResult<()> {
+        let res = sqlx::query!(
+            r#"
+                DELETE FROM webhooks
+                WHERE webhook_id = $1
+            "#,
+            event.webhook_id as _
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        if res.rows_affected() < 1 {
+            return Err(webhook_not_found(event.webhook_id));
+        }
+        Ok(())
+    }
+    async fn find_deliveries(
+        &self,
+        webhook_id: WebhookId,
+        options: CursorListOptions,
+    ) -> AppResult<CursorPaginatedList<WebhookDelivery>> {
+        let exists = sqlx::query_scalar!(
+            r#"
+                SELECT EXISTS (SELECT 1 FROM webhooks WHERE webhook_id = $1) AS "exists!"
+            "#,
+            webhook_id as _
+        )
+        .fetch_one(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        if !exists {
+            return Err(webhook_not_found(webhook_id));
+        }
+
+        let CursorListOptions { limit, cursor } = options;
+        let (after_at, after_id) = cursor.map(|c| (c.timestamp, c.id)).unzip();
+
+        let rows = match cursor.map(|c| c.direction) {
+            None | Some(CursorDirection::Next) => {
+                sqlx::query_as!(
+                    WebhookDeliveryRow,
+                    r#"
+                SELECT
+                    delivery_id AS "delivery_id: WebhookDeliveryId",
+                    webhook_id AS "webhook_id: WebhookId",
+                    event_type,
+                    payload,
+                    status,
+                    attempts,
+                    next_attempt_at,
+                    last_attempt_at,
+                    response_status,
+                    last_error,
+                    created_at,
+                    delivered_at
+                FROM webhook_deliveries
+                WHERE webhook_id = $1
+                    AND ($3::timestamptz IS NULL OR (created_at, delivery_id) < ($3, $4::uuid))
+                ORDER BY created_at DESC, delivery_id DESC
+                LIMIT $2
+            "#,
+                    webhook_id as _,
+                    limit + 1,
+                    after_at,
+                    after_id
+                )
+                .fetch_all(self.db.inner_ref())
+                .await
+            }
+            Some(CursorDirection::Prev) => {
+                sqlx::query_as!(
+                    WebhookDeliveryRow,
+                    r#"
+                SELECT
+                    delivery_id AS "delivery_id: WebhookDeliveryId",
+                    webhook_id AS "webhook_id: WebhookId",
+                    event_type,
+                    payload,
+                    status,
+                    attempts,
+                    next_attempt_at,
+                    last_attempt_at,
+                    response_status,
+                    last_error,
+                    created_at,
+                    delivered_at
+                FROM webhook_deliveries
+                WHERE webhook_id = $1 AND (created_at, delivery_id) > ($3, $4::uuid)
+                ORDER BY created_at ASC, delivery_id ASC
+                LIMIT $2
+            "#,
+                    webhook_id as _,
+                    limit + 1,
+                    after_at,
+                    after_id
+                )
+                .fetch_all(self.db.inner_ref())
+                .await
+            }
+        }
+        .map_err(AppError::SpecificOperationError)?
+        .into_iter()
+        .map(WebhookDelivery::try_from)
+        .collect::<AppResult<Vec<_>>>()?;
+
+        Ok(CursorPaginatedList::from_rows(
+            rows,
+            limit,
+            cursor,
+            |d: &WebhookDelivery| (d.created_at, d.id.raw()),
+        ))
+    }
+    async fn find_pending_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> AppResult<Vec<PendingDelivery>> {
+        sqlx::query_as!(
+            PendingDeliveryRow,
+            r#"
+                SELECT
+                    d.delivery_id AS "delivery_id: WebhookDeliveryId",
+                    w.url,
+                    w.secret,
+                    d.event_type,
+                    d.payload,
+                    d.attempts
+                FROM webhook_deliveries AS d
+                INNER JOIN webhooks AS w ON w.webhook_id = d.webhook_id
+                WHERE d.next_attempt_at <= $1
+                ORDER BY d.next_attempt_at, d.created_at
+                LIMIT $2
+            "#,
+            now,
+            limit
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .into_iter()
+        .map(PendingDelivery::try_from)
+        .collect()
+    }
+    async fn mark_delivered(&self, event: MarkDeliveryDelivered) -> AppResult<()> {
+        let res = sqlx::query!(
+            r#"
+                UPDATE webhook_deliveries
+                SET
+                    status = 'delivered',
+                    next_attempt_at = NULL,
+                    last_attempt_at = $2,
+                    delivered_at = $2,
+                    response_status = $3,
+                    last_error = NULL
+                WHERE delivery_id = $1
+            "#,
+            event.delivery_id as _,
+            event.delivered_at,
+            event.response_status
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        if res.rows_affected() < 1 {
+            return Err(delivery_not_found(event.delivery_id));
+        }
+        Ok(())
+    }
+    async fn mark_failed(&self, event: MarkDeliveryFailed) -> AppResult<()> {
+        let res = sqlx::query!(
+            r#"
+                UPDATE webhook_deliveries
+                SET
+                    status = CASE WHEN $5::timestamptz IS NULL THEN 'dead_letter' ELSE 'pending' END,
+                    attempts = attempts + 1,
+                    next_attempt_at = $5,
+                    last_attempt_at = $2,
+                    response_status = $3,
+                    last_error = $4
+                WHERE delivery_id = $1
+            "#,
+            event.delivery_id as _,
+            event.failed_at,
+            event.response_status,
+            event.error,
+            event.next_attempt_at
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        if res.rows_affected() < 1 {
+            return Err(delivery_not_found(event.delivery_id));
+        }
+        Ok(())
+    }
+    async fn retry_delivery(&self, event: RetryDelivery) -> AppResult<()> {
+        let res = sqlx::query!(
+            r#"
+                UPDATE webhook_deliveries
+                SET status = 'pending', attempts = 0, next_attempt_at = $3
+                WHERE delivery_id = $1 AND webhook_id = $2 AND status = 'dead_letter'
+            "#,
+            event.delivery_id as _,
+            event.webhook_id as _,
+            event.retry_at
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        if res.rows_affected() < 1 {
+            return Err(dead_letter_not_found(event.delivery_id));
+        }
+        Ok(())
+    }
+    async fn delete_delivered(&self, before: DateTime<Utc>) -> AppResult<u64> {
+        let res = sqlx::query!(
+            r#"
+                DELETE FROM webhook_deliveries
+                WHERE status = 'delivered' AND delivered_at < $1
+            "#,
+            before
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        Ok(res.rows_affected())
+    }
+}
+
+// queues a delivery of the event for every subscription to its type, in the
+// transaction of the change it is about
+pub(crate) async fn queue_webhook_event(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    event_type: WebhookEventType,
+    data: serde_json::Value,
+    occurred_at: DateTime<Utc>,
+) -> AppResult<()> {
+    sqlx::query!(
+        r#"
+            INSERT INTO webhook_deliveries
+                (delivery_id, webhook_id, event_type, payload, created_at, next_attempt_at)
+            SELECT gen_random_uuid(), webhook_id, $1::VARCHAR, $2, $3, $3
+            FROM webhooks
+            WHERE $1::VARCHAR = ANY(event_types)
+        "#,
+        event_type.as_ref(),
+        webhook_payload(event_type, data, occurred_at),
+        occurred_at
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+    Ok(())
+}
+
+// the body posted to subscribers; `data` is specific to the event type
+pub(crate) fn webhook_payload(
+    event_type: WebhookEventType,
+    data: serde_json::Value,
+    occurred_at: DateTime<Utc>,
+) -> String {
+    serde_json::json!({
+        "type": event_type.as_ref(),
+        "occurredAt": occurred_at.to_rfc3339_opts(SecondsFormat::Millis, true),
+        "data": data,
+    })
+    .to_string()
+}
+
+// 32 random bytes, hex encoded after the prefix
+pub(crate) fn generate_secret() -> String {
+    format!(
+        "{WEBHOOK_SECRET_PREFIX}{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+pub(crate) fn webhook_not_found(id: WebhookId) -> AppError {
+    AppError::EntityNotFound(format!("Webhook with id {id} not found"))
+}
+
+pub(crate) fn delivery_not_found(id: WebhookDeliveryId) -> AppError {
+    AppError::EntityNotFound(format!("Webhook delivery with id {id} not found"))
+}
+
+pub(crate) fn dead_letter_not_found(id: WebhookDeliveryId) -> AppError {
+    AppError::EntityNotFound(format!(
+        "Dead-lettered webhook delivery with id {id} not found"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chrono::{Duration, SubsecRound};
+    use kernel::{
+        model::{
+            book::event::{DeleteBook, UpdateBook},
+            checkout::event::CreateCheckout,
+            id::{BookId, UserId},
+            webhook::DeliveryStatus,
+        },
+        repository::{book::BookRepository, checkout::CheckoutRepository},
+    };
+
+    use super::*;
+    use crate::repository::{book::BookRepositoryImpl, checkout::CheckoutRepositoryImpl};
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn subscribed_events_are_delivered_retried_and_dead_lettered(
+        pool: sqlx::PgPool,
+    ) -> anyhow::Result<()> {
+        let repo = WebhookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let books = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let checkouts = CheckoutRepositoryImpl::new(ConnectionPool::new(pool), Default::default());
+        let book_id = BookId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4d")?;
+        let user_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let now = Utc::now().trunc_subsecs(3);
+
+        let created = repo
+            .create(CreateWebhook::new(
+                "https://hooks.example.com/library".into(),
+                vec![
+                    WebhookEventType::BookUpdated,
+                    WebhookEventType::CheckoutCreated,
+                ],
+                user_id,
+            ))
+            .await?;
+        assert!(created.secret.starts_with(WEBHOOK_SECRET_PREFIX));
+        let webhook_id = created.webhook.id;
+        assert_eq!(repo.find_all().await?.len(), 1);
+
+        checkouts
+            .create(CreateCheckout::new(book_id, None, user_id, user_id, now))
+            .await?;
+        let book = books.find_by_id(book_id).await?.expect("Book not found");
+        books
+            .update(UpdateBook {
+                book_id,
+                title: "The Rust Programming Language, 2nd Edition".into(),
+                author: book.author,
+                isbn: book.isbn.parse()?,
+                description: book.description,
+                requested_user: user_id,
+                manages_books: false,
+            })
+            .await?;
+
+        // the update happened at the time of the database, which may be after `now`
+        let later = (Utc::now() + Duration::seconds(1)).trunc_subsecs(3);
+        let pending = repo.find_pending_deliveries(later, 10).await?;
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].event_type, WebhookEventType::CheckoutCreated);
+        assert_eq!(pending[0].secret, created.secret);
+        let payload = serde_json::from_str::<serde_json::Value>(&pending[1].payload)?;
+        assert_eq!(payload["type"], "book.updated");
+        assert_eq!(payload["data"]["bookId"], book_id.to_string());
+        assert_eq!(
+            payload["data"]["title"],
+            "The Rust Programming Language, 2nd Edition"
+        );
+
+        repo.mark_delivered(MarkDeliveryDelivered::new(pending[0].id, later, 200))
+            .await?;
+        repo.mark_failed(MarkDeliveryFailed::new(
+            pending[1].id,
+            later,
+            Some(500),
+            "server error".into(),
+            None,
+        ))
+        .await?;
+        assert!(repo.find_pending_deliveries(later, 10).await?.is_empty());
+
+        let log = repo
+            .find_deliveries(
+                webhook_id,
+                CursorListOptions {
+                    limit: 10,
+                    cursor: None,
+                },
+            )
+            .await?;
+        assert_eq!(log.items.len(), 2);
+        let dead = log
+            .items
+            .iter()
+            .find(|d| d.status == DeliveryStatus::DeadLetter)
+            .expect("Dead letter not found");
+        assert_eq!(dead.attempts, 1);
+        assert_eq!(dead.response_status, Some(500));
+
+        // only dead letters are retried
+        let res = repo
+            .retry_delivery(RetryDelivery::new(webhook_id, pending[0].id, later))
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+        repo.retry_delivery(RetryDelivery::new(webhook_id, dead.id, later))
+            .await?;
+        let retried = repo.find_pending_deliveries(later, 10).await?;
+        assert_eq!(retried.len(), 1);
+        assert_eq!(retried[0].attempts, 0);
+
+        // deleting the book is not subscribed to
+        books
+            .delete(DeleteBook {
+                book_id,
+                requested_user: user_id,
+                manages_books: false,
+            })
+            .await?;
+        assert_eq!(repo.find_pending_deliveries(later, 10).await?.len(), 1);
+
+        assert_eq!(
+            repo.delete_delivered(later + Duration::seconds(1)).await?,
+            1
+        );
+        repo.delete(DeleteWebhook::new(webhook_id)).await?;
+        let res = repo
+            .find_deliveries(
+                webhook_id,
+                CursorListOptions {
+                    limit: 10,
+                    cursor: None,
+                },
+            )
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+        Ok(())
+    }
+}
@@ -0,0 +1,253 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use http::{Request, Uri, header};
+use http_body_util::Full;
+use hyper_util::rt::TokioIo;
+use kernel::{model::webhook::PendingDelivery, webhook::WebhookSender};
+use sha2::Sha256;
+use shared::error::{AppError, AppResult};
+use tokio::net::TcpStream;
+use tokio_rustls::{
+    TlsConnector,
+    rustls::{ClientConfig, RootCertStore, crypto::ring, pki_types::ServerName},
+};
+
+// endpoints that take longer count as failed, and are tried again later
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const USER_AGENT: &str = "rust-book-manager-webhooks";
+
+// posts deliveries over HTTP/1.1, or over TLS with the web's root certificates
+// for `https` endpoints
+pub struct HttpWebhookSender {
+    tls: TlsConnector,
+    timeout: Duration,
+}
+
+impl HttpWebhookSender {
+    pub fn new() -> AppResult<Self> {
+        let mut roots = RootCertStore::empty();
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        let config = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(webhook_error)?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        Ok(Self {
+            tls: TlsConnector::from(Arc::new(config)),
+            timeout: REQUEST_TIMEOUT,
+        })
+    }
+
+    async fn post(&self, delivery: &PendingDelivery) -> AppResult<u16> {
+        let uri = delivery.url.parse::<Uri>().map_err(webhook_error)?;
+        let https = match uri.scheme_str() {
+            Some("https") => true,
+            Some("http") => false,
+            _ => return Err(AppError::WebhookError(format!("unsupported url {uri}"))),
+        };
+        let host = uri
+            .host()
+            .ok_or_else(|| AppError::WebhookError(format!("no host in url {uri}")))?
+            .to_string();
+        let port = uri.port_u16().unwrap_or(if https { 443 } else { 80 });
+        let request = signed_request(&uri, delivery)?;
+
+        let stream = TcpStream::connect((host.as_str(), port))
+            .await
+            .map_err(webhook_error)?;
+        if https {
+            let server_name = ServerName::try_from(host).map_err(webhook_error)?;
+            let stream = self
+                .tls
+                .connect(server_name, stream)
+                .await
+                .map_err(webhook_error)?;
+            send_request(TokioIo::new(stream), request).await
+        } else {
+            send_request(TokioIo::new(stream), request).await
+        }
+    }
+}
+
+#[async_trait]
+impl WebhookSender for HttpWebhookSender {
+    async fn send(&self, delivery: &PendingDelivery) -> AppResult<u16> {
+        tokio::time::timeout(self.timeout, self.post(delivery))
+            .await
+            .map_err(|_| AppError::WebhookError(format!("no response within {:?}", self.timeout)))?
+    }
+}
+
+// subscribers check `X-Webhook-Signature` by computing the same HMAC over
+// `X-Webhook-Timestamp`, a dot and the body, and may reject stale timestamps
+fn signed_request(uri: &Uri, delivery: &PendingDelivery) -> AppResult<Request<Full<Bytes>>> {
+    let timestamp = Utc::now().timestamp();
+    let signature = sign(&delivery.secret, timestamp, &delivery.payload);
+    let authority = uri
+        .authority()
+        .map(|a| a.as_str())
+        .unwrap_or_default()
+        .to_string();
+    let path = uri
+        .path_and_query()
+        .map(|p| p.as_str())
+        .unwrap_or("/")
+        .to_string();
+
+    Request::post(path)
+        .header(header::HOST, authority)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::USER_AGENT, USER_AGENT)
+        .header(header::CONNECTION, "close")
+        .header("X-Webhook-Id", delivery.id.to_string())
+        .header("X-Webhook-Event", delivery.event_type.as_ref())
+        .header("X-Webhook-Timestamp", timestamp.to_string())
+        .header("X-Webhook-Signature", format!("sha256={signature}"))
+        .body(Full::new(Bytes::from(delivery.payload.clone())))
+        .map_err(webhook_error)
+}
+
+async fn send_request<S>(io: TokioIo<S>, request: Request<Full<Bytes>>) -> AppResult<u16>
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
+{
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(io)
+        .await
+        .map_err(webhook_error)?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            tracing::debug!(error.message = %e, "webhook connection closed with an error");
+        }
+    });
+    let response = sender.send_request(request).await.map_err(webhook_error)?;
+    Ok(response.status().as_u16())
+}
+
+// the hex encoded HMAC-SHA256 of `{timestamp}.{payload}`
+pub fn sign(secret: &str, timestamp: i64, payload: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+    mac.update(format!("{timestamp}.{payload}").as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn webhook_error(e: impl std::fmt::Display) -> AppError {
+    AppError::WebhookError(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use kernel::model::{id::WebhookDeliveryId, webhook::WebhookEventType};
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    // accepts one request, answers it with `status` and returns its request
+    // line, headers (lowercased names) and body
+    async fn http_sink(
+        listener: TcpListener,
+        status: &'static str,
+    ) -> anyhow::Result<(String, HashMap<String, String>, String)> {
+        let (stream, _) = listener.accept().await?;
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).await?;
+        let mut headers = HashMap::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).await?;
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                headers.insert(name.to_ascii_lowercase(), value.trim().to_string());
+            }
+        }
+        let length = headers
+            .get("content-length")
+            .map(|l| l.parse::<usize>())
+            .transpose()?
+            .unwrap_or_default();
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).await?;
+
+        writer
+            .write_all(format!("HTTP/1.1 {status}\r\ncontent-length: 0\r\n\r\n").as_bytes())
+            .await?;
+        Ok((
+            request_line.trim_end().to_string(),
+            headers,
+            String::from_utf8(body)?,
+        ))
+    }
+
+    fn delivery(url: String) -> PendingDelivery {
+        PendingDelivery {
+            id: WebhookDeliveryId::new(),
+            url,
+            secret: "whsec_test".into(),
+            event_type: WebhookEventType::BookCreated,
+            payload: r#"{"type":"book.created"}"#.into(),
+            attempts: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn deliveries_are_posted_with_a_signature_of_the_body() -> anyhow::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let port = listener.local_addr()?.port();
+        let sink = tokio::spawn(http_sink(listener, "204 No Content"));
+
+        let delivery = delivery(format!("http://127.0.0.1:{port}/hooks/library?v=1"));
+        let status = HttpWebhookSender::new()?.send(&delivery).await?;
+        assert_eq!(status, 204);
+
+        let (request_line, headers, body) =
+            tokio::time::timeout(Duration::from_secs(5), sink).await???;
+        assert_eq!(request_line, "POST /hooks/library?v=1 HTTP/1.1");
+        assert_eq!(body, delivery.payload);
+        assert_eq!(headers["x-webhook-event"], "book.created");
+        assert_eq!(headers["x-webhook-id"], delivery.id.to_string());
+        let timestamp = headers["x-webhook-timestamp"].parse::<i64>()?;
+        assert_eq!(
+            headers["x-webhook-signature"],
+            format!("sha256={}", sign("whsec_test", timestamp, &body))
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn error_responses_are_returned_and_unreachable_endpoints_fail() -> anyhow::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let port = listener.local_addr()?.port();
+        let sink = tokio::spawn(http_sink(listener, "503 Service Unavailable"));
+
+        let sender = HttpWebhookSender::new()?;
+        let status = sender
+            .send(&delivery(format!("http://127.0.0.1:{port}/")))
+            .await?;
+        assert_eq!(status, 503);
+        tokio::time::timeout(Duration::from_secs(5), sink).await???;
+
+        // nothing listens on the port anymore
+        let res = sender
+            .send(&delivery(format!("http://127.0.0.1:{port}/")))
+            .await;
+        assert!(matches!(res, Err(AppError::WebhookError(_))));
+        let res = sender.send(&delivery("ftp://127.0.0.1/".into())).await;
+        assert!(matches!(res, Err(AppError::WebhookError(_))));
+        Ok(())
+    }
+}
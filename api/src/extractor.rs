@@ -1,23 +1,50 @@
-use axum::{RequestPartsExt, async_trait, extract::FromRequestParts, http::request::Parts};
+use std::{
+    convert::Infallible,
+    marker::PhantomData,
+    net::{IpAddr, SocketAddr},
+    ops::Deref,
+};
+
+use axum::{
+    RequestPartsExt, async_trait,
+    body::Bytes,
+    extract::{ConnectInfo, FromRequest, FromRequestParts, MatchedPath, Request},
+    http::{Method, header::USER_AGENT, request::Parts},
+    response::{IntoResponse, Response},
+};
 
 use axum_extra::{
     TypedHeader,
     headers::{Authorization, authorization::Bearer},
 };
-use kernel::model::{auth::AccessToken, id::UserId, role::Role, user::User};
+use kernel::model::{
+    api_key::{API_KEY_PREFIX, ApiKeyOwner, ApiKeyScope, ApiKeySecret},
+    auth::{AccessToken, SessionClient, TokenOwner},
+    id::{ApiKeyId, SessionId, UserId},
+    role::Permission,
+    user::User,
+};
 use registry::AppRegistry;
+use serde::{Serialize, de::DeserializeOwned};
 use shared::error::AppError;
 
 pub struct AuthorizedUser {
     pub access_token: AccessToken,
     pub user: User,
+    // none for tokens issued before sessions existed
+    pub session_id: Option<SessionId>,
+    // set when the request was made with an API key instead of a session token
+    pub api_key_id: Option<ApiKeyId>,
 }
 impl AuthorizedUser {
     pub fn id(&self) -> UserId {
         self.user.id
     }
     pub fn is_admin(&self) -> bool {
-        self.user.role == Role::Admin
+        self.user.role.is_admin()
+    }
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.user.role.has_permission(permission)
     }
 }
 
@@ -36,18 +63,243 @@ impl FromRequestParts<AppRegistry> for AuthorizedUser {
 
         let access_token = AccessToken(bearer.token().to_string());
 
-        let user_id = registry
+        if access_token.0.starts_with(API_KEY_PREFIX) {
+            let ApiKeyOwner {
+                api_key_id,
+                user_id,
+                scopes,
+            } = registry
+                .api_key_repository()
+                .authenticate(&ApiKeySecret(access_token.0.clone()))
+                .await?
+                .ok_or(AppError::UnauthorizedError)?;
+
+            let path = parts
+                .extensions
+                .get::<MatchedPath>()
+                .map(MatchedPath::as_str);
+            let permitted = path
+                .and_then(|path| required_scope(&parts.method, path))
+                .is_some_and(|scope| scopes.contains(&scope));
+            if !permitted {
+                return Err(AppError::ForbidenOperation);
+            }
+
+            let user = find_user(registry, user_id).await?;
+            return Ok(AuthorizedUser {
+                access_token,
+                user,
+                session_id: None,
+                api_key_id: Some(api_key_id),
+            });
+        }
+
+        let TokenOwner {
+            user_id,
+            session_id,
+        } = registry
             .auth_repository()
-            .fetch_user_id_from_token(&access_token)
+            .fetch_token_owner(&access_token)
             .await?
             .ok_or(AppError::UnauthorizedError)?;
 
-        let user = registry
-            .user_repository()
-            .find_current_user(user_id)
-            .await?
-            .ok_or(AppError::UnauthorizedError)?;
+        let user = find_user(registry, user_id).await?;
+
+        Ok(AuthorizedUser {
+            access_token,
+            user,
+            session_id,
+            api_key_id: None,
+        })
+    }
+}
+
+async fn find_user(registry: &AppRegistry, user_id: UserId) -> Result<User, AppError> {
+    registry
+        .user_repository()
+        .find_current_user(user_id)
+        .await?
+        .ok_or(AppError::UnauthorizedError)
+}
+
+// the scope an API key needs for a route; none for routes keys cannot be used
+// for at all, like managing the account or its keys
+fn required_scope(method: &Method, path: &str) -> Option<ApiKeyScope> {
+    if path == "/api/v1/users/me/checkouts" {
+        return Some(ApiKeyScope::Checkouts);
+    }
+    if path == "/api/v1/users/me/reservations" {
+        return Some(ApiKeyScope::Reservations);
+    }
+    if path == "/api/v1/events" {
+        return Some(ApiKeyScope::BooksRead);
+    }
+    let rest = path.strip_prefix("/api/v1/books")?;
+    if rest.contains("/checkout") {
+        Some(ApiKeyScope::Checkouts)
+    } else if rest.contains("/reservations") {
+        Some(ApiKeyScope::Reservations)
+    } else if method == Method::GET {
+        Some(ApiKeyScope::BooksRead)
+    } else {
+        Some(ApiKeyScope::BooksWrite)
+    }
+}
+
+// an `AuthorizedUser` whose role grants `P::PERMISSION`, so a handler declares
+// what it requires in its signature; rejects everyone else with 403
+pub struct Permitted<P: RequiredPermission> {
+    pub user: AuthorizedUser,
+    permission: PhantomData<P>,
+}
+
+pub trait RequiredPermission: Send + Sync {
+    const PERMISSION: Permission;
+}
+
+// a type standing for each permission, to name it in `Permitted`
+pub mod permission {
+    use kernel::model::role::Permission;
+
+    use super::RequiredPermission;
+
+    macro_rules! required_permission {
+        ($($name:ident),*) => {
+            $(
+                pub struct $name;
+                impl RequiredPermission for $name {
+                    const PERMISSION: Permission = Permission::$name;
+                }
+            )*
+        };
+    }
+
+    required_permission!(
+        ManageBooks,
+        ManageCheckouts,
+        ManageRoles,
+        ManageUsers,
+        ManageWebhooks,
+        ViewAuditLog
+    );
+}
+
+impl<P: RequiredPermission> Deref for Permitted<P> {
+    type Target = AuthorizedUser;
+
+    fn deref(&self) -> &Self::Target {
+        &self.user
+    }
+}
+
+#[async_trait]
+impl<P: RequiredPermission> FromRequestParts<AppRegistry> for Permitted<P> {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        registry: &AppRegistry,
+    ) -> Result<Self, Self::Rejection> {
+        let user = AuthorizedUser::from_request_parts(parts, registry).await?;
+        if !user.has_permission(P::PERMISSION) {
+            return Err(AppError::ForbidenOperation);
+        }
+        Ok(Self {
+            user,
+            permission: PhantomData,
+        })
+    }
+}
+
+// `axum`'s `Json`, `Path` and `Query`, rejecting requests they cannot read with
+// an `ErrorResponse` like every other error
+#[derive(FromRequest)]
+#[from_request(via(axum::Json), rejection(AppError))]
+pub struct Json<T>(pub T);
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(AppError))]
+pub struct Path<T>(pub T);
+
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(AppError))]
+pub struct Query<T>(pub T);
+
+// a JSON body that may be left out; only an empty body is missing, where
+// `Option<Json<T>>` would take any body it cannot read for one too
+pub struct OptionalJson<T>(pub Option<T>);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for OptionalJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let body = Bytes::from_request(req, state)
+            .await
+            .map_err(|rejection| AppError::InvalidRequest(rejection.body_text()))?;
+        if body.is_empty() {
+            return Ok(Self(None));
+        }
+        let axum::Json(value) = axum::Json::from_bytes(&body)?;
+        Ok(Self(Some(value)))
+    }
+}
+
+// the client a session is started from; logins are throttled on its address, so
+// `X-Forwarded-For` is only read when the peer is a trusted proxy
+pub struct Client(pub SessionClient);
+
+#[async_trait]
+impl FromRequestParts<AppRegistry> for Client {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        registry: &AppRegistry,
+    ) -> Result<Self, Self::Rejection> {
+        let user_agent = parts
+            .headers
+            .get(USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        let trusted_proxies = registry.proxy_config().trusted_proxies;
+        let client = peer.map(|peer| {
+            if !trusted_proxies.contains(&peer) {
+                return peer;
+            }
+            // each proxy appends the address it got the request from, so the client
+            // is the last one that is not a trusted proxy; earlier ones may be forged
+            parts
+                .headers
+                .get_all("X-Forwarded-For")
+                .iter()
+                .filter_map(|v| v.to_str().ok())
+                .flat_map(|v| v.split(','))
+                .map(|v| v.trim().parse::<IpAddr>().ok())
+                .rev()
+                .take_while(Option::is_some)
+                .flatten()
+                .find(|ip| !trusted_proxies.contains(ip))
+                .unwrap_or(peer)
+        });
 
-        Ok(AuthorizedUser { access_token, user })
+        Ok(Client(SessionClient {
+            user_agent,
+            ip_address: client.map(|ip| ip.to_string()),
+        }))
     }
 }
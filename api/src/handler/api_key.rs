@@ -0,0 +1,106 @@
+use axum::{extract::State, http::StatusCode};
+use garde::Validate;
+use kernel::model::{
+    api_key::event::{CreateApiKey, RevokeApiKey, RotateApiKey},
+    id::ApiKeyId,
+};
+use registry::AppRegistry;
+use shared::error::AppResult;
+
+use crate::{
+    extractor::{AuthorizedUser, Json, Path},
+    model::api_key::{ApiKeysResponse, CreateApiKeyRequest, IssuedApiKeyResponse},
+};
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/me/api-keys",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 201, description = "Created the key; it is shown only this once", body = IssuedApiKeyResponse),
+        (status = 400, description = "Invalid request body", body = ErrorResponse)
+    )
+)]
+pub async fn create_api_key(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<CreateApiKeyRequest>,
+) -> AppResult<(StatusCode, Json<IssuedApiKeyResponse>)> {
+    req.validate(&())?;
+
+    let scopes = req.scopes.into_iter().map(Into::into).collect();
+    registry
+        .api_key_repository()
+        .create(CreateApiKey::new(user.id(), req.name, scopes))
+        .await
+        .map(|k| (StatusCode::CREATED, Json(k.into())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/api-keys",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    responses((status = 200, description = "Keys of the user that are not revoked, newest first", body = ApiKeysResponse))
+)]
+pub async fn list_api_keys(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<ApiKeysResponse>> {
+    registry
+        .api_key_repository()
+        .find_by_user_id(user.id())
+        .await
+        .map(ApiKeysResponse::from)
+        .map(Json)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/me/api-keys/{api_key_id}/rotate",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    params(("api_key_id" = ApiKeyId, Path, description = "API key id")),
+    responses(
+        (status = 200, description = "Replaced the key, which stops the previous one from working; it is shown only this once", body = IssuedApiKeyResponse),
+        (status = 404, description = "The user has no such key", body = ErrorResponse)
+    )
+)]
+pub async fn rotate_api_key(
+    user: AuthorizedUser,
+    Path(api_key_id): Path<ApiKeyId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<IssuedApiKeyResponse>> {
+    registry
+        .api_key_repository()
+        .rotate(RotateApiKey::new(api_key_id, user.id()))
+        .await
+        .map(IssuedApiKeyResponse::from)
+        .map(Json)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/me/api-keys/{api_key_id}",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    params(("api_key_id" = ApiKeyId, Path, description = "API key id")),
+    responses(
+        (status = 204, description = "Revoked the key"),
+        (status = 404, description = "The user has no such key", body = ErrorResponse)
+    )
+)]
+pub async fn revoke_api_key(
+    user: AuthorizedUser,
+    Path(api_key_id): Path<ApiKeyId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    registry
+        .api_key_repository()
+        .revoke(RevokeApiKey::new(api_key_id, user.id()))
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
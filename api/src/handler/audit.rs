@@ -0,0 +1,36 @@
+use axum::extract::State;
+use garde::Validate;
+use registry::AppRegistry;
+use shared::error::AppResult;
+
+use crate::{
+    extractor::{Json, Permitted, Query, permission::ViewAuditLog},
+    model::audit::{AuditLogQuery, PaginatedAuditEntriesResponse},
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/audit-log",
+    tag = "audit",
+    security(("bearer_auth" = [])),
+    params(AuditLogQuery),
+    responses(
+        (status = 200, description = "Changes to books, users, roles and checkouts, newest first", body = PaginatedAuditEntriesResponse),
+        (status = 400, description = "Invalid query", body = ErrorResponse),
+        (status = 403, description = "The user may not view the audit log", body = ErrorResponse)
+    )
+)]
+pub async fn list_audit_log(
+    _user: Permitted<ViewAuditLog>,
+    Query(query): Query<AuditLogQuery>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<PaginatedAuditEntriesResponse>> {
+    query.validate(&())?;
+
+    registry
+        .audit_repository()
+        .find_all(query.try_into()?)
+        .await
+        .map(PaginatedAuditEntriesResponse::from)
+        .map(Json)
+}
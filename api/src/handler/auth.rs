@@ -1,33 +1,213 @@
-use axum::{Json, extract::State, http::StatusCode};
-use kernel::model::auth::event::CreateToken;
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use garde::Validate;
+use kernel::{
+    mailer::Mail,
+    model::{
+        auth::{
+            EmailVerification, EmailVerificationToken, LoginAttempt, LoginChallengeToken,
+            PasswordReset, PasswordResetToken, RefreshToken,
+            event::{
+                CreateEmailVerification, CreateLoginChallenge, CreatePasswordReset, CreateToken,
+                RotateToken,
+            },
+        },
+        id::UserId,
+        totp::event::{BeginTotpEnrollment, ConfirmTotpEnrollment, VerifyTotp},
+        user::{
+            UserStatus,
+            event::{CreateUser, ResetUserPassword, UpdateUserStatus},
+        },
+    },
+};
 use registry::AppRegistry;
-use shared::error::AppResult;
+use shared::error::{AppError, AppResult};
 
 use crate::{
-    extractor::AuthorizedUser,
-    model::auth::{AccessTokenResponse, LoginRequest},
+    extractor::{AuthorizedUser, Client, Json},
+    model::{
+        auth::{
+            AccessTokenResponse, ConfirmPasswordResetRequest, LoginRequest, PasswordResetRequest,
+            RefreshTokenRequest, ResendEmailVerificationRequest, VerifyEmailRequest,
+        },
+        totp::{LoginChallengeResponse, TotpLoginRequest, TotpLoginResponse},
+        user::{CreaterUserRequest, UserResponse},
+    },
 };
 
+#[utoipa::path(
+    post,
+    path = "/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Logged in", body = AccessTokenResponse),
+        (status = 202, description = "The password was right; a TOTP code completes the login at `/auth/login/totp`", body = LoginChallengeResponse),
+        (status = 401, description = "Wrong email or password", body = ErrorResponse),
+        (status = 429, description = "Too many failed logins for the email or from the address; see the `Retry-After` header", body = ErrorResponse)
+    )
+)]
 pub async fn login(
+    Client(client): Client,
     State(registry): State<AppRegistry>,
     Json(req): Json<LoginRequest>,
-) -> AppResult<Json<AccessTokenResponse>> {
-    let user_id = registry
+) -> AppResult<Response> {
+    let attempt = LoginAttempt {
+        email: req.email.clone(),
+        ip_address: client.ip_address.clone(),
+    };
+    let login_attempts = registry.login_attempt_repository();
+    login_attempts.check(&attempt).await?;
+
+    let user_id = match registry
         .auth_repository()
         .verify_user(&req.email, &req.password)
+        .await
+    {
+        Err(AppError::UnauthorizedError) => {
+            login_attempts.record_failure(&attempt).await?;
+            return Err(AppError::UnauthorizedError);
+        }
+        result => result?,
+    };
+
+    let totp = registry.totp_repository();
+    let enrollment = if totp.is_enabled(user_id).await? {
+        None
+    } else {
+        match registry
+            .user_repository()
+            .find_current_user(user_id)
+            .await?
+        {
+            Some(user) if user.role.is_admin() && registry.totp_config().required_for_admins => {
+                Some(
+                    totp.begin_enrollment(BeginTotpEnrollment::new(user_id, user.email))
+                        .await?,
+                )
+            }
+            _ => {
+                login_attempts.record_success(&attempt).await?;
+                let tokens = registry
+                    .auth_repository()
+                    .create_token(CreateToken::new(user_id, client))
+                    .await?;
+                return Ok(Json(AccessTokenResponse::from(tokens)).into_response());
+            }
+        }
+    };
+
+    // the failures stay counted until the second factor is passed too, or logging
+    // in again would reset the backoff on guessing codes
+    let challenge = registry
+        .login_challenge_repository()
+        .create(CreateLoginChallenge::new(user_id))
         .await?;
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(LoginChallengeResponse::new(challenge, enrollment)),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/login/totp",
+    tag = "auth",
+    request_body = TotpLoginRequest,
+    responses(
+        (status = 200, description = "Logged in", body = TotpLoginResponse),
+        (status = 400, description = "Invalid request body", body = ErrorResponse),
+        (status = 401, description = "Unknown or expired challenge, or wrong code", body = ErrorResponse),
+        (status = 429, description = "Too many failed logins for the email or from the address; see the `Retry-After` header", body = ErrorResponse)
+    )
+)]
+pub async fn login_totp(
+    Client(client): Client,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<TotpLoginRequest>,
+) -> AppResult<Json<TotpLoginResponse>> {
+    req.validate(&())?;
 
-    let access_token = registry
+    let token = LoginChallengeToken(req.challenge_token);
+    let challenges = registry.login_challenge_repository();
+    let user_id = challenges
+        .find(&token)
+        .await?
+        .ok_or(AppError::UnauthorizedError)?;
+    let user = registry
+        .user_repository()
+        .find_current_user(user_id)
+        .await?
+        .ok_or(AppError::UnauthorizedError)?;
+
+    // wrong codes count as failed logins, which keeps codes from being guessed
+    let attempt = LoginAttempt {
+        email: user.email,
+        ip_address: client.ip_address.clone(),
+    };
+    let login_attempts = registry.login_attempt_repository();
+    login_attempts.check(&attempt).await?;
+
+    let totp = registry.totp_repository();
+    let (valid, recovery_codes) = if totp.is_enabled(user_id).await? {
+        let valid = totp.verify(VerifyTotp::new(user_id, req.code)).await?;
+        (valid, None)
+    } else {
+        let codes = totp
+            .confirm_enrollment(ConfirmTotpEnrollment::new(user_id, req.code))
+            .await?;
+        (codes.is_some(), codes)
+    };
+    if !valid {
+        login_attempts.record_failure(&attempt).await?;
+        return Err(AppError::UnauthorizedError);
+    }
+    challenges.delete(&token).await?;
+    login_attempts.record_success(&attempt).await?;
+
+    let tokens = registry
         .auth_repository()
-        .create_token(CreateToken::new(user_id))
+        .create_token(CreateToken::new(user_id, client))
         .await?;
+    Ok(Json(TotpLoginResponse::new(tokens, recovery_codes)))
+}
 
-    Ok(Json(AccessTokenResponse {
-        user_id,
-        access_token: access_token.0,
-    }))
+#[utoipa::path(
+    post,
+    path = "/auth/refresh",
+    tag = "auth",
+    request_body = RefreshTokenRequest,
+    responses(
+        (status = 200, description = "Issued a new pair of tokens", body = AccessTokenResponse),
+        (status = 401, description = "Unknown, expired or already used refresh token; a reused token also ends its session", body = ErrorResponse)
+    )
+)]
+pub async fn refresh(
+    State(registry): State<AppRegistry>,
+    Json(req): Json<RefreshTokenRequest>,
+) -> AppResult<Json<AccessTokenResponse>> {
+    registry
+        .auth_repository()
+        .rotate_token(RotateToken::new(RefreshToken(req.refresh_token)))
+        .await
+        .map(AccessTokenResponse::from)
+        .map(Json)
 }
 
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    tag = "auth",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 204, description = "Logged out; the refresh token of the session is revoked too"),
+        (status = 401, description = "Missing or invalid access token", body = ErrorResponse)
+    )
+)]
 pub async fn logout(
     user: AuthorizedUser,
     State(registry): State<AppRegistry>,
@@ -38,3 +218,327 @@ pub async fn logout(
         .await?;
     Ok(StatusCode::NO_CONTENT)
 }
+
+#[utoipa::path(
+    post,
+    path = "/auth/password-reset/request",
+    tag = "auth",
+    request_body = PasswordResetRequest,
+    responses(
+        (status = 202, description = "If the email belongs to a user, a reset token is mailed to it"),
+        (status = 400, description = "Invalid request body", body = ErrorResponse)
+    )
+)]
+pub async fn request_password_reset(
+    State(registry): State<AppRegistry>,
+    Json(req): Json<PasswordResetRequest>,
+) -> AppResult<StatusCode> {
+    req.validate(&())?;
+
+    // the response is the same for unknown emails, so it tells nobody who has an account
+    let Some(user) = registry.user_repository().find_by_email(&req.email).await? else {
+        return Ok(StatusCode::ACCEPTED);
+    };
+    let reset = registry
+        .password_reset_repository()
+        .create(CreatePasswordReset::new(user.id))
+        .await?;
+
+    if let Err(e) = registry
+        .mailer()
+        .send(password_reset_mail(user.email, &reset))
+        .await
+    {
+        tracing::error!(
+            error.cause_chain = ?e,
+            error.message = %e,
+            "Failed to mail a password reset token"
+        );
+    }
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/password-reset/confirm",
+    tag = "auth",
+    request_body = ConfirmPasswordResetRequest,
+    responses(
+        (status = 204, description = "Changed the password and logged the user out everywhere"),
+        (status = 400, description = "Invalid request body", body = ErrorResponse),
+        (status = 422, description = "Unknown, used or expired token", body = ErrorResponse)
+    )
+)]
+pub async fn confirm_password_reset(
+    State(registry): State<AppRegistry>,
+    Json(req): Json<ConfirmPasswordResetRequest>,
+) -> AppResult<StatusCode> {
+    req.validate(&())?;
+
+    let user_id = registry
+        .password_reset_repository()
+        .consume(&PasswordResetToken(req.token))
+        .await?
+        .ok_or_else(|| {
+            AppError::UnprocessableEntity("password reset token is invalid or expired".into())
+        })?;
+
+    registry
+        .user_repository()
+        .reset_password(ResetUserPassword {
+            user_id,
+            new_password: req.new_password,
+        })
+        .await?;
+    // whoever knew the old password loses access, and a lockout no longer matters
+    registry
+        .auth_repository()
+        .delete_all_sessions(user_id)
+        .await?;
+    if let Some(user) = registry
+        .user_repository()
+        .find_current_user(user_id)
+        .await?
+    {
+        registry
+            .login_attempt_repository()
+            .unlock(&user.email)
+            .await?;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/register",
+    tag = "auth",
+    request_body = CreaterUserRequest,
+    responses(
+        (status = 202, description = "Signed up, unless the email is taken; unless the user is active, a verification token is mailed or an admin has to approve"),
+        (status = 400, description = "Invalid request body", body = ErrorResponse),
+        (status = 403, description = "Sign-up is disabled", body = ErrorResponse),
+        (status = 422, description = "The domain of the email is not allowed", body = ErrorResponse)
+    )
+)]
+pub async fn register(
+    State(registry): State<AppRegistry>,
+    Json(req): Json<CreaterUserRequest>,
+) -> AppResult<StatusCode> {
+    let config = registry.registration_config();
+    if !config.enabled {
+        return Err(AppError::ForbidenOperation);
+    }
+    req.validate(&())?;
+    if !config.allows_email(&req.email) {
+        return Err(AppError::UnprocessableEntity(
+            "the domain of the email is not allowed to sign up".into(),
+        ));
+    }
+
+    let status = if config.require_verification {
+        UserStatus::Unverified
+    } else if config.require_approval {
+        UserStatus::PendingApproval
+    } else {
+        UserStatus::Active
+    };
+    let email = req.email.clone();
+    let event = CreateUser {
+        status,
+        ..req.into()
+    };
+    // the token is kept before the user, so there is never an unverified user
+    // without one; a token left by a failed sign-up just expires
+    let verification = if status == UserStatus::Unverified {
+        Some(
+            registry
+                .email_verification_repository()
+                .create(CreateEmailVerification::new(event.user_id))
+                .await?,
+        )
+    } else {
+        None
+    };
+    let user = match registry.user_repository().create(event).await {
+        Ok(user) => user,
+        // like password resets, the response tells nobody who has an account;
+        // the owner of the email is told about the attempt instead
+        Err(AppError::Conflict(_)) => {
+            if let Some(user) = registry.user_repository().find_by_email(&email).await? {
+                if user.status == UserStatus::Unverified {
+                    mail_email_verification(&registry, user.id, user.email).await?;
+                } else {
+                    send_mail(&registry, account_exists_mail(user.email)).await;
+                }
+            }
+            return Ok(StatusCode::ACCEPTED);
+        }
+        Err(e) => return Err(e),
+    };
+
+    if let Some(verification) = verification {
+        send_mail(
+            &registry,
+            email_verification_mail(user.email, &verification),
+        )
+        .await;
+    }
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/register/resend",
+    tag = "auth",
+    request_body = ResendEmailVerificationRequest,
+    responses(
+        (status = 202, description = "If the email belongs to an unverified user, a new verification token is mailed to it"),
+        (status = 400, description = "Invalid request body", body = ErrorResponse)
+    )
+)]
+pub async fn resend_email_verification(
+    State(registry): State<AppRegistry>,
+    Json(req): Json<ResendEmailVerificationRequest>,
+) -> AppResult<StatusCode> {
+    req.validate(&())?;
+
+    // like password resets, the response tells nobody who has signed up
+    let Some(user) = registry
+        .user_repository()
+        .find_by_email(&req.email)
+        .await?
+        .filter(|user| user.status == UserStatus::Unverified)
+    else {
+        return Ok(StatusCode::ACCEPTED);
+    };
+    mail_email_verification(&registry, user.id, user.email).await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/register/verify",
+    tag = "auth",
+    request_body = VerifyEmailRequest,
+    responses(
+        (status = 200, description = "Verified the email; the user is active, or waits for approval", body = UserResponse),
+        (status = 400, description = "Invalid request body", body = ErrorResponse),
+        (status = 422, description = "Unknown, used or expired token", body = ErrorResponse)
+    )
+)]
+pub async fn verify_email(
+    State(registry): State<AppRegistry>,
+    Json(req): Json<VerifyEmailRequest>,
+) -> AppResult<Json<UserResponse>> {
+    req.validate(&())?;
+
+    let user_id = registry
+        .email_verification_repository()
+        .consume(&EmailVerificationToken(req.token))
+        .await?
+        .ok_or_else(|| {
+            AppError::UnprocessableEntity("email verification token is invalid or expired".into())
+        })?;
+
+    let to = if registry.registration_config().require_approval {
+        UserStatus::PendingApproval
+    } else {
+        UserStatus::Active
+    };
+    registry
+        .user_repository()
+        .update_status(UpdateUserStatus {
+            user_id,
+            from: UserStatus::Unverified,
+            to,
+            requested_user: user_id,
+        })
+        .await?;
+
+    registry
+        .user_repository()
+        .find_current_user(user_id)
+        .await?
+        .map(UserResponse::from)
+        .map(Json)
+        .ok_or_else(|| AppError::EntityNotFound(format!("User with id {user_id} not found")))
+}
+
+// mails a new token to a user who still has to verify their email
+async fn mail_email_verification(
+    registry: &AppRegistry,
+    user_id: UserId,
+    to: String,
+) -> AppResult<()> {
+    let verification = registry
+        .email_verification_repository()
+        .create(CreateEmailVerification::new(user_id))
+        .await?;
+    send_mail(registry, email_verification_mail(to, &verification)).await;
+    Ok(())
+}
+
+// a sign-up stands even when its mail fails; the user can ask for another one
+async fn send_mail(registry: &AppRegistry, mail: Mail) {
+    if let Err(e) = registry.mailer().send(mail).await {
+        tracing::error!(
+            error.cause_chain = ?e,
+            error.message = %e,
+            "Failed to mail a sign-up notice"
+        );
+    }
+}
+
+fn account_exists_mail(to: String) -> Mail {
+    Mail {
+        to,
+        subject: "You already have an account".into(),
+        body: "Someone tried to sign up to the library with this email, which already has an account.\n\n\
+               If it was you, log in, or reset your password if you forgot it. \
+               Otherwise you can ignore this mail."
+            .into(),
+    }
+}
+
+fn email_verification_mail(to: String, verification: &EmailVerification) -> Mail {
+    let instructions = match &verification.link {
+        Some(link) => format!("Open this link to verify your email:\n\n{link}"),
+        None => format!(
+            "Use this token to verify your email:\n\n{}",
+            verification.token.0
+        ),
+    };
+    Mail {
+        to,
+        subject: "Verify your email".into(),
+        body: format!(
+            "Welcome to the library.\n\n\
+             {instructions}\n\n\
+             This expires in {} hours. If you did not sign up, you can ignore this mail.",
+            verification.expires_in.div_ceil(60 * 60)
+        ),
+    }
+}
+
+fn password_reset_mail(to: String, reset: &PasswordReset) -> Mail {
+    let instructions = match &reset.link {
+        Some(link) => format!("Open this link to choose a new password:\n\n{link}"),
+        None => format!(
+            "Use this token to choose a new password:\n\n{}",
+            reset.token.0
+        ),
+    };
+    Mail {
+        to,
+        subject: "Reset your password".into(),
+        body: format!(
+            "Someone asked to reset the password of your library account.\n\n\
+             {instructions}\n\n\
+             This expires in {} minutes. If you did not ask for it, you can ignore this mail.",
+            reset.expires_in.div_ceil(60)
+        ),
+    }
+}
@@ -1,21 +1,40 @@
-use axum::{
-    Json,
-    extract::{Path, Query, State},
-    http::StatusCode,
-};
+use axum::{extract::State, http::StatusCode};
 use garde::Validate;
-use kernel::model::{book::event::DeleteBook, id::BookId};
+use kernel::model::{
+    book::{
+        BookListOptions,
+        event::{DeleteBook, DeleteBookCopy},
+    },
+    id::{BookCopyId, BookId},
+    live_event::LiveEventKind,
+    role::Permission,
+};
 use registry::AppRegistry;
 use shared::error::AppResult;
 
 use crate::{
-    extractor::AuthorizedUser,
+    extractor::{AuthorizedUser, Json, Path, Query},
+    handler::event::publish_book_event,
     model::book::{
-        BookListQuery, BookResponse, CreateBookRequest, PaginatedBookResponse, UpdateBookRequest,
+        BookCopiesResponse, BookListQuery, BookListResponse, BookResponse, CreateBookCopyRequest,
+        CreateBookCopyRequestWithId, CreateBookRequest, CreatedBookCopyResponse,
+        UpdateBookCopyRequest, UpdateBookCopyRequestWithIds, UpdateBookRequest,
         UpdateBookRequestWithId,
     },
 };
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/books",
+    tag = "books",
+    security(("bearer_auth" = [])),
+    request_body = CreateBookRequest,
+    responses(
+        (status = 201, description = "Registered the book or added a copy of it"),
+        (status = 400, description = "Invalid request body", body = ErrorResponse),
+        (status = 409, description = "A book with the same ISBN already exists", body = ErrorResponse)
+    )
+)]
 pub async fn register_book(
     user: AuthorizedUser,
     State(registry): State<AppRegistry>,
@@ -23,27 +42,65 @@ pub async fn register_book(
 ) -> AppResult<StatusCode> {
     req.validate(&())?;
 
-    registry
+    let add_copy = req.add_copy;
+    let book_id = registry
         .book_repository()
-        .create(req.into(), user.id())
-        .await
-        .map(|_| StatusCode::CREATED)
+        .create(req.try_into()?, user.id())
+        .await?;
+
+    let kind = if add_copy {
+        LiveEventKind::BookUpdated
+    } else {
+        LiveEventKind::BookCreated
+    };
+    publish_book_event(&registry, kind, book_id).await;
+    Ok(StatusCode::CREATED)
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/books",
+    tag = "books",
+    security(("bearer_auth" = [])),
+    params(BookListQuery),
+    responses(
+        (status = 200, description = "Books matching the query", body = BookListResponse),
+        (status = 400, description = "Invalid query", body = ErrorResponse)
+    )
+)]
 pub async fn show_book_list(
     _user: AuthorizedUser,
     Query(query): Query<BookListQuery>,
     State(registry): State<AppRegistry>,
-) -> AppResult<Json<PaginatedBookResponse>> {
+) -> AppResult<Json<BookListResponse>> {
     query.validate(&())?;
 
-    registry
-        .book_repository()
-        .find_all(query.into())
-        .await
-        .map(PaginatedBookResponse::from)
-        .map(Json)
+    let use_cursor = query.cursor.is_some();
+    let options = BookListOptions::try_from(query)?;
+    let response = if use_cursor {
+        let list = registry
+            .book_repository()
+            .find_all_by_cursor(options)
+            .await?;
+        BookListResponse::Cursor(list.into())
+    } else {
+        let list = registry.book_repository().find_all(options).await?;
+        BookListResponse::Offset(list.into())
+    };
+
+    Ok(Json(response))
 }
+#[utoipa::path(
+    get,
+    path = "/api/v1/books/{book_id}",
+    tag = "books",
+    security(("bearer_auth" = [])),
+    params(("book_id" = BookId, Path, description = "Book id")),
+    responses(
+        (status = 200, description = "The book", body = BookResponse),
+        (status = 404, description = "Book not found", body = ErrorResponse)
+    )
+)]
 pub async fn show_book(
     _user: AuthorizedUser,
     Path(book_id): Path<BookId>,
@@ -61,6 +118,20 @@ pub async fn show_book(
         })
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/books/{book_id}",
+    tag = "books",
+    security(("bearer_auth" = [])),
+    params(("book_id" = BookId, Path, description = "Book id")),
+    request_body = UpdateBookRequest,
+    responses(
+        (status = 200, description = "Updated the book"),
+        (status = 400, description = "Invalid request body", body = ErrorResponse),
+        (status = 404, description = "Book not found, or owned by someone else and the user may not manage books", body = ErrorResponse),
+        (status = 409, description = "Another book has the same ISBN", body = ErrorResponse)
+    )
+)]
 pub async fn update_book(
     user: AuthorizedUser,
     Path(book_id): Path<BookId>,
@@ -69,15 +140,28 @@ pub async fn update_book(
 ) -> AppResult<StatusCode> {
     req.validate(&())?;
 
-    let update_book = UpdateBookRequestWithId::new(book_id, user.id(), req);
+    let update_book = UpdateBookRequestWithId::new(book_id, user.id(), manages_books(&user), req);
 
     registry
         .book_repository()
-        .update(update_book.into())
-        .await
-        .map(|_| StatusCode::OK)
+        .update(update_book.try_into()?)
+        .await?;
+
+    publish_book_event(&registry, LiveEventKind::BookUpdated, book_id).await;
+    Ok(StatusCode::OK)
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/books/{book_id}",
+    tag = "books",
+    security(("bearer_auth" = [])),
+    params(("book_id" = BookId, Path, description = "Book id")),
+    responses(
+        (status = 200, description = "Deleted the book"),
+        (status = 404, description = "Book not found, or owned by someone else and the user may not manage books", body = ErrorResponse)
+    )
+)]
 pub async fn delete_book(
     user: AuthorizedUser,
     Path(book_id): Path<BookId>,
@@ -86,10 +170,139 @@ pub async fn delete_book(
     let delete_book = DeleteBook {
         book_id,
         requested_user: user.id(),
+        manages_books: manages_books(&user),
     };
+    registry.book_repository().delete(delete_book).await?;
+
+    publish_book_event(&registry, LiveEventKind::BookDeleted, book_id).await;
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/books/{book_id}/copies",
+    tag = "books",
+    security(("bearer_auth" = [])),
+    params(("book_id" = BookId, Path, description = "Book id")),
+    responses((status = 200, description = "Copies of the book", body = BookCopiesResponse))
+)]
+pub async fn show_book_copies(
+    _user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<BookCopiesResponse>> {
     registry
         .book_repository()
-        .delete(delete_book)
+        .find_copies(book_id)
         .await
-        .map(|_| StatusCode::OK)
+        .map(BookCopiesResponse::from)
+        .map(Json)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/books/{book_id}/copies",
+    tag = "books",
+    security(("bearer_auth" = [])),
+    params(("book_id" = BookId, Path, description = "Book id")),
+    request_body = CreateBookCopyRequest,
+    responses(
+        (status = 201, description = "Added a copy", body = CreatedBookCopyResponse),
+        (status = 400, description = "Invalid request body", body = ErrorResponse),
+        (status = 404, description = "Book not found, or owned by someone else and the user may not manage books", body = ErrorResponse),
+        (status = 422, description = "The barcode is already in use", body = ErrorResponse)
+    )
+)]
+pub async fn add_book_copy(
+    user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<CreateBookCopyRequest>,
+) -> AppResult<(StatusCode, Json<CreatedBookCopyResponse>)> {
+    req.validate(&())?;
+
+    let create_copy =
+        CreateBookCopyRequestWithId::new(book_id, user.id(), manages_books(&user), req);
+
+    let id = registry
+        .book_repository()
+        .create_copy(create_copy.into())
+        .await?;
+
+    publish_book_event(&registry, LiveEventKind::BookUpdated, book_id).await;
+    Ok((StatusCode::CREATED, Json(CreatedBookCopyResponse { id })))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/books/{book_id}/copies/{copy_id}",
+    tag = "books",
+    security(("bearer_auth" = [])),
+    params(
+        ("book_id" = BookId, Path, description = "Book id"),
+        ("copy_id" = BookCopyId, Path, description = "Copy id")
+    ),
+    request_body = UpdateBookCopyRequest,
+    responses(
+        (status = 200, description = "Updated the copy"),
+        (status = 400, description = "Invalid request body", body = ErrorResponse),
+        (status = 404, description = "Copy not found, or owned by someone else and the user may not manage books", body = ErrorResponse),
+        (status = 422, description = "The barcode is already in use", body = ErrorResponse)
+    )
+)]
+pub async fn update_book_copy(
+    user: AuthorizedUser,
+    Path((book_id, copy_id)): Path<(BookId, BookCopyId)>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<UpdateBookCopyRequest>,
+) -> AppResult<StatusCode> {
+    req.validate(&())?;
+
+    let update_copy =
+        UpdateBookCopyRequestWithIds::new(book_id, copy_id, user.id(), manages_books(&user), req);
+
+    registry
+        .book_repository()
+        .update_copy(update_copy.into())
+        .await?;
+
+    publish_book_event(&registry, LiveEventKind::BookUpdated, book_id).await;
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/books/{book_id}/copies/{copy_id}",
+    tag = "books",
+    security(("bearer_auth" = [])),
+    params(
+        ("book_id" = BookId, Path, description = "Book id"),
+        ("copy_id" = BookCopyId, Path, description = "Copy id")
+    ),
+    responses(
+        (status = 200, description = "Deleted the copy"),
+        (status = 404, description = "Copy not found, or owned by someone else and the user may not manage books", body = ErrorResponse),
+        (status = 422, description = "The copy is checked out", body = ErrorResponse)
+    )
+)]
+pub async fn delete_book_copy(
+    user: AuthorizedUser,
+    Path((book_id, copy_id)): Path<(BookId, BookCopyId)>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    let delete_copy = DeleteBookCopy {
+        copy_id,
+        book_id,
+        requested_user: user.id(),
+        manages_books: manages_books(&user),
+    };
+    registry.book_repository().delete_copy(delete_copy).await?;
+
+    publish_book_event(&registry, LiveEventKind::BookUpdated, book_id).await;
+    Ok(StatusCode::OK)
+}
+
+// librarians change any book, others only their own
+fn manages_books(user: &AuthorizedUser) -> bool {
+    user.has_permission(Permission::ManageBooks)
 }
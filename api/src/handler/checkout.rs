@@ -1,44 +1,218 @@
-use axum::{
-    Json,
-    extract::{Path, State},
-    http::StatusCode,
-};
+use axum::{extract::State, http::StatusCode};
 use kernel::model::{
-    checkout::event::{CreateCheckout, UpdateReturned},
+    checkout::event::{CreateCheckout, RenewCheckout, UpdateReturned},
     id::{BookId, CheckoutId},
+    live_event::LiveEventKind,
 };
 use registry::AppRegistry;
 use shared::error::AppResult;
 
-use crate::{extractor::AuthorizedUser, model::checkout::CheckoutsResponse};
+use garde::Validate;
+
+use crate::{
+    extractor::{
+        AuthorizedUser, Json, OptionalJson, Path, Permitted, Query, permission::ManageCheckouts,
+    },
+    handler::event::publish_book_event,
+    model::{
+        checkout::{
+            CheckoutsResponse, CreateCheckoutRequest, CreateDeskCheckoutRequest,
+            ForceReturnRequest, PaginatedCheckoutsResponse,
+        },
+        list::CursorListQuery,
+    },
+};
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/books/{book_id}/checkouts",
+    tag = "checkouts",
+    security(("bearer_auth" = [])),
+    params(("book_id" = BookId, Path, description = "Book id")),
+    request_body(content = Option<CreateCheckoutRequest>, description = "Copy to check out; any available copy when omitted"),
+    responses(
+        (status = 201, description = "Checked out a copy"),
+        (status = 400, description = "Invalid request body", body = ErrorResponse),
+        (status = 404, description = "Book or copy not found", body = ErrorResponse),
+        (status = 422, description = "No copy is available to the user, or the user's borrowing policy refuses the checkout, named by `loan_limit_reached`, `overdue_loans` or `own_book`", body = ErrorResponse)
+    )
+)]
 pub async fn checkout_book(
     user: AuthorizedUser,
     Path(book_id): Path<BookId>,
     State(registry): State<AppRegistry>,
+    OptionalJson(req): OptionalJson<CreateCheckoutRequest>,
 ) -> AppResult<StatusCode> {
-    let create_checkout_history = CreateCheckout::new(book_id, user.id(), chrono::Utc::now());
+    let req = req.unwrap_or_default();
+    let create_checkout_history = CreateCheckout::new(
+        book_id,
+        req.copy_id,
+        user.id(),
+        user.id(),
+        chrono::Utc::now(),
+    );
 
     registry
         .checkout_repository()
         .create(create_checkout_history)
-        .await
-        .map(|_| StatusCode::CREATED)
+        .await?;
+
+    publish_book_event(&registry, LiveEventKind::CheckoutCreated, book_id).await;
+    Ok(StatusCode::CREATED)
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/books/{book_id}/checkouts/desk",
+    tag = "checkouts",
+    security(("bearer_auth" = [])),
+    params(("book_id" = BookId, Path, description = "Book id")),
+    request_body = CreateDeskCheckoutRequest,
+    responses(
+        (status = 201, description = "Checked out a copy to the user"),
+        (status = 403, description = "The user may not manage checkouts", body = ErrorResponse),
+        (status = 404, description = "Book, copy or borrower not found", body = ErrorResponse),
+        (status = 422, description = "No copy is available to the borrower, or their borrowing policy refuses the checkout", body = ErrorResponse)
+    )
+)]
+pub async fn checkout_book_at_desk(
+    user: Permitted<ManageCheckouts>,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<CreateDeskCheckoutRequest>,
+) -> AppResult<StatusCode> {
+    let create_checkout = CreateCheckout::new(
+        book_id,
+        req.copy_id,
+        req.user_id,
+        user.id(),
+        chrono::Utc::now(),
+    );
+
+    registry
+        .checkout_repository()
+        .create(create_checkout)
+        .await?;
+
+    publish_book_event(&registry, LiveEventKind::CheckoutCreated, book_id).await;
+    Ok(StatusCode::CREATED)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/books/{book_id}/checkouts/{checkout_id}/returned",
+    tag = "checkouts",
+    security(("bearer_auth" = [])),
+    params(
+        ("book_id" = BookId, Path, description = "Book id"),
+        ("checkout_id" = CheckoutId, Path, description = "Checkout id")
+    ),
+    responses(
+        (status = 200, description = "Returned the copy"),
+        (status = 404, description = "Book not found", body = ErrorResponse),
+        (status = 422, description = "The checkout does not belong to the user", body = ErrorResponse)
+    )
+)]
 pub async fn return_book(
     user: AuthorizedUser,
     Path((book_id, checkout_id)): Path<(BookId, CheckoutId)>,
     State(registry): State<AppRegistry>,
 ) -> AppResult<StatusCode> {
-    let update_returned = UpdateReturned::new(checkout_id, book_id, user.id(), chrono::Utc::now());
+    let update_returned = UpdateReturned::new(
+        checkout_id,
+        book_id,
+        user.id(),
+        false,
+        None,
+        chrono::Utc::now(),
+    );
+    registry
+        .checkout_repository()
+        .update_returned(update_returned)
+        .await?;
+
+    publish_book_event(&registry, LiveEventKind::CheckoutReturned, book_id).await;
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/books/{book_id}/checkouts/{checkout_id}/force-returned",
+    tag = "checkouts",
+    security(("bearer_auth" = [])),
+    params(
+        ("book_id" = BookId, Path, description = "Book id"),
+        ("checkout_id" = CheckoutId, Path, description = "Checkout id")
+    ),
+    request_body = ForceReturnRequest,
+    responses(
+        (status = 200, description = "Returned the copy on the borrower's behalf"),
+        (status = 400, description = "Invalid request body", body = ErrorResponse),
+        (status = 403, description = "The user may not manage checkouts", body = ErrorResponse),
+        (status = 404, description = "Book not found", body = ErrorResponse),
+        (status = 422, description = "The checkout is not an open one of the book", body = ErrorResponse)
+    )
+)]
+pub async fn force_return_book(
+    user: Permitted<ManageCheckouts>,
+    Path((book_id, checkout_id)): Path<(BookId, CheckoutId)>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<ForceReturnRequest>,
+) -> AppResult<StatusCode> {
+    req.validate(&())?;
+
+    let update_returned = UpdateReturned::new(
+        checkout_id,
+        book_id,
+        user.id(),
+        true,
+        Some(req.reason),
+        chrono::Utc::now(),
+    );
     registry
         .checkout_repository()
         .update_returned(update_returned)
+        .await?;
+
+    publish_book_event(&registry, LiveEventKind::CheckoutReturned, book_id).await;
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/books/{book_id}/checkouts/{checkout_id}/renew",
+    tag = "checkouts",
+    security(("bearer_auth" = [])),
+    params(
+        ("book_id" = BookId, Path, description = "Book id"),
+        ("checkout_id" = CheckoutId, Path, description = "Checkout id")
+    ),
+    responses(
+        (status = 200, description = "Extended the due date"),
+        (status = 404, description = "Checkout not found", body = ErrorResponse),
+        (status = 422, description = "The checkout cannot be renewed", body = ErrorResponse)
+    )
+)]
+pub async fn renew_book(
+    user: AuthorizedUser,
+    Path((book_id, checkout_id)): Path<(BookId, CheckoutId)>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    let renew_checkout = RenewCheckout::new(checkout_id, book_id, user.id(), chrono::Utc::now());
+    registry
+        .checkout_repository()
+        .renew(renew_checkout)
         .await
         .map(|_| StatusCode::OK)
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/books/checkouts",
+    tag = "checkouts",
+    security(("bearer_auth" = [])),
+    responses((status = 200, description = "All unreturned checkouts", body = CheckoutsResponse))
+)]
 pub async fn show_checked_out_list(
     _user: AuthorizedUser,
     State(registry): State<AppRegistry>,
@@ -51,15 +225,48 @@ pub async fn show_checked_out_list(
         .map(Json)
 }
 
-pub async fn checkout_history(
+#[utoipa::path(
+    get,
+    path = "/api/v1/books/checkouts/overdue",
+    tag = "checkouts",
+    security(("bearer_auth" = [])),
+    responses((status = 200, description = "Checkouts past their due date", body = CheckoutsResponse))
+)]
+pub async fn show_overdue_list(
     _user: AuthorizedUser,
-    Path(book_id): Path<BookId>,
     State(registry): State<AppRegistry>,
 ) -> AppResult<Json<CheckoutsResponse>> {
     registry
         .checkout_repository()
-        .find_history_by_book_id(book_id)
+        .find_overdue_all(chrono::Utc::now())
         .await
         .map(CheckoutsResponse::from)
         .map(Json)
 }
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/books/{book_id}/checkout-history",
+    tag = "checkouts",
+    security(("bearer_auth" = [])),
+    params(("book_id" = BookId, Path, description = "Book id"), CursorListQuery),
+    responses(
+        (status = 200, description = "Checkouts of the book, newest first", body = PaginatedCheckoutsResponse),
+        (status = 400, description = "Invalid query", body = ErrorResponse)
+    )
+)]
+pub async fn checkout_history(
+    _user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    Query(query): Query<CursorListQuery>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<PaginatedCheckoutsResponse>> {
+    query.validate(&())?;
+
+    registry
+        .checkout_repository()
+        .find_history_by_book_id(book_id, query.try_into()?)
+        .await
+        .map(PaginatedCheckoutsResponse::from)
+        .map(Json)
+}
@@ -0,0 +1,96 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use kernel::model::{
+    id::BookId,
+    live_event::{BookAvailability, LiveEventKind, NewLiveEvent},
+};
+use registry::AppRegistry;
+use shared::error::{AppError, AppResult};
+use tokio_stream::{Stream, StreamExt};
+
+use crate::{extractor::AuthorizedUser, model::live_event::LiveEventResponse};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/events",
+    tag = "events",
+    security(("bearer_auth" = [])),
+    params(("Last-Event-ID" = Option<u64>, Header, description = "Id of the last event received; the events after it that are still kept are sent first")),
+    responses(
+        (status = 200, description = "Server-sent events named `book.created`, `book.updated`, `book.deleted`, `checkout.created` or `checkout.returned`", content_type = "text/event-stream", body = LiveEventResponse),
+        (status = 400, description = "Invalid Last-Event-ID header", body = ErrorResponse)
+    )
+)]
+pub async fn stream_events(
+    _user: AuthorizedUser,
+    headers: HeaderMap,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Sse<impl Stream<Item = Result<Event, Infallible>>>> {
+    let last_event_id = headers
+        .get("last-event-id")
+        .map(|value| {
+            value
+                .to_str()
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .ok_or_else(|| AppError::InvalidRequest("invalid Last-Event-ID header".into()))
+        })
+        .transpose()?;
+
+    let events = registry
+        .live_event_broker()
+        .subscribe(last_event_id)
+        .await?
+        .filter_map(|event| match event {
+            Ok(event) => Some(Ok(Event::default()
+                .id(event.id.to_string())
+                .event(event.kind.as_ref())
+                .json_data(LiveEventResponse::from(event))
+                .expect("events serialize to JSON"))),
+            Err(e) => {
+                tracing::warn!(error.message = %e, "Skipped an unreadable live event");
+                None
+            }
+        });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+// tells the subscribers of the events about a change that has been made;
+// failing to do so does not undo the change, so it is only logged
+pub(crate) async fn publish_book_event(
+    registry: &AppRegistry,
+    kind: LiveEventKind,
+    book_id: BookId,
+) {
+    let result = async {
+        let availability = match kind {
+            LiveEventKind::BookDeleted => None,
+            _ => registry
+                .book_repository()
+                .find_by_id(book_id)
+                .await?
+                .map(|book| BookAvailability {
+                    available_copies: book.available_copies() as i64,
+                    total_copies: book.total_copies() as i64,
+                }),
+        };
+        let event = NewLiveEvent::new(kind, book_id, availability, chrono::Utc::now());
+        registry.live_event_broker().publish(event).await
+    }
+    .await;
+
+    if let Err(e) = result {
+        tracing::warn!(
+            error.message = %e,
+            event.kind = kind.as_ref(),
+            %book_id,
+            "Failed to publish a live event"
+        );
+    }
+}
@@ -1,10 +1,25 @@
 use axum::{extract::State, http::StatusCode};
 use registry::AppRegistry;
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/health",
+    tag = "health",
+    responses((status = 200, description = "The API server is running"))
+)]
 pub async fn health_check() -> StatusCode {
     StatusCode::OK
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/health/db",
+    tag = "health",
+    responses(
+        (status = 200, description = "The database is reachable"),
+        (status = 500, description = "The database is unreachable")
+    )
+)]
 pub async fn health_check_db(State(registry): State<AppRegistry>) -> StatusCode {
     if registry.health_check_repository().check_db().await {
         StatusCode::OK
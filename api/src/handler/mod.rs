@@ -1,5 +1,13 @@
+pub mod api_key;
+pub mod audit;
 pub mod auth;
 pub mod book;
 pub mod checkout;
+pub mod event;
 pub mod health;
+pub mod notification;
+pub mod reservation;
+pub mod role;
+pub mod totp;
 pub mod user;
+pub mod webhook;
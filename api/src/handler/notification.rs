@@ -0,0 +1,61 @@
+use axum::extract::State;
+use garde::Validate;
+use kernel::model::notification::event::UpdateNotificationPreferences;
+use registry::AppRegistry;
+use shared::error::AppResult;
+
+use crate::{
+    extractor::{AuthorizedUser, Json},
+    model::notification::{NotificationPreferencesResponse, UpdateNotificationPreferencesRequest},
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/notification-preferences",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    responses((status = 200, description = "Whether the user gets each kind of notification", body = NotificationPreferencesResponse))
+)]
+pub async fn show_notification_preferences(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<NotificationPreferencesResponse>> {
+    registry
+        .notification_repository()
+        .find_preferences(user.id())
+        .await
+        .map(NotificationPreferencesResponse::from)
+        .map(Json)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/me/notification-preferences",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    request_body = UpdateNotificationPreferencesRequest,
+    responses(
+        (status = 200, description = "The preferences after the update", body = NotificationPreferencesResponse),
+        (status = 400, description = "Invalid request body", body = ErrorResponse)
+    )
+)]
+pub async fn update_notification_preferences(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<UpdateNotificationPreferencesRequest>,
+) -> AppResult<Json<NotificationPreferencesResponse>> {
+    req.validate(&())?;
+
+    let repository = registry.notification_repository();
+    repository
+        .update_preferences(UpdateNotificationPreferences::new(
+            user.id(),
+            req.items.into_iter().map(Into::into).collect(),
+        ))
+        .await?;
+    repository
+        .find_preferences(user.id())
+        .await
+        .map(NotificationPreferencesResponse::from)
+        .map(Json)
+}
@@ -0,0 +1,93 @@
+use axum::{extract::State, http::StatusCode};
+use kernel::model::{
+    id::{BookId, ReservationId},
+    reservation::event::{CreateReservation, DeleteReservation},
+    role::Permission,
+};
+use registry::AppRegistry;
+use shared::error::AppResult;
+
+use crate::{
+    extractor::{AuthorizedUser, Json, Path},
+    model::reservation::{ReservationResponse, ReservationsResponse},
+};
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/books/{book_id}/reservations",
+    tag = "reservations",
+    security(("bearer_auth" = [])),
+    params(("book_id" = BookId, Path, description = "Book id")),
+    responses(
+        (status = 201, description = "Joined the reservation queue", body = ReservationResponse),
+        (status = 404, description = "Book not found", body = ErrorResponse),
+        (status = 422, description = "The book cannot be reserved by the user", body = ErrorResponse)
+    )
+)]
+pub async fn reserve_book(
+    user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<(StatusCode, Json<ReservationResponse>)> {
+    let create_reservation = CreateReservation::new(book_id, user.id(), chrono::Utc::now());
+
+    registry
+        .reservation_repository()
+        .create(create_reservation)
+        .await
+        .map(|r| (StatusCode::CREATED, Json(r.into())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/books/{book_id}/reservations",
+    tag = "reservations",
+    security(("bearer_auth" = [])),
+    params(("book_id" = BookId, Path, description = "Book id")),
+    responses((status = 200, description = "The reservation queue, oldest first", body = ReservationsResponse))
+)]
+pub async fn show_reservation_list(
+    _user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<ReservationsResponse>> {
+    registry
+        .reservation_repository()
+        .find_by_book_id(book_id)
+        .await
+        .map(ReservationsResponse::from)
+        .map(Json)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/books/{book_id}/reservations/{reservation_id}",
+    tag = "reservations",
+    security(("bearer_auth" = [])),
+    params(
+        ("book_id" = BookId, Path, description = "Book id"),
+        ("reservation_id" = ReservationId, Path, description = "Reservation id")
+    ),
+    responses(
+        (status = 200, description = "Cancelled the reservation"),
+        (status = 404, description = "Reservation not found", body = ErrorResponse)
+    )
+)]
+pub async fn cancel_reservation(
+    user: AuthorizedUser,
+    Path((book_id, reservation_id)): Path<(BookId, ReservationId)>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    let delete_reservation = DeleteReservation::new(
+        reservation_id,
+        book_id,
+        user.id(),
+        user.has_permission(Permission::ManageCheckouts),
+    );
+
+    registry
+        .reservation_repository()
+        .delete(delete_reservation)
+        .await
+        .map(|_| StatusCode::OK)
+}
@@ -0,0 +1,117 @@
+use axum::{extract::State, http::StatusCode};
+use garde::Validate;
+use kernel::model::role::event::UpdateBorrowingPolicy;
+use registry::AppRegistry;
+use shared::error::AppResult;
+
+use crate::{
+    extractor::{Json, Path, Permitted, permission::ManageRoles},
+    model::role::{
+        BorrowingPolicyResponse, CreateRoleRequest, RoleResponse, RolesResponse,
+        UpdateBorrowingPolicyRequest,
+    },
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/roles",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Roles and what they allow, sorted by name", body = RolesResponse),
+        (status = 403, description = "The user may not manage roles", body = ErrorResponse)
+    )
+)]
+pub async fn list_roles(
+    _user: Permitted<ManageRoles>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<RolesResponse>> {
+    registry
+        .role_repository()
+        .find_all()
+        .await
+        .map(RolesResponse::from)
+        .map(Json)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/roles",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    request_body = CreateRoleRequest,
+    responses(
+        (status = 201, description = "Created the role", body = RoleResponse),
+        (status = 400, description = "Invalid request body", body = ErrorResponse),
+        (status = 403, description = "The user may not manage roles", body = ErrorResponse),
+        (status = 409, description = "A role of the same name exists", body = ErrorResponse)
+    )
+)]
+pub async fn create_role(
+    user: Permitted<ManageRoles>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<CreateRoleRequest>,
+) -> AppResult<(StatusCode, Json<RoleResponse>)> {
+    req.validate(&())?;
+
+    registry
+        .role_repository()
+        .create(req.into_event(user.id()))
+        .await
+        .map(|role| (StatusCode::CREATED, Json(role.into())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/roles/{role_name}/borrowing-policy",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    params(("role_name" = String, Path, description = "Name of the role")),
+    responses(
+        (status = 200, description = "What members of the role may borrow", body = BorrowingPolicyResponse),
+        (status = 403, description = "The user may not manage roles", body = ErrorResponse),
+        (status = 404, description = "No role has the name", body = ErrorResponse)
+    )
+)]
+pub async fn show_borrowing_policy(
+    _user: Permitted<ManageRoles>,
+    Path(role_name): Path<String>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<BorrowingPolicyResponse>> {
+    registry
+        .role_repository()
+        .find_borrowing_policy(&role_name)
+        .await
+        .map(BorrowingPolicyResponse::from)
+        .map(Json)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/roles/{role_name}/borrowing-policy",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    params(("role_name" = String, Path, description = "Name of the role")),
+    request_body = UpdateBorrowingPolicyRequest,
+    responses(
+        (status = 200, description = "Replaced the role's borrowing policy", body = BorrowingPolicyResponse),
+        (status = 400, description = "Invalid request body", body = ErrorResponse),
+        (status = 403, description = "The user may not manage roles", body = ErrorResponse),
+        (status = 404, description = "No role has the name", body = ErrorResponse)
+    )
+)]
+pub async fn update_borrowing_policy(
+    user: Permitted<ManageRoles>,
+    Path(role_name): Path<String>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<UpdateBorrowingPolicyRequest>,
+) -> AppResult<Json<BorrowingPolicyResponse>> {
+    req.validate(&())?;
+
+    registry
+        .role_repository()
+        .update_borrowing_policy(UpdateBorrowingPolicy::new(role_name, req.into(), user.id()))
+        .await
+        .map(BorrowingPolicyResponse::from)
+        .map(Json)
+}
@@ -0,0 +1,130 @@
+use axum::{extract::State, http::StatusCode};
+use garde::Validate;
+use kernel::model::totp::event::{BeginTotpEnrollment, ConfirmTotpEnrollment, VerifyTotp};
+use registry::AppRegistry;
+use shared::error::{AppError, AppResult};
+
+use crate::{
+    extractor::{AuthorizedUser, Json},
+    model::totp::{RecoveryCodesResponse, TotpCodeRequest, TotpEnrollmentResponse},
+};
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/me/totp",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "A new secret; it is enabled once a code of it is confirmed", body = TotpEnrollmentResponse),
+        (status = 409, description = "Two-factor authentication is already enabled", body = ErrorResponse)
+    )
+)]
+pub async fn begin_totp_enrollment(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<TotpEnrollmentResponse>> {
+    registry
+        .totp_repository()
+        .begin_enrollment(BeginTotpEnrollment::new(user.id(), user.user.email))
+        .await
+        .map(TotpEnrollmentResponse::from)
+        .map(Json)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/me/totp/confirm",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    request_body = TotpCodeRequest,
+    responses(
+        (status = 200, description = "Enabled two-factor authentication; the recovery codes are shown only this once", body = RecoveryCodesResponse),
+        (status = 400, description = "Invalid request body", body = ErrorResponse),
+        (status = 422, description = "Wrong code, or no enrollment was begun", body = ErrorResponse)
+    )
+)]
+pub async fn confirm_totp_enrollment(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<TotpCodeRequest>,
+) -> AppResult<Json<RecoveryCodesResponse>> {
+    req.validate(&())?;
+
+    registry
+        .totp_repository()
+        .confirm_enrollment(ConfirmTotpEnrollment::new(user.id(), req.code))
+        .await?
+        .map(RecoveryCodesResponse::from)
+        .map(Json)
+        .ok_or_else(|| AppError::UnprocessableEntity("the code is not valid".into()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/me/totp/recovery-codes",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    request_body = TotpCodeRequest,
+    responses(
+        (status = 200, description = "New recovery codes; the previous ones no longer work", body = RecoveryCodesResponse),
+        (status = 400, description = "Invalid request body", body = ErrorResponse),
+        (status = 422, description = "Wrong code, or two-factor authentication is not enabled", body = ErrorResponse)
+    )
+)]
+pub async fn regenerate_recovery_codes(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<TotpCodeRequest>,
+) -> AppResult<Json<RecoveryCodesResponse>> {
+    req.validate(&())?;
+    verify_code(&registry, &user, req.code).await?;
+
+    registry
+        .totp_repository()
+        .regenerate_recovery_codes(user.id())
+        .await
+        .map(RecoveryCodesResponse::from)
+        .map(Json)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/me/totp",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    request_body = TotpCodeRequest,
+    responses(
+        (status = 204, description = "Disabled two-factor authentication"),
+        (status = 400, description = "Invalid request body", body = ErrorResponse),
+        (status = 403, description = "Two-factor authentication is mandatory for admins", body = ErrorResponse),
+        (status = 422, description = "Wrong code, or two-factor authentication is not enabled", body = ErrorResponse)
+    )
+)]
+pub async fn disable_totp(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<TotpCodeRequest>,
+) -> AppResult<StatusCode> {
+    req.validate(&())?;
+    if user.is_admin() && registry.totp_config().required_for_admins {
+        return Err(AppError::ForbidenOperation);
+    }
+    verify_code(&registry, &user, req.code).await?;
+
+    registry.totp_repository().disable(user.id()).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// whoever holds the session has to prove they also hold the second factor
+async fn verify_code(registry: &AppRegistry, user: &AuthorizedUser, code: String) -> AppResult<()> {
+    let valid = registry
+        .totp_repository()
+        .verify(VerifyTotp::new(user.id(), code))
+        .await?;
+    if !valid {
+        return Err(AppError::UnprocessableEntity(
+            "the code is not valid".into(),
+        ));
+    }
+    Ok(())
+}
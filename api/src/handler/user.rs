@@ -1,92 +1,172 @@
-use axum::{
-    Json,
-    extract::{Path, State},
-    http::StatusCode,
-};
+use axum::{extract::State, http::StatusCode};
 use garde::Validate;
-use kernel::model::{id::UserId, user::event::DeleteUser};
+use kernel::model::{
+    auth::event::DeleteSession,
+    id::{SessionId, UserId},
+    user::{
+        UserStatus,
+        event::{CreateUser, DeleteUser, UpdateUserStatus},
+    },
+};
 use registry::AppRegistry;
 use shared::error::{AppError, AppResult};
 
 use crate::{
-    extractor::AuthorizedUser,
+    extractor::{
+        AuthorizedUser, Json, Path, Permitted, Query,
+        permission::{ManageRoles, ManageUsers},
+    },
     model::{
+        auth::SessionsResponse,
         checkout::CheckoutsResponse,
+        list::CursorListQuery,
+        reservation::ReservationsResponse,
         user::{
-            CreaterUserRequest, UpdateUserPasswordRequest, UpdateUserPasswordRequestWithUserId,
-            UpdateUserRoleRequest, UpdateUserRoleRequestWithUserId, UserResponse, UsersResponse,
+            CreaterUserRequest, PendingUsersResponse, UpdateUserPasswordRequest,
+            UpdateUserPasswordRequestWithUserId, UpdateUserRoleRequest,
+            UpdateUserRoleRequestWithUserId, UserApprovalRequest, UserResponse, UsersResponse,
         },
     },
 };
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/users",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    request_body = CreaterUserRequest,
+    responses(
+        (status = 200, description = "Registered the user", body = UserResponse),
+        (status = 400, description = "Invalid request body", body = ErrorResponse),
+        (status = 403, description = "The user may not manage users", body = ErrorResponse)
+    )
+)]
 pub async fn register_user(
-    user: AuthorizedUser,
+    user: Permitted<ManageUsers>,
     State(registry): State<AppRegistry>,
     Json(req): Json<CreaterUserRequest>,
 ) -> AppResult<Json<UserResponse>> {
-    if !user.is_admin() {
-        return Err(AppError::ForbidenOperation);
-    }
-
     req.validate(&())?;
 
-    let registered_user = registry.user_repository().create(req.into()).await?;
+    let registered_user = registry
+        .user_repository()
+        .create(CreateUser {
+            created_by: Some(user.id()),
+            ..req.into()
+        })
+        .await?;
 
     Ok(Json(registered_user.into()))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/users",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    params(CursorListQuery),
+    responses(
+        (status = 200, description = "Users, newest first", body = UsersResponse),
+        (status = 400, description = "Invalid query", body = ErrorResponse)
+    )
+)]
 pub async fn list_users(
     _user: AuthorizedUser,
+    Query(query): Query<CursorListQuery>,
     State(registry): State<AppRegistry>,
 ) -> AppResult<Json<UsersResponse>> {
-    let items = registry
-        .user_repository()
-        .find_all()
-        .await?
-        .into_iter()
-        .map(UserResponse::from)
-        .collect();
+    query.validate(&())?;
 
-    Ok(Json(UsersResponse { items }))
+    registry
+        .user_repository()
+        .find_all(query.try_into()?)
+        .await
+        .map(UsersResponse::from)
+        .map(Json)
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/{user_id}",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    params(("user_id" = UserId, Path, description = "User id")),
+    responses(
+        (status = 200, description = "Deleted the user"),
+        (status = 403, description = "The user may not manage users", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse)
+    )
+)]
 pub async fn delete_user(
-    user: AuthorizedUser,
+    user: Permitted<ManageUsers>,
     Path(user_id): Path<UserId>,
     State(registry): State<AppRegistry>,
 ) -> AppResult<StatusCode> {
-    if !user.is_admin() {
-        return Err(AppError::ForbidenOperation);
-    }
     registry
         .user_repository()
-        .delete(DeleteUser { user_id })
+        .delete(DeleteUser {
+            user_id,
+            requested_user: user.id(),
+        })
         .await?;
 
     Ok(StatusCode::OK)
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/{user_id}/role",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    params(("user_id" = UserId, Path, description = "User id")),
+    request_body = UpdateUserRoleRequest,
+    responses(
+        (status = 200, description = "Changed the role"),
+        (status = 400, description = "Invalid request body", body = ErrorResponse),
+        (status = 403, description = "The user may not manage roles", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+        (status = 422, description = "No role has that name", body = ErrorResponse)
+    )
+)]
 pub async fn change_role(
-    user: AuthorizedUser,
+    user: Permitted<ManageRoles>,
     Path(user_id): Path<UserId>,
     State(registry): State<AppRegistry>,
     Json(role_name): Json<UpdateUserRoleRequest>,
 ) -> AppResult<StatusCode> {
-    if !user.is_admin() {
-        return Err(AppError::ForbidenOperation);
-    }
+    role_name.validate(&())?;
+
     registry
         .user_repository()
-        .update_role(UpdateUserRoleRequestWithUserId::new(user_id, role_name).into())
+        .update_role(UpdateUserRoleRequestWithUserId::new(user_id, role_name, user.id()).into())
         .await?;
 
     Ok(StatusCode::OK)
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    responses((status = 200, description = "The logged-in user", body = UserResponse))
+)]
 pub async fn get_current_user(user: AuthorizedUser) -> AppResult<Json<UserResponse>> {
     Ok(Json(UserResponse::from(user.user)))
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/me/password",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    request_body = UpdateUserPasswordRequest,
+    responses(
+        (status = 200, description = "Changed the password"),
+        (status = 400, description = "Invalid request body", body = ErrorResponse),
+        (status = 403, description = "The current password is wrong", body = ErrorResponse)
+    )
+)]
 pub async fn change_password(
     user: AuthorizedUser,
     State(registry): State<AppRegistry>,
@@ -102,6 +182,13 @@ pub async fn change_password(
     Ok(StatusCode::OK)
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/checkouts",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    responses((status = 200, description = "Books the user has checked out", body = CheckoutsResponse))
+)]
 pub async fn get_checkouts(
     user: AuthorizedUser,
     State(registry): State<AppRegistry>,
@@ -113,3 +200,195 @@ pub async fn get_checkouts(
         .map(CheckoutsResponse::from)
         .map(Json)
 }
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/reservations",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    responses((status = 200, description = "The user's reservations", body = ReservationsResponse))
+)]
+pub async fn get_reservations(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<ReservationsResponse>> {
+    registry
+        .reservation_repository()
+        .find_by_user_id(user.id())
+        .await
+        .map(ReservationsResponse::from)
+        .map(Json)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/sessions",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    responses((status = 200, description = "Where the user is logged in, most recently used first", body = SessionsResponse))
+)]
+pub async fn get_sessions(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<SessionsResponse>> {
+    let sessions = registry.auth_repository().find_sessions(user.id()).await?;
+
+    Ok(Json(SessionsResponse::new(sessions, user.session_id)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/me/sessions/{session_id}",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    params(("session_id" = SessionId, Path, description = "Session id")),
+    responses(
+        (status = 204, description = "Logged the session out"),
+        (status = 404, description = "The user has no such session", body = ErrorResponse)
+    )
+)]
+pub async fn delete_session(
+    user: AuthorizedUser,
+    Path(session_id): Path<SessionId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    registry
+        .auth_repository()
+        .delete_session(DeleteSession::new(session_id, user.id()))
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/me/sessions",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    responses((status = 204, description = "Logged out everywhere, including this session"))
+)]
+pub async fn delete_sessions(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    registry
+        .auth_repository()
+        .delete_all_sessions(user.id())
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/{user_id}/sessions",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    params(("user_id" = UserId, Path, description = "User id")),
+    responses(
+        (status = 204, description = "Logged the user out everywhere"),
+        (status = 403, description = "The user may not manage users", body = ErrorResponse)
+    )
+)]
+pub async fn delete_user_sessions(
+    _user: Permitted<ManageUsers>,
+    Path(user_id): Path<UserId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    registry
+        .auth_repository()
+        .delete_all_sessions(user_id)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/{user_id}/lockout",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    params(("user_id" = UserId, Path, description = "User id")),
+    responses(
+        (status = 204, description = "The user can log in again right away"),
+        (status = 403, description = "The user may not manage users", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse)
+    )
+)]
+pub async fn unlock_user(
+    _user: Permitted<ManageUsers>,
+    Path(user_id): Path<UserId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    let locked_user = registry
+        .user_repository()
+        .find_current_user(user_id)
+        .await?
+        .ok_or_else(|| AppError::EntityNotFound(format!("User with id {user_id} not found")))?;
+
+    registry
+        .login_attempt_repository()
+        .unlock(&locked_user.email)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/pending",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Signed-up users waiting for approval, oldest first", body = PendingUsersResponse),
+        (status = 403, description = "The user may not manage users", body = ErrorResponse)
+    )
+)]
+pub async fn list_pending_users(
+    _user: Permitted<ManageUsers>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<PendingUsersResponse>> {
+    registry
+        .user_repository()
+        .find_by_status(UserStatus::PendingApproval)
+        .await
+        .map(PendingUsersResponse::from)
+        .map(Json)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/{user_id}/approval",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    params(("user_id" = UserId, Path, description = "User id")),
+    request_body = UserApprovalRequest,
+    responses(
+        (status = 204, description = "Approved the user, who can log in now, or rejected them"),
+        (status = 403, description = "The user may not manage users", body = ErrorResponse),
+        (status = 422, description = "The user is not waiting for approval", body = ErrorResponse)
+    )
+)]
+pub async fn approve_user(
+    user: Permitted<ManageUsers>,
+    Path(user_id): Path<UserId>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<UserApprovalRequest>,
+) -> AppResult<StatusCode> {
+    let to = if req.approved {
+        UserStatus::Active
+    } else {
+        UserStatus::Rejected
+    };
+    registry
+        .user_repository()
+        .update_status(UpdateUserStatus {
+            user_id,
+            from: UserStatus::PendingApproval,
+            to,
+            requested_user: user.id(),
+        })
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
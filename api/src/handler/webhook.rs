@@ -0,0 +1,150 @@
+use axum::{extract::State, http::StatusCode};
+use chrono::Utc;
+use garde::Validate;
+use kernel::model::{
+    id::{WebhookDeliveryId, WebhookId},
+    webhook::event::{DeleteWebhook, RetryDelivery},
+};
+use registry::AppRegistry;
+use shared::error::AppResult;
+
+use crate::{
+    extractor::{Json, Path, Permitted, Query, permission::ManageWebhooks},
+    model::{
+        list::CursorListQuery,
+        webhook::{
+            CreateWebhookRequest, CreatedWebhookResponse, PaginatedWebhookDeliveriesResponse,
+            WebhooksResponse,
+        },
+    },
+};
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/webhooks",
+    tag = "webhooks",
+    security(("bearer_auth" = [])),
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 201, description = "Subscribed the endpoint; the signing secret is shown only this once", body = CreatedWebhookResponse),
+        (status = 400, description = "Invalid request body", body = ErrorResponse),
+        (status = 403, description = "The user may not manage webhooks", body = ErrorResponse)
+    )
+)]
+pub async fn create_webhook(
+    user: Permitted<ManageWebhooks>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<CreateWebhookRequest>,
+) -> AppResult<(StatusCode, Json<CreatedWebhookResponse>)> {
+    req.validate(&())?;
+
+    registry
+        .webhook_repository()
+        .create(req.into_event(user.id()))
+        .await
+        .map(|w| (StatusCode::CREATED, Json(w.into())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks",
+    tag = "webhooks",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Subscriptions, oldest first", body = WebhooksResponse),
+        (status = 403, description = "The user may not manage webhooks", body = ErrorResponse)
+    )
+)]
+pub async fn list_webhooks(
+    _user: Permitted<ManageWebhooks>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<WebhooksResponse>> {
+    registry
+        .webhook_repository()
+        .find_all()
+        .await
+        .map(WebhooksResponse::from)
+        .map(Json)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/webhooks/{webhook_id}",
+    tag = "webhooks",
+    security(("bearer_auth" = [])),
+    params(("webhook_id" = WebhookId, Path, description = "Webhook id")),
+    responses(
+        (status = 204, description = "Unsubscribed the endpoint and deleted its deliveries"),
+        (status = 403, description = "The user may not manage webhooks", body = ErrorResponse),
+        (status = 404, description = "Webhook not found", body = ErrorResponse)
+    )
+)]
+pub async fn delete_webhook(
+    _user: Permitted<ManageWebhooks>,
+    Path(webhook_id): Path<WebhookId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    registry
+        .webhook_repository()
+        .delete(DeleteWebhook::new(webhook_id))
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks/{webhook_id}/deliveries",
+    tag = "webhooks",
+    security(("bearer_auth" = [])),
+    params(("webhook_id" = WebhookId, Path, description = "Webhook id"), CursorListQuery),
+    responses(
+        (status = 200, description = "Deliveries to the endpoint and their last attempt, newest first", body = PaginatedWebhookDeliveriesResponse),
+        (status = 400, description = "Invalid query", body = ErrorResponse),
+        (status = 403, description = "The user may not manage webhooks", body = ErrorResponse),
+        (status = 404, description = "Webhook not found", body = ErrorResponse)
+    )
+)]
+pub async fn list_webhook_deliveries(
+    _user: Permitted<ManageWebhooks>,
+    Path(webhook_id): Path<WebhookId>,
+    Query(query): Query<CursorListQuery>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<PaginatedWebhookDeliveriesResponse>> {
+    query.validate(&())?;
+
+    registry
+        .webhook_repository()
+        .find_deliveries(webhook_id, query.try_into()?)
+        .await
+        .map(PaginatedWebhookDeliveriesResponse::from)
+        .map(Json)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/webhooks/{webhook_id}/deliveries/{delivery_id}/retry",
+    tag = "webhooks",
+    security(("bearer_auth" = [])),
+    params(
+        ("webhook_id" = WebhookId, Path, description = "Webhook id"),
+        ("delivery_id" = WebhookDeliveryId, Path, description = "Delivery id")
+    ),
+    responses(
+        (status = 202, description = "Queued the dead letter to be posted again, with a fresh set of attempts"),
+        (status = 403, description = "The user may not manage webhooks", body = ErrorResponse),
+        (status = 404, description = "The webhook has no such dead letter", body = ErrorResponse)
+    )
+)]
+pub async fn retry_webhook_delivery(
+    _user: Permitted<ManageWebhooks>,
+    Path((webhook_id, delivery_id)): Path<(WebhookId, WebhookDeliveryId)>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    registry
+        .webhook_repository()
+        .retry_delivery(RetryDelivery::new(webhook_id, delivery_id, Utc::now()))
+        .await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
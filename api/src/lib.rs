@@ -1,4 +1,6 @@
 pub mod extractor;
 pub mod handler;
+pub mod middleware;
 pub mod model;
+pub mod openapi;
 pub mod route;
@@ -0,0 +1,24 @@
+use axum::{extract::Request, http::HeaderValue, middleware::Next, response::Response};
+use shared::request_id::{self, REQUEST_ID_HEADER};
+
+// reuses the caller's request id or issues a new one, exposing it to error
+// responses and echoing it back in the response header
+pub async fn request_id(mut req: Request, next: Next) -> Response {
+    let id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty() && v.len() <= 128)
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let header = HeaderValue::from_str(&id).ok();
+
+    if let Some(header) = header.clone() {
+        req.headers_mut().insert(REQUEST_ID_HEADER, header);
+    }
+    let mut res = request_id::scope(id, next.run(req)).await;
+    if let Some(header) = header {
+        res.headers_mut().insert(REQUEST_ID_HEADER, header);
+    }
+    res
+}
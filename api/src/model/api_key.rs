@@ -0,0 +1,113 @@
+use chrono::{DateTime, Utc};
+use garde::Validate;
+use kernel::model::{
+    api_key::{ApiKey, ApiKeyScope, IssuedApiKey},
+    id::ApiKeyId,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeyScopeName {
+    BooksRead,
+    BooksWrite,
+    Checkouts,
+    Reservations,
+}
+
+impl From<ApiKeyScope> for ApiKeyScopeName {
+    fn from(value: ApiKeyScope) -> Self {
+        match value {
+            ApiKeyScope::BooksRead => ApiKeyScopeName::BooksRead,
+            ApiKeyScope::BooksWrite => ApiKeyScopeName::BooksWrite,
+            ApiKeyScope::Checkouts => ApiKeyScopeName::Checkouts,
+            ApiKeyScope::Reservations => ApiKeyScopeName::Reservations,
+        }
+    }
+}
+impl From<ApiKeyScopeName> for ApiKeyScope {
+    fn from(value: ApiKeyScopeName) -> Self {
+        match value {
+            ApiKeyScopeName::BooksRead => ApiKeyScope::BooksRead,
+            ApiKeyScopeName::BooksWrite => ApiKeyScope::BooksWrite,
+            ApiKeyScopeName::Checkouts => ApiKeyScope::Checkouts,
+            ApiKeyScopeName::Reservations => ApiKeyScope::Reservations,
+        }
+    }
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiKeyRequest {
+    // tells the key apart, like the kiosk it is set up on
+    #[garde(length(min = 1))]
+    pub name: String,
+    #[garde(length(min = 1))]
+    pub scopes: Vec<ApiKeyScopeName>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyResponse {
+    pub id: ApiKeyId,
+    pub name: String,
+    // the start of the key, to recognize it by
+    pub prefix: String,
+    pub scopes: Vec<ApiKeyScopeName>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl From<ApiKey> for ApiKeyResponse {
+    fn from(value: ApiKey) -> Self {
+        let ApiKey {
+            id,
+            user_id: _,
+            name,
+            prefix,
+            scopes,
+            created_at,
+            last_used_at,
+        } = value;
+        Self {
+            id,
+            name,
+            prefix,
+            scopes: scopes.into_iter().map(ApiKeyScopeName::from).collect(),
+            created_at,
+            last_used_at,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeysResponse {
+    pub items: Vec<ApiKeyResponse>,
+}
+
+impl From<Vec<ApiKey>> for ApiKeysResponse {
+    fn from(value: Vec<ApiKey>) -> Self {
+        Self {
+            items: value.into_iter().map(ApiKeyResponse::from).collect(),
+        }
+    }
+}
+
+// the key is shown only this once; it is sent as a bearer token
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct IssuedApiKeyResponse {
+    pub api_key: ApiKeyResponse,
+    pub key: String,
+}
+
+impl From<IssuedApiKey> for IssuedApiKeyResponse {
+    fn from(value: IssuedApiKey) -> Self {
+        Self {
+            api_key: value.api_key.into(),
+            key: value.secret.0,
+        }
+    }
+}
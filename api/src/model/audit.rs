@@ -0,0 +1,199 @@
+use chrono::{DateTime, Utc};
+use garde::Validate;
+use kernel::model::{
+    audit::{AuditAction, AuditEntry, AuditLogOptions, AuditTargetKind},
+    id::{AuditEntryId, UserId},
+    list::CursorPaginatedList,
+};
+use serde::{Deserialize, Serialize};
+use shared::error::AppError;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::model::list::{default_limit, parse_cursor};
+
+#[derive(Debug, Clone, Copy, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditActionName {
+    Create,
+    Update,
+    Delete,
+    ChangePassword,
+    ResetPassword,
+    ChangeRole,
+    ChangeStatus,
+    Return,
+    Renew,
+}
+
+impl From<AuditAction> for AuditActionName {
+    fn from(value: AuditAction) -> Self {
+        match value {
+            AuditAction::Create => AuditActionName::Create,
+            AuditAction::Update => AuditActionName::Update,
+            AuditAction::Delete => AuditActionName::Delete,
+            AuditAction::ChangePassword => AuditActionName::ChangePassword,
+            AuditAction::ResetPassword => AuditActionName::ResetPassword,
+            AuditAction::ChangeRole => AuditActionName::ChangeRole,
+            AuditAction::ChangeStatus => AuditActionName::ChangeStatus,
+            AuditAction::Return => AuditActionName::Return,
+            AuditAction::Renew => AuditActionName::Renew,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditTargetType {
+    Book,
+    BookCopy,
+    User,
+    Role,
+    Checkout,
+}
+
+impl From<AuditTargetKind> for AuditTargetType {
+    fn from(value: AuditTargetKind) -> Self {
+        match value {
+            AuditTargetKind::Book => AuditTargetType::Book,
+            AuditTargetKind::BookCopy => AuditTargetType::BookCopy,
+            AuditTargetKind::User => AuditTargetType::User,
+            AuditTargetKind::Role => AuditTargetType::Role,
+            AuditTargetKind::Checkout => AuditTargetType::Checkout,
+        }
+    }
+}
+impl From<AuditTargetType> for AuditTargetKind {
+    fn from(value: AuditTargetType) -> Self {
+        match value {
+            AuditTargetType::Book => AuditTargetKind::Book,
+            AuditTargetType::BookCopy => AuditTargetKind::BookCopy,
+            AuditTargetType::User => AuditTargetKind::User,
+            AuditTargetType::Role => AuditTargetKind::Role,
+            AuditTargetType::Checkout => AuditTargetKind::Checkout,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct AuditLogQuery {
+    #[garde(range(min = 0))]
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+
+    #[garde(skip)]
+    pub cursor: Option<String>,
+
+    /// Only entries of changes the user made.
+    #[garde(skip)]
+    pub actor: Option<UserId>,
+
+    #[garde(skip)]
+    pub target_type: Option<AuditTargetType>,
+
+    /// The id of the target, or the name of a role.
+    #[garde(inner(length(min = 1, max = 255)))]
+    pub target_id: Option<String>,
+
+    /// Only entries at or after this time.
+    #[garde(skip)]
+    pub since: Option<DateTime<Utc>>,
+
+    /// Only entries at or before this time.
+    #[garde(skip)]
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<AuditLogQuery> for AuditLogOptions {
+    type Error = AppError;
+
+    fn try_from(value: AuditLogQuery) -> Result<Self, Self::Error> {
+        let AuditLogQuery {
+            limit,
+            cursor,
+            actor,
+            target_type,
+            target_id,
+            since,
+            until,
+        } = value;
+        Ok(AuditLogOptions {
+            limit,
+            cursor: parse_cursor(cursor)?,
+            actor,
+            target_kind: target_type.map(Into::into),
+            target_id,
+            since,
+            until,
+        })
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntryResponse {
+    pub id: AuditEntryId,
+    /// The user who made the change; null when the application made it.
+    pub actor_id: Option<UserId>,
+    pub action: AuditActionName,
+    pub target_type: AuditTargetType,
+    pub target_id: String,
+    /// JSON of the target before the change.
+    pub before: Option<String>,
+    /// JSON of the target after the change.
+    pub after: Option<String>,
+    /// `x-request-id` of the request that made the change.
+    pub request_id: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl From<AuditEntry> for AuditEntryResponse {
+    fn from(value: AuditEntry) -> Self {
+        let AuditEntry {
+            id,
+            actor,
+            action,
+            target,
+            before,
+            after,
+            request_id,
+            occurred_at,
+        } = value;
+        Self {
+            id,
+            actor_id: actor,
+            action: action.into(),
+            target_type: target.kind.into(),
+            target_id: target.id,
+            before,
+            after,
+            request_id,
+            occurred_at,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PaginatedAuditEntriesResponse {
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+    pub items: Vec<AuditEntryResponse>,
+}
+
+impl From<CursorPaginatedList<AuditEntry>> for PaginatedAuditEntriesResponse {
+    fn from(value: CursorPaginatedList<AuditEntry>) -> Self {
+        let CursorPaginatedList {
+            next_cursor,
+            prev_cursor,
+            items,
+            ..
+        } = value;
+        Self {
+            next_cursor: next_cursor.map(|c| c.encode()),
+            prev_cursor: prev_cursor.map(|c| c.encode()),
+            items: items.into_iter().map(Into::into).collect(),
+        }
+    }
+}
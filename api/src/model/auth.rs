@@ -1,16 +1,132 @@
-use kernel::model::id::UserId;
+use chrono::{DateTime, Utc};
+use garde::Validate;
+use kernel::model::{
+    auth::{AuthTokens, Session},
+    id::{SessionId, UserId},
+};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct LoginRequest {
     pub email: String,
     pub password: String,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshTokenRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordResetRequest {
+    #[garde(email)]
+    pub email: String,
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfirmPasswordResetRequest {
+    #[garde(length(min = 1))]
+    pub token: String,
+    #[garde(length(min = 1))]
+    pub new_password: String,
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ResendEmailVerificationRequest {
+    #[garde(email)]
+    pub email: String,
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyEmailRequest {
+    #[garde(length(min = 1))]
+    pub token: String,
+}
+
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct AccessTokenResponse {
     pub user_id: UserId,
     pub access_token: String,
+    // exchanged at `/auth/refresh` for a new pair of tokens; valid only once
+    pub refresh_token: String,
+    // seconds until `access_token` expires
+    pub expires_in: u64,
+}
+
+impl From<AuthTokens> for AccessTokenResponse {
+    fn from(value: AuthTokens) -> Self {
+        let AuthTokens {
+            user_id,
+            access_token,
+            refresh_token,
+            expires_in,
+        } = value;
+        Self {
+            user_id,
+            access_token: access_token.0,
+            refresh_token: refresh_token.0,
+            expires_in,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionsResponse {
+    pub items: Vec<SessionResponse>,
+}
+
+impl SessionsResponse {
+    // flags the session the request was made with
+    pub fn new(sessions: Vec<Session>, current: Option<SessionId>) -> Self {
+        Self {
+            items: sessions
+                .into_iter()
+                .map(|s| {
+                    let current = current == Some(s.id);
+                    SessionResponse::new(s, current)
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionResponse {
+    pub id: SessionId,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    pub current: bool,
+}
+
+impl SessionResponse {
+    fn new(value: Session, current: bool) -> Self {
+        let Session {
+            id,
+            user_id: _,
+            user_agent,
+            ip_address,
+            created_at,
+            last_used_at,
+        } = value;
+        Self {
+            id,
+            user_agent,
+            ip_address,
+            created_at,
+            last_used_at,
+            current,
+        }
+    }
 }
@@ -3,83 +3,109 @@ use derive_new::new;
 use garde::Validate;
 use kernel::model::{
     book::{
-        Book, BookListOptions, Checkout,
-        event::{CreateBook, UpdateBook},
+        Book, BookCopy, BookListOptions, BookSort, Checkout, CopyCondition,
+        event::{CreateBook, CreateBookCopy, UpdateBook, UpdateBookCopy},
     },
-    id::{BookId, CheckoutId, UserId},
-    list::PaginatedList,
+    id::{BookCopyId, BookId, CheckoutId, UserId},
+    isbn::Isbn,
+    list::{CursorPaginatedList, PaginatedList},
 };
 use serde::{Deserialize, Serialize};
+use shared::error::AppError;
+use utoipa::{IntoParams, ToSchema};
 
-use crate::model::user::{BookOwner, CheckoutUser};
+use crate::model::{
+    list::{default_limit, parse_cursor},
+    user::{BookOwner, CheckoutUser},
+};
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateBookRequest {
     #[garde(length(min = 1))]
     pub title: String,
     #[garde(length(min = 1))]
     pub author: String,
-    #[garde(length(min = 1))]
+    #[garde(custom(validate_isbn))]
     pub isbn: String,
     #[garde(skip)]
     pub description: String,
+    // adds a copy to the existing title instead of answering 409 when the ISBN is taken
+    #[garde(skip)]
+    #[serde(default)]
+    pub add_copy: bool,
 }
 
-impl From<CreateBookRequest> for CreateBook {
-    fn from(req: CreateBookRequest) -> Self {
+impl TryFrom<CreateBookRequest> for CreateBook {
+    type Error = AppError;
+
+    fn try_from(req: CreateBookRequest) -> Result<Self, Self::Error> {
         let CreateBookRequest {
             title,
             author,
             isbn,
             description,
+            add_copy,
         } = req;
-        CreateBook {
+        Ok(CreateBook {
             title,
             author,
-            isbn,
+            isbn: isbn.parse()?,
             description,
-        }
+            add_copy,
+        })
     }
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateBookRequest {
     #[garde(length(min = 1))]
     pub title: String,
     #[garde(length(min = 1))]
     pub author: String,
-    #[garde(length(min = 1))]
+    #[garde(custom(validate_isbn))]
     pub isbn: String,
     #[garde(skip)]
     pub description: String,
 }
 
 #[derive(new)]
-pub struct UpdateBookRequestWithId(BookId, UserId, UpdateBookRequest);
+pub struct UpdateBookRequestWithId(BookId, UserId, bool, UpdateBookRequest);
 
-impl From<UpdateBookRequestWithId> for UpdateBook {
-    fn from(value: UpdateBookRequestWithId) -> Self {
-        let UpdateBookRequestWithId(book_id, requested_user, request) = value;
+impl TryFrom<UpdateBookRequestWithId> for UpdateBook {
+    type Error = AppError;
+
+    fn try_from(value: UpdateBookRequestWithId) -> Result<Self, Self::Error> {
+        let UpdateBookRequestWithId(book_id, requested_user, manages_books, request) = value;
         let UpdateBookRequest {
             title,
             author,
             isbn,
             description,
         } = request;
-        UpdateBook {
+        Ok(UpdateBook {
             book_id,
             title,
             author,
-            isbn,
+            isbn: isbn.parse()?,
             description,
             requested_user,
-        }
+            manages_books,
+        })
     }
 }
 
-#[derive(Debug, Deserialize, Validate)]
+fn validate_isbn(value: &str, _: &()) -> garde::Result {
+    value
+        .parse::<Isbn>()
+        .map(|_| ())
+        .map_err(|e| garde::Error::new(e.to_string()))
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
 pub struct BookListQuery {
     #[garde(range(min = 0))]
     #[serde(default = "default_limit")]
@@ -88,21 +114,72 @@ pub struct BookListQuery {
     #[garde(range(min = 0))]
     #[serde(default)]
     pub offset: i64,
+
+    #[garde(inner(length(min = 1, max = 255)))]
+    pub q: Option<String>,
+
+    #[garde(skip)]
+    pub owner: Option<UserId>,
+
+    #[garde(skip)]
+    pub checked_out: Option<bool>,
+
+    #[garde(skip)]
+    #[serde(default)]
+    pub sort: BookSortQuery,
+
+    // switches the list to keyset pagination; pass it empty for the first page
+    #[garde(skip)]
+    pub cursor: Option<String>,
 }
 
-const DEFAULT_LIMIT: i64 = 20;
-const fn default_limit() -> i64 {
-    DEFAULT_LIMIT
+#[derive(Debug, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum BookSortQuery {
+    #[default]
+    Newest,
+    Oldest,
+    Title,
+    Author,
 }
 
-impl From<BookListQuery> for BookListOptions {
-    fn from(value: BookListQuery) -> Self {
-        let BookListQuery { limit, offset } = value;
-        BookListOptions { limit, offset }
+impl From<BookSortQuery> for BookSort {
+    fn from(value: BookSortQuery) -> Self {
+        match value {
+            BookSortQuery::Newest => BookSort::Newest,
+            BookSortQuery::Oldest => BookSort::Oldest,
+            BookSortQuery::Title => BookSort::Title,
+            BookSortQuery::Author => BookSort::Author,
+        }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl TryFrom<BookListQuery> for BookListOptions {
+    type Error = AppError;
+
+    fn try_from(value: BookListQuery) -> Result<Self, Self::Error> {
+        let BookListQuery {
+            limit,
+            offset,
+            q,
+            owner,
+            checked_out,
+            sort,
+            cursor,
+        } = value;
+        Ok(BookListOptions {
+            limit,
+            offset,
+            keyword: q.map(|q| q.trim().to_string()).filter(|q| !q.is_empty()),
+            owner,
+            checked_out,
+            sort: sort.into(),
+            cursor: parse_cursor(cursor)?,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct BookResponse {
     pub id: BookId,
@@ -111,11 +188,16 @@ pub struct BookResponse {
     pub isbn: String,
     pub description: String,
     pub owner: BookOwner,
-    pub checkout: Option<BookCheckoutResponse>,
+    pub total_copies: usize,
+    pub available_copies: usize,
+    pub availability: String,
+    pub copies: Vec<BookCopyResponse>,
 }
 
 impl From<Book> for BookResponse {
     fn from(book: Book) -> Self {
+        let total_copies = book.total_copies();
+        let available_copies = book.available_copies();
         let Book {
             id,
             title,
@@ -123,7 +205,7 @@ impl From<Book> for BookResponse {
             isbn,
             description,
             owner,
-            checkout,
+            copies,
         } = book;
         BookResponse {
             id,
@@ -132,12 +214,15 @@ impl From<Book> for BookResponse {
             isbn,
             description,
             owner: owner.into(),
-            checkout: checkout.map(BookCheckoutResponse::from),
+            total_copies,
+            available_copies,
+            availability: format!("{available_copies} of {total_copies} copies available"),
+            copies: copies.into_iter().map(BookCopyResponse::from).collect(),
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PaginatedBookResponse {
     pub total: i64,
@@ -163,7 +248,40 @@ impl From<PaginatedList<Book>> for PaginatedBookResponse {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CursorPaginatedBookResponse {
+    pub limit: i64,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+    pub items: Vec<BookResponse>,
+}
+
+impl From<CursorPaginatedList<Book>> for CursorPaginatedBookResponse {
+    fn from(paginated: CursorPaginatedList<Book>) -> Self {
+        let CursorPaginatedList {
+            limit,
+            next_cursor,
+            prev_cursor,
+            items,
+        } = paginated;
+        CursorPaginatedBookResponse {
+            limit,
+            next_cursor: next_cursor.map(|c| c.encode()),
+            prev_cursor: prev_cursor.map(|c| c.encode()),
+            items: items.into_iter().map(BookResponse::from).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum BookListResponse {
+    Cursor(CursorPaginatedBookResponse),
+    Offset(PaginatedBookResponse),
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct BookCheckoutResponse {
     pub id: CheckoutId,
@@ -185,3 +303,158 @@ impl From<Checkout> for BookCheckoutResponse {
         }
     }
 }
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BookCopyResponse {
+    pub id: BookCopyId,
+    pub barcode: String,
+    pub condition: CopyConditionName,
+    pub shelf_location: String,
+    pub checkout: Option<BookCheckoutResponse>,
+}
+
+impl From<BookCopy> for BookCopyResponse {
+    fn from(value: BookCopy) -> Self {
+        let BookCopy {
+            id,
+            barcode,
+            condition,
+            shelf_location,
+            checkout,
+            ..
+        } = value;
+        BookCopyResponse {
+            id,
+            barcode,
+            condition: condition.into(),
+            shelf_location,
+            checkout: checkout.map(BookCheckoutResponse::from),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BookCopiesResponse {
+    pub items: Vec<BookCopyResponse>,
+}
+
+impl From<Vec<BookCopy>> for BookCopiesResponse {
+    fn from(value: Vec<BookCopy>) -> Self {
+        BookCopiesResponse {
+            items: value.into_iter().map(BookCopyResponse::from).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum CopyConditionName {
+    New,
+    #[default]
+    Good,
+    Fair,
+    Poor,
+    Damaged,
+}
+
+impl From<CopyCondition> for CopyConditionName {
+    fn from(value: CopyCondition) -> Self {
+        match value {
+            CopyCondition::New => CopyConditionName::New,
+            CopyCondition::Good => CopyConditionName::Good,
+            CopyCondition::Fair => CopyConditionName::Fair,
+            CopyCondition::Poor => CopyConditionName::Poor,
+            CopyCondition::Damaged => CopyConditionName::Damaged,
+        }
+    }
+}
+
+impl From<CopyConditionName> for CopyCondition {
+    fn from(value: CopyConditionName) -> Self {
+        match value {
+            CopyConditionName::New => CopyCondition::New,
+            CopyConditionName::Good => CopyCondition::Good,
+            CopyConditionName::Fair => CopyCondition::Fair,
+            CopyConditionName::Poor => CopyCondition::Poor,
+            CopyConditionName::Damaged => CopyCondition::Damaged,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateBookCopyRequest {
+    #[garde(inner(length(min = 1, max = 64)))]
+    pub barcode: Option<String>,
+    #[garde(skip)]
+    #[serde(default)]
+    pub condition: CopyConditionName,
+    #[garde(length(max = 255))]
+    #[serde(default)]
+    pub shelf_location: String,
+}
+
+#[derive(new)]
+pub struct CreateBookCopyRequestWithId(BookId, UserId, bool, CreateBookCopyRequest);
+
+impl From<CreateBookCopyRequestWithId> for CreateBookCopy {
+    fn from(value: CreateBookCopyRequestWithId) -> Self {
+        let CreateBookCopyRequestWithId(book_id, requested_user, manages_books, request) = value;
+        let CreateBookCopyRequest {
+            barcode,
+            condition,
+            shelf_location,
+        } = request;
+        CreateBookCopy {
+            book_id,
+            barcode,
+            condition: condition.into(),
+            shelf_location,
+            requested_user,
+            manages_books,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateBookCopyRequest {
+    #[garde(length(min = 1, max = 64))]
+    pub barcode: String,
+    #[garde(skip)]
+    pub condition: CopyConditionName,
+    #[garde(length(max = 255))]
+    pub shelf_location: String,
+}
+
+#[derive(new)]
+pub struct UpdateBookCopyRequestWithIds(BookId, BookCopyId, UserId, bool, UpdateBookCopyRequest);
+
+impl From<UpdateBookCopyRequestWithIds> for UpdateBookCopy {
+    fn from(value: UpdateBookCopyRequestWithIds) -> Self {
+        let UpdateBookCopyRequestWithIds(book_id, copy_id, requested_user, manages_books, request) =
+            value;
+        let UpdateBookCopyRequest {
+            barcode,
+            condition,
+            shelf_location,
+        } = request;
+        UpdateBookCopy {
+            copy_id,
+            book_id,
+            barcode,
+            condition: condition.into(),
+            shelf_location,
+            requested_user,
+            manages_books,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedBookCopyResponse {
+    pub id: BookCopyId,
+}
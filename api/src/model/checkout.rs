@@ -1,11 +1,38 @@
 use chrono::{DateTime, Utc};
+use garde::Validate;
 use kernel::model::{
     checkout::{Checkout, CheckoutBook},
-    id::{BookId, CheckoutId, UserId},
+    id::{BookCopyId, BookId, CheckoutId, UserId},
+    list::CursorPaginatedList,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Serialize)]
+#[derive(Debug, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateCheckoutRequest {
+    // any available copy is lent when omitted
+    pub copy_id: Option<BookCopyId>,
+}
+
+// a checkout staff make at the desk for the given user
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateDeskCheckoutRequest {
+    pub user_id: UserId,
+    // any available copy is lent when omitted
+    pub copy_id: Option<BookCopyId>,
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ForceReturnRequest {
+    // kept in the checkout history
+    #[garde(length(min = 1, max = 500))]
+    pub reason: String,
+}
+
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CheckoutsResponse {
     pub items: Vec<CheckoutResponse>,
@@ -13,45 +40,99 @@ pub struct CheckoutsResponse {
 
 impl From<Vec<Checkout>> for CheckoutsResponse {
     fn from(value: Vec<Checkout>) -> Self {
+        let now = Utc::now();
+        Self {
+            items: value
+                .into_iter()
+                .map(|c| CheckoutResponse::new(c, now))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PaginatedCheckoutsResponse {
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+    pub items: Vec<CheckoutResponse>,
+}
+
+impl From<CursorPaginatedList<Checkout>> for PaginatedCheckoutsResponse {
+    fn from(value: CursorPaginatedList<Checkout>) -> Self {
+        let CursorPaginatedList {
+            next_cursor,
+            prev_cursor,
+            items,
+            ..
+        } = value;
+        let now = Utc::now();
         Self {
-            items: value.into_iter().map(CheckoutResponse::from).collect(),
+            next_cursor: next_cursor.map(|c| c.encode()),
+            prev_cursor: prev_cursor.map(|c| c.encode()),
+            items: items
+                .into_iter()
+                .map(|c| CheckoutResponse::new(c, now))
+                .collect(),
         }
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CheckoutResponse {
     pub id: CheckoutId,
     pub checked_out_by: UserId,
+    /// Who lent the copy; differs from `checkedOutBy` for desk checkouts.
+    pub issued_by: UserId,
     pub checked_out_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+    pub renewal_count: i32,
+    pub is_overdue: bool,
     pub returned_at: Option<DateTime<Utc>>,
+    /// Who returned the copy; differs from `checkedOutBy` for forced returns.
+    pub returned_by: Option<UserId>,
+    /// Why staff forced the return.
+    pub return_reason: Option<String>,
     pub book: CheckoutBookResponse,
 }
 
-impl From<Checkout> for CheckoutResponse {
-    fn from(value: Checkout) -> Self {
+impl CheckoutResponse {
+    pub fn new(value: Checkout, now: DateTime<Utc>) -> Self {
+        let is_overdue = value.is_overdue(now);
         let Checkout {
             id,
             checked_out_by,
+            issued_by,
             checked_out_at,
+            due_at,
+            renewal_count,
             returned_at,
+            returned_by,
+            return_reason,
             book,
         } = value;
         Self {
             id,
             checked_out_by,
+            issued_by,
             checked_out_at,
+            due_at,
+            renewal_count,
+            is_overdue,
             returned_at,
+            returned_by,
+            return_reason,
             book: CheckoutBookResponse::from(book),
         }
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CheckoutBookResponse {
     pub id: BookId,
+    pub copy_id: BookCopyId,
     pub title: String,
     pub author: String,
     pub isbn: String,
@@ -61,12 +142,14 @@ impl From<CheckoutBook> for CheckoutBookResponse {
     fn from(value: CheckoutBook) -> Self {
         let CheckoutBook {
             book_id: id,
+            copy_id,
             title,
             author,
             isbn,
         } = value;
         Self {
             id,
+            copy_id,
             title,
             author,
             isbn,
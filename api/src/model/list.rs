@@ -0,0 +1,41 @@
+use garde::Validate;
+use kernel::model::list::{Cursor, CursorListOptions};
+use serde::Deserialize;
+use shared::error::{AppError, AppResult};
+use utoipa::IntoParams;
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CursorListQuery {
+    #[garde(range(min = 0))]
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+
+    #[garde(skip)]
+    pub cursor: Option<String>,
+}
+
+const DEFAULT_LIMIT: i64 = 20;
+pub(crate) const fn default_limit() -> i64 {
+    DEFAULT_LIMIT
+}
+
+impl TryFrom<CursorListQuery> for CursorListOptions {
+    type Error = AppError;
+
+    fn try_from(value: CursorListQuery) -> Result<Self, Self::Error> {
+        let CursorListQuery { limit, cursor } = value;
+        Ok(CursorListOptions {
+            limit,
+            cursor: parse_cursor(cursor)?,
+        })
+    }
+}
+
+// an empty cursor asks for the first page
+pub fn parse_cursor(cursor: Option<String>) -> AppResult<Option<Cursor>> {
+    cursor
+        .filter(|c| !c.is_empty())
+        .map(|c| c.parse::<Cursor>())
+        .transpose()
+}
@@ -0,0 +1,32 @@
+use chrono::{DateTime, Utc};
+use kernel::model::{id::BookId, live_event::LiveEvent};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+// the data of an event; its kind and id are the SSE `event` and `id` fields
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveEventResponse {
+    pub book_id: BookId,
+    // absent once the book is deleted
+    pub available_copies: Option<i64>,
+    pub total_copies: Option<i64>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl From<LiveEvent> for LiveEventResponse {
+    fn from(value: LiveEvent) -> Self {
+        let LiveEvent {
+            book_id,
+            availability,
+            occurred_at,
+            ..
+        } = value;
+        Self {
+            book_id,
+            available_copies: availability.map(|a| a.available_copies),
+            total_copies: availability.map(|a| a.total_copies),
+            occurred_at,
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use garde::Validate;
+use kernel::model::notification::{NotificationKind, NotificationPreference};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKindName {
+    CheckoutCreated,
+    CheckoutReturned,
+    DueSoon,
+    Overdue,
+    ReservationAvailable,
+}
+
+impl From<NotificationKind> for NotificationKindName {
+    fn from(value: NotificationKind) -> Self {
+        match value {
+            NotificationKind::CheckoutCreated => NotificationKindName::CheckoutCreated,
+            NotificationKind::CheckoutReturned => NotificationKindName::CheckoutReturned,
+            NotificationKind::DueSoon => NotificationKindName::DueSoon,
+            NotificationKind::Overdue => NotificationKindName::Overdue,
+            NotificationKind::ReservationAvailable => NotificationKindName::ReservationAvailable,
+        }
+    }
+}
+impl From<NotificationKindName> for NotificationKind {
+    fn from(value: NotificationKindName) -> Self {
+        match value {
+            NotificationKindName::CheckoutCreated => NotificationKind::CheckoutCreated,
+            NotificationKindName::CheckoutReturned => NotificationKind::CheckoutReturned,
+            NotificationKindName::DueSoon => NotificationKind::DueSoon,
+            NotificationKindName::Overdue => NotificationKind::Overdue,
+            NotificationKindName::ReservationAvailable => NotificationKind::ReservationAvailable,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationPreferenceItem {
+    #[garde(skip)]
+    pub kind: NotificationKindName,
+    #[garde(skip)]
+    pub enabled: bool,
+}
+
+impl From<NotificationPreference> for NotificationPreferenceItem {
+    fn from(value: NotificationPreference) -> Self {
+        Self {
+            kind: value.kind.into(),
+            enabled: value.enabled,
+        }
+    }
+}
+impl From<NotificationPreferenceItem> for NotificationPreference {
+    fn from(value: NotificationPreferenceItem) -> Self {
+        Self {
+            kind: value.kind.into(),
+            enabled: value.enabled,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationPreferencesResponse {
+    pub items: Vec<NotificationPreferenceItem>,
+}
+
+impl From<Vec<NotificationPreference>> for NotificationPreferencesResponse {
+    fn from(value: Vec<NotificationPreference>) -> Self {
+        Self {
+            items: value
+                .into_iter()
+                .map(NotificationPreferenceItem::from)
+                .collect(),
+        }
+    }
+}
+
+// kinds left out keep their setting
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateNotificationPreferencesRequest {
+    #[garde(dive)]
+    pub items: Vec<NotificationPreferenceItem>,
+}
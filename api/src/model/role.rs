@@ -0,0 +1,148 @@
+use garde::Validate;
+use kernel::model::{
+    id::UserId,
+    role::{BorrowingPolicy, Permission, Role, event::CreateRole},
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionName {
+    ManageBooks,
+    ManageCheckouts,
+    ManageRoles,
+    ManageUsers,
+    ManageWebhooks,
+    ViewAuditLog,
+}
+
+impl From<Permission> for PermissionName {
+    fn from(value: Permission) -> Self {
+        match value {
+            Permission::ManageBooks => PermissionName::ManageBooks,
+            Permission::ManageCheckouts => PermissionName::ManageCheckouts,
+            Permission::ManageRoles => PermissionName::ManageRoles,
+            Permission::ManageUsers => PermissionName::ManageUsers,
+            Permission::ManageWebhooks => PermissionName::ManageWebhooks,
+            Permission::ViewAuditLog => PermissionName::ViewAuditLog,
+        }
+    }
+}
+impl From<PermissionName> for Permission {
+    fn from(value: PermissionName) -> Self {
+        match value {
+            PermissionName::ManageBooks => Permission::ManageBooks,
+            PermissionName::ManageCheckouts => Permission::ManageCheckouts,
+            PermissionName::ManageRoles => Permission::ManageRoles,
+            PermissionName::ManageUsers => Permission::ManageUsers,
+            PermissionName::ManageWebhooks => Permission::ManageWebhooks,
+            PermissionName::ViewAuditLog => Permission::ViewAuditLog,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RoleResponse {
+    pub name: String,
+    pub permissions: Vec<PermissionName>,
+}
+
+impl From<Role> for RoleResponse {
+    fn from(value: Role) -> Self {
+        let Role { name, permissions } = value;
+        Self {
+            name,
+            permissions: permissions.into_iter().map(PermissionName::from).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RolesResponse {
+    pub items: Vec<RoleResponse>,
+}
+
+impl From<Vec<Role>> for RolesResponse {
+    fn from(value: Vec<Role>) -> Self {
+        Self {
+            items: value.into_iter().map(RoleResponse::from).collect(),
+        }
+    }
+}
+
+// a role without permissions lets its users do what members do
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateRoleRequest {
+    #[garde(length(min = 1, max = 100))]
+    pub name: String,
+    #[garde(skip)]
+    pub permissions: Vec<PermissionName>,
+}
+
+impl CreateRoleRequest {
+    pub fn into_event(self, requested_user: UserId) -> CreateRole {
+        let CreateRoleRequest { name, permissions } = self;
+        CreateRole::new(
+            name,
+            permissions.into_iter().map(Permission::from).collect(),
+            requested_user,
+        )
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BorrowingPolicyResponse {
+    /// Loans a member may hold at once; absent when unlimited.
+    pub max_loans: Option<i32>,
+    /// Whether members with overdue loans are refused new checkouts.
+    pub block_when_overdue: bool,
+    /// Whether members are refused books they registered themselves.
+    pub block_own_books: bool,
+}
+
+impl From<BorrowingPolicy> for BorrowingPolicyResponse {
+    fn from(value: BorrowingPolicy) -> Self {
+        let BorrowingPolicy {
+            max_loans,
+            block_when_overdue,
+            block_own_books,
+        } = value;
+        Self {
+            max_loans,
+            block_when_overdue,
+            block_own_books,
+        }
+    }
+}
+
+// replaces the whole policy; leaving out `maxLoans` lifts the limit
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateBorrowingPolicyRequest {
+    #[garde(range(min = 0))]
+    pub max_loans: Option<i32>,
+    #[garde(skip)]
+    pub block_when_overdue: bool,
+    #[garde(skip)]
+    pub block_own_books: bool,
+}
+
+impl From<UpdateBorrowingPolicyRequest> for BorrowingPolicy {
+    fn from(value: UpdateBorrowingPolicyRequest) -> Self {
+        let UpdateBorrowingPolicyRequest {
+            max_loans,
+            block_when_overdue,
+            block_own_books,
+        } = value;
+        BorrowingPolicy {
+            max_loans,
+            block_when_overdue,
+            block_own_books,
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use garde::Validate;
+use kernel::model::{
+    auth::{AuthTokens, LoginChallenge},
+    id::UserId,
+    totp::{RecoveryCode, TotpEnrollment},
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpEnrollmentResponse {
+    // base32, for entering into an authenticator app by hand
+    pub secret: String,
+    // `otpauth://` URI to show as a QR code
+    pub provisioning_uri: String,
+}
+
+impl From<TotpEnrollment> for TotpEnrollmentResponse {
+    fn from(value: TotpEnrollment) -> Self {
+        let TotpEnrollment {
+            secret,
+            provisioning_uri,
+        } = value;
+        Self {
+            secret,
+            provisioning_uri,
+        }
+    }
+}
+
+// the password was right; the login is completed at `/auth/login/totp`
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginChallengeResponse {
+    pub challenge_token: String,
+    // seconds until `challenge_token` expires
+    pub expires_in: u64,
+    // set when the user has to enroll first, because TOTP is mandatory for them;
+    // the login is completed with a code of the new secret
+    pub enrollment: Option<TotpEnrollmentResponse>,
+}
+
+impl LoginChallengeResponse {
+    pub fn new(challenge: LoginChallenge, enrollment: Option<TotpEnrollment>) -> Self {
+        Self {
+            challenge_token: challenge.token.0,
+            expires_in: challenge.expires_in,
+            enrollment: enrollment.map(TotpEnrollmentResponse::from),
+        }
+    }
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpLoginRequest {
+    #[garde(length(min = 1))]
+    pub challenge_token: String,
+    // a code of the authenticator app, or a recovery code
+    #[garde(length(min = 1))]
+    pub code: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpLoginResponse {
+    pub user_id: UserId,
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_in: u64,
+    // set when the login completed an enrollment; shown only this once
+    pub recovery_codes: Option<Vec<String>>,
+}
+
+impl TotpLoginResponse {
+    pub fn new(tokens: AuthTokens, recovery_codes: Option<Vec<RecoveryCode>>) -> Self {
+        let AuthTokens {
+            user_id,
+            access_token,
+            refresh_token,
+            expires_in,
+        } = tokens;
+        Self {
+            user_id,
+            access_token: access_token.0,
+            refresh_token: refresh_token.0,
+            expires_in,
+            recovery_codes: recovery_codes.map(|codes| codes.into_iter().map(|c| c.0).collect()),
+        }
+    }
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpCodeRequest {
+    #[garde(length(min = 1))]
+    pub code: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryCodesResponse {
+    // each works once instead of a code of the authenticator app
+    pub items: Vec<String>,
+}
+
+impl From<Vec<RecoveryCode>> for RecoveryCodesResponse {
+    fn from(value: Vec<RecoveryCode>) -> Self {
+        Self {
+            items: value.into_iter().map(|c| c.0).collect(),
+        }
+    }
+}
@@ -2,51 +2,71 @@ use derive_new::new;
 use garde::Validate;
 use kernel::model::{
     id::UserId,
-    role::Role,
+    list::CursorPaginatedList,
     user::{
-        User,
+        User, UserStatus,
         event::{CreateUser, UpdateUserPassword, UpdateUserRole},
     },
 };
 use serde::{Deserialize, Serialize};
-use strum::VariantNames;
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize, VariantNames)]
-#[strum(serialize_all = "kebab-case")]
-pub enum RoleName {
-    Admin,
-    User,
-}
+use crate::model::role::PermissionName;
 
-impl From<Role> for RoleName {
-    fn from(value: Role) -> Self {
-        match value {
-            Role::Admin => RoleName::Admin,
-            Role::User => RoleName::User,
-        }
-    }
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UserStatusName {
+    Active,
+    Unverified,
+    PendingApproval,
+    Rejected,
 }
-impl From<RoleName> for Role {
-    fn from(value: RoleName) -> Self {
+
+impl From<UserStatus> for UserStatusName {
+    fn from(value: UserStatus) -> Self {
         match value {
-            RoleName::Admin => Role::Admin,
-            RoleName::User => Role::User,
+            UserStatus::Active => UserStatusName::Active,
+            UserStatus::Unverified => UserStatusName::Unverified,
+            UserStatus::PendingApproval => UserStatusName::PendingApproval,
+            UserStatus::Rejected => UserStatusName::Rejected,
         }
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UsersResponse {
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
     pub items: Vec<UserResponse>,
 }
-#[derive(Serialize, Deserialize)]
+
+impl From<CursorPaginatedList<User>> for UsersResponse {
+    fn from(value: CursorPaginatedList<User>) -> Self {
+        let CursorPaginatedList {
+            next_cursor,
+            prev_cursor,
+            items,
+            ..
+        } = value;
+        Self {
+            next_cursor: next_cursor.map(|c| c.encode()),
+            prev_cursor: prev_cursor.map(|c| c.encode()),
+            items: items.into_iter().map(UserResponse::from).collect(),
+        }
+    }
+}
+#[derive(Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UserResponse {
     pub id: UserId,
     pub name: String,
     pub email: String,
-    pub role: RoleName,
+    // the name of the role
+    pub role: String,
+    // what the role allows
+    pub permissions: Vec<PermissionName>,
+    pub status: UserStatusName,
 }
 
 impl From<User> for UserResponse {
@@ -56,17 +76,24 @@ impl From<User> for UserResponse {
             name,
             email,
             role,
+            status,
         } = value;
         UserResponse {
             id,
             name,
             email,
-            role: role.into(),
+            role: role.name,
+            permissions: role
+                .permissions
+                .into_iter()
+                .map(PermissionName::from)
+                .collect(),
+            status: status.into(),
         }
     }
 }
 
-#[derive(Validate, Deserialize)]
+#[derive(Validate, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateUserPasswordRequest {
     #[garde(length(min = 1))]
@@ -96,7 +123,7 @@ impl From<UpdateUserPasswordRequestWithUserId> for UpdateUserPassword {
     }
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Deserialize, Validate, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreaterUserRequest {
     #[garde(length(min = 1))]
@@ -115,37 +142,68 @@ impl From<CreaterUserRequest> for CreateUser {
             password,
         } = value;
         CreateUser {
+            user_id: UserId::new(),
             name,
             email,
             password,
+            status: UserStatus::Active,
+            created_by: None,
         }
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingUsersResponse {
+    pub items: Vec<UserResponse>,
+}
+
+impl From<Vec<User>> for PendingUsersResponse {
+    fn from(value: Vec<User>) -> Self {
+        Self {
+            items: value.into_iter().map(UserResponse::from).collect(),
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UserApprovalRequest {
+    pub approved: bool,
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateUserRoleRequest {
-    role: RoleName,
+    // the name of the role
+    #[garde(length(min = 1))]
+    role: String,
 }
 
 #[derive(new)]
 pub struct UpdateUserRoleRequestWithUserId {
     pub user_id: UserId,
     pub request: UpdateUserRoleRequest,
+    pub requested_user: UserId,
 }
 
 impl From<UpdateUserRoleRequestWithUserId> for UpdateUserRole {
     fn from(value: UpdateUserRoleRequestWithUserId) -> Self {
-        let UpdateUserRoleRequestWithUserId { user_id, request } = value;
+        let UpdateUserRoleRequestWithUserId {
+            user_id,
+            request,
+            requested_user,
+        } = value;
         let UpdateUserRoleRequest { role } = request;
         UpdateUserRole {
             user_id,
-            role: role.into(),
+            role,
+            requested_user,
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct BookOwner {
     pub id: UserId,
@@ -159,7 +217,7 @@ impl From<kernel::model::user::BookOwner> for BookOwner {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CheckoutUser {
     pub id: UserId,
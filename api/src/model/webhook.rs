@@ -0,0 +1,237 @@
+use axum::http::Uri;
+use chrono::{DateTime, Utc};
+use garde::Validate;
+use kernel::model::{
+    id::{UserId, WebhookDeliveryId, WebhookId},
+    list::CursorPaginatedList,
+    webhook::{
+        CreatedWebhook, DeliveryStatus, Webhook, WebhookDelivery, WebhookEventType,
+        event::CreateWebhook,
+    },
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum WebhookEventTypeName {
+    #[serde(rename = "book.created")]
+    BookCreated,
+    #[serde(rename = "book.updated")]
+    BookUpdated,
+    #[serde(rename = "book.deleted")]
+    BookDeleted,
+    #[serde(rename = "checkout.created")]
+    CheckoutCreated,
+    #[serde(rename = "checkout.returned")]
+    CheckoutReturned,
+}
+
+impl From<WebhookEventType> for WebhookEventTypeName {
+    fn from(value: WebhookEventType) -> Self {
+        match value {
+            WebhookEventType::BookCreated => WebhookEventTypeName::BookCreated,
+            WebhookEventType::BookUpdated => WebhookEventTypeName::BookUpdated,
+            WebhookEventType::BookDeleted => WebhookEventTypeName::BookDeleted,
+            WebhookEventType::CheckoutCreated => WebhookEventTypeName::CheckoutCreated,
+            WebhookEventType::CheckoutReturned => WebhookEventTypeName::CheckoutReturned,
+        }
+    }
+}
+impl From<WebhookEventTypeName> for WebhookEventType {
+    fn from(value: WebhookEventTypeName) -> Self {
+        match value {
+            WebhookEventTypeName::BookCreated => WebhookEventType::BookCreated,
+            WebhookEventTypeName::BookUpdated => WebhookEventType::BookUpdated,
+            WebhookEventTypeName::BookDeleted => WebhookEventType::BookDeleted,
+            WebhookEventTypeName::CheckoutCreated => WebhookEventType::CheckoutCreated,
+            WebhookEventTypeName::CheckoutReturned => WebhookEventType::CheckoutReturned,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatusName {
+    Pending,
+    Delivered,
+    DeadLetter,
+}
+
+impl From<DeliveryStatus> for DeliveryStatusName {
+    fn from(value: DeliveryStatus) -> Self {
+        match value {
+            DeliveryStatus::Pending => DeliveryStatusName::Pending,
+            DeliveryStatus::Delivered => DeliveryStatusName::Delivered,
+            DeliveryStatus::DeadLetter => DeliveryStatusName::DeadLetter,
+        }
+    }
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateWebhookRequest {
+    /// An `http` or `https` URL the events are posted to.
+    #[garde(length(max = 2048), custom(validate_webhook_url))]
+    pub url: String,
+    #[garde(length(min = 1))]
+    pub event_types: Vec<WebhookEventTypeName>,
+}
+
+impl CreateWebhookRequest {
+    pub fn into_event(self, created_by: UserId) -> CreateWebhook {
+        let mut event_types = Vec::new();
+        for event_type in self.event_types.into_iter().map(WebhookEventType::from) {
+            if !event_types.contains(&event_type) {
+                event_types.push(event_type);
+            }
+        }
+        CreateWebhook::new(self.url, event_types, created_by)
+    }
+}
+
+fn validate_webhook_url(value: &str, _: &()) -> garde::Result {
+    let uri = value
+        .parse::<Uri>()
+        .map_err(|e| garde::Error::new(e.to_string()))?;
+    match (uri.scheme_str(), uri.host()) {
+        (Some("http" | "https"), Some(_)) => Ok(()),
+        _ => Err(garde::Error::new("not an http or https URL")),
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookResponse {
+    pub id: WebhookId,
+    pub url: String,
+    pub event_types: Vec<WebhookEventTypeName>,
+    pub created_by: UserId,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<Webhook> for WebhookResponse {
+    fn from(value: Webhook) -> Self {
+        let Webhook {
+            id,
+            url,
+            event_types,
+            created_by,
+            created_at,
+        } = value;
+        Self {
+            id,
+            url,
+            event_types: event_types.into_iter().map(Into::into).collect(),
+            created_by,
+            created_at,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhooksResponse {
+    pub items: Vec<WebhookResponse>,
+}
+
+impl From<Vec<Webhook>> for WebhooksResponse {
+    fn from(value: Vec<Webhook>) -> Self {
+        Self {
+            items: value.into_iter().map(WebhookResponse::from).collect(),
+        }
+    }
+}
+
+// the secret is shown only this once
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedWebhookResponse {
+    pub webhook: WebhookResponse,
+    /// Key of the HMAC-SHA256 in `X-Webhook-Signature`, which signs
+    /// `X-Webhook-Timestamp`, a dot and the body of each delivery.
+    pub secret: String,
+}
+
+impl From<CreatedWebhook> for CreatedWebhookResponse {
+    fn from(value: CreatedWebhook) -> Self {
+        Self {
+            webhook: value.webhook.into(),
+            secret: value.secret,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDeliveryResponse {
+    pub id: WebhookDeliveryId,
+    pub event_type: WebhookEventTypeName,
+    /// The JSON body as posted and signed.
+    pub payload: String,
+    pub status: DeliveryStatusName,
+    /// Failed attempts so far.
+    pub attempts: i32,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    /// HTTP status of the last response, if the endpoint answered.
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+impl From<WebhookDelivery> for WebhookDeliveryResponse {
+    fn from(value: WebhookDelivery) -> Self {
+        let WebhookDelivery {
+            id,
+            webhook_id: _,
+            event_type,
+            payload,
+            status,
+            attempts,
+            next_attempt_at,
+            last_attempt_at,
+            response_status,
+            last_error,
+            created_at,
+            delivered_at,
+        } = value;
+        Self {
+            id,
+            event_type: event_type.into(),
+            payload,
+            status: status.into(),
+            attempts,
+            next_attempt_at,
+            last_attempt_at,
+            response_status,
+            last_error,
+            created_at,
+            delivered_at,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PaginatedWebhookDeliveriesResponse {
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+    pub items: Vec<WebhookDeliveryResponse>,
+}
+
+impl From<CursorPaginatedList<WebhookDelivery>> for PaginatedWebhookDeliveriesResponse {
+    fn from(value: CursorPaginatedList<WebhookDelivery>) -> Self {
+        let CursorPaginatedList {
+            next_cursor,
+            prev_cursor,
+            items,
+            ..
+        } = value;
+        Self {
+            next_cursor: next_cursor.map(|c| c.encode()),
+            prev_cursor: prev_cursor.map(|c| c.encode()),
+            items: items.into_iter().map(Into::into).collect(),
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use kernel::model::id::{
+    ApiKeyId, AuditEntryId, BookCopyId, BookId, CheckoutId, ReservationId, SessionId, UserId,
+    WebhookDeliveryId, WebhookId,
+};
+use shared::error::{ErrorDetail, ErrorResponse};
+use utoipa::{
+    Modify, OpenApi,
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+};
+
+use crate::{handler, model};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Rusty Book Manager API"),
+    paths(
+        handler::health::health_check,
+        handler::health::health_check_db,
+        handler::auth::login,
+        handler::auth::login_totp,
+        handler::auth::logout,
+        handler::auth::refresh,
+        handler::auth::request_password_reset,
+        handler::auth::confirm_password_reset,
+        handler::auth::register,
+        handler::auth::verify_email,
+        handler::auth::resend_email_verification,
+        handler::book::register_book,
+        handler::book::show_book_list,
+        handler::book::show_book,
+        handler::book::update_book,
+        handler::book::delete_book,
+        handler::book::show_book_copies,
+        handler::book::add_book_copy,
+        handler::book::update_book_copy,
+        handler::book::delete_book_copy,
+        handler::checkout::checkout_book,
+        handler::checkout::checkout_book_at_desk,
+        handler::checkout::return_book,
+        handler::checkout::force_return_book,
+        handler::checkout::renew_book,
+        handler::checkout::show_checked_out_list,
+        handler::checkout::show_overdue_list,
+        handler::checkout::checkout_history,
+        handler::reservation::reserve_book,
+        handler::reservation::show_reservation_list,
+        handler::reservation::cancel_reservation,
+        handler::user::register_user,
+        handler::user::list_users,
+        handler::user::delete_user,
+        handler::user::change_role,
+        handler::user::get_current_user,
+        handler::user::change_password,
+        handler::user::get_checkouts,
+        handler::user::get_reservations,
+        handler::user::get_sessions,
+        handler::user::delete_session,
+        handler::user::delete_sessions,
+        handler::user::delete_user_sessions,
+        handler::user::unlock_user,
+        handler::user::list_pending_users,
+        handler::user::approve_user,
+        handler::totp::begin_totp_enrollment,
+        handler::totp::confirm_totp_enrollment,
+        handler::totp::regenerate_recovery_codes,
+        handler::totp::disable_totp,
+        handler::api_key::create_api_key,
+        handler::api_key::list_api_keys,
+        handler::api_key::rotate_api_key,
+        handler::api_key::revoke_api_key,
+        handler::notification::show_notification_preferences,
+        handler::notification::update_notification_preferences,
+        handler::role::list_roles,
+        handler::role::create_role,
+        handler::role::show_borrowing_policy,
+        handler::role::update_borrowing_policy,
+        handler::webhook::create_webhook,
+        handler::webhook::list_webhooks,
+        handler::webhook::delete_webhook,
+        handler::webhook::list_webhook_deliveries,
+        handler::webhook::retry_webhook_delivery,
+        handler::event::stream_events,
+        handler::audit::list_audit_log,
+    ),
+    components(schemas(
+        ApiKeyId,
+        AuditEntryId,
+        BookId,
+        BookCopyId,
+        CheckoutId,
+        ReservationId,
+        SessionId,
+        UserId,
+        WebhookId,
+        WebhookDeliveryId,
+        ErrorResponse,
+        ErrorDetail,
+        model::auth::LoginRequest,
+        model::auth::AccessTokenResponse,
+        model::auth::RefreshTokenRequest,
+        model::auth::PasswordResetRequest,
+        model::auth::ConfirmPasswordResetRequest,
+        model::auth::VerifyEmailRequest,
+        model::auth::ResendEmailVerificationRequest,
+        model::auth::SessionsResponse,
+        model::auth::SessionResponse,
+        model::book::CreateBookRequest,
+        model::book::UpdateBookRequest,
+        model::book::BookSortQuery,
+        model::book::BookResponse,
+        model::book::PaginatedBookResponse,
+        model::book::CursorPaginatedBookResponse,
+        model::book::BookListResponse,
+        model::book::BookCheckoutResponse,
+        model::book::BookCopyResponse,
+        model::book::BookCopiesResponse,
+        model::book::CopyConditionName,
+        model::book::CreateBookCopyRequest,
+        model::book::UpdateBookCopyRequest,
+        model::book::CreatedBookCopyResponse,
+        model::checkout::CreateCheckoutRequest,
+        model::checkout::CreateDeskCheckoutRequest,
+        model::checkout::ForceReturnRequest,
+        model::checkout::CheckoutsResponse,
+        model::checkout::PaginatedCheckoutsResponse,
+        model::checkout::CheckoutResponse,
+        model::checkout::CheckoutBookResponse,
+        model::totp::TotpEnrollmentResponse,
+        model::totp::LoginChallengeResponse,
+        model::totp::TotpLoginRequest,
+        model::totp::TotpLoginResponse,
+        model::totp::TotpCodeRequest,
+        model::totp::RecoveryCodesResponse,
+        model::api_key::ApiKeyScopeName,
+        model::api_key::CreateApiKeyRequest,
+        model::api_key::ApiKeyResponse,
+        model::api_key::ApiKeysResponse,
+        model::api_key::IssuedApiKeyResponse,
+        model::notification::NotificationKindName,
+        model::notification::NotificationPreferenceItem,
+        model::notification::NotificationPreferencesResponse,
+        model::notification::UpdateNotificationPreferencesRequest,
+        model::reservation::ReservationsResponse,
+        model::reservation::ReservationResponse,
+        model::role::PermissionName,
+        model::role::RoleResponse,
+        model::role::RolesResponse,
+        model::role::CreateRoleRequest,
+        model::role::BorrowingPolicyResponse,
+        model::role::UpdateBorrowingPolicyRequest,
+        model::user::UserStatusName,
+        model::user::UsersResponse,
+        model::user::UserResponse,
+        model::user::UpdateUserPasswordRequest,
+        model::user::CreaterUserRequest,
+        model::user::UpdateUserRoleRequest,
+        model::user::PendingUsersResponse,
+        model::user::UserApprovalRequest,
+        model::user::BookOwner,
+        model::user::CheckoutUser,
+        model::webhook::WebhookEventTypeName,
+        model::webhook::DeliveryStatusName,
+        model::webhook::CreateWebhookRequest,
+        model::webhook::WebhookResponse,
+        model::webhook::WebhooksResponse,
+        model::webhook::CreatedWebhookResponse,
+        model::webhook::WebhookDeliveryResponse,
+        model::webhook::PaginatedWebhookDeliveriesResponse,
+        model::audit::AuditActionName,
+        model::audit::AuditTargetType,
+        model::audit::AuditEntryResponse,
+        model::audit::PaginatedAuditEntriesResponse,
+        model::live_event::LiveEventResponse,
+    )),
+    modifiers(&BearerAuth),
+    tags(
+        (name = "health", description = "Liveness checks"),
+        (name = "auth", description = "Login, logout and sign-up"),
+        (name = "books", description = "Book titles and their physical copies"),
+        (name = "checkouts", description = "Lending books"),
+        (name = "reservations", description = "Waiting for checked-out books"),
+        (name = "users", description = "User accounts"),
+        (name = "webhooks", description = "Telling other systems about changes to books and checkouts"),
+        (name = "events", description = "Watching changes to books and checkouts as they happen"),
+        (name = "audit", description = "Who changed books, users, roles and checkouts, and when"),
+    )
+)]
+pub struct ApiDoc;
+
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "bearer_auth",
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+            );
+        }
+    }
+}
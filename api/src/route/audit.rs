@@ -0,0 +1,8 @@
+use axum::{Router, routing::get};
+use registry::AppRegistry;
+
+use crate::handler::audit::list_audit_log;
+
+pub fn build_audit_routers() -> Router<AppRegistry> {
+    Router::new().route("/audit-log", get(list_audit_log))
+}
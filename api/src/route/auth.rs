@@ -1,11 +1,21 @@
 use axum::{Router, routing::post};
 use registry::AppRegistry;
 
-use crate::handler::auth::{login, logout};
+use crate::handler::auth::{
+    confirm_password_reset, login, login_totp, logout, refresh, register, request_password_reset,
+    resend_email_verification, verify_email,
+};
 
 pub fn routes() -> Router<AppRegistry> {
     let auth_router = Router::new()
         .route("/login", post(login))
-        .route("/logout", post(logout));
+        .route("/login/totp", post(login_totp))
+        .route("/logout", post(logout))
+        .route("/refresh", post(refresh))
+        .route("/password-reset/request", post(request_password_reset))
+        .route("/password-reset/confirm", post(confirm_password_reset))
+        .route("/register", post(register))
+        .route("/register/verify", post(verify_email))
+        .route("/register/resend", post(resend_email_verification));
     Router::new().nest("/auth", auth_router)
 }
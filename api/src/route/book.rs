@@ -5,8 +5,15 @@ use axum::{
 use registry::AppRegistry;
 
 use crate::handler::{
-    book::{delete_book, register_book, show_book, show_book_list, update_book},
-    checkout::{checkout_book, checkout_history, return_book, show_checked_out_list},
+    book::{
+        add_book_copy, delete_book, delete_book_copy, register_book, show_book, show_book_copies,
+        show_book_list, update_book, update_book_copy,
+    },
+    checkout::{
+        checkout_book, checkout_book_at_desk, checkout_history, force_return_book, renew_book,
+        return_book, show_checked_out_list, show_overdue_list,
+    },
+    reservation::{cancel_reservation, reserve_book, show_reservation_list},
 };
 
 pub fn build_book_routers() -> Router<AppRegistry> {
@@ -17,14 +24,47 @@ pub fn build_book_routers() -> Router<AppRegistry> {
         .route("/:book_id", put(update_book))
         .route("/:book_id", delete(delete_book));
 
+    let copy_router = Router::new()
+        .route(
+            "/:book_id/copies",
+            get(show_book_copies).post(add_book_copy),
+        )
+        .route(
+            "/:book_id/copies/:copy_id",
+            put(update_book_copy).delete(delete_book_copy),
+        );
+
     let checkout_router = Router::new()
         .route("/checkouts", get(show_checked_out_list))
+        .route("/checkouts/overdue", get(show_overdue_list))
         .route("/:book_id/checkouts", post(checkout_book))
+        .route("/:book_id/checkouts/desk", post(checkout_book_at_desk))
         .route(
             "/:book_id/checkouts/:checkout_id/returned",
             put(return_book),
         )
+        .route(
+            "/:book_id/checkouts/:checkout_id/force-returned",
+            put(force_return_book),
+        )
+        .route("/:book_id/checkouts/:checkout_id/renew", put(renew_book))
         .route("/:book_id/checkout-history", get(checkout_history));
 
-    Router::new().nest("/books", books_routers.merge(checkout_router))
+    let reservation_router = Router::new()
+        .route(
+            "/:book_id/reservations",
+            post(reserve_book).get(show_reservation_list),
+        )
+        .route(
+            "/:book_id/reservations/:reservation_id",
+            delete(cancel_reservation),
+        );
+
+    Router::new().nest(
+        "/books",
+        books_routers
+            .merge(copy_router)
+            .merge(checkout_router)
+            .merge(reservation_router),
+    )
 }
@@ -0,0 +1,8 @@
+use axum::{Router, routing::get};
+use registry::AppRegistry;
+
+use crate::handler::event::stream_events;
+
+pub fn build_event_routers() -> Router<AppRegistry> {
+    Router::new().route("/events", get(stream_events))
+}
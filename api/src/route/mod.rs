@@ -1,5 +1,9 @@
+pub mod audit;
 pub mod auth;
 pub mod book;
+pub mod event;
 pub mod health;
+pub mod role;
 pub mod user;
 pub mod v1;
+pub mod webhook;
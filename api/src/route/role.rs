@@ -0,0 +1,15 @@
+use axum::{Router, routing::get};
+use registry::AppRegistry;
+
+use crate::handler::role::{
+    create_role, list_roles, show_borrowing_policy, update_borrowing_policy,
+};
+
+pub fn build_role_routers() -> Router<AppRegistry> {
+    Router::new()
+        .route("/roles", get(list_roles).post(create_role))
+        .route(
+            "/roles/:role_name/borrowing-policy",
+            get(show_borrowing_policy).put(update_borrowing_policy),
+        )
+}
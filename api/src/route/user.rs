@@ -1,12 +1,20 @@
 use axum::{
     Router,
-    routing::{delete, get, put},
+    routing::{delete, get, post, put},
 };
 use registry::AppRegistry;
 
-use crate::handler::user::{
-    change_password, change_role, delete_user, get_checkouts, get_current_user, list_users,
-    register_user,
+use crate::handler::{
+    api_key::{create_api_key, list_api_keys, revoke_api_key, rotate_api_key},
+    notification::{show_notification_preferences, update_notification_preferences},
+    totp::{
+        begin_totp_enrollment, confirm_totp_enrollment, disable_totp, regenerate_recovery_codes,
+    },
+    user::{
+        approve_user, change_password, change_role, delete_session, delete_sessions, delete_user,
+        delete_user_sessions, get_checkouts, get_current_user, get_reservations, get_sessions,
+        list_pending_users, list_users, register_user, unlock_user,
+    },
 };
 
 pub fn build_user_router() -> Router<AppRegistry> {
@@ -14,7 +22,39 @@ pub fn build_user_router() -> Router<AppRegistry> {
         .route("/users/me", get(get_current_user))
         .route("/users/me/password", put(change_password))
         .route("/users/me/checkouts", get(get_checkouts))
+        .route("/users/me/reservations", get(get_reservations))
+        .route(
+            "/users/me/sessions",
+            get(get_sessions).delete(delete_sessions),
+        )
+        .route("/users/me/sessions/:session_id", delete(delete_session))
+        .route(
+            "/users/me/totp",
+            post(begin_totp_enrollment).delete(disable_totp),
+        )
+        .route("/users/me/totp/confirm", post(confirm_totp_enrollment))
+        .route(
+            "/users/me/totp/recovery-codes",
+            post(regenerate_recovery_codes),
+        )
+        .route(
+            "/users/me/api-keys",
+            get(list_api_keys).post(create_api_key),
+        )
+        .route("/users/me/api-keys/:api_key_id", delete(revoke_api_key))
+        .route(
+            "/users/me/api-keys/:api_key_id/rotate",
+            post(rotate_api_key),
+        )
+        .route(
+            "/users/me/notification-preferences",
+            get(show_notification_preferences).put(update_notification_preferences),
+        )
         .route("/users", get(list_users).post(register_user))
+        .route("/users/pending", get(list_pending_users))
         .route("/users/:user_id", delete(delete_user))
         .route("/users/:user_id/role", put(change_role))
+        .route("/users/:user_id/sessions", delete(delete_user_sessions))
+        .route("/users/:user_id/lockout", delete(unlock_user))
+        .route("/users/:user_id/approval", put(approve_user))
 }
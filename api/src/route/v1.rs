@@ -2,14 +2,20 @@ use axum::Router;
 use registry::AppRegistry;
 
 use crate::route::{
-    book::build_book_routers, health::build_health_check_routers, user::build_user_router,
+    audit::build_audit_routers, book::build_book_routers, event::build_event_routers,
+    health::build_health_check_routers, role::build_role_routers, user::build_user_router,
+    webhook::build_webhook_routers,
 };
 
 pub fn routes() -> Router<AppRegistry> {
     let router = Router::new()
         .merge(build_health_check_routers())
         .merge(build_book_routers())
-        .merge(build_user_router());
+        .merge(build_user_router())
+        .merge(build_role_routers())
+        .merge(build_webhook_routers())
+        .merge(build_event_routers())
+        .merge(build_audit_routers());
 
     Router::new().nest("/api/v1", router)
 }
@@ -0,0 +1,23 @@
+use axum::{
+    Router,
+    routing::{delete, get, post},
+};
+use registry::AppRegistry;
+
+use crate::handler::webhook::{
+    create_webhook, delete_webhook, list_webhook_deliveries, list_webhooks, retry_webhook_delivery,
+};
+
+pub fn build_webhook_routers() -> Router<AppRegistry> {
+    Router::new()
+        .route("/webhooks", get(list_webhooks).post(create_webhook))
+        .route("/webhooks/:webhook_id", delete(delete_webhook))
+        .route(
+            "/webhooks/:webhook_id/deliveries",
+            get(list_webhook_deliveries),
+        )
+        .route(
+            "/webhooks/:webhook_id/deliveries/:delivery_id/retry",
+            post(retry_webhook_delivery),
+        )
+}
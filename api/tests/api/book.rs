@@ -2,7 +2,13 @@ use api::model::book::PaginatedBookResponse;
 use axum::{body::Body, http::Request};
 use kernel::model::user::BookOwner;
 use kernel::{
-    model::{book::Book, id::BookId, list::PaginatedList},
+    live_event::MockLiveEventBroker,
+    model::{
+        book::{Book, BookSort, CopyCondition},
+        id::{BookCopyId, BookId},
+        list::PaginatedList,
+        live_event::LiveEventKind,
+    },
     repository::book::MockBookRepository,
 };
 use rstest::rstest;
@@ -44,7 +50,7 @@ async fn show_book_list_with_query_200(
                     id: kernel::model::id::UserId::new(),
                     name: "Alice".to_string(),
                 },
-                checkout: None,
+                copies: vec![],
             }];
             Ok(PaginatedList {
                 total: 1,
@@ -68,3 +74,104 @@ async fn show_book_list_with_query_200(
 
     Ok(())
 }
+
+#[rstest]
+#[case("/books?q=rust", Some("rust"), None, BookSort::Newest)]
+#[case("/books?q=%20%20", None, None, BookSort::Newest)]
+#[case("/books?checkedOut=true&sort=title", None, Some(true), BookSort::Title)]
+#[case(
+    "/books?q=klabnik&checkedOut=false&sort=oldest",
+    Some("klabnik"),
+    Some(false),
+    BookSort::Oldest
+)]
+#[tokio::test]
+async fn show_book_list_with_filter_200(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] path: &str,
+    #[case] expected_keyword: Option<&'static str>,
+    #[case] expected_checked_out: Option<bool>,
+    #[case] expected_sort: BookSort,
+) -> anyhow::Result<()> {
+    use crate::helper::{TestRequestExt, make_router};
+
+    fixture.expect_book_repository().returning(move || {
+        let mut mock = MockBookRepository::new();
+        mock.expect_find_all()
+            .withf(move |opt| {
+                opt.keyword.as_deref() == expected_keyword
+                    && opt.checked_out == expected_checked_out
+                    && opt.sort == expected_sort
+            })
+            .returning(|opt| {
+                Ok(PaginatedList {
+                    total: 0,
+                    limit: opt.limit,
+                    offset: opt.offset,
+                    items: vec![],
+                })
+            });
+        Arc::new(mock)
+    });
+
+    let app = make_router(fixture);
+
+    let req = Request::get(v1(path)).bearer().body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::OK);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(
+    r#"{"condition": "fair", "shelfLocation": "A-3"}"#,
+    None,
+    CopyCondition::Fair
+)]
+#[case(r#"{"barcode": "LIB-0002"}"#, Some("LIB-0002"), CopyCondition::Good)]
+#[tokio::test]
+async fn add_book_copy_201(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] body: &'static str,
+    #[case] expected_barcode: Option<&'static str>,
+    #[case] expected_condition: CopyCondition,
+) -> anyhow::Result<()> {
+    use crate::helper::{TestRequestExt, make_router};
+
+    let book_id = BookId::new();
+
+    fixture.expect_book_repository().returning(move || {
+        let mut mock = MockBookRepository::new();
+        mock.expect_create_copy()
+            .withf(move |event| {
+                event.book_id == book_id
+                    && event.barcode.as_deref() == expected_barcode
+                    && event.condition == expected_condition
+            })
+            .returning(|_| Ok(BookCopyId::new()));
+        mock.expect_find_by_id().returning(|_| Ok(None));
+        Arc::new(mock)
+    });
+    fixture.expect_live_event_broker().returning(move || {
+        let mut mock = MockLiveEventBroker::new();
+        mock.expect_publish()
+            .withf(move |event| {
+                event.book_id == book_id && event.kind == LiveEventKind::BookUpdated
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+        Arc::new(mock)
+    });
+
+    let app = make_router(fixture);
+
+    let req = Request::post(v1(&format!("/books/{book_id}/copies")))
+        .bearer()
+        .application_json()
+        .body(Body::from(body))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::CREATED);
+
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use axum::{body::Body, http::Request};
+use kernel::repository::book::MockBookRepository;
+use rstest::rstest;
+use shared::error::{AppError, ErrorResponse};
+use tower::util::ServiceExt;
+
+use crate::{
+    deserialize_json,
+    helper::{TestRequestExt, fixture, make_router, v1},
+};
+
+#[rstest]
+#[tokio::test]
+async fn validation_error_has_field_details(
+    fixture: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let app = make_router(fixture);
+
+    let body = r#"{"title": "", "author": "Author", "isbn": "9781593278282", "description": ""}"#;
+    let req = Request::post(v1("/books"))
+        .bearer()
+        .application_json()
+        .header("x-request-id", "test-request")
+        .body(Body::from(body))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::BAD_REQUEST);
+    assert_eq!(resp.headers()["x-request-id"], "test-request");
+
+    let result = deserialize_json!(resp, ErrorResponse);
+    assert_eq!(result.code, "validation_error");
+    assert_eq!(result.request_id.as_deref(), Some("test-request"));
+    let mut fields = result
+        .details
+        .iter()
+        .map(|d| d.field.as_str())
+        .collect::<Vec<_>>();
+    fields.sort();
+    assert_eq!(fields, ["isbn", "title"]);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(AppError::Conflict("Book already exists".into()), 409, "conflict", "Book already exists")]
+#[case(
+    AppError::NoRowsAffectedError("books table is locked".into()),
+    500,
+    "no_rows_affected",
+    "Internal server error"
+)]
+#[tokio::test]
+async fn error_envelope(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] error: AppError,
+    #[case] expected_status: u16,
+    #[case] expected_code: &str,
+    #[case] expected_message: &str,
+) -> anyhow::Result<()> {
+    fixture.expect_book_repository().return_once(move || {
+        let mut mock = MockBookRepository::new();
+        mock.expect_create().return_once(move |_, _| Err(error));
+        Arc::new(mock)
+    });
+    let app = make_router(fixture);
+
+    let body =
+        r#"{"title": "Title", "author": "Author", "isbn": "9781593278281", "description": ""}"#;
+    let req = Request::post(v1("/books"))
+        .bearer()
+        .application_json()
+        .body(Body::from(body))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status().as_u16(), expected_status);
+    let request_id = resp.headers()["x-request-id"].to_str()?.to_string();
+
+    let result = deserialize_json!(resp, ErrorResponse);
+    assert_eq!(result.code, expected_code);
+    assert_eq!(result.message, expected_message);
+    assert!(result.details.is_empty());
+    assert_eq!(result.request_id, Some(request_id));
+
+    Ok(())
+}
+
+#[rstest]
+#[case("POST", "/books", Some(r#"{"title": "Title""#))]
+#[case("POST", "/books", Some(r#"{"title": 1}"#))]
+#[case("GET", "/books/not-a-uuid", None)]
+#[case(
+    "POST",
+    "/books/5f6ec0b9-4f0f-4c1c-9d55-6c3b1b1d0a6e/checkouts",
+    Some(r#"{"copyId": "not-a-uuid"}"#)
+)]
+#[case("GET", "/books?limit=many", None)]
+#[tokio::test]
+async fn unreadable_requests_use_the_envelope(
+    fixture: registry::MockAppRegistryExt,
+    #[case] method: &str,
+    #[case] uri: &str,
+    #[case] body: Option<&'static str>,
+) -> anyhow::Result<()> {
+    let app = make_router(fixture);
+
+    let req = Request::builder()
+        .method(method)
+        .uri(v1(uri))
+        .bearer()
+        .application_json()
+        .header("x-request-id", "test-request")
+        .body(body.map(Body::from).unwrap_or_else(Body::empty))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::BAD_REQUEST);
+
+    let result = deserialize_json!(resp, ErrorResponse);
+    assert_eq!(result.code, "invalid_request");
+    assert_eq!(result.request_id.as_deref(), Some("test-request"));
+
+    Ok(())
+}
@@ -1,9 +1,17 @@
 use std::sync::Arc;
 
-use api::route::{auth, v1};
+use api::{
+    middleware::request_id,
+    route::{auth, v1},
+};
 use axum::{Router, http::request::Builder};
 use kernel::{
-    model::{auth::AccessToken, id::UserId, role::Role, user::User},
+    model::{
+        auth::{AccessToken, AuthTokens, RefreshToken, TokenOwner},
+        id::{SessionId, UserId},
+        role::{BuiltinRole, Role},
+        user::{User, UserStatus},
+    },
     repository::{auth::MockAuthRepository, user::MockUserRepository},
 };
 use registry::MockAppRegistryExt;
@@ -17,6 +25,7 @@ pub fn make_router(registory: MockAppRegistryExt) -> Router {
     Router::new()
         .merge(v1::routes())
         .merge(auth::routes())
+        .layer(axum::middleware::from_fn(request_id))
         .with_state(Arc::new(registory))
 }
 
@@ -30,14 +39,26 @@ pub fn fixture_auth(mut fixture_registory: MockAppRegistryExt) -> MockAppRegistr
     fixture_registory.expect_auth_repository().returning(|| {
         let mut mock_auth_repository = MockAuthRepository::new();
         mock_auth_repository
-            .expect_fetch_user_id_from_token()
-            .returning(|_| Ok(Some(UserId::new())));
+            .expect_fetch_token_owner()
+            .returning(|_| {
+                Ok(Some(TokenOwner {
+                    user_id: UserId::new(),
+                    session_id: Some(SessionId::new()),
+                }))
+            });
         mock_auth_repository
             .expect_verify_user()
             .returning(|_, _| Ok(UserId::new()));
         mock_auth_repository
             .expect_create_token()
-            .returning(|_| Ok(AccessToken("dummy".into())));
+            .returning(|event| {
+                Ok(AuthTokens {
+                    user_id: event.user_id,
+                    access_token: AccessToken("dummy".into()),
+                    refresh_token: RefreshToken("dummy-refresh".into()),
+                    expires_in: 900,
+                })
+            });
         Arc::new(mock_auth_repository)
     });
     fixture_registory
@@ -54,7 +75,8 @@ pub fn fixture(mut fixture_auth: MockAppRegistryExt) -> MockAppRegistryExt {
                     id,
                     name: "dummy-user".to_string(),
                     email: "dummy@example.com".to_string(),
-                    role: Role::User,
+                    role: Role::from(BuiltinRole::User),
+                    status: UserStatus::Active,
                 }))
             });
         Arc::new(mock_user_repogitory)
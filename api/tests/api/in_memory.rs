@@ -0,0 +1,1870 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use adapter::repository::memory::InMemoryStore;
+use api::{
+    middleware::request_id,
+    route::{auth, v1},
+};
+use axum::{
+    Router,
+    body::Body,
+    extract::ConnectInfo,
+    http::{Request, StatusCode, header::RETRY_AFTER},
+    response::Response,
+};
+use kernel::model::role::BuiltinRole;
+use registry::AppRegistryImpl;
+use serde_json::{Value, json};
+use shared::config::{
+    AppConfig, AuthConfig, CheckoutConfig, LoginThrottleConfig, MailConfig, PasswordHashConfig,
+    PasswordResetConfig, ProxyConfig, RegistrationConfig, RepositoryBackend, SchedulerConfig,
+    TotpConfig,
+};
+use tower::util::ServiceExt;
+
+use crate::{deserialize_json, helper::v1};
+
+fn make_in_memory_router(store: InMemoryStore) -> Router {
+    make_throttled_router(store, LoginThrottleConfig::default())
+}
+
+fn make_throttled_router(store: InMemoryStore, login_throttle: LoginThrottleConfig) -> Router {
+    make_router_with(store, |config| config.login_throttle = login_throttle)
+}
+
+// a router whose configuration `configure` adjusts from the defaults
+fn make_router_with(store: InMemoryStore, configure: impl FnOnce(&mut AppConfig)) -> Router {
+    let mut app_config = AppConfig {
+        backend: RepositoryBackend::InMemory,
+        auth: AuthConfig {
+            ttl: 60,
+            refresh_ttl: 600,
+        },
+        checkout: CheckoutConfig::default(),
+        login_throttle: LoginThrottleConfig::default(),
+        proxy: ProxyConfig::default(),
+        password_hash: PasswordHashConfig::default(),
+        totp: TotpConfig::default(),
+        password_reset: PasswordResetConfig::default(),
+        registration: RegistrationConfig::default(),
+        mail: MailConfig::default(),
+        scheduler: SchedulerConfig::default(),
+    };
+    configure(&mut app_config);
+    let registry = AppRegistryImpl::in_memory(store, app_config).expect("in-memory registry");
+
+    Router::new()
+        .merge(v1::routes())
+        .merge(auth::routes())
+        .layer(axum::middleware::from_fn(request_id))
+        .with_state(Arc::new(registry))
+}
+
+async fn send(
+    app: &Router,
+    method: &str,
+    uri: &str,
+    token: Option<&str>,
+    body: Option<Value>,
+) -> anyhow::Result<(StatusCode, Value)> {
+    let mut req = Request::builder().method(method).uri(uri);
+    if let Some(token) = token {
+        req = req.header("Authorization", format!("Bearer {token}"));
+    }
+    let req = match body {
+        Some(body) => req
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))?,
+        None => req.body(Body::empty())?,
+    };
+
+    let resp = app.clone().oneshot(req).await?;
+    let status = resp.status();
+    let body = if resp
+        .headers()
+        .get("Content-Type")
+        .is_some_and(|v| v == "application/json")
+    {
+        deserialize_json!(resp, Value)
+    } else {
+        Value::Null
+    };
+    Ok((status, body))
+}
+
+#[tokio::test]
+async fn checkout_flow_runs_in_process() -> anyhow::Result<()> {
+    let store = InMemoryStore::new();
+    store.insert_user(
+        "Librarian",
+        "librarian@example.com",
+        "Pa55w0rd",
+        BuiltinRole::Admin,
+    )?;
+    let app = make_in_memory_router(store);
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        "/auth/login",
+        None,
+        Some(json!({ "email": "librarian@example.com", "password": "Pa55w0rd" })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    let token = body["accessToken"].as_str().unwrap().to_string();
+    let token = Some(token.as_str());
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        &v1("/books"),
+        token,
+        Some(json!({
+            "title": "The Rust Programming Language",
+            "author": "Steve Klabnik and Carol Nichols",
+            "isbn": "978-1-59327-828-1",
+            "description": "A comprehensive guide to Rust programming."
+        })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, body) = send(&app, "GET", &v1("/books"), token, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["total"], 1);
+    assert_eq!(body["items"][0]["isbn"], "9781593278281");
+    let book_id = body["items"][0]["id"].as_str().unwrap().to_string();
+
+    let checkout = v1(&format!("/books/{book_id}/checkouts"));
+    let (status, _) = send(&app, "POST", &checkout, token, None).await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // the only copy is already lent to this user
+    let (status, body) = send(&app, "POST", &checkout, token, None).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], "unprocessable_entity");
+
+    let (status, body) = send(&app, "GET", &v1("/books/checkouts"), token, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    let checkout_id = body["items"][0]["id"].as_str().unwrap().to_string();
+
+    let (status, _) = send(
+        &app,
+        "PUT",
+        &v1(&format!(
+            "/books/{book_id}/checkouts/{checkout_id}/returned"
+        )),
+        token,
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = send(
+        &app,
+        "GET",
+        &v1(&format!("/books/{book_id}/checkout-history")),
+        token,
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["items"][0]["returnedAt"].is_string());
+
+    let (status, _) = send(&app, "POST", &checkout, token, None).await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn reused_refresh_token_revokes_session() -> anyhow::Result<()> {
+    let store = InMemoryStore::new();
+    store.insert_user(
+        "Reader",
+        "reader@example.com",
+        "Pa55w0rd",
+        BuiltinRole::User,
+    )?;
+    let app = make_in_memory_router(store);
+
+    let (status, login) = send(
+        &app,
+        "POST",
+        "/auth/login",
+        None,
+        Some(json!({ "email": "reader@example.com", "password": "Pa55w0rd" })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(login["expiresIn"], 60);
+
+    let (status, rotated) = send(
+        &app,
+        "POST",
+        "/auth/refresh",
+        None,
+        Some(json!({ "refreshToken": login["refreshToken"] })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_ne!(rotated["accessToken"], login["accessToken"]);
+    assert_ne!(rotated["refreshToken"], login["refreshToken"]);
+
+    // the access token of the previous pair no longer works
+    let (status, _) = send(
+        &app,
+        "GET",
+        &v1("/users/me"),
+        login["accessToken"].as_str(),
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(
+        &app,
+        "GET",
+        &v1("/users/me"),
+        rotated["accessToken"].as_str(),
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+
+    // replaying the exchanged refresh token ends the whole session
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/auth/refresh",
+        None,
+        Some(json!({ "refreshToken": login["refreshToken"] })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(
+        &app,
+        "GET",
+        &v1("/users/me"),
+        rotated["accessToken"].as_str(),
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/auth/refresh",
+        None,
+        Some(json!({ "refreshToken": rotated["refreshToken"] })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    Ok(())
+}
+
+async fn login_from(app: &Router, email: &str, user_agent: &str) -> anyhow::Result<Value> {
+    let req = Request::builder()
+        .method("POST")
+        .uri("/auth/login")
+        .header("Content-Type", "application/json")
+        .header("User-Agent", user_agent)
+        .body(Body::from(
+            json!({ "email": email, "password": "Pa55w0rd" }).to_string(),
+        ))?;
+    let resp = app.clone().oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    Ok(deserialize_json!(resp, Value))
+}
+
+#[tokio::test]
+async fn sessions_can_be_listed_and_revoked() -> anyhow::Result<()> {
+    let store = InMemoryStore::new();
+    store.insert_user(
+        "Reader",
+        "reader@example.com",
+        "Pa55w0rd",
+        BuiltinRole::User,
+    )?;
+    store.insert_user(
+        "Librarian",
+        "librarian@example.com",
+        "Pa55w0rd",
+        BuiltinRole::Admin,
+    )?;
+    let app = make_in_memory_router(store);
+
+    let phone = login_from(&app, "reader@example.com", "phone").await?;
+    let laptop = login_from(&app, "reader@example.com", "laptop").await?;
+    let admin = login_from(&app, "librarian@example.com", "desk").await?;
+    let laptop_token = laptop["accessToken"].as_str();
+
+    let (status, body) = send(&app, "GET", &v1("/users/me/sessions"), laptop_token, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    let sessions = body["items"].as_array().unwrap();
+    assert_eq!(sessions.len(), 2);
+    let session = |user_agent: &str| {
+        sessions
+            .iter()
+            .find(|s| s["userAgent"] == user_agent)
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(session("laptop")["current"], true);
+    assert_eq!(session("phone")["current"], false);
+    let phone_session = session("phone")["id"].as_str().unwrap().to_string();
+    let laptop_session = session("laptop")["id"].as_str().unwrap().to_string();
+
+    // sessions of other users are out of reach
+    let (status, _) = send(
+        &app,
+        "DELETE",
+        &v1(&format!("/users/me/sessions/{laptop_session}")),
+        admin["accessToken"].as_str(),
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = send(
+        &app,
+        "DELETE",
+        &v1(&format!("/users/me/sessions/{phone_session}")),
+        laptop_token,
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send(
+        &app,
+        "GET",
+        &v1("/users/me"),
+        phone["accessToken"].as_str(),
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/auth/refresh",
+        None,
+        Some(json!({ "refreshToken": phone["refreshToken"] })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let reader_sessions = v1(&format!(
+        "/users/{}/sessions",
+        laptop["userId"].as_str().unwrap()
+    ));
+    let (status, _) = send(&app, "DELETE", &reader_sessions, laptop_token, None).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = send(
+        &app,
+        "DELETE",
+        &reader_sessions,
+        admin["accessToken"].as_str(),
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send(&app, "GET", &v1("/users/me"), laptop_token, None).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // the admin's own session is untouched
+    let (status, body) = send(
+        &app,
+        "GET",
+        &v1("/users/me/sessions"),
+        admin["accessToken"].as_str(),
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["items"].as_array().unwrap().len(), 1);
+
+    Ok(())
+}
+
+async fn attempt_login(
+    app: &Router,
+    email: &str,
+    password: &str,
+    ip: &str,
+) -> anyhow::Result<Response> {
+    attempt_login_via(app, email, password, ip, None).await
+}
+
+// logs in from the peer `ip`, which claims to forward for `forwarded_for`
+async fn attempt_login_via(
+    app: &Router,
+    email: &str,
+    password: &str,
+    ip: &str,
+    forwarded_for: Option<&str>,
+) -> anyhow::Result<Response> {
+    let mut req = Request::builder()
+        .method("POST")
+        .uri("/auth/login")
+        .header("Content-Type", "application/json")
+        .extension(ConnectInfo(SocketAddr::new(ip.parse()?, 40000)));
+    if let Some(forwarded_for) = forwarded_for {
+        req = req.header("X-Forwarded-For", forwarded_for);
+    }
+    let req = req.body(Body::from(
+        json!({ "email": email, "password": password }).to_string(),
+    ))?;
+    Ok(app.clone().oneshot(req).await?)
+}
+
+#[tokio::test]
+async fn failed_logins_back_off() -> anyhow::Result<()> {
+    let store = InMemoryStore::new();
+    store.insert_user(
+        "Reader",
+        "reader@example.com",
+        "Pa55w0rd",
+        BuiltinRole::User,
+    )?;
+    let app = make_in_memory_router(store);
+
+    let resp = attempt_login(&app, "reader@example.com", "wrong", "192.0.2.1").await?;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    // even the right password has to wait, whatever the address
+    let resp = attempt_login(&app, "Reader@example.com", "Pa55w0rd", "192.0.2.2").await?;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(resp.headers()[RETRY_AFTER], "1");
+    let body = deserialize_json!(resp, Value);
+    assert_eq!(body["code"], "too_many_requests");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn locked_account_can_be_unlocked_by_admin() -> anyhow::Result<()> {
+    let store = InMemoryStore::new();
+    store.insert_user(
+        "Reader",
+        "reader@example.com",
+        "Pa55w0rd",
+        BuiltinRole::User,
+    )?;
+    store.insert_user(
+        "Librarian",
+        "librarian@example.com",
+        "Pa55w0rd",
+        BuiltinRole::Admin,
+    )?;
+    let app = make_throttled_router(
+        store,
+        LoginThrottleConfig {
+            max_failures: 3,
+            backoff_base_secs: 0,
+            ..Default::default()
+        },
+    );
+
+    for _ in 0..3 {
+        let resp = attempt_login(&app, "reader@example.com", "wrong", "192.0.2.1").await?;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+    let resp = attempt_login(&app, "reader@example.com", "Pa55w0rd", "192.0.2.1").await?;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(resp.headers()[RETRY_AFTER], "900");
+
+    let admin = login_from(&app, "librarian@example.com", "desk").await?;
+    let (status, body) = send(
+        &app,
+        "GET",
+        &v1("/users"),
+        admin["accessToken"].as_str(),
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    let reader_id = body["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|u| u["email"] == "reader@example.com")
+        .unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let (status, _) = send(
+        &app,
+        "DELETE",
+        &v1(&format!("/users/{reader_id}/lockout")),
+        admin["accessToken"].as_str(),
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let resp = attempt_login(&app, "reader@example.com", "Pa55w0rd", "192.0.2.1").await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn failing_address_is_throttled() -> anyhow::Result<()> {
+    let store = InMemoryStore::new();
+    store.insert_user(
+        "Reader",
+        "reader@example.com",
+        "Pa55w0rd",
+        BuiltinRole::User,
+    )?;
+    let app = make_throttled_router(
+        store,
+        LoginThrottleConfig {
+            max_failures_per_ip: 2,
+            backoff_base_secs: 0,
+            ..Default::default()
+        },
+    );
+
+    for email in ["a@example.com", "b@example.com"] {
+        let resp = attempt_login(&app, email, "guess", "198.51.100.7").await?;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+    let resp = attempt_login(&app, "reader@example.com", "Pa55w0rd", "198.51.100.7").await?;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    let resp = attempt_login(&app, "reader@example.com", "Pa55w0rd", "198.51.100.8").await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // a forged forwarded address does not get around the limit
+    let resp = attempt_login_via(
+        &app,
+        "reader@example.com",
+        "Pa55w0rd",
+        "198.51.100.7",
+        Some("203.0.113.1"),
+    )
+    .await?;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn trusted_proxies_forward_the_client_address() -> anyhow::Result<()> {
+    let store = InMemoryStore::new();
+    store.insert_user(
+        "Reader",
+        "reader@example.com",
+        "Pa55w0rd",
+        BuiltinRole::User,
+    )?;
+    let app = make_router_with(store, |config| {
+        config.login_throttle = LoginThrottleConfig {
+            max_failures_per_ip: 2,
+            backoff_base_secs: 0,
+            ..Default::default()
+        };
+        config.proxy = ProxyConfig {
+            trusted_proxies: vec!["10.0.0.1".parse().unwrap()],
+        };
+    });
+
+    // the proxy appends the address it was connected from to what the client sent
+    for email in ["a@example.com", "b@example.com"] {
+        let resp = attempt_login_via(
+            &app,
+            email,
+            "guess",
+            "10.0.0.1",
+            Some("192.0.2.99, 198.51.100.7"),
+        )
+        .await?;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+    let resp = attempt_login_via(
+        &app,
+        "reader@example.com",
+        "Pa55w0rd",
+        "10.0.0.1",
+        Some("203.0.113.1, 198.51.100.7"),
+    )
+    .await?;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    let resp = attempt_login_via(
+        &app,
+        "reader@example.com",
+        "Pa55w0rd",
+        "10.0.0.1",
+        Some("198.51.100.8"),
+    )
+    .await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn password_can_be_reset_with_mailed_token() -> anyhow::Result<()> {
+    let outbox = std::env::temp_dir().join(format!("outbox-{}", uuid::Uuid::new_v4()));
+    let store = InMemoryStore::new();
+    store.insert_user(
+        "Reader",
+        "reader@example.com",
+        "Pa55w0rd",
+        BuiltinRole::User,
+    )?;
+    let app = make_router_with(store, |config| {
+        config.mail.outbox_dir = Some(outbox.to_string_lossy().into_owned());
+    });
+    let session = login_from(&app, "reader@example.com", "phone").await?;
+
+    // unknown emails get the same answer, and no mail
+    for email in ["nobody@example.com", "reader@example.com"] {
+        let (status, _) = send(
+            &app,
+            "POST",
+            "/auth/password-reset/request",
+            None,
+            Some(json!({ "email": email })),
+        )
+        .await?;
+        assert_eq!(status, StatusCode::ACCEPTED);
+    }
+    let mails = std::fs::read_dir(&outbox)?.collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(mails.len(), 1);
+    let mail = std::fs::read_to_string(mails[0].path())?;
+    assert!(mail.contains("To: reader@example.com"));
+    let token = mail
+        .lines()
+        .find(|l| l.len() == 32 && l.chars().all(|c| c.is_ascii_hexdigit()))
+        .unwrap()
+        .to_string();
+    std::fs::remove_dir_all(&outbox)?;
+
+    let confirm = json!({ "token": token, "newPassword": "N3wPa55w0rd" });
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/auth/password-reset/confirm",
+        None,
+        Some(confirm.clone()),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    // the token works once
+    let (status, body) = send(
+        &app,
+        "POST",
+        "/auth/password-reset/confirm",
+        None,
+        Some(confirm),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], "unprocessable_entity");
+
+    let (status, _) = send(
+        &app,
+        "GET",
+        &v1("/users/me"),
+        session["accessToken"].as_str(),
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let resp = attempt_login(&app, "reader@example.com", "N3wPa55w0rd", "192.0.2.1").await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let resp = attempt_login(&app, "reader@example.com", "Pa55w0rd", "192.0.2.1").await?;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn signed_up_user_logs_in_after_verification_and_approval() -> anyhow::Result<()> {
+    let outbox = std::env::temp_dir().join(format!("outbox-{}", uuid::Uuid::new_v4()));
+    let store = InMemoryStore::new();
+    store.insert_user(
+        "Librarian",
+        "librarian@example.com",
+        "Pa55w0rd",
+        BuiltinRole::Admin,
+    )?;
+    let app = make_router_with(store, |config| {
+        config.mail.outbox_dir = Some(outbox.to_string_lossy().into_owned());
+        config.registration = RegistrationConfig {
+            enabled: true,
+            allowed_domains: vec!["example.com".into()],
+            require_approval: true,
+            ..Default::default()
+        };
+    });
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/auth/register",
+        None,
+        Some(json!({ "name": "Outsider", "email": "someone@example.org", "password": "Pa55w0rd" })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let sign_up = || {
+        send(
+            &app,
+            "POST",
+            "/auth/register",
+            None,
+            Some(
+                json!({ "name": "Reader", "email": "reader@example.com", "password": "Pa55w0rd" }),
+            ),
+        )
+    };
+    let (status, _) = sign_up().await?;
+    assert_eq!(status, StatusCode::ACCEPTED);
+
+    let resp = attempt_login(&app, "reader@example.com", "Pa55w0rd", "192.0.2.1").await?;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    assert_eq!(deserialize_json!(resp, Value)["code"], "account_not_active");
+
+    let mails = std::fs::read_dir(&outbox)?.collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(mails.len(), 1);
+    let mail = std::fs::read_to_string(mails[0].path())?;
+    assert!(mail.contains("To: reader@example.com"));
+    std::fs::remove_dir_all(&outbox)?;
+
+    // a lost mail is sent again, but only to users who still have to verify
+    for email in [
+        "reader@example.com",
+        "librarian@example.com",
+        "nobody@example.com",
+    ] {
+        let (status, _) = send(
+            &app,
+            "POST",
+            "/auth/register/resend",
+            None,
+            Some(json!({ "email": email })),
+        )
+        .await?;
+        assert_eq!(status, StatusCode::ACCEPTED);
+    }
+    let mails = std::fs::read_dir(&outbox)?.collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(mails.len(), 1);
+    let mail = std::fs::read_to_string(mails[0].path())?;
+    assert!(mail.contains("To: reader@example.com"));
+    let token = mail
+        .lines()
+        .find(|l| l.len() == 32 && l.chars().all(|c| c.is_ascii_hexdigit()))
+        .unwrap()
+        .to_string();
+    std::fs::remove_dir_all(&outbox)?;
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        "/auth/register/verify",
+        None,
+        Some(json!({ "token": token })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "pending_approval");
+    let reader_id = body["id"].as_str().unwrap().to_string();
+
+    let resp = attempt_login(&app, "reader@example.com", "Pa55w0rd", "192.0.2.1").await?;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    // signing up with a taken email is answered like a new sign-up, and the
+    // owner of the email is told
+    let (status, _) = sign_up().await?;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let mails = std::fs::read_dir(&outbox)?.collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(mails.len(), 1);
+    let mail = std::fs::read_to_string(mails[0].path())?;
+    assert!(mail.contains("To: reader@example.com"));
+    assert!(mail.contains("You already have an account"));
+    std::fs::remove_dir_all(&outbox)?;
+
+    let admin = login_from(&app, "librarian@example.com", "desk").await?;
+    let admin_token = admin["accessToken"].as_str();
+    let (status, body) = send(&app, "GET", &v1("/users/pending"), admin_token, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["items"].as_array().unwrap().len(), 1);
+    assert_eq!(body["items"][0]["id"], reader_id.as_str());
+
+    let approval = v1(&format!("/users/{reader_id}/approval"));
+    let (status, _) = send(
+        &app,
+        "PUT",
+        &approval,
+        admin_token,
+        Some(json!({ "approved": true })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    // only pending users can be approved or rejected
+    let (status, _) = send(
+        &app,
+        "PUT",
+        &approval,
+        admin_token,
+        Some(json!({ "approved": false })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let resp = attempt_login(&app, "reader@example.com", "Pa55w0rd", "192.0.2.1").await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn sign_up_is_disabled_by_default() -> anyhow::Result<()> {
+    let app = make_in_memory_router(InMemoryStore::new());
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/auth/register",
+        None,
+        Some(json!({ "name": "Reader", "email": "reader@example.com", "password": "Pa55w0rd" })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn seeded_admin_can_log_in() -> anyhow::Result<()> {
+    let app = make_in_memory_router(InMemoryStore::with_initial_setup());
+
+    let resp = attempt_login(&app, "eleazar.fig@example.com", "Pa55w0rd", "192.0.2.1").await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    Ok(())
+}
+
+fn totp_code(secret: &str, at: u64) -> String {
+    let secret = totp_rs::Secret::Encoded(secret.into()).to_bytes().unwrap();
+    totp_rs::TOTP::new(
+        totp_rs::Algorithm::SHA1,
+        6,
+        1,
+        30,
+        secret,
+        None,
+        String::new(),
+    )
+    .unwrap()
+    .generate(at)
+}
+
+fn now_secs() -> u64 {
+    chrono::Utc::now().timestamp() as u64
+}
+
+#[tokio::test]
+async fn enrolled_user_logs_in_with_second_factor() -> anyhow::Result<()> {
+    let store = InMemoryStore::new();
+    store.insert_user(
+        "Reader",
+        "reader@example.com",
+        "Pa55w0rd",
+        BuiltinRole::User,
+    )?;
+    let app = make_router_with(store, |config| {
+        config.login_throttle.backoff_base_secs = 0;
+    });
+    let session = login_from(&app, "reader@example.com", "phone").await?;
+    let token = session["accessToken"].as_str();
+
+    let (status, enrollment) = send(&app, "POST", &v1("/users/me/totp"), token, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    let secret = enrollment["secret"].as_str().unwrap().to_string();
+    assert!(
+        enrollment["provisioningUri"]
+            .as_str()
+            .unwrap()
+            .starts_with("otpauth://totp/")
+    );
+
+    let confirmed_code = totp_code(&secret, now_secs());
+    let (status, codes) = send(
+        &app,
+        "POST",
+        &v1("/users/me/totp/confirm"),
+        token,
+        Some(json!({ "code": confirmed_code })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    let recovery_codes = codes["items"].as_array().unwrap().clone();
+    assert_eq!(recovery_codes.len(), 10);
+
+    let challenge = || async {
+        let resp = attempt_login(&app, "reader@example.com", "Pa55w0rd", "192.0.2.1").await?;
+        assert_eq!(resp.status(), StatusCode::ACCEPTED);
+        let body = deserialize_json!(resp, Value);
+        anyhow::Ok(body["challengeToken"].as_str().unwrap().to_string())
+    };
+    let complete = |challenge_token: String, code: String| {
+        send(
+            &app,
+            "POST",
+            "/auth/login/totp",
+            None,
+            Some(json!({ "challengeToken": challenge_token, "code": code })),
+        )
+    };
+
+    // the code used to enroll is spent, but the next one works
+    let challenge_token = challenge().await?;
+    let (status, _) = complete(challenge_token.clone(), confirmed_code).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, body) =
+        complete(challenge_token.clone(), totp_code(&secret, now_secs() + 30)).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["accessToken"].is_string());
+    assert!(body["recoveryCodes"].is_null());
+    // a challenge completes one login
+    let (status, _) = complete(challenge_token, recovery_codes[0].as_str().unwrap().into()).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // recovery codes work once each
+    let recovery_code = recovery_codes[1].as_str().unwrap().to_string();
+    let (status, _) = complete(challenge().await?, recovery_code.clone()).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = complete(challenge().await?, recovery_code).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn wrong_codes_count_across_logins() -> anyhow::Result<()> {
+    let store = InMemoryStore::new();
+    store.insert_user(
+        "Reader",
+        "reader@example.com",
+        "Pa55w0rd",
+        BuiltinRole::User,
+    )?;
+    let app = make_router_with(store, |config| {
+        config.login_throttle.max_failures = 2;
+        config.login_throttle.backoff_base_secs = 0;
+    });
+    let session = login_from(&app, "reader@example.com", "phone").await?;
+    let token = session["accessToken"].as_str();
+    let (_, enrollment) = send(&app, "POST", &v1("/users/me/totp"), token, None).await?;
+    let secret = enrollment["secret"].as_str().unwrap().to_string();
+    let (status, _) = send(
+        &app,
+        "POST",
+        &v1("/users/me/totp/confirm"),
+        token,
+        Some(json!({ "code": totp_code(&secret, now_secs()) })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+
+    // logging in with the password again does not reset the count of wrong codes
+    for _ in 0..2 {
+        let resp = attempt_login(&app, "reader@example.com", "Pa55w0rd", "192.0.2.1").await?;
+        assert_eq!(resp.status(), StatusCode::ACCEPTED);
+        let body = deserialize_json!(resp, Value);
+        let (status, _) = send(
+            &app,
+            "POST",
+            "/auth/login/totp",
+            None,
+            Some(json!({
+                "challengeToken": body["challengeToken"],
+                "code": totp_code(&secret, now_secs() - 3600),
+            })),
+        )
+        .await?;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+    let resp = attempt_login(&app, "reader@example.com", "Pa55w0rd", "192.0.2.1").await?;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn admins_enroll_while_logging_in_when_required() -> anyhow::Result<()> {
+    let store = InMemoryStore::new();
+    store.insert_user(
+        "Librarian",
+        "librarian@example.com",
+        "Pa55w0rd",
+        BuiltinRole::Admin,
+    )?;
+    store.insert_user(
+        "Reader",
+        "reader@example.com",
+        "Pa55w0rd",
+        BuiltinRole::User,
+    )?;
+    let app = make_router_with(store, |config| config.totp.required_for_admins = true);
+
+    let resp = attempt_login(&app, "reader@example.com", "Pa55w0rd", "192.0.2.1").await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = attempt_login(&app, "librarian@example.com", "Pa55w0rd", "192.0.2.1").await?;
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+    let challenge = deserialize_json!(resp, Value);
+    let secret = challenge["enrollment"]["secret"].as_str().unwrap();
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        "/auth/login/totp",
+        None,
+        Some(json!({
+            "challengeToken": challenge["challengeToken"],
+            "code": totp_code(secret, now_secs()),
+        })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["recoveryCodes"].as_array().unwrap().len(), 10);
+
+    // admins cannot opt out while it is required
+    let (status, _) = send(
+        &app,
+        "DELETE",
+        &v1("/users/me/totp"),
+        body["accessToken"].as_str(),
+        Some(json!({ "code": body["recoveryCodes"][0] })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let resp = attempt_login(&app, "librarian@example.com", "Pa55w0rd", "192.0.2.1").await?;
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+    assert!(deserialize_json!(resp, Value)["enrollment"].is_null());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn api_keys_work_within_their_scopes() -> anyhow::Result<()> {
+    let store = InMemoryStore::new();
+    store.insert_user(
+        "Librarian",
+        "librarian@example.com",
+        "Pa55w0rd",
+        BuiltinRole::Admin,
+    )?;
+    let app = make_in_memory_router(store);
+    let login = login_from(&app, "librarian@example.com", "test").await?;
+    let token = login["accessToken"].as_str();
+
+    let book = json!({
+        "title": "The Rust Programming Language",
+        "author": "Steve Klabnik and Carol Nichols",
+        "isbn": "978-1-59327-828-1",
+        "description": "A comprehensive guide to Rust programming."
+    });
+    let (status, _) = send(&app, "POST", &v1("/books"), token, Some(book.clone())).await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        &v1("/users/me/api-keys"),
+        token,
+        Some(json!({ "name": "scanner kiosk", "scopes": ["books_read", "checkouts"] })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let key = body["key"].as_str().unwrap().to_string();
+    let key_id = body["apiKey"]["id"].as_str().unwrap().to_string();
+    assert!(key.starts_with(body["apiKey"]["prefix"].as_str().unwrap()));
+    assert!(body["apiKey"]["lastUsedAt"].is_null());
+
+    let (status, body) = send(&app, "GET", &v1("/books"), Some(&key), None).await?;
+    assert_eq!(status, StatusCode::OK);
+    let book_id = body["items"][0]["id"].as_str().unwrap().to_string();
+    let (status, _) = send(
+        &app,
+        "POST",
+        &v1(&format!("/books/{book_id}/checkouts")),
+        Some(&key),
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // out of its scopes, even though the user may do it
+    let (status, _) = send(&app, "POST", &v1("/books"), Some(&key), Some(book)).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, "GET", &v1("/users/me/api-keys"), Some(&key), None).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = send(&app, "GET", &v1("/users/me/api-keys"), token, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["items"][0]["name"], "scanner kiosk");
+    assert!(body["items"][0]["lastUsedAt"].is_string());
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        &v1(&format!("/users/me/api-keys/{key_id}/rotate")),
+        token,
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    let rotated = body["key"].as_str().unwrap().to_string();
+    let (status, _) = send(&app, "GET", &v1("/books"), Some(&key), None).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(&app, "GET", &v1("/books"), Some(&rotated), None).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let revoke = v1(&format!("/users/me/api-keys/{key_id}"));
+    let (status, _) = send(&app, "DELETE", &revoke, token, None).await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send(&app, "GET", &v1("/books"), Some(&rotated), None).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(&app, "DELETE", &revoke, token, None).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn roles_grant_permissions() -> anyhow::Result<()> {
+    let store = InMemoryStore::new();
+    store.insert_user("Admin", "admin@example.com", "Pa55w0rd", BuiltinRole::Admin)?;
+    store.insert_user(
+        "Librarian",
+        "librarian@example.com",
+        "Pa55w0rd",
+        BuiltinRole::Librarian,
+    )?;
+    let reader_id = store.insert_user(
+        "Reader",
+        "reader@example.com",
+        "Pa55w0rd",
+        BuiltinRole::User,
+    )?;
+    store.insert_user("Other", "other@example.com", "Pa55w0rd", BuiltinRole::User)?;
+    let app = make_in_memory_router(store);
+    let token = |login: &Value| login["accessToken"].as_str().unwrap().to_string();
+    let admin = token(&login_from(&app, "admin@example.com", "test").await?);
+    let librarian = token(&login_from(&app, "librarian@example.com", "test").await?);
+    let reader = token(&login_from(&app, "reader@example.com", "test").await?);
+    let other = token(&login_from(&app, "other@example.com", "test").await?);
+
+    let book = json!({
+        "title": "The Rust Programming Language",
+        "author": "Steve Klabnik and Carol Nichols",
+        "isbn": "978-1-59327-828-1",
+        "description": "A comprehensive guide to Rust programming."
+    });
+    let (status, _) = send(
+        &app,
+        "POST",
+        &v1("/books"),
+        Some(&reader),
+        Some(book.clone()),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let (_, body) = send(&app, "GET", &v1("/books"), Some(&reader), None).await?;
+    let book_uri = v1(&format!(
+        "/books/{}",
+        body["items"][0]["id"].as_str().unwrap()
+    ));
+
+    // librarians manage every book, but not users
+    let (status, _) = send(&app, "PUT", &book_uri, Some(&other), Some(book.clone())).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, "PUT", &book_uri, Some(&librarian), Some(book)).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "GET", &v1("/users/pending"), Some(&librarian), None).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, "GET", &v1("/roles"), Some(&librarian), None).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let front_desk = json!({ "name": "Front desk", "permissions": ["manage_users"] });
+    let (status, body) = send(
+        &app,
+        "POST",
+        &v1("/roles"),
+        Some(&admin),
+        Some(front_desk.clone()),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["permissions"], json!(["manage_users"]));
+    let (status, _) = send(&app, "POST", &v1("/roles"), Some(&admin), Some(front_desk)).await?;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, body) = send(&app, "GET", &v1("/roles"), Some(&admin), None).await?;
+    assert_eq!(status, StatusCode::OK);
+    let names = body["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["name"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["Admin", "Front desk", "Librarian", "User"]);
+
+    let role_uri = v1(&format!("/users/{reader_id}/role"));
+    let (status, _) = send(
+        &app,
+        "PUT",
+        &role_uri,
+        Some(&admin),
+        Some(json!({ "role": "Janitor" })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let (status, _) = send(
+        &app,
+        "PUT",
+        &role_uri,
+        Some(&admin),
+        Some(json!({ "role": "Front desk" })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, body) = send(&app, "GET", &v1("/users/me"), Some(&reader), None).await?;
+    assert_eq!(body["role"], "Front desk");
+    assert_eq!(body["permissions"], json!(["manage_users"]));
+    let (status, _) = send(&app, "GET", &v1("/users/pending"), Some(&reader), None).await?;
+    assert_eq!(status, StatusCode::OK);
+    // assigning roles takes managing roles
+    let (status, _) = send(
+        &app,
+        "PUT",
+        &role_uri,
+        Some(&reader),
+        Some(json!({ "role": "Admin" })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn borrowing_policies_refuse_checkouts() -> anyhow::Result<()> {
+    let store = InMemoryStore::new();
+    store.insert_user("Admin", "admin@example.com", "Pa55w0rd", BuiltinRole::Admin)?;
+    store.insert_user(
+        "Reader",
+        "reader@example.com",
+        "Pa55w0rd",
+        BuiltinRole::User,
+    )?;
+    // every loan is overdue as soon as it is made
+    let app = make_router_with(store, |config| config.checkout.user_loan_days = 0);
+    let token = |login: &Value| login["accessToken"].as_str().unwrap().to_string();
+    let admin = token(&login_from(&app, "admin@example.com", "test").await?);
+    let reader = token(&login_from(&app, "reader@example.com", "test").await?);
+
+    for (owner, title, isbn) in [
+        (&admin, "First", "978-1-59327-828-1"),
+        (&admin, "Second", "978-0-13-235088-4"),
+        (&reader, "Own", "978-1-7185-0044-0"),
+    ] {
+        let book = json!({ "title": title, "author": "Author", "isbn": isbn, "description": "" });
+        let (status, _) = send(&app, "POST", &v1("/books"), Some(owner), Some(book)).await?;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+    let (_, body) = send(&app, "GET", &v1("/books"), Some(&reader), None).await?;
+    let checkout_uri = |title: &str| {
+        let book = body["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|b| b["title"] == title)
+            .unwrap();
+        v1(&format!(
+            "/books/{}/checkouts",
+            book["id"].as_str().unwrap()
+        ))
+    };
+
+    let policy_uri = v1("/roles/User/borrowing-policy");
+    let (status, _) = send(&app, "GET", &policy_uri, Some(&reader), None).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, body) = send(&app, "GET", &policy_uri, Some(&admin), None).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body,
+        json!({ "maxLoans": 5, "blockWhenOverdue": true, "blockOwnBooks": false })
+    );
+
+    let policy = json!({ "maxLoans": 1, "blockWhenOverdue": false, "blockOwnBooks": true });
+    let (status, body) = send(&app, "PUT", &policy_uri, Some(&admin), Some(policy.clone())).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, policy);
+    let (status, _) = send(
+        &app,
+        "PUT",
+        &v1("/roles/Nobody/borrowing-policy"),
+        Some(&admin),
+        Some(policy),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let negative = json!({ "maxLoans": -1, "blockWhenOverdue": false, "blockOwnBooks": false });
+    let (status, _) = send(&app, "PUT", &policy_uri, Some(&admin), Some(negative)).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = send(&app, "POST", &checkout_uri("Own"), Some(&reader), None).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], "own_book");
+    let (status, _) = send(&app, "POST", &checkout_uri("First"), Some(&reader), None).await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, body) = send(&app, "POST", &checkout_uri("Second"), Some(&reader), None).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], "loan_limit_reached");
+
+    // lifting the limit leaves the overdue loan in the way
+    let policy = json!({ "blockWhenOverdue": true, "blockOwnBooks": false });
+    let (status, body) = send(&app, "PUT", &policy_uri, Some(&admin), Some(policy)).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["maxLoans"].is_null());
+    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    let (status, body) = send(&app, "POST", &checkout_uri("Second"), Some(&reader), None).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], "overdue_loans");
+
+    // other roles keep their own policies
+    let (status, _) = send(&app, "POST", &checkout_uri("Second"), Some(&admin), None).await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn staff_lend_and_force_return_for_users() -> anyhow::Result<()> {
+    let store = InMemoryStore::new();
+    let librarian_id = store.insert_user(
+        "Librarian",
+        "librarian@example.com",
+        "Pa55w0rd",
+        BuiltinRole::Librarian,
+    )?;
+    let reader_id = store.insert_user(
+        "Reader",
+        "reader@example.com",
+        "Pa55w0rd",
+        BuiltinRole::User,
+    )?;
+    let app = make_in_memory_router(store);
+    let token = |login: &Value| login["accessToken"].as_str().unwrap().to_string();
+    let librarian = token(&login_from(&app, "librarian@example.com", "test").await?);
+    let reader = token(&login_from(&app, "reader@example.com", "test").await?);
+
+    let book = json!({
+        "title": "The Rust Programming Language",
+        "author": "Steve Klabnik and Carol Nichols",
+        "isbn": "978-1-59327-828-1",
+        "description": "A comprehensive guide to Rust programming."
+    });
+    let (status, _) = send(&app, "POST", &v1("/books"), Some(&librarian), Some(book)).await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let (_, body) = send(&app, "GET", &v1("/books"), Some(&reader), None).await?;
+    let book_id = body["items"][0]["id"].as_str().unwrap().to_string();
+
+    let desk = v1(&format!("/books/{book_id}/checkouts/desk"));
+    let (status, _) = send(
+        &app,
+        "POST",
+        &desk,
+        Some(&reader),
+        Some(json!({ "userId": reader_id })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(
+        &app,
+        "POST",
+        &desk,
+        Some(&librarian),
+        Some(json!({ "userId": reader_id })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (_, body) = send(&app, "GET", &v1("/users/me/checkouts"), Some(&reader), None).await?;
+    let checkout = &body["items"][0];
+    assert_eq!(checkout["checkedOutBy"], json!(reader_id));
+    assert_eq!(checkout["issuedBy"], json!(librarian_id));
+    let checkout_id = checkout["id"].as_str().unwrap().to_string();
+
+    // staff return others' checkouts only by forcing it, with a reason
+    let (status, _) = send(
+        &app,
+        "PUT",
+        &v1(&format!(
+            "/books/{book_id}/checkouts/{checkout_id}/returned"
+        )),
+        Some(&librarian),
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let force = v1(&format!(
+        "/books/{book_id}/checkouts/{checkout_id}/force-returned"
+    ));
+    let (status, _) = send(
+        &app,
+        "PUT",
+        &force,
+        Some(&reader),
+        Some(json!({ "reason": "done with it" })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(
+        &app,
+        "PUT",
+        &force,
+        Some(&librarian),
+        Some(json!({ "reason": "" })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(
+        &app,
+        "PUT",
+        &force,
+        Some(&librarian),
+        Some(json!({ "reason": "left the company" })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = send(
+        &app,
+        "GET",
+        &v1(&format!("/books/{book_id}/checkout-history")),
+        Some(&reader),
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    let returned = &body["items"][0];
+    assert_eq!(returned["checkedOutBy"], json!(reader_id));
+    assert_eq!(returned["returnedBy"], json!(librarian_id));
+    assert_eq!(returned["returnReason"], "left the company");
+    let (_, body) = send(&app, "GET", &v1("/users/me/checkouts"), Some(&reader), None).await?;
+    assert_eq!(body["items"], json!([]));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn users_opt_out_of_notification_kinds() -> anyhow::Result<()> {
+    let store = InMemoryStore::new();
+    store.insert_user(
+        "Reader",
+        "reader@example.com",
+        "Pa55w0rd",
+        BuiltinRole::User,
+    )?;
+    let app = make_in_memory_router(store);
+    let login = login_from(&app, "reader@example.com", "test").await?;
+    let token = login["accessToken"].as_str();
+
+    let (status, body) = send(
+        &app,
+        "GET",
+        &v1("/users/me/notification-preferences"),
+        token,
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    let items = body["items"].as_array().unwrap();
+    assert_eq!(items.len(), 5);
+    assert!(items.iter().all(|p| p["enabled"] == true));
+
+    let (status, body) = send(
+        &app,
+        "PUT",
+        &v1("/users/me/notification-preferences"),
+        token,
+        Some(json!({ "items": [{ "kind": "due_soon", "enabled": false }] })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    for preference in body["items"].as_array().unwrap() {
+        assert_eq!(preference["enabled"], preference["kind"] != "due_soon");
+    }
+
+    let (status, _) = send(
+        &app,
+        "PUT",
+        &v1("/users/me/notification-preferences"),
+        token,
+        Some(json!({ "items": [{ "kind": "newsletter", "enabled": false }] })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    Ok(())
+}
+
+#[tokio::test]
+async fn admins_subscribe_webhooks_to_book_events() -> anyhow::Result<()> {
+    let store = InMemoryStore::new();
+    let admin_id =
+        store.insert_user("Admin", "admin@example.com", "Pa55w0rd", BuiltinRole::Admin)?;
+    store.insert_user(
+        "Librarian",
+        "librarian@example.com",
+        "Pa55w0rd",
+        BuiltinRole::Librarian,
+    )?;
+    let app = make_in_memory_router(store);
+    let token = |login: &Value| login["accessToken"].as_str().unwrap().to_string();
+    let admin = token(&login_from(&app, "admin@example.com", "test").await?);
+    let librarian = token(&login_from(&app, "librarian@example.com", "test").await?);
+
+    let subscription = json!({
+        "url": "https://hooks.example.com/library",
+        "eventTypes": ["book.created", "book.deleted", "book.created"]
+    });
+    let (status, _) = send(
+        &app,
+        "POST",
+        &v1("/webhooks"),
+        Some(&librarian),
+        Some(subscription.clone()),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(
+        &app,
+        "POST",
+        &v1("/webhooks"),
+        Some(&admin),
+        Some(json!({ "url": "ftp://hooks.example.com/", "eventTypes": ["book.created"] })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, body) = send(
+        &app,
+        "POST",
+        &v1("/webhooks"),
+        Some(&admin),
+        Some(subscription),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    assert!(body["secret"].as_str().unwrap().starts_with("whsec_"));
+    assert_eq!(
+        body["webhook"]["eventTypes"],
+        json!(["book.created", "book.deleted"])
+    );
+    assert_eq!(body["webhook"]["createdBy"], json!(admin_id));
+    let webhook_id = body["webhook"]["id"].as_str().unwrap().to_string();
+
+    let (_, body) = send(&app, "GET", &v1("/webhooks"), Some(&admin), None).await?;
+    assert_eq!(body["items"].as_array().unwrap().len(), 1);
+    assert!(body["items"][0].get("secret").is_none());
+
+    let book = json!({
+        "title": "The Rust Programming Language",
+        "author": "Steve Klabnik and Carol Nichols",
+        "isbn": "978-1-59327-828-1",
+        "description": "A comprehensive guide to Rust programming."
+    });
+    let (status, _) = send(&app, "POST", &v1("/books"), Some(&librarian), Some(book)).await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let deliveries = v1(&format!("/webhooks/{webhook_id}/deliveries"));
+    let (status, body) = send(&app, "GET", &deliveries, Some(&admin), None).await?;
+    assert_eq!(status, StatusCode::OK);
+    let items = body["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["eventType"], "book.created");
+    assert_eq!(items[0]["status"], "pending");
+    let payload = serde_json::from_str::<Value>(items[0]["payload"].as_str().unwrap())?;
+    assert_eq!(payload["type"], "book.created");
+    assert_eq!(payload["data"]["title"], "The Rust Programming Language");
+
+    // only dead letters are sent again on request
+    let delivery_id = items[0]["id"].as_str().unwrap();
+    let (status, _) = send(
+        &app,
+        "POST",
+        &v1(&format!(
+            "/webhooks/{webhook_id}/deliveries/{delivery_id}/retry"
+        )),
+        Some(&admin),
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = send(
+        &app,
+        "DELETE",
+        &v1(&format!("/webhooks/{webhook_id}")),
+        Some(&admin),
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send(&app, "GET", &deliveries, Some(&admin), None).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    Ok(())
+}
+
+// reads the stream up to the end of the next event; yields its id, name and data
+async fn next_event(
+    body: &mut (impl tokio_stream::Stream<Item = Result<axum::body::Bytes, axum::Error>> + Unpin),
+    buffer: &mut String,
+) -> anyhow::Result<(String, String, Value)> {
+    use tokio_stream::StreamExt;
+
+    loop {
+        if let Some(end) = buffer.find("\n\n") {
+            let frame = buffer[..end].to_string();
+            buffer.drain(..end + 2);
+            let field = |name: &str| {
+                frame
+                    .lines()
+                    .find_map(|line| line.strip_prefix(name))
+                    .map(str::to_string)
+            };
+            // keep-alive comments carry no event
+            let (Some(id), Some(event), Some(data)) =
+                (field("id:"), field("event:"), field("data:"))
+            else {
+                continue;
+            };
+            return Ok((
+                id.trim().into(),
+                event.trim().into(),
+                serde_json::from_str(&data)?,
+            ));
+        }
+        let chunk = tokio::time::timeout(std::time::Duration::from_secs(5), body.next())
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("the event stream ended"))??;
+        buffer.push_str(std::str::from_utf8(&chunk)?);
+    }
+}
+
+#[tokio::test]
+async fn users_watch_availability_over_server_sent_events() -> anyhow::Result<()> {
+    let store = InMemoryStore::new();
+    store.insert_user(
+        "Librarian",
+        "librarian@example.com",
+        "Pa55w0rd",
+        BuiltinRole::Librarian,
+    )?;
+    let app = make_in_memory_router(store);
+    let login = login_from(&app, "librarian@example.com", "test").await?;
+    let token = login["accessToken"].as_str().unwrap().to_string();
+    let subscribe = |last_event_id: Option<&str>| {
+        let mut req =
+            Request::get(v1("/events")).header("Authorization", format!("Bearer {token}"));
+        if let Some(id) = last_event_id {
+            req = req.header("Last-Event-ID", id);
+        }
+        app.clone().oneshot(req.body(Body::empty()).unwrap())
+    };
+
+    let resp = subscribe(None).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()["Content-Type"], "text/event-stream");
+    let mut events = resp.into_body().into_data_stream();
+    let mut buffer = String::new();
+
+    let book = json!({
+        "title": "The Rust Programming Language",
+        "author": "Steve Klabnik and Carol Nichols",
+        "isbn": "978-1-59327-828-1",
+        "description": "A comprehensive guide to Rust programming."
+    });
+    let (status, _) = send(&app, "POST", &v1("/books"), Some(&token), Some(book)).await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (id, event, data) = next_event(&mut events, &mut buffer).await?;
+    assert_eq!((id.as_str(), event.as_str()), ("1", "book.created"));
+    assert_eq!(data["availableCopies"], 1);
+    assert_eq!(data["totalCopies"], 1);
+    let book_id = data["bookId"].as_str().unwrap().to_string();
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        &v1(&format!("/books/{book_id}/checkouts")),
+        Some(&token),
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (id, event, data) = next_event(&mut events, &mut buffer).await?;
+    assert_eq!((id.as_str(), event.as_str()), ("2", "checkout.created"));
+    assert_eq!(data["bookId"], book_id);
+    assert_eq!(data["availableCopies"], 0);
+
+    // a reconnecting client is sent what it missed first
+    let resp = subscribe(Some("1")).await?;
+    let mut resumed = resp.into_body().into_data_stream();
+    let mut buffer = String::new();
+    let (id, event, _) = next_event(&mut resumed, &mut buffer).await?;
+    assert_eq!((id.as_str(), event.as_str()), ("2", "checkout.created"));
+
+    let resp = subscribe(Some("latest")).await?;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(deserialize_json!(resp, Value)["code"], "invalid_request");
+    Ok(())
+}
+
+#[tokio::test]
+async fn admins_see_who_changed_what() -> anyhow::Result<()> {
+    let store = InMemoryStore::new();
+    let admin_id =
+        store.insert_user("Admin", "admin@example.com", "Pa55w0rd", BuiltinRole::Admin)?;
+    let librarian_id = store.insert_user(
+        "Librarian",
+        "librarian@example.com",
+        "Pa55w0rd",
+        BuiltinRole::Librarian,
+    )?;
+    let reader_id = store.insert_user(
+        "Reader",
+        "reader@example.com",
+        "Pa55w0rd",
+        BuiltinRole::User,
+    )?;
+    let app = make_in_memory_router(store);
+    let token = |login: &Value| login["accessToken"].as_str().unwrap().to_string();
+    let admin = token(&login_from(&app, "admin@example.com", "test").await?);
+    let librarian = token(&login_from(&app, "librarian@example.com", "test").await?);
+    let reader = token(&login_from(&app, "reader@example.com", "test").await?);
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        &v1("/books"),
+        Some(&reader),
+        Some(json!({
+            "title": "The Rust Programming Language",
+            "author": "Steve Klabnik and Carol Nichols",
+            "isbn": "978-1-59327-828-1",
+            "description": "A comprehensive guide to Rust programming."
+        })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let (_, body) = send(&app, "GET", &v1("/books"), Some(&reader), None).await?;
+    let book_id = body["items"][0]["id"].as_str().unwrap().to_string();
+    let (status, _) = send(
+        &app,
+        "DELETE",
+        &v1(&format!("/books/{book_id}")),
+        Some(&librarian),
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(
+        &app,
+        "PUT",
+        &v1(&format!("/users/{reader_id}/role")),
+        Some(&admin),
+        Some(json!({ "role": "Librarian" })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+
+    // reading the log takes its own permission, which librarians lack
+    let (status, _) = send(&app, "GET", &v1("/audit-log"), Some(&librarian), None).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = send(
+        &app,
+        "GET",
+        &v1(&format!("/audit-log?targetType=book&targetId={book_id}")),
+        Some(&admin),
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    let entries = body["items"].as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    let deleted = entries.iter().find(|e| e["action"] == "delete").unwrap();
+    assert_eq!(deleted["actorId"], librarian_id.to_string());
+    assert!(
+        deleted["before"]
+            .as_str()
+            .unwrap()
+            .contains("The Rust Programming Language")
+    );
+    assert_eq!(deleted["after"], Value::Null);
+    assert!(deleted["requestId"].is_string());
+    let created = entries.iter().find(|e| e["action"] == "create").unwrap();
+    assert_eq!(created["actorId"], reader_id.to_string());
+
+    let (_, body) = send(
+        &app,
+        "GET",
+        &v1(&format!("/audit-log?actor={admin_id}")),
+        Some(&admin),
+        None,
+    )
+    .await?;
+    assert_eq!(
+        body["items"],
+        json!([{
+            "id": body["items"][0]["id"],
+            "actorId": admin_id.to_string(),
+            "action": "change_role",
+            "targetType": "user",
+            "targetId": reader_id.to_string(),
+            "before": r#"{"role":"User"}"#,
+            "after": r#"{"role":"Librarian"}"#,
+            "requestId": body["items"][0]["requestId"],
+            "occurredAt": body["items"][0]["occurredAt"],
+        }])
+    );
+
+    let (_, body) = send(
+        &app,
+        "GET",
+        &v1("/audit-log?since=2999-01-01T00:00:00Z"),
+        Some(&admin),
+        None,
+    )
+    .await?;
+    assert_eq!(body["items"], json!([]));
+    let (status, _) = send(
+        &app,
+        "GET",
+        &v1("/audit-log?targetType=shelf"),
+        Some(&admin),
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
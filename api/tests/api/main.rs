@@ -1,2 +1,5 @@
 mod book;
+mod error;
 mod helper;
+mod in_memory;
+mod openapi;
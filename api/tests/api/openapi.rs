@@ -0,0 +1,128 @@
+use std::collections::BTreeSet;
+
+use api::openapi::ApiDoc;
+use utoipa::OpenApi;
+
+const ROUTING_METHODS: [&str; 5] = ["get", "post", "put", "delete", "patch"];
+
+// handler names passed to `get(...)`, `post(...)` etc. in a route module; an
+// argument that is not a handler path, like a closure, fails the test instead of
+// going unchecked
+fn routed_handlers(source: &str) -> anyhow::Result<Vec<String>> {
+    let mut handlers = Vec::new();
+    for method in ROUTING_METHODS {
+        let pattern = format!("{method}(");
+        for (pos, _) in source.match_indices(&pattern) {
+            let preceded_by_ident = source[..pos]
+                .chars()
+                .next_back()
+                .is_some_and(|c| c.is_alphanumeric() || c == '_');
+            if preceded_by_ident {
+                continue;
+            }
+            let rest = &source[pos + pattern.len()..];
+            let mut depth = 0;
+            let end = rest
+                .find(|c| {
+                    match c {
+                        '(' => depth += 1,
+                        ')' if depth == 0 => return true,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
+                    false
+                })
+                .ok_or_else(|| anyhow::anyhow!("unclosed `{pattern}` in a route module"))?;
+            let argument = rest[..end].split_whitespace().collect::<String>();
+            let argument = argument.trim_end_matches(',');
+            let name = argument.rsplit("::").next().unwrap_or_default();
+            anyhow::ensure!(
+                !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_'),
+                "cannot tell the handler of `{pattern}{}`; route a named handler",
+                &rest[..end]
+            );
+            handlers.push(name.to_string());
+        }
+    }
+    Ok(handlers)
+}
+
+#[test]
+fn handlers_are_read_from_any_routing_call() -> anyhow::Result<()> {
+    let source = r#"
+        Router::new()
+            .route("/", get(list_books).post(
+                handler::book::register_book,
+            ))
+            .route("/:id", routing::delete(delete_book))
+    "#;
+    assert_eq!(
+        routed_handlers(source)?,
+        ["list_books", "register_book", "delete_book"]
+    );
+    assert!(routed_handlers(r#".route("/", get(|| async { "ok" }))"#).is_err());
+
+    Ok(())
+}
+
+fn collect_refs(value: &serde_json::Value, refs: &mut BTreeSet<String>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map {
+                match value {
+                    serde_json::Value::String(r) if key == "$ref" => {
+                        refs.insert(r.clone());
+                    }
+                    _ => collect_refs(value, refs),
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values.iter().for_each(|v| collect_refs(v, refs)),
+        _ => {}
+    }
+}
+
+#[test]
+fn every_route_is_documented() -> anyhow::Result<()> {
+    let spec = ApiDoc::openapi();
+    let documented = spec
+        .paths
+        .paths
+        .values()
+        .flat_map(|item| item.operations.values())
+        .filter_map(|op| op.operation_id.clone())
+        .collect::<BTreeSet<_>>();
+
+    let route_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/src/route");
+    let mut undocumented = Vec::new();
+    for entry in std::fs::read_dir(route_dir)? {
+        let source = std::fs::read_to_string(entry?.path())?;
+        undocumented.extend(
+            routed_handlers(&source)?
+                .into_iter()
+                .filter(|h| !documented.contains(h)),
+        );
+    }
+    assert!(
+        undocumented.is_empty(),
+        "routes missing from api::openapi::ApiDoc: {undocumented:?}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn every_schema_reference_resolves() -> anyhow::Result<()> {
+    let spec = serde_json::to_value(ApiDoc::openapi())?;
+
+    let mut refs = BTreeSet::new();
+    collect_refs(&spec, &mut refs);
+    let missing = refs
+        .iter()
+        .filter_map(|r| r.strip_prefix("#/components/schemas/"))
+        .filter(|name| spec["components"]["schemas"].get(name).is_none())
+        .collect::<Vec<_>>();
+    assert!(missing.is_empty(), "unregistered schemas: {missing:?}");
+
+    Ok(())
+}
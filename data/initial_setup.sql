@@ -1,16 +1,10 @@
-INSERT INTO
-    roles (name)
-VALUES
-    ('Admin'),
-    ('User')
-ON CONFLICT DO NOTHING;
-
+-- the password is `Pa55w0rd`; change it after the first login
 INSERT INTO
     users (name, email, password_hash, role_id)
 SELECT
     'Eleazar Fig',
     'eleazar.fig@example.com',
-    '$2b$12$L3ySTHvV8OcJ597/8wUw.OgEwgpZXL02f2.s7nC7hdLDrN5e.iwji',
+    '$argon2id$v=19$m=19456,t=2,p=1$liG3Pqxc+NgWtnKuWTMXiA$suCFu/0jYlKbJuFwofAv7gGVgOoszQNLQaixhF5iq/I',
     role_id
 FROM
     roles
@@ -1,7 +1,7 @@
 import useSWR from "swr";
 import useLocalStorageState from "use-local-storage-state";
 import { ACCESS_TOKEN_KEY } from "../_components/auth";
-import { fetchWithToken } from "../_lib/client";
+import { fetchAllWithToken, fetchWithToken } from "../_lib/client";
 import { Checkout } from "../_types/book";
 
 export const useMyCheckouts = () => {
@@ -34,7 +34,7 @@ export const useBookCheckouts = (bookId: string) => {
   const [accessToken] = useLocalStorageState(ACCESS_TOKEN_KEY);
   const { data, error } = useSWR<{ items: Checkout[] }>(
     [`/api/v1/books/${bookId}/checkout-history`, accessToken],
-    ([destination, token]) => fetchWithToken(destination, token),
+    ([destination, token]) => fetchAllWithToken<Checkout>(destination, token),
   );
   return {
     checkouts: data?.items,
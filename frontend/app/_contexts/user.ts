@@ -1,7 +1,7 @@
 import useSWR from "swr";
 import useLocalStorageState from "use-local-storage-state";
 import { ACCESS_TOKEN_KEY } from "../_components/auth";
-import { fetchWithToken } from "../_lib/client";
+import { fetchAllWithToken, fetchWithToken } from "../_lib/client";
 import { User, Users } from "../_types/user";
 
 export const useCurrentUser = () => {
@@ -21,7 +21,7 @@ export const useUsers = () => {
   const [accessToken] = useLocalStorageState(ACCESS_TOKEN_KEY);
   const { data, error } = useSWR<Users>(
     ["/api/v1/users", accessToken],
-    ([destination, token]) => fetchWithToken(destination, token),
+    ([destination, token]) => fetchAllWithToken<User>(destination, token),
   );
   return {
     users: data,
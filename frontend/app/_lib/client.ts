@@ -10,6 +10,26 @@ export const fetchWithToken = async (
   }).then((res) => res.json());
 };
 
+// follows `nextCursor` until the last page, for lists the API pages through
+export const fetchAllWithToken = async <T>(
+  destination: string,
+  token: string | unknown,
+): Promise<{ items: T[] }> => {
+  const items: T[] = [];
+  let cursor: string | null = "";
+  while (cursor !== null) {
+    const separator = destination.includes("?") ? "&" : "?";
+    const page: { items: T[]; nextCursor: string | null } =
+      await fetchWithToken(
+        `${destination}${separator}limit=100&cursor=${encodeURIComponent(cursor)}`,
+        token,
+      );
+    items.push(...page.items);
+    cursor = page.nextCursor ?? null;
+  }
+  return { items };
+};
+
 const fetcher = async (destination: string, init: RequestInit) => {
   const res = await fetch(
     `${process.env.API_ROOT_PROTOCOL ?? "http"}://${process.env.API_ROOT_URL?.replace(/\/$/g, "") ?? "localhost"
@@ -1,2 +1,5 @@
+pub mod live_event;
+pub mod mailer;
 pub mod model;
 pub mod repository;
+pub mod webhook;
@@ -0,0 +1,21 @@
+use std::pin::Pin;
+
+use async_trait::async_trait;
+use shared::error::AppResult;
+use tokio_stream::Stream;
+
+use crate::model::live_event::{LiveEvent, NewLiveEvent};
+
+pub type LiveEventStream = Pin<Box<dyn Stream<Item = AppResult<LiveEvent>> + Send>>;
+
+// hands events to every subscriber of every instance; only the latest events
+// are kept, for subscribers resuming after a dropped connection
+#[mockall::automock]
+#[async_trait]
+pub trait LiveEventBroker: Send + Sync {
+    async fn publish(&self, event: NewLiveEvent) -> AppResult<()>;
+    // yields the kept events after `last_event_id`, if given, then the ones published
+    // from now on; ends when the subscriber falls too far behind, which it
+    // recovers from by subscribing again
+    async fn subscribe(&self, last_event_id: Option<u64>) -> AppResult<LiveEventStream>;
+}
@@ -0,0 +1,16 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+// a plain-text mail
+#[derive(Debug, Clone)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[mockall::automock]
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, mail: Mail) -> AppResult<()>;
+}
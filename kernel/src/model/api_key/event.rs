@@ -0,0 +1,25 @@
+use derive_new::new;
+
+use crate::model::{
+    api_key::ApiKeyScope,
+    id::{ApiKeyId, UserId},
+};
+
+#[derive(new)]
+pub struct CreateApiKey {
+    pub user_id: UserId,
+    pub name: String,
+    pub scopes: Vec<ApiKeyScope>,
+}
+
+#[derive(new)]
+pub struct RotateApiKey {
+    pub api_key_id: ApiKeyId,
+    pub user_id: UserId,
+}
+
+#[derive(new)]
+pub struct RevokeApiKey {
+    pub api_key_id: ApiKeyId,
+    pub user_id: UserId,
+}
@@ -0,0 +1,51 @@
+use chrono::{DateTime, Utc};
+use strum::{AsRefStr, EnumIter, EnumString};
+
+use crate::model::id::{ApiKeyId, UserId};
+
+pub mod event;
+
+// every key starts with it, so the extractor can tell keys from session tokens
+pub const API_KEY_PREFIX: &str = "rbm_";
+
+// what a key may be used for; a key acts as its user, so it never gets
+// further than the user would
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumString, AsRefStr, EnumIter)]
+#[strum(serialize_all = "snake_case")]
+pub enum ApiKeyScope {
+    // looking up titles and copies
+    BooksRead,
+    // registering and editing titles and copies
+    BooksWrite,
+    // checking out, returning and renewing
+    Checkouts,
+    Reservations,
+}
+
+#[derive(Debug)]
+pub struct ApiKey {
+    pub id: ApiKeyId,
+    pub user_id: UserId,
+    pub name: String,
+    // the start of the key, to tell keys apart without storing them
+    pub prefix: String,
+    pub scopes: Vec<ApiKeyScope>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+pub struct ApiKeySecret(pub String);
+
+// a key as created or rotated; only a hash of `secret` is kept
+pub struct IssuedApiKey {
+    pub api_key: ApiKey,
+    pub secret: ApiKeySecret,
+}
+
+// whom a key acts for, and what for
+#[derive(Debug, Clone)]
+pub struct ApiKeyOwner {
+    pub api_key_id: ApiKeyId,
+    pub user_id: UserId,
+    pub scopes: Vec<ApiKeyScope>,
+}
@@ -0,0 +1,16 @@
+use derive_new::new;
+
+use crate::model::{
+    audit::{AuditAction, AuditTarget},
+    id::UserId,
+};
+
+// the request id and the time are added when the entry is written
+#[derive(new)]
+pub struct RecordAudit {
+    pub actor: Option<UserId>,
+    pub action: AuditAction,
+    pub target: AuditTarget,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
@@ -0,0 +1,96 @@
+use chrono::{DateTime, Utc};
+use strum::{AsRefStr, EnumIter, EnumString};
+
+use crate::model::{
+    id::{AuditEntryId, BookCopyId, BookId, CheckoutId, UserId},
+    list::Cursor,
+};
+
+pub mod event;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, AsRefStr, EnumIter)]
+#[strum(serialize_all = "snake_case")]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+    // by the user, knowing the current password
+    ChangePassword,
+    // with a mailed token
+    ResetPassword,
+    ChangeRole,
+    ChangeStatus,
+    Return,
+    Renew,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, AsRefStr, EnumIter)]
+#[strum(serialize_all = "snake_case")]
+pub enum AuditTargetKind {
+    Book,
+    BookCopy,
+    User,
+    Role,
+    Checkout,
+}
+
+// what an entry is about; roles are named by their name, the rest by their id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditTarget {
+    pub kind: AuditTargetKind,
+    pub id: String,
+}
+
+impl AuditTarget {
+    pub fn book(book_id: BookId) -> Self {
+        Self::new(AuditTargetKind::Book, book_id)
+    }
+    pub fn book_copy(copy_id: BookCopyId) -> Self {
+        Self::new(AuditTargetKind::BookCopy, copy_id)
+    }
+    pub fn user(user_id: UserId) -> Self {
+        Self::new(AuditTargetKind::User, user_id)
+    }
+    pub fn role(name: &str) -> Self {
+        Self::new(AuditTargetKind::Role, name)
+    }
+    pub fn checkout(checkout_id: CheckoutId) -> Self {
+        Self::new(AuditTargetKind::Checkout, checkout_id)
+    }
+
+    fn new(kind: AuditTargetKind, id: impl Into<String>) -> Self {
+        Self {
+            kind,
+            id: id.into(),
+        }
+    }
+}
+
+// one change, as recorded in the transaction that made it; entries are never
+// changed or removed
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub id: AuditEntryId,
+    // none for changes the application makes itself, like purging sign-ups
+    pub actor: Option<UserId>,
+    pub action: AuditAction,
+    pub target: AuditTarget,
+    // JSON of the target; none before it was created and after it was deleted
+    pub before: Option<String>,
+    pub after: Option<String>,
+    // of the HTTP request that made the change, if any
+    pub request_id: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+// every filter given must match; the times are inclusive
+#[derive(Debug, Default)]
+pub struct AuditLogOptions {
+    pub limit: i64,
+    pub cursor: Option<Cursor>,
+    pub actor: Option<UserId>,
+    pub target_kind: Option<AuditTargetKind>,
+    pub target_id: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
@@ -1,16 +1,93 @@
-use crate::model::id::UserId;
+use derive_new::new;
+
+use crate::model::{
+    auth::{RefreshToken, SessionClient},
+    id::{SessionId, UserId},
+};
 
 pub struct CreateToken {
     pub user_id: UserId,
+    pub client: SessionClient,
     pub access_token: String,
+    pub refresh_token: String,
 }
 
 impl CreateToken {
+    pub fn new(user_id: UserId, client: SessionClient) -> Self {
+        Self {
+            user_id,
+            client,
+            access_token: new_token(),
+            refresh_token: new_token(),
+        }
+    }
+}
+
+// exchanges `refresh_token` for the next pair of tokens of the same session
+pub struct RotateToken {
+    pub refresh_token: RefreshToken,
+    pub access_token: String,
+    pub next_refresh_token: String,
+}
+
+impl RotateToken {
+    pub fn new(refresh_token: RefreshToken) -> Self {
+        Self {
+            refresh_token,
+            access_token: new_token(),
+            next_refresh_token: new_token(),
+        }
+    }
+}
+
+#[derive(new)]
+pub struct DeleteSession {
+    pub session_id: SessionId,
+    pub user_id: UserId,
+}
+
+pub struct CreatePasswordReset {
+    pub user_id: UserId,
+    pub token: String,
+}
+
+impl CreatePasswordReset {
     pub fn new(user_id: UserId) -> Self {
-        let access_token = uuid::Uuid::new_v4().simple().to_string();
         Self {
             user_id,
-            access_token,
+            token: new_token(),
         }
     }
 }
+
+pub struct CreateEmailVerification {
+    pub user_id: UserId,
+    pub token: String,
+}
+
+impl CreateEmailVerification {
+    pub fn new(user_id: UserId) -> Self {
+        Self {
+            user_id,
+            token: new_token(),
+        }
+    }
+}
+
+pub struct CreateLoginChallenge {
+    pub user_id: UserId,
+    pub token: String,
+}
+
+impl CreateLoginChallenge {
+    pub fn new(user_id: UserId) -> Self {
+        Self {
+            user_id,
+            token: new_token(),
+        }
+    }
+}
+
+fn new_token() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
@@ -1,3 +1,81 @@
+use chrono::{DateTime, Utc};
+
+use crate::model::id::{SessionId, UserId};
+
 pub mod event;
 
 pub struct AccessToken(pub String);
+
+pub struct RefreshToken(pub String);
+
+pub struct PasswordResetToken(pub String);
+
+pub struct PasswordReset {
+    pub user_id: UserId,
+    pub token: PasswordResetToken,
+    // where the reset is confirmed with the token, if the client has such a page
+    pub link: Option<String>,
+    // seconds until `token` expires
+    pub expires_in: u64,
+}
+
+// a short-lived access token and the refresh token that replaces it once expired
+pub struct AuthTokens {
+    pub user_id: UserId,
+    pub access_token: AccessToken,
+    pub refresh_token: RefreshToken,
+    // seconds until `access_token` expires
+    pub expires_in: u64,
+}
+
+pub struct EmailVerificationToken(pub String);
+
+pub struct EmailVerification {
+    pub user_id: UserId,
+    pub token: EmailVerificationToken,
+    // where the email is verified with the token, if the client has such a page
+    pub link: Option<String>,
+    // seconds until `token` expires
+    pub expires_in: u64,
+}
+
+pub struct LoginChallengeToken(pub String);
+
+// a login whose password was right, waiting for the second factor
+pub struct LoginChallenge {
+    pub user_id: UserId,
+    pub token: LoginChallengeToken,
+    // seconds until `token` expires
+    pub expires_in: u64,
+}
+
+// whom an access token was issued to; tokens issued before sessions existed have none
+#[derive(Debug, Clone, Copy)]
+pub struct TokenOwner {
+    pub user_id: UserId,
+    pub session_id: Option<SessionId>,
+}
+
+// where a session was started from, as reported by the client
+#[derive(Debug, Clone, Default)]
+pub struct SessionClient {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+// a login for `email`, throttled per email and per address
+#[derive(Debug, Clone)]
+pub struct LoginAttempt {
+    pub email: String,
+    pub ip_address: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Session {
+    pub id: SessionId,
+    pub user_id: UserId,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+}
@@ -1,10 +1,16 @@
-use crate::model::id::{BookId, UserId};
+use crate::model::{
+    book::CopyCondition,
+    id::{BookCopyId, BookId, UserId},
+    isbn::Isbn,
+};
 
 pub struct CreateBook {
     pub title: String,
     pub author: String,
-    pub isbn: String,
+    pub isbn: Isbn,
     pub description: String,
+    // registers another copy of an existing title with the same ISBN instead of failing
+    pub add_copy: bool,
 }
 
 #[derive(Debug)]
@@ -12,13 +18,46 @@ pub struct UpdateBook {
     pub book_id: BookId,
     pub title: String,
     pub author: String,
-    pub isbn: String,
+    pub isbn: Isbn,
     pub description: String,
     pub requested_user: UserId,
+    // whether the user may change books they do not own
+    pub manages_books: bool,
 }
 
 #[derive(Debug)]
 pub struct DeleteBook {
     pub book_id: BookId,
     pub requested_user: UserId,
+    pub manages_books: bool,
+}
+
+#[derive(Debug)]
+pub struct CreateBookCopy {
+    pub book_id: BookId,
+    // generated from the copy id when not given
+    pub barcode: Option<String>,
+    pub condition: CopyCondition,
+    pub shelf_location: String,
+    pub requested_user: UserId,
+    pub manages_books: bool,
+}
+
+#[derive(Debug)]
+pub struct UpdateBookCopy {
+    pub copy_id: BookCopyId,
+    pub book_id: BookId,
+    pub barcode: String,
+    pub condition: CopyCondition,
+    pub shelf_location: String,
+    pub requested_user: UserId,
+    pub manages_books: bool,
+}
+
+#[derive(Debug)]
+pub struct DeleteBookCopy {
+    pub copy_id: BookCopyId,
+    pub book_id: BookId,
+    pub requested_user: UserId,
+    pub manages_books: bool,
 }
@@ -1,7 +1,9 @@
 use chrono::{DateTime, Utc};
+use strum::{AsRefStr, EnumIter, EnumString};
 
 use crate::model::{
-    id::{BookId, CheckoutId},
+    id::{BookCopyId, BookId, CheckoutId, UserId},
+    list::Cursor,
     user::{BookOwner, CheckoutUser},
 };
 
@@ -15,13 +17,59 @@ pub struct Book {
     pub isbn: String,
     pub description: String,
     pub owner: BookOwner,
-    pub checkout: Option<Checkout>,
+    pub copies: Vec<BookCopy>,
+}
+
+impl Book {
+    pub fn total_copies(&self) -> usize {
+        self.copies.len()
+    }
+    pub fn available_copies(&self) -> usize {
+        self.copies.iter().filter(|c| c.checkout.is_none()).count()
+    }
 }
 
 #[derive(Debug)]
+pub struct BookCopy {
+    pub id: BookCopyId,
+    pub book_id: BookId,
+    pub barcode: String,
+    pub condition: CopyCondition,
+    pub shelf_location: String,
+    pub checkout: Option<Checkout>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, EnumString, AsRefStr, EnumIter)]
+pub enum CopyCondition {
+    New,
+    #[default]
+    Good,
+    Fair,
+    Poor,
+    Damaged,
+}
+
+#[derive(Debug, Default)]
 pub struct BookListOptions {
     pub limit: i64,
     pub offset: i64,
+    pub keyword: Option<String>,
+    pub owner: Option<UserId>,
+    // `Some(true)` keeps titles whose every copy is checked out
+    pub checked_out: Option<bool>,
+    pub sort: BookSort,
+    // switches `find_all_by_cursor` to the page next to (or before) this position
+    pub cursor: Option<Cursor>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum BookSort {
+    #[default]
+    Newest,
+    Oldest,
+    Title,
+    Author,
 }
 
 #[derive(Debug)]
@@ -1,12 +1,16 @@
 use chrono::{DateTime, Utc};
 use derive_new::new;
 
-use crate::model::id::{BookId, CheckoutId, UserId};
+use crate::model::id::{BookCopyId, BookId, CheckoutId, UserId};
 
 #[derive(new)]
 pub struct CreateCheckout {
     pub book_id: BookId,
+    // any available copy is lent when not given
+    pub copy_id: Option<BookCopyId>,
     pub checked_out_by: UserId,
+    // the borrower, or the staff member lending to them at the desk
+    pub issued_by: UserId,
     pub checked_out_at: DateTime<Utc>,
 }
 
@@ -15,5 +19,17 @@ pub struct UpdateReturned {
     pub checkout_id: CheckoutId,
     pub book_id: BookId,
     pub returned_by: UserId,
+    // lets `returned_by` return a checkout that is not theirs
+    pub manages_checkouts: bool,
+    // why staff forced the return
+    pub reason: Option<String>,
     pub returned_at: DateTime<Utc>,
 }
+
+#[derive(new)]
+pub struct RenewCheckout {
+    pub checkout_id: CheckoutId,
+    pub book_id: BookId,
+    pub renewed_by: UserId,
+    pub renewed_at: DateTime<Utc>,
+}
@@ -1,6 +1,6 @@
 use chrono::{DateTime, Utc};
 
-use crate::model::id::{BookId, CheckoutId, UserId};
+use crate::model::id::{BookCopyId, BookId, CheckoutId, UserId};
 
 pub mod event;
 
@@ -8,14 +8,29 @@ pub mod event;
 pub struct Checkout {
     pub id: CheckoutId,
     pub checked_out_by: UserId,
+    // who lent the copy, the borrower unless checked out at the desk
+    pub issued_by: UserId,
     pub checked_out_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+    pub renewal_count: i32,
     pub returned_at: Option<DateTime<Utc>>,
+    // who returned the copy, with a reason when staff forced it
+    pub returned_by: Option<UserId>,
+    pub return_reason: Option<String>,
     pub book: CheckoutBook,
 }
 
+impl Checkout {
+    // returned checkouts are judged by their return time, open ones by `now`
+    pub fn is_overdue(&self, now: DateTime<Utc>) -> bool {
+        self.returned_at.unwrap_or(now) > self.due_at
+    }
+}
+
 #[derive(Debug)]
 pub struct CheckoutBook {
     pub book_id: BookId,
+    pub copy_id: BookCopyId,
     pub title: String,
     pub author: String,
     pub isbn: String,
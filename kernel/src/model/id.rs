@@ -14,6 +14,7 @@ macro_rules! define_id {
             serde::Serialize,
             serde::Deserialize,
             sqlx::Type,
+            utoipa::ToSchema,
         )]
         #[serde(into = "String")]
         #[sqlx(transparent)]
@@ -65,4 +66,13 @@ macro_rules! define_id {
 
 define_id!(UserId);
 define_id!(BookId);
+define_id!(BookCopyId);
 define_id!(CheckoutId);
+define_id!(ReservationId);
+define_id!(SessionId);
+define_id!(ApiKeyId);
+define_id!(JobLeaseId);
+define_id!(NotificationId);
+define_id!(WebhookId);
+define_id!(WebhookDeliveryId);
+define_id!(AuditEntryId);
@@ -0,0 +1,126 @@
+use std::{fmt, str::FromStr};
+
+use shared::error::{AppError, AppResult};
+
+// an ISBN normalized to its 13-digit form without hyphens
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Isbn(String);
+
+impl Isbn {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl FromStr for Isbn {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> AppResult<Self> {
+        let invalid = || AppError::InvalidIsbn(format!("{s} is not a valid ISBN"));
+
+        let chars = s
+            .chars()
+            .filter(|c| !matches!(c, '-' | ' '))
+            .collect::<Vec<_>>();
+        let digits = match chars.as_slice() {
+            [body @ .., check] if chars.len() == 10 => {
+                let mut digits = body
+                    .iter()
+                    .map(|c| c.to_digit(10))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(invalid)?;
+                digits.push(match check {
+                    'X' | 'x' => 10,
+                    c => c.to_digit(10).ok_or_else(invalid)?,
+                });
+                if isbn10_checksum(&digits) != 0 {
+                    return Err(invalid());
+                }
+                let mut digits = [9, 7, 8]
+                    .into_iter()
+                    .chain(digits.into_iter().take(9))
+                    .collect::<Vec<_>>();
+                digits.push((10 - isbn13_checksum(&digits)) % 10);
+                digits
+            }
+            _ if chars.len() == 13 => {
+                let digits = chars
+                    .iter()
+                    .map(|c| c.to_digit(10))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(invalid)?;
+                if !matches!(digits[..3], [9, 7, 8] | [9, 7, 9]) || isbn13_checksum(&digits) != 0 {
+                    return Err(invalid());
+                }
+                digits
+            }
+            _ => return Err(invalid()),
+        };
+
+        Ok(Self(digits.iter().map(|d| d.to_string()).collect()))
+    }
+}
+
+// weights the digits 10, 9, ..., 1; a valid ISBN-10 sums to a multiple of 11
+fn isbn10_checksum(digits: &[u32]) -> u32 {
+    digits
+        .iter()
+        .zip((1..=10).rev())
+        .map(|(d, w)| d * w)
+        .sum::<u32>()
+        % 11
+}
+
+// weights the digits alternately 1 and 3; a valid ISBN-13 sums to a multiple of 10
+fn isbn13_checksum(digits: &[u32]) -> u32 {
+    digits
+        .iter()
+        .zip([1, 3].into_iter().cycle())
+        .map(|(d, w)| d * w)
+        .sum::<u32>()
+        % 10
+}
+
+impl fmt::Display for Isbn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for Isbn {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_isbn() -> anyhow::Result<()> {
+        assert_eq!("9781593278281".parse::<Isbn>()?.as_str(), "9781593278281");
+        assert_eq!(
+            "978-1-59327-828-1".parse::<Isbn>()?.as_str(),
+            "9781593278281"
+        );
+        assert_eq!("1593278284".parse::<Isbn>()?.as_str(), "9781593278281");
+        assert_eq!("0-8044-2957-X".parse::<Isbn>()?.as_str(), "9780804429573");
+        assert_eq!(
+            "979-10-90636-07-1".parse::<Isbn>()?.as_str(),
+            "9791090636071"
+        );
+
+        assert!("9781593278282".parse::<Isbn>().is_err());
+        assert!("1593278285".parse::<Isbn>().is_err());
+        assert!("9771593278282".parse::<Isbn>().is_err());
+        assert!("159327828".parse::<Isbn>().is_err());
+        assert!("X593278284".parse::<Isbn>().is_err());
+        assert!("".parse::<Isbn>().is_err());
+        Ok(())
+    }
+}
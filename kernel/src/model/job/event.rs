@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+use derive_new::new;
+
+use crate::model::id::JobLeaseId;
+
+#[derive(new)]
+pub struct ScheduleJob {
+    pub name: String,
+    // a cron expression, kept to tell when the schedule changes
+    pub schedule: String,
+    pub next_run_at: DateTime<Utc>,
+}
+
+#[derive(new)]
+pub struct CompleteJob {
+    pub name: String,
+    pub lease_id: JobLeaseId,
+    pub finished_at: DateTime<Utc>,
+    pub next_run_at: DateTime<Utc>,
+}
+
+#[derive(new)]
+pub struct FailJob {
+    pub name: String,
+    pub lease_id: JobLeaseId,
+    pub failed_at: DateTime<Utc>,
+    pub error: String,
+    // failed runs so far, or 0 when giving up until the next scheduled time
+    pub attempts: i32,
+    pub next_run_at: DateTime<Utc>,
+}
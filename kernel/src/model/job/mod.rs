@@ -0,0 +1,13 @@
+use crate::model::id::JobLeaseId;
+
+pub mod event;
+
+// a due job an instance holds until it reports the run or the lease runs out;
+// reports have to name the lease, so a run overtaken by another instance is ignored
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobLease {
+    pub name: String,
+    pub lease_id: JobLeaseId,
+    // failed runs since the last successful one
+    pub attempts: i32,
+}
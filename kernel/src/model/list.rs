@@ -1,3 +1,8 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use shared::error::AppError;
+
 #[derive(Debug)]
 pub struct PaginatedList<T> {
     pub total: i64,
@@ -11,3 +16,163 @@ impl<T> PaginatedList<T> {
         self.items
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorDirection {
+    Next,
+    Prev,
+}
+
+// position of a row in a keyset ordered by `(timestamp, id)`;
+// clients only ever see the encoded form
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub direction: CursorDirection,
+    pub timestamp: DateTime<Utc>,
+    pub id: uuid::Uuid,
+}
+
+impl Cursor {
+    pub fn new(direction: CursorDirection, timestamp: DateTime<Utc>, id: uuid::Uuid) -> Self {
+        Self {
+            direction,
+            timestamp,
+            id,
+        }
+    }
+    pub fn encode(&self) -> String {
+        let direction = match self.direction {
+            CursorDirection::Next => 'n',
+            CursorDirection::Prev => 'p',
+        };
+        format!(
+            "{}{:x}.{:x}.{}",
+            direction,
+            self.timestamp.timestamp(),
+            self.timestamp.timestamp_subsec_micros(),
+            self.id.simple()
+        )
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || AppError::InvalidCursor(format!("invalid cursor: {s}"));
+
+        let direction = match s.chars().next() {
+            Some('n') => CursorDirection::Next,
+            Some('p') => CursorDirection::Prev,
+            _ => return Err(invalid()),
+        };
+        let mut parts = s[1..].splitn(3, '.');
+        let (Some(secs), Some(micros), Some(id)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        let secs = i64::from_str_radix(secs, 16).map_err(|_| invalid())?;
+        let micros = u32::from_str_radix(micros, 16)
+            .ok()
+            .filter(|micros| *micros < 1_000_000)
+            .ok_or_else(invalid)?;
+        let timestamp = DateTime::from_timestamp(secs, micros * 1_000).ok_or_else(invalid)?;
+        let id = uuid::Uuid::parse_str(id).map_err(|_| invalid())?;
+
+        Ok(Self::new(direction, timestamp, id))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CursorListOptions {
+    pub limit: i64,
+    pub cursor: Option<Cursor>,
+}
+
+#[derive(Debug)]
+pub struct CursorPaginatedList<T> {
+    pub limit: i64,
+    pub next_cursor: Option<Cursor>,
+    pub prev_cursor: Option<Cursor>,
+    pub items: Vec<T>,
+}
+
+impl<T> CursorPaginatedList<T> {
+    // `rows` must hold up to `limit + 1` rows read in the direction of `cursor`,
+    // the extra row only tells whether another page exists
+    pub fn from_rows(
+        mut rows: Vec<T>,
+        limit: i64,
+        cursor: Option<Cursor>,
+        key: impl Fn(&T) -> (DateTime<Utc>, uuid::Uuid),
+    ) -> Self {
+        let direction = cursor.map(|c| c.direction).unwrap_or(CursorDirection::Next);
+        let has_more = rows.len() as i64 > limit;
+        rows.truncate(limit.max(0) as usize);
+        if direction == CursorDirection::Prev {
+            rows.reverse();
+        }
+
+        let (has_next, has_prev) = match direction {
+            CursorDirection::Next => (has_more, cursor.is_some()),
+            CursorDirection::Prev => (true, has_more),
+        };
+        let next_cursor = rows.last().filter(|_| has_next).map(|item| {
+            let (timestamp, id) = key(item);
+            Cursor::new(CursorDirection::Next, timestamp, id)
+        });
+        let prev_cursor = rows.first().filter(|_| has_prev).map(|item| {
+            let (timestamp, id) = key(item);
+            Cursor::new(CursorDirection::Prev, timestamp, id)
+        });
+
+        Self {
+            limit,
+            next_cursor,
+            prev_cursor,
+            items: rows,
+        }
+    }
+
+    pub fn into_inner(self) -> Vec<T> {
+        self.items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_roundtrip() -> anyhow::Result<()> {
+        let timestamp = DateTime::from_timestamp(1_767_225_600, 123_000_000).unwrap();
+        let cursor = Cursor::new(CursorDirection::Prev, timestamp, uuid::Uuid::new_v4());
+        assert_eq!(cursor.encode().parse::<Cursor>()?, cursor);
+        assert!("x1.2.3".parse::<Cursor>().is_err());
+        assert!("n1.2".parse::<Cursor>().is_err());
+        // more microseconds than a second has
+        let id = uuid::Uuid::new_v4().simple();
+        assert!(format!("n1.f4240.{id}").parse::<Cursor>().is_err());
+        assert!(format!("n1.ffffffff.{id}").parse::<Cursor>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_rows() {
+        let base = DateTime::from_timestamp(1_767_225_600, 0).unwrap();
+        let rows = (0..4)
+            .map(|i| (base + chrono::Duration::seconds(i), uuid::Uuid::new_v4()))
+            .collect::<Vec<_>>();
+
+        let page = CursorPaginatedList::from_rows(rows.clone(), 3, None, |r| *r);
+        assert_eq!(page.items.len(), 3);
+        assert!(page.prev_cursor.is_none());
+        assert_eq!(page.next_cursor.map(|c| c.id), Some(rows[2].1));
+
+        let cursor = Cursor::new(CursorDirection::Prev, base, uuid::Uuid::new_v4());
+        let page = CursorPaginatedList::from_rows(rows.clone(), 5, Some(cursor), |r| *r);
+        assert_eq!(page.items.first(), rows.last());
+        assert!(page.prev_cursor.is_none());
+        assert!(page.next_cursor.is_some());
+    }
+}
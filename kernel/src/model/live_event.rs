@@ -0,0 +1,65 @@
+use chrono::{DateTime, Utc};
+use derive_new::new;
+use strum::{AsRefStr, EnumString};
+
+use crate::model::id::BookId;
+
+// changes clients watching the library are told of as they happen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, AsRefStr)]
+pub enum LiveEventKind {
+    #[strum(serialize = "book.created")]
+    BookCreated,
+    // also sent when copies are added, changed or removed
+    #[strum(serialize = "book.updated")]
+    BookUpdated,
+    #[strum(serialize = "book.deleted")]
+    BookDeleted,
+    #[strum(serialize = "checkout.created")]
+    CheckoutCreated,
+    #[strum(serialize = "checkout.returned")]
+    CheckoutReturned,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookAvailability {
+    // copies on the shelf, that is not checked out
+    pub available_copies: i64,
+    pub total_copies: i64,
+}
+
+#[derive(Debug, Clone, new)]
+pub struct NewLiveEvent {
+    pub kind: LiveEventKind,
+    pub book_id: BookId,
+    // none once the book is deleted
+    pub availability: Option<BookAvailability>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LiveEvent {
+    // increases with every event across all instances; clients resume after it
+    pub id: u64,
+    pub kind: LiveEventKind,
+    pub book_id: BookId,
+    pub availability: Option<BookAvailability>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl LiveEvent {
+    pub fn new(id: u64, event: NewLiveEvent) -> Self {
+        let NewLiveEvent {
+            kind,
+            book_id,
+            availability,
+            occurred_at,
+        } = event;
+        Self {
+            id,
+            kind,
+            book_id,
+            availability,
+            occurred_at,
+        }
+    }
+}
@@ -1,7 +1,16 @@
+pub mod api_key;
+pub mod audit;
 pub mod auth;
 pub mod book;
 pub mod checkout;
 pub mod id;
+pub mod isbn;
+pub mod job;
 pub mod list;
+pub mod live_event;
+pub mod notification;
+pub mod reservation;
 pub mod role;
+pub mod totp;
 pub mod user;
+pub mod webhook;
@@ -0,0 +1,41 @@
+use chrono::{DateTime, Utc};
+use derive_new::new;
+
+use crate::model::{
+    id::{BookId, NotificationId, UserId},
+    notification::{NotificationKind, NotificationPreference},
+};
+
+#[derive(new)]
+pub struct QueueNotification {
+    pub user_id: UserId,
+    pub kind: NotificationKind,
+    pub book_id: BookId,
+    pub due_at: Option<DateTime<Utc>>,
+    // a notification with the key of one queued before is dropped, so a
+    // reminder queued again by a retried job goes out once
+    pub key: Option<String>,
+    pub queued_at: DateTime<Utc>,
+}
+
+#[derive(new)]
+pub struct MarkNotificationSent {
+    pub id: NotificationId,
+    pub sent_at: DateTime<Utc>,
+}
+
+#[derive(new)]
+pub struct MarkNotificationFailed {
+    pub id: NotificationId,
+    pub failed_at: DateTime<Utc>,
+    pub error: String,
+    // no further attempt when `None`
+    pub next_attempt_at: Option<DateTime<Utc>>,
+}
+
+#[derive(new)]
+pub struct UpdateNotificationPreferences {
+    pub user_id: UserId,
+    // kinds left out keep their setting
+    pub preferences: Vec<NotificationPreference>,
+}
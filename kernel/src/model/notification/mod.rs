@@ -0,0 +1,52 @@
+use chrono::{DateTime, Utc};
+use strum::{AsRefStr, EnumIter, EnumString};
+
+use crate::model::id::{BookId, NotificationId, UserId};
+
+pub mod event;
+pub mod template;
+
+// what a notification tells its recipient about; users opt out per kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumString, AsRefStr, EnumIter)]
+#[strum(serialize_all = "snake_case")]
+pub enum NotificationKind {
+    CheckoutCreated,
+    CheckoutReturned,
+    DueSoon,
+    Overdue,
+    // a reserved book came back and is held for the user
+    ReservationAvailable,
+}
+
+// a notification waiting in the outbox, with what its template needs
+#[derive(Debug)]
+pub struct Notification {
+    pub id: NotificationId,
+    pub kind: NotificationKind,
+    pub recipient: NotificationRecipient,
+    pub book: NotificationBook,
+    // when the checkout is due, or when the hold on a reserved book ends
+    pub due_at: Option<DateTime<Utc>>,
+    // failed deliveries so far
+    pub attempts: i32,
+}
+
+#[derive(Debug)]
+pub struct NotificationRecipient {
+    pub user_id: UserId,
+    pub name: String,
+    pub email: String,
+}
+
+#[derive(Debug)]
+pub struct NotificationBook {
+    pub book_id: BookId,
+    pub title: String,
+    pub author: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotificationPreference {
+    pub kind: NotificationKind,
+    pub enabled: bool,
+}
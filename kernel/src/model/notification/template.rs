@@ -0,0 +1,67 @@
+use crate::{
+    mailer::Mail,
+    model::notification::{Notification, NotificationKind},
+};
+
+// the plain-text mail of a notification kind; `{name}`, `{title}`, `{author}`
+// and `{due_date}` are replaced with the notification's values
+pub struct Template {
+    pub subject: &'static str,
+    pub body: &'static str,
+}
+
+impl NotificationKind {
+    pub fn template(self) -> Template {
+        match self {
+            NotificationKind::CheckoutCreated => Template {
+                subject: "You checked out {title}",
+                body: "Hello {name},\n\n\
+                       you checked out {title} by {author}. Please return it by {due_date}.",
+            },
+            NotificationKind::CheckoutReturned => Template {
+                subject: "You returned {title}",
+                body: "Hello {name},\n\n\
+                       we received {title} by {author} back. Thank you!",
+            },
+            NotificationKind::DueSoon => Template {
+                subject: "{title} is due soon",
+                body: "Hello {name},\n\n\
+                       {title} by {author} is due on {due_date}. \
+                       Please return or renew it by then.",
+            },
+            NotificationKind::Overdue => Template {
+                subject: "{title} is overdue",
+                body: "Hello {name},\n\n\
+                       {title} by {author} was due on {due_date}. \
+                       Please return or renew it as soon as you can.",
+            },
+            NotificationKind::ReservationAvailable => Template {
+                subject: "{title} is ready for you",
+                body: "Hello {name},\n\n\
+                       {title} by {author}, which you reserved, is back. \
+                       It is held for you until {due_date}.",
+            },
+        }
+    }
+}
+
+impl Notification {
+    pub fn render(&self) -> Mail {
+        let template = self.kind.template();
+        let due_date = self
+            .due_at
+            .map(|at| at.format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+        let fill = |text: &str| {
+            text.replace("{name}", &self.recipient.name)
+                .replace("{title}", &self.book.title)
+                .replace("{author}", &self.book.author)
+                .replace("{due_date}", &due_date)
+        };
+        Mail {
+            to: self.recipient.email.clone(),
+            subject: fill(template.subject),
+            body: fill(template.body),
+        }
+    }
+}
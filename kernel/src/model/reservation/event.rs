@@ -0,0 +1,20 @@
+use chrono::{DateTime, Utc};
+use derive_new::new;
+
+use crate::model::id::{BookId, ReservationId, UserId};
+
+#[derive(new)]
+pub struct CreateReservation {
+    pub book_id: BookId,
+    pub reserved_by: UserId,
+    pub reserved_at: DateTime<Utc>,
+}
+
+#[derive(new)]
+pub struct DeleteReservation {
+    pub reservation_id: ReservationId,
+    pub book_id: BookId,
+    pub requested_user: UserId,
+    // whether the user may cancel others' reservations too
+    pub manages_checkouts: bool,
+}
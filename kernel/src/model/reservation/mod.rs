@@ -0,0 +1,15 @@
+use chrono::{DateTime, Utc};
+
+use crate::model::id::{BookId, ReservationId, UserId};
+
+pub mod event;
+
+#[derive(Debug)]
+pub struct Reservation {
+    pub id: ReservationId,
+    pub book_id: BookId,
+    pub reserved_by: UserId,
+    pub reserved_at: DateTime<Utc>,
+    // set while the reservation holds the exclusive right to check out the book
+    pub claim_expires_at: Option<DateTime<Utc>>,
+}
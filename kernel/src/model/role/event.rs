@@ -0,0 +1,20 @@
+use derive_new::new;
+
+use crate::model::{
+    id::UserId,
+    role::{BorrowingPolicy, Permission},
+};
+
+#[derive(new)]
+pub struct CreateRole {
+    pub name: String,
+    pub permissions: Vec<Permission>,
+    pub requested_user: UserId,
+}
+
+#[derive(new)]
+pub struct UpdateBorrowingPolicy {
+    pub role_name: String,
+    pub policy: BorrowingPolicy,
+    pub requested_user: UserId,
+}
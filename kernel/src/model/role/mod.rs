@@ -0,0 +1,94 @@
+use strum::{AsRefStr, EnumIter, EnumString, IntoEnumIterator};
+
+pub mod event;
+
+// what a role allows beyond using the library as a member; declared in the
+// order of their names, which is the order roles list them in
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, EnumString, AsRefStr, EnumIter,
+)]
+#[strum(serialize_all = "snake_case")]
+pub enum Permission {
+    // editing and deleting any title and its copies, not only the user's own
+    ManageBooks,
+    // lending on behalf of others, like returning or cancelling for them
+    ManageCheckouts,
+    // creating roles and assigning them to users
+    ManageRoles,
+    // registering, approving, unlocking and deleting users
+    ManageUsers,
+    // subscribing endpoints to changes and inspecting their deliveries
+    ManageWebhooks,
+    // reading the log of who changed what
+    ViewAuditLog,
+}
+
+// roles are told apart by their name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Role {
+    pub name: String,
+    pub permissions: Vec<Permission>,
+}
+
+impl Role {
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.permissions.contains(&permission)
+    }
+
+    // a role that manages accounts counts as an admin one, like for mandatory TOTP
+    pub fn is_admin(&self) -> bool {
+        self.has_permission(Permission::ManageUsers) || self.has_permission(Permission::ManageRoles)
+    }
+}
+
+// limits on what members of a role may borrow, checked on every checkout;
+// the default one restricts nothing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BorrowingPolicy {
+    // concurrent loans, unlimited when `None`
+    pub max_loans: Option<i32>,
+    // no new checkouts while any loan is past its due date
+    pub block_when_overdue: bool,
+    // no checking out books the borrower registered themselves
+    pub block_own_books: bool,
+}
+
+// the roles every installation starts with; admins can create others
+#[derive(Debug, Clone, Copy, EnumString, AsRefStr, EnumIter, Default, PartialEq, Eq)]
+pub enum BuiltinRole {
+    Admin,
+    Librarian,
+    #[default]
+    User,
+}
+
+impl BuiltinRole {
+    pub fn permissions(self) -> Vec<Permission> {
+        match self {
+            BuiltinRole::Admin => Permission::iter().collect(),
+            BuiltinRole::Librarian => vec![Permission::ManageBooks, Permission::ManageCheckouts],
+            BuiltinRole::User => Vec::new(),
+        }
+    }
+
+    // what the migration creating policies gives each of them
+    pub fn borrowing_policy(self) -> BorrowingPolicy {
+        match self {
+            BuiltinRole::Admin | BuiltinRole::Librarian => BorrowingPolicy::default(),
+            BuiltinRole::User => BorrowingPolicy {
+                max_loans: Some(5),
+                block_when_overdue: true,
+                block_own_books: false,
+            },
+        }
+    }
+}
+
+impl From<BuiltinRole> for Role {
+    fn from(value: BuiltinRole) -> Self {
+        Role {
+            name: value.as_ref().into(),
+            permissions: value.permissions(),
+        }
+    }
+}
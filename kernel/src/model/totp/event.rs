@@ -0,0 +1,23 @@
+use derive_new::new;
+
+use crate::model::id::UserId;
+
+// `account_name` is how authenticator apps label the account, like its email
+#[derive(new)]
+pub struct BeginTotpEnrollment {
+    pub user_id: UserId,
+    pub account_name: String,
+}
+
+#[derive(new)]
+pub struct ConfirmTotpEnrollment {
+    pub user_id: UserId,
+    pub code: String,
+}
+
+// `code` is either a code of the authenticator or a recovery code
+#[derive(new)]
+pub struct VerifyTotp {
+    pub user_id: UserId,
+    pub code: String,
+}
@@ -0,0 +1,11 @@
+pub mod event;
+
+// what an authenticator app needs to add the account
+pub struct TotpEnrollment {
+    // base32, for entering by hand
+    pub secret: String,
+    // `otpauth://` URI, usually shown as a QR code
+    pub provisioning_uri: String,
+}
+
+pub struct RecoveryCode(pub String);
@@ -1,15 +1,22 @@
-use crate::model::{id::UserId, role::Role};
+use crate::model::{id::UserId, user::UserStatus};
 
 pub struct CreateUser {
+    // chosen by the caller, so anything keyed by the user can be kept before it
+    pub user_id: UserId,
     pub name: String,
     pub email: String,
     pub password: String,
+    pub status: UserStatus,
+    // the admin registering the user; none when they sign up themselves
+    pub created_by: Option<UserId>,
 }
 
 #[derive(Debug)]
 pub struct UpdateUserRole {
     pub user_id: UserId,
-    pub role: Role,
+    // the name of the role
+    pub role: String,
+    pub requested_user: UserId,
 }
 
 #[derive(Debug)]
@@ -19,7 +26,25 @@ pub struct UpdateUserPassword {
     pub new_password: String,
 }
 
+// sets a new password without knowing the current one, after a confirmed reset
+#[derive(Debug)]
+pub struct ResetUserPassword {
+    pub user_id: UserId,
+    pub new_password: String,
+}
+
+// moves a user on from `from`; fails when the user is in another status by now
+#[derive(Debug)]
+pub struct UpdateUserStatus {
+    pub user_id: UserId,
+    pub from: UserStatus,
+    pub to: UserStatus,
+    // the user themselves when they verify their address
+    pub requested_user: UserId,
+}
+
 #[derive(Debug)]
 pub struct DeleteUser {
     pub user_id: UserId,
+    pub requested_user: UserId,
 }
@@ -1,3 +1,5 @@
+use strum::{AsRefStr, EnumString};
+
 use crate::model::{id::UserId, role::Role};
 
 pub mod event;
@@ -8,6 +10,19 @@ pub struct User {
     pub name: String,
     pub email: String,
     pub role: Role,
+    pub status: UserStatus,
+}
+
+// only active users can log in; the others signed up themselves and are not
+// through verification or approval yet, or were turned down
+#[derive(Debug, Clone, Copy, EnumString, AsRefStr, Default, PartialEq, Eq)]
+#[strum(serialize_all = "snake_case")]
+pub enum UserStatus {
+    #[default]
+    Active,
+    Unverified,
+    PendingApproval,
+    Rejected,
 }
 
 #[derive(Debug)]
@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+use derive_new::new;
+
+use crate::model::{
+    id::{UserId, WebhookDeliveryId, WebhookId},
+    webhook::WebhookEventType,
+};
+
+#[derive(new)]
+pub struct CreateWebhook {
+    pub url: String,
+    pub event_types: Vec<WebhookEventType>,
+    pub created_by: UserId,
+}
+
+#[derive(new)]
+pub struct DeleteWebhook {
+    pub webhook_id: WebhookId,
+}
+
+#[derive(new)]
+pub struct MarkDeliveryDelivered {
+    pub delivery_id: WebhookDeliveryId,
+    pub delivered_at: DateTime<Utc>,
+    pub response_status: i32,
+}
+
+#[derive(new)]
+pub struct MarkDeliveryFailed {
+    pub delivery_id: WebhookDeliveryId,
+    pub failed_at: DateTime<Utc>,
+    pub response_status: Option<i32>,
+    pub error: String,
+    // moves the delivery to the dead letters when `None`
+    pub next_attempt_at: Option<DateTime<Utc>>,
+}
+
+// sends a dead letter again, starting over with its attempts
+#[derive(new)]
+pub struct RetryDelivery {
+    pub webhook_id: WebhookId,
+    pub delivery_id: WebhookDeliveryId,
+    pub retry_at: DateTime<Utc>,
+}
@@ -0,0 +1,76 @@
+use chrono::{DateTime, Utc};
+use strum::{AsRefStr, EnumIter, EnumString};
+
+use crate::model::id::{UserId, WebhookDeliveryId, WebhookId};
+
+pub mod event;
+
+// what a subscription can be notified of; the names are part of the payloads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumString, AsRefStr, EnumIter)]
+pub enum WebhookEventType {
+    #[strum(serialize = "book.created")]
+    BookCreated,
+    #[strum(serialize = "book.updated")]
+    BookUpdated,
+    #[strum(serialize = "book.deleted")]
+    BookDeleted,
+    #[strum(serialize = "checkout.created")]
+    CheckoutCreated,
+    #[strum(serialize = "checkout.returned")]
+    CheckoutReturned,
+}
+
+// an endpoint that gets a signed POST for each event of `event_types`
+#[derive(Debug)]
+pub struct Webhook {
+    pub id: WebhookId,
+    pub url: String,
+    pub event_types: Vec<WebhookEventType>,
+    pub created_by: UserId,
+    pub created_at: DateTime<Utc>,
+}
+
+// a subscription as created; `secret` signs its payloads and is shown only once
+pub struct CreatedWebhook {
+    pub webhook: Webhook,
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    // every attempt failed; only a manual retry sends it again
+    DeadLetter,
+}
+
+// one event sent to one subscription, with the outcome of its last attempt
+#[derive(Debug)]
+pub struct WebhookDelivery {
+    pub id: WebhookDeliveryId,
+    pub webhook_id: WebhookId,
+    pub event_type: WebhookEventType,
+    // the JSON body, as signed
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    // the HTTP status of the last response, if the endpoint answered at all
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+// a delivery due for an attempt, with what is needed to send it
+#[derive(Debug)]
+pub struct PendingDelivery {
+    pub id: WebhookDeliveryId,
+    pub url: String,
+    pub secret: String,
+    pub event_type: WebhookEventType,
+    pub payload: String,
+    pub attempts: i32,
+}
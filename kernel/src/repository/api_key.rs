@@ -0,0 +1,28 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use shared::error::AppResult;
+
+use crate::model::{
+    api_key::{
+        ApiKey, ApiKeyOwner, ApiKeySecret, IssuedApiKey,
+        event::{CreateApiKey, RevokeApiKey, RotateApiKey},
+    },
+    id::UserId,
+};
+
+#[mockall::automock]
+#[async_trait]
+pub trait ApiKeyRepository: Send + Sync {
+    async fn create(&self, event: CreateApiKey) -> AppResult<IssuedApiKey>;
+    // keys that are not revoked, newest first
+    async fn find_by_user_id(&self, user_id: UserId) -> AppResult<Vec<ApiKey>>;
+    // replaces the secret of the key, keeping its name and scopes; fails with
+    // `EntityNotFound` unless the key belongs to the user and is not revoked
+    async fn rotate(&self, event: RotateApiKey) -> AppResult<IssuedApiKey>;
+    // fails with `EntityNotFound` unless the key belongs to the user and is not revoked
+    async fn revoke(&self, event: RevokeApiKey) -> AppResult<()>;
+    // none unless the key exists and is not revoked; also records that it was used
+    async fn authenticate(&self, secret: &ApiKeySecret) -> AppResult<Option<ApiKeyOwner>>;
+    // removes keys revoked before `revoked_before`, returning how many
+    async fn delete_revoked(&self, revoked_before: DateTime<Utc>) -> AppResult<u64>;
+}
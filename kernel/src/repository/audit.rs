@@ -0,0 +1,19 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::{
+    audit::{AuditEntry, AuditLogOptions},
+    list::CursorPaginatedList,
+};
+
+// the log of changes to books, users, roles and checkouts; the repositories of
+// those write it in the transactions of the changes, so it is only read here
+#[mockall::automock]
+#[async_trait]
+pub trait AuditRepository: Send + Sync {
+    // newest first
+    async fn find_all(
+        &self,
+        options: AuditLogOptions,
+    ) -> AppResult<CursorPaginatedList<AuditEntry>>;
+}
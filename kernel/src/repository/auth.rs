@@ -2,18 +2,28 @@ use async_trait::async_trait;
 use shared::error::AppResult;
 
 use crate::model::{
-    auth::{AccessToken, event::CreateToken},
+    auth::{
+        AccessToken, AuthTokens, Session, TokenOwner,
+        event::{CreateToken, DeleteSession, RotateToken},
+    },
     id::UserId,
 };
 
 #[mockall::automock]
 #[async_trait]
 pub trait AuthRepository: Send + Sync {
-    async fn fetch_user_id_from_token(
-        &self,
-        access_token: &AccessToken,
-    ) -> AppResult<Option<UserId>>;
+    // also records that the token's session has just been used
+    async fn fetch_token_owner(&self, access_token: &AccessToken) -> AppResult<Option<TokenOwner>>;
     async fn verify_user(&self, email: &str, password: &str) -> AppResult<UserId>;
-    async fn create_token(&self, event: CreateToken) -> AppResult<AccessToken>;
+    // starts a new session
+    async fn create_token(&self, event: CreateToken) -> AppResult<AuthTokens>;
+    // presenting a refresh token that was already rotated revokes its whole session
+    async fn rotate_token(&self, event: RotateToken) -> AppResult<AuthTokens>;
+    // ends the session the access token belongs to
     async fn delete_token(&self, access_token: AccessToken) -> AppResult<()>;
+    // active sessions of the user, most recently used first
+    async fn find_sessions(&self, user_id: UserId) -> AppResult<Vec<Session>>;
+    // fails with `EntityNotFound` unless the session belongs to the user
+    async fn delete_session(&self, event: DeleteSession) -> AppResult<()>;
+    async fn delete_all_sessions(&self, user_id: UserId) -> AppResult<()>;
 }
@@ -3,19 +3,30 @@ use shared::error::AppResult;
 
 use crate::model::{
     book::{
-        Book, BookListOptions,
-        event::{CreateBook, DeleteBook, UpdateBook},
+        Book, BookCopy, BookListOptions,
+        event::{
+            CreateBook, CreateBookCopy, DeleteBook, DeleteBookCopy, UpdateBook, UpdateBookCopy,
+        },
     },
-    id::{BookId, UserId},
-    list::PaginatedList,
+    id::{BookCopyId, BookId, UserId},
+    list::{CursorPaginatedList, PaginatedList},
 };
 
 #[mockall::automock]
 #[async_trait]
 pub trait BookRepository: Send + Sync {
-    async fn create(&self, event: CreateBook, user: UserId) -> AppResult<()>;
+    // returns the id of the title, which is the existing one when a copy was added
+    async fn create(&self, event: CreateBook, user: UserId) -> AppResult<BookId>;
     async fn find_all(&self, options: BookListOptions) -> AppResult<PaginatedList<Book>>;
+    async fn find_all_by_cursor(
+        &self,
+        options: BookListOptions,
+    ) -> AppResult<CursorPaginatedList<Book>>;
     async fn find_by_id(&self, book_id: BookId) -> AppResult<Option<Book>>;
     async fn update(&self, event: UpdateBook) -> AppResult<()>;
     async fn delete(&self, event: DeleteBook) -> AppResult<()>;
+    async fn create_copy(&self, event: CreateBookCopy) -> AppResult<BookCopyId>;
+    async fn find_copies(&self, book_id: BookId) -> AppResult<Vec<BookCopy>>;
+    async fn update_copy(&self, event: UpdateBookCopy) -> AppResult<()>;
+    async fn delete_copy(&self, event: DeleteBookCopy) -> AppResult<()>;
 }
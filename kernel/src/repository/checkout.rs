@@ -1,12 +1,14 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use shared::error::AppResult;
 
 use crate::model::{
     checkout::{
         Checkout,
-        event::{CreateCheckout, UpdateReturned},
+        event::{CreateCheckout, RenewCheckout, UpdateReturned},
     },
     id::{BookId, UserId},
+    list::{CursorListOptions, CursorPaginatedList},
 };
 
 #[mockall::automock]
@@ -14,7 +16,18 @@ use crate::model::{
 pub trait CheckoutRepository: Send + Sync {
     async fn create(&self, event: CreateCheckout) -> AppResult<()>;
     async fn update_returned(&self, event: UpdateReturned) -> AppResult<()>;
+    async fn renew(&self, event: RenewCheckout) -> AppResult<()>;
     async fn find_unreturned_all(&self) -> AppResult<Vec<Checkout>>;
     async fn find_unreturned_by_user_id(&self, user_id: UserId) -> AppResult<Vec<Checkout>>;
-    async fn find_history_by_book_id(&self, book_id: BookId) -> AppResult<Vec<Checkout>>;
+    async fn find_overdue_all(&self, now: DateTime<Utc>) -> AppResult<Vec<Checkout>>;
+    async fn find_overdue_by_user_id(
+        &self,
+        user_id: UserId,
+        now: DateTime<Utc>,
+    ) -> AppResult<Vec<Checkout>>;
+    async fn find_history_by_book_id(
+        &self,
+        book_id: BookId,
+        options: CursorListOptions,
+    ) -> AppResult<CursorPaginatedList<Checkout>>;
 }
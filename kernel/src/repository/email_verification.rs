@@ -0,0 +1,15 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::{
+    auth::{EmailVerification, EmailVerificationToken, event::CreateEmailVerification},
+    id::UserId,
+};
+
+#[mockall::automock]
+#[async_trait]
+pub trait EmailVerificationRepository: Send + Sync {
+    async fn create(&self, event: CreateEmailVerification) -> AppResult<EmailVerification>;
+    // a token can be consumed once; none if it is unknown, used or expired
+    async fn consume(&self, token: &EmailVerificationToken) -> AppResult<Option<UserId>>;
+}
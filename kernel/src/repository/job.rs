@@ -0,0 +1,23 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use shared::error::AppResult;
+
+use crate::model::job::{
+    JobLease,
+    event::{CompleteJob, FailJob, ScheduleJob},
+};
+
+#[mockall::automock]
+#[async_trait]
+pub trait JobRepository: Send + Sync {
+    // adds the job when it is new; a changed schedule restarts it at `next_run_at`,
+    // while an unchanged one keeps the time already stored
+    async fn schedule(&self, event: ScheduleJob) -> AppResult<()>;
+    // the job due the longest at `now` that no instance holds, held for `lease`;
+    // instances skip each other's candidates instead of waiting for them
+    async fn lease_due(&self, now: DateTime<Utc>, lease: Duration) -> AppResult<Option<JobLease>>;
+    // both release the job, and fail with `EntityNotFound` once the lease
+    // has passed to another instance
+    async fn complete(&self, event: CompleteJob) -> AppResult<()>;
+    async fn fail(&self, event: FailJob) -> AppResult<()>;
+}
@@ -0,0 +1,18 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::auth::LoginAttempt;
+
+#[mockall::automock]
+#[async_trait]
+pub trait LoginAttemptRepository: Send + Sync {
+    // fails with `TooManyRequests` while the email is locked or backing off,
+    // or once its address has failed too often
+    async fn check(&self, attempt: &LoginAttempt) -> AppResult<()>;
+    // backs the email off exponentially, and locks it after too many failures
+    async fn record_failure(&self, attempt: &LoginAttempt) -> AppResult<()>;
+    // forgets the failures of the email after a successful login
+    async fn record_success(&self, attempt: &LoginAttempt) -> AppResult<()>;
+    // lifts a lockout of the email along with its failures
+    async fn unlock(&self, email: &str) -> AppResult<()>;
+}
@@ -0,0 +1,16 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::{
+    auth::{LoginChallenge, LoginChallengeToken, event::CreateLoginChallenge},
+    id::UserId,
+};
+
+#[mockall::automock]
+#[async_trait]
+pub trait LoginChallengeRepository: Send + Sync {
+    async fn create(&self, event: CreateLoginChallenge) -> AppResult<LoginChallenge>;
+    // none if the challenge is unknown, completed or expired
+    async fn find(&self, token: &LoginChallengeToken) -> AppResult<Option<UserId>>;
+    async fn delete(&self, token: &LoginChallengeToken) -> AppResult<()>;
+}
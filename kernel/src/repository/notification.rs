@@ -0,0 +1,32 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use shared::error::AppResult;
+
+use crate::model::{
+    id::UserId,
+    notification::{
+        Notification, NotificationPreference,
+        event::{
+            MarkNotificationFailed, MarkNotificationSent, QueueNotification,
+            UpdateNotificationPreferences,
+        },
+    },
+};
+
+// the outbox notifications wait in until they are mailed; checkouts and
+// returns queue theirs in their own transaction
+#[mockall::automock]
+#[async_trait]
+pub trait NotificationRepository: Send + Sync {
+    // drops the notification when the user opted out of its kind
+    async fn queue(&self, event: QueueNotification) -> AppResult<()>;
+    // up to `limit` notifications due for delivery at `now`, oldest first
+    async fn find_pending(&self, now: DateTime<Utc>, limit: i64) -> AppResult<Vec<Notification>>;
+    async fn mark_sent(&self, event: MarkNotificationSent) -> AppResult<()>;
+    async fn mark_failed(&self, event: MarkNotificationFailed) -> AppResult<()>;
+    // deletes notifications sent or given up on before `before`, returning how many
+    async fn delete_finished(&self, before: DateTime<Utc>) -> AppResult<u64>;
+    // one entry per kind; kinds the user never changed are enabled
+    async fn find_preferences(&self, user_id: UserId) -> AppResult<Vec<NotificationPreference>>;
+    async fn update_preferences(&self, event: UpdateNotificationPreferences) -> AppResult<()>;
+}
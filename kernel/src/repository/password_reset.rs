@@ -0,0 +1,16 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::{
+    auth::{PasswordReset, PasswordResetToken, event::CreatePasswordReset},
+    id::UserId,
+};
+
+#[mockall::automock]
+#[async_trait]
+pub trait PasswordResetRepository: Send + Sync {
+    // replaces any reset the user has requested before
+    async fn create(&self, event: CreatePasswordReset) -> AppResult<PasswordReset>;
+    // a token can be consumed once; none if it is unknown, used or expired
+    async fn consume(&self, token: &PasswordResetToken) -> AppResult<Option<UserId>>;
+}
@@ -0,0 +1,19 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::{
+    id::{BookId, UserId},
+    reservation::{
+        Reservation,
+        event::{CreateReservation, DeleteReservation},
+    },
+};
+
+#[mockall::automock]
+#[async_trait]
+pub trait ReservationRepository: Send + Sync {
+    async fn create(&self, event: CreateReservation) -> AppResult<Reservation>;
+    async fn find_by_book_id(&self, book_id: BookId) -> AppResult<Vec<Reservation>>;
+    async fn find_by_user_id(&self, user_id: UserId) -> AppResult<Vec<Reservation>>;
+    async fn delete(&self, event: DeleteReservation) -> AppResult<()>;
+}
@@ -0,0 +1,22 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::role::{
+    BorrowingPolicy, Role,
+    event::{CreateRole, UpdateBorrowingPolicy},
+};
+
+#[mockall::automock]
+#[async_trait]
+pub trait RoleRepository: Send + Sync {
+    // sorted by name
+    async fn find_all(&self) -> AppResult<Vec<Role>>;
+    // fails with `Conflict` when a role of the same name exists
+    async fn create(&self, event: CreateRole) -> AppResult<Role>;
+    // both fail with `EntityNotFound` when no role has the name
+    async fn find_borrowing_policy(&self, role_name: &str) -> AppResult<BorrowingPolicy>;
+    async fn update_borrowing_policy(
+        &self,
+        event: UpdateBorrowingPolicy,
+    ) -> AppResult<BorrowingPolicy>;
+}
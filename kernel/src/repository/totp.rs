@@ -0,0 +1,31 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::{
+    id::UserId,
+    totp::{
+        RecoveryCode, TotpEnrollment,
+        event::{BeginTotpEnrollment, ConfirmTotpEnrollment, VerifyTotp},
+    },
+};
+
+#[mockall::automock]
+#[async_trait]
+pub trait TotpRepository: Send + Sync {
+    async fn is_enabled(&self, user_id: UserId) -> AppResult<bool>;
+    // replaces an unconfirmed secret; fails with `Conflict` once TOTP is enabled
+    async fn begin_enrollment(&self, event: BeginTotpEnrollment) -> AppResult<TotpEnrollment>;
+    // enables TOTP if the code matches the new secret, and returns fresh recovery
+    // codes; none if it does not match or no enrollment was begun
+    async fn confirm_enrollment(
+        &self,
+        event: ConfirmTotpEnrollment,
+    ) -> AppResult<Option<Vec<RecoveryCode>>>;
+    // accepts each code of the authenticator once, or uses up a recovery code;
+    // false unless TOTP is enabled
+    async fn verify(&self, event: VerifyTotp) -> AppResult<bool>;
+    // invalidates the previous recovery codes
+    async fn regenerate_recovery_codes(&self, user_id: UserId) -> AppResult<Vec<RecoveryCode>>;
+    // removes the secret and the recovery codes
+    async fn disable(&self, user_id: UserId) -> AppResult<()>;
+}
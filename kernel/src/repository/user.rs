@@ -1,11 +1,16 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use shared::error::AppResult;
 
 use crate::model::{
     id::UserId,
+    list::{CursorListOptions, CursorPaginatedList},
     user::{
-        User,
-        event::{CreateUser, DeleteUser, UpdateUserPassword, UpdateUserRole},
+        User, UserStatus,
+        event::{
+            CreateUser, DeleteUser, ResetUserPassword, UpdateUserPassword, UpdateUserRole,
+            UpdateUserStatus,
+        },
     },
 };
 
@@ -13,9 +18,17 @@ use crate::model::{
 #[async_trait]
 pub trait UserRepository: Send + Sync {
     async fn find_current_user(&self, current_user_id: UserId) -> AppResult<Option<User>>;
-    async fn find_all(&self) -> AppResult<Vec<User>>;
+    async fn find_by_email(&self, email: &str) -> AppResult<Option<User>>;
+    // oldest first
+    async fn find_by_status(&self, status: UserStatus) -> AppResult<Vec<User>>;
+    async fn find_all(&self, options: CursorListOptions) -> AppResult<CursorPaginatedList<User>>;
     async fn create(&self, event: CreateUser) -> AppResult<User>;
     async fn update_password(&self, event: UpdateUserPassword) -> AppResult<()>;
+    async fn reset_password(&self, event: ResetUserPassword) -> AppResult<()>;
     async fn update_role(&self, event: UpdateUserRole) -> AppResult<()>;
+    async fn update_status(&self, event: UpdateUserStatus) -> AppResult<()>;
     async fn delete(&self, event: DeleteUser) -> AppResult<()>;
+    // removes sign-ups created before `created_before` that were never verified
+    // or were rejected, returning how many
+    async fn delete_stale_sign_ups(&self, created_before: DateTime<Utc>) -> AppResult<u64>;
 }
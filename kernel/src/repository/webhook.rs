@@ -0,0 +1,44 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use shared::error::AppResult;
+
+use crate::model::{
+    id::WebhookId,
+    list::{CursorListOptions, CursorPaginatedList},
+    webhook::{
+        CreatedWebhook, PendingDelivery, Webhook, WebhookDelivery,
+        event::{
+            CreateWebhook, DeleteWebhook, MarkDeliveryDelivered, MarkDeliveryFailed, RetryDelivery,
+        },
+    },
+};
+
+// subscriptions and their deliveries; changes to books and checkouts queue a
+// delivery for every subscription of their event type in their own transaction
+#[mockall::automock]
+#[async_trait]
+pub trait WebhookRepository: Send + Sync {
+    async fn create(&self, event: CreateWebhook) -> AppResult<CreatedWebhook>;
+    // oldest first
+    async fn find_all(&self) -> AppResult<Vec<Webhook>>;
+    // also deletes its deliveries; fails with `EntityNotFound` for unknown ones
+    async fn delete(&self, event: DeleteWebhook) -> AppResult<()>;
+    // newest first; fails with `EntityNotFound` for unknown subscriptions
+    async fn find_deliveries(
+        &self,
+        webhook_id: WebhookId,
+        options: CursorListOptions,
+    ) -> AppResult<CursorPaginatedList<WebhookDelivery>>;
+    // up to `limit` deliveries due for an attempt at `now`, longest due first
+    async fn find_pending_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> AppResult<Vec<PendingDelivery>>;
+    async fn mark_delivered(&self, event: MarkDeliveryDelivered) -> AppResult<()>;
+    async fn mark_failed(&self, event: MarkDeliveryFailed) -> AppResult<()>;
+    // fails with `EntityNotFound` unless the delivery is a dead letter of the subscription
+    async fn retry_delivery(&self, event: RetryDelivery) -> AppResult<()>;
+    // deletes deliveries that were delivered before `before`, returning how many
+    async fn delete_delivered(&self, before: DateTime<Utc>) -> AppResult<u64>;
+}
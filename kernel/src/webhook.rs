@@ -0,0 +1,12 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::webhook::PendingDelivery;
+
+// posts a delivery's payload to its subscription, signed with its secret;
+// returns the HTTP status of any response, and fails when none came
+#[mockall::automock]
+#[async_trait]
+pub trait WebhookSender: Send + Sync {
+    async fn send(&self, delivery: &PendingDelivery) -> AppResult<u16>;
+}
@@ -1,18 +1,68 @@
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
 use adapter::{
-    database::ConnectionPool,
+    database::{ConnectionPool, connect_database_with},
+    live_event::{memory::InMemoryLiveEventBroker, redis::RedisLiveEventBroker},
+    mailer::{file::FileMailer, log::LogMailer, smtp::SmtpMailer},
+    password::{Argon2PasswordHasher, PasswordHasher},
     redis::RedisClient,
     repository::{
-        auth::AuthRepositoryImpl, book::BookRepositoryImpl, checkout::CheckoutRepositoryImpl,
-        health::HealthCheckRepositoryImpl, user::UserRepositoryImpl,
+        api_key::ApiKeyRepositoryImpl,
+        audit::AuditRepositoryImpl,
+        auth::AuthRepositoryImpl,
+        book::BookRepositoryImpl,
+        checkout::CheckoutRepositoryImpl,
+        email_verification::EmailVerificationRepositoryImpl,
+        health::HealthCheckRepositoryImpl,
+        job::JobRepositoryImpl,
+        login_attempt::LoginAttemptRepositoryImpl,
+        login_challenge::LoginChallengeRepositoryImpl,
+        memory::{
+            InMemoryStore, api_key::InMemoryApiKeyRepository, audit::InMemoryAuditRepository,
+            auth::InMemoryAuthRepository, book::InMemoryBookRepository,
+            checkout::InMemoryCheckoutRepository,
+            email_verification::InMemoryEmailVerificationRepository,
+            health::InMemoryHealthCheckRepository, job::InMemoryJobRepository,
+            login_attempt::InMemoryLoginAttemptRepository,
+            login_challenge::InMemoryLoginChallengeRepository,
+            notification::InMemoryNotificationRepository,
+            password_reset::InMemoryPasswordResetRepository,
+            reservation::InMemoryReservationRepository, role::InMemoryRoleRepository,
+            totp::InMemoryTotpRepository, user::InMemoryUserRepository,
+            webhook::InMemoryWebhookRepository,
+        },
+        notification::NotificationRepositoryImpl,
+        password_reset::PasswordResetRepositoryImpl,
+        reservation::ReservationRepositoryImpl,
+        role::RoleRepositoryImpl,
+        totp::TotpRepositoryImpl,
+        user::UserRepositoryImpl,
+        webhook::WebhookRepositoryImpl,
     },
+    webhook::HttpWebhookSender,
 };
-use kernel::repository::{
-    auth::AuthRepository, book::BookRepository, checkout::CheckoutRepository,
-    health::HealthCheckRepository, user::UserRepository,
+use kernel::{
+    live_event::LiveEventBroker,
+    mailer::Mailer,
+    repository::{
+        api_key::ApiKeyRepository, audit::AuditRepository, auth::AuthRepository,
+        book::BookRepository, checkout::CheckoutRepository,
+        email_verification::EmailVerificationRepository, health::HealthCheckRepository,
+        job::JobRepository, login_attempt::LoginAttemptRepository,
+        login_challenge::LoginChallengeRepository, notification::NotificationRepository,
+        password_reset::PasswordResetRepository, reservation::ReservationRepository,
+        role::RoleRepository, totp::TotpRepository, user::UserRepository,
+        webhook::WebhookRepository,
+    },
+    webhook::WebhookSender,
+};
+use shared::{
+    config::{
+        AppConfig, MailBackend, MailConfig, ProxyConfig, RegistrationConfig, RepositoryBackend,
+        TotpConfig,
+    },
+    error::AppResult,
 };
-use shared::config::AppConfig;
 
 #[derive(Clone)]
 pub struct AppRegistryImpl {
@@ -21,6 +71,24 @@ pub struct AppRegistryImpl {
     auth_repository: Arc<dyn AuthRepository>,
     user_repository: Arc<dyn UserRepository>,
     checkout_repository: Arc<dyn CheckoutRepository>,
+    reservation_repository: Arc<dyn ReservationRepository>,
+    login_attempt_repository: Arc<dyn LoginAttemptRepository>,
+    password_reset_repository: Arc<dyn PasswordResetRepository>,
+    email_verification_repository: Arc<dyn EmailVerificationRepository>,
+    totp_repository: Arc<dyn TotpRepository>,
+    login_challenge_repository: Arc<dyn LoginChallengeRepository>,
+    api_key_repository: Arc<dyn ApiKeyRepository>,
+    role_repository: Arc<dyn RoleRepository>,
+    job_repository: Arc<dyn JobRepository>,
+    notification_repository: Arc<dyn NotificationRepository>,
+    webhook_repository: Arc<dyn WebhookRepository>,
+    audit_repository: Arc<dyn AuditRepository>,
+    mailer: Arc<dyn Mailer>,
+    webhook_sender: Arc<dyn WebhookSender>,
+    live_event_broker: Arc<dyn LiveEventBroker>,
+    registration_config: RegistrationConfig,
+    proxy_config: ProxyConfig,
+    totp_config: TotpConfig,
 }
 
 #[mockall::automock]
@@ -30,32 +98,187 @@ pub trait AppRegistryExt {
     fn auth_repository(&self) -> Arc<dyn AuthRepository>;
     fn checkout_repository(&self) -> Arc<dyn CheckoutRepository>;
     fn user_repository(&self) -> Arc<dyn UserRepository>;
+    fn reservation_repository(&self) -> Arc<dyn ReservationRepository>;
+    fn login_attempt_repository(&self) -> Arc<dyn LoginAttemptRepository>;
+    fn password_reset_repository(&self) -> Arc<dyn PasswordResetRepository>;
+    fn email_verification_repository(&self) -> Arc<dyn EmailVerificationRepository>;
+    fn totp_repository(&self) -> Arc<dyn TotpRepository>;
+    fn login_challenge_repository(&self) -> Arc<dyn LoginChallengeRepository>;
+    fn api_key_repository(&self) -> Arc<dyn ApiKeyRepository>;
+    fn role_repository(&self) -> Arc<dyn RoleRepository>;
+    fn job_repository(&self) -> Arc<dyn JobRepository>;
+    fn notification_repository(&self) -> Arc<dyn NotificationRepository>;
+    fn webhook_repository(&self) -> Arc<dyn WebhookRepository>;
+    fn audit_repository(&self) -> Arc<dyn AuditRepository>;
+    fn mailer(&self) -> Arc<dyn Mailer>;
+    fn webhook_sender(&self) -> Arc<dyn WebhookSender>;
+    fn live_event_broker(&self) -> Arc<dyn LiveEventBroker>;
+    fn registration_config(&self) -> RegistrationConfig;
+    fn proxy_config(&self) -> ProxyConfig;
+    fn totp_config(&self) -> TotpConfig;
 }
 
 impl AppRegistryImpl {
+    // builds the repositories of the backend `app_config.backend` selects
+    pub fn from_config(app_config: AppConfig) -> AppResult<Self> {
+        match &app_config.backend {
+            RepositoryBackend::Postgres { database, redis } => {
+                let pool = connect_database_with(database);
+                let redis_client = Arc::new(RedisClient::new(redis)?);
+                Self::new(pool, redis_client, app_config)
+            }
+            RepositoryBackend::InMemory => {
+                Self::in_memory(InMemoryStore::with_initial_setup(), app_config)
+            }
+        }
+    }
+
     pub fn new(
         pool: ConnectionPool,
         redis_client: Arc<RedisClient>,
         app_config: AppConfig,
-    ) -> Self {
+    ) -> AppResult<Self> {
+        let hasher: Arc<dyn PasswordHasher> =
+            Arc::new(Argon2PasswordHasher::new(&app_config.password_hash)?);
         let health_check_repository = Arc::new(HealthCheckRepositoryImpl::new(pool.clone()));
         let book_repository = Arc::new(BookRepositoryImpl::new(pool.clone()));
         let auth_repository = Arc::new(AuthRepositoryImpl::new(
             pool.clone(),
-            redis_client,
+            redis_client.clone(),
+            hasher.clone(),
             app_config.auth.ttl,
+            app_config.auth.refresh_ttl,
         ));
-        let user_repository = Arc::new(UserRepositoryImpl::new(pool.clone()));
-        let checkout_repository = Arc::new(CheckoutRepositoryImpl::new(pool.clone()));
+        let user_repository = Arc::new(UserRepositoryImpl::new(pool.clone(), hasher));
+        let checkout_repository = Arc::new(CheckoutRepositoryImpl::new(
+            pool.clone(),
+            app_config.checkout.clone(),
+        ));
+        let reservation_repository = Arc::new(ReservationRepositoryImpl::new(
+            pool.clone(),
+            app_config.checkout,
+        ));
+        let login_attempt_repository = Arc::new(LoginAttemptRepositoryImpl::new(
+            redis_client.clone(),
+            app_config.login_throttle,
+        ));
+        let password_reset_repository = Arc::new(PasswordResetRepositoryImpl::new(
+            redis_client.clone(),
+            app_config.password_reset,
+        ));
+        let email_verification_repository = Arc::new(EmailVerificationRepositoryImpl::new(
+            redis_client.clone(),
+            app_config.registration.clone(),
+        ));
+        let totp_repository = Arc::new(TotpRepositoryImpl::new(
+            pool.clone(),
+            app_config.totp.clone(),
+        ));
+        let login_challenge_repository = Arc::new(LoginChallengeRepositoryImpl::new(
+            redis_client.clone(),
+            app_config.totp.clone(),
+        ));
+        let live_event_broker = Arc::new(RedisLiveEventBroker::new(redis_client));
+        let api_key_repository = Arc::new(ApiKeyRepositoryImpl::new(pool.clone()));
+        let role_repository = Arc::new(RoleRepositoryImpl::new(pool.clone()));
+        let job_repository = Arc::new(JobRepositoryImpl::new(pool.clone()));
+        let notification_repository = Arc::new(NotificationRepositoryImpl::new(pool.clone()));
+        let webhook_repository = Arc::new(WebhookRepositoryImpl::new(pool.clone()));
+        let audit_repository = Arc::new(AuditRepositoryImpl::new(pool));
 
-        Self {
+        Ok(Self {
             health_check_repository,
             book_repository,
             auth_repository,
             user_repository,
             checkout_repository,
-        }
+            reservation_repository,
+            login_attempt_repository,
+            password_reset_repository,
+            email_verification_repository,
+            totp_repository,
+            login_challenge_repository,
+            api_key_repository,
+            role_repository,
+            job_repository,
+            notification_repository,
+            webhook_repository,
+            audit_repository,
+            mailer: build_mailer(&app_config.mail)?,
+            webhook_sender: Arc::new(HttpWebhookSender::new()?),
+            live_event_broker,
+            registration_config: app_config.registration,
+            proxy_config: app_config.proxy,
+            totp_config: app_config.totp,
+        })
     }
+
+    pub fn in_memory(store: InMemoryStore, app_config: AppConfig) -> AppResult<Self> {
+        let hasher: Arc<dyn PasswordHasher> =
+            Arc::new(Argon2PasswordHasher::new(&app_config.password_hash)?);
+        Ok(Self {
+            health_check_repository: Arc::new(InMemoryHealthCheckRepository),
+            book_repository: Arc::new(InMemoryBookRepository::new(store.clone())),
+            auth_repository: Arc::new(InMemoryAuthRepository::new(
+                store.clone(),
+                hasher.clone(),
+                app_config.auth.ttl,
+                app_config.auth.refresh_ttl,
+            )),
+            user_repository: Arc::new(InMemoryUserRepository::new(store.clone(), hasher)),
+            checkout_repository: Arc::new(InMemoryCheckoutRepository::new(
+                store.clone(),
+                app_config.checkout.clone(),
+            )),
+            reservation_repository: Arc::new(InMemoryReservationRepository::new(
+                store.clone(),
+                app_config.checkout,
+            )),
+            login_attempt_repository: Arc::new(InMemoryLoginAttemptRepository::new(
+                store.clone(),
+                app_config.login_throttle,
+            )),
+            password_reset_repository: Arc::new(InMemoryPasswordResetRepository::new(
+                store.clone(),
+                app_config.password_reset,
+            )),
+            email_verification_repository: Arc::new(InMemoryEmailVerificationRepository::new(
+                store.clone(),
+                app_config.registration.clone(),
+            )),
+            totp_repository: Arc::new(InMemoryTotpRepository::new(
+                store.clone(),
+                app_config.totp.clone(),
+            )),
+            login_challenge_repository: Arc::new(InMemoryLoginChallengeRepository::new(
+                store.clone(),
+                app_config.totp.clone(),
+            )),
+            api_key_repository: Arc::new(InMemoryApiKeyRepository::new(store.clone())),
+            role_repository: Arc::new(InMemoryRoleRepository::new(store.clone())),
+            job_repository: Arc::new(InMemoryJobRepository::new(store.clone())),
+            notification_repository: Arc::new(InMemoryNotificationRepository::new(store.clone())),
+            webhook_repository: Arc::new(InMemoryWebhookRepository::new(store.clone())),
+            audit_repository: Arc::new(InMemoryAuditRepository::new(store)),
+            mailer: build_mailer(&app_config.mail)?,
+            webhook_sender: Arc::new(HttpWebhookSender::new()?),
+            live_event_broker: Arc::new(InMemoryLiveEventBroker::new()),
+            registration_config: app_config.registration,
+            proxy_config: app_config.proxy,
+            totp_config: app_config.totp,
+        })
+    }
+}
+
+fn build_mailer(config: &MailConfig) -> AppResult<Arc<dyn Mailer>> {
+    Ok(match config.backend {
+        MailBackend::Log => Arc::new(LogMailer::new(
+            &config.from,
+            config.outbox_dir.as_ref().map(PathBuf::from),
+        )),
+        MailBackend::File => Arc::new(FileMailer::new(&config.from, &config.spool_dir)),
+        MailBackend::Smtp => Arc::new(SmtpMailer::new(&config.from, &config.smtp)?),
+    })
 }
 
 impl AppRegistryExt for AppRegistryImpl {
@@ -78,6 +301,78 @@ impl AppRegistryExt for AppRegistryImpl {
     fn checkout_repository(&self) -> Arc<dyn CheckoutRepository> {
         self.checkout_repository.clone()
     }
+
+    fn reservation_repository(&self) -> Arc<dyn ReservationRepository> {
+        self.reservation_repository.clone()
+    }
+
+    fn login_attempt_repository(&self) -> Arc<dyn LoginAttemptRepository> {
+        self.login_attempt_repository.clone()
+    }
+
+    fn password_reset_repository(&self) -> Arc<dyn PasswordResetRepository> {
+        self.password_reset_repository.clone()
+    }
+
+    fn email_verification_repository(&self) -> Arc<dyn EmailVerificationRepository> {
+        self.email_verification_repository.clone()
+    }
+
+    fn totp_repository(&self) -> Arc<dyn TotpRepository> {
+        self.totp_repository.clone()
+    }
+
+    fn login_challenge_repository(&self) -> Arc<dyn LoginChallengeRepository> {
+        self.login_challenge_repository.clone()
+    }
+
+    fn api_key_repository(&self) -> Arc<dyn ApiKeyRepository> {
+        self.api_key_repository.clone()
+    }
+
+    fn role_repository(&self) -> Arc<dyn RoleRepository> {
+        self.role_repository.clone()
+    }
+
+    fn job_repository(&self) -> Arc<dyn JobRepository> {
+        self.job_repository.clone()
+    }
+
+    fn notification_repository(&self) -> Arc<dyn NotificationRepository> {
+        self.notification_repository.clone()
+    }
+
+    fn webhook_repository(&self) -> Arc<dyn WebhookRepository> {
+        self.webhook_repository.clone()
+    }
+
+    fn audit_repository(&self) -> Arc<dyn AuditRepository> {
+        self.audit_repository.clone()
+    }
+
+    fn mailer(&self) -> Arc<dyn Mailer> {
+        self.mailer.clone()
+    }
+
+    fn webhook_sender(&self) -> Arc<dyn WebhookSender> {
+        self.webhook_sender.clone()
+    }
+
+    fn live_event_broker(&self) -> Arc<dyn LiveEventBroker> {
+        self.live_event_broker.clone()
+    }
+
+    fn registration_config(&self) -> RegistrationConfig {
+        self.registration_config.clone()
+    }
+
+    fn proxy_config(&self) -> ProxyConfig {
+        self.proxy_config.clone()
+    }
+
+    fn totp_config(&self) -> TotpConfig {
+        self.totp_config.clone()
+    }
 }
 
 pub type AppRegistry = Arc<dyn AppRegistryExt + Send + Sync + 'static>;
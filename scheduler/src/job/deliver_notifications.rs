@@ -0,0 +1,172 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use kernel::model::notification::event::{MarkNotificationFailed, MarkNotificationSent};
+use registry::AppRegistry;
+use shared::error::AppResult;
+
+use super::{Job, backoff};
+
+// mails up to `batch_size` notifications from the outbox; one that fails is
+// tried again after a growing wait, until `max_attempts` deliveries failed
+pub struct DeliverNotifications {
+    batch_size: i64,
+    max_attempts: i32,
+    retry_base: Duration,
+}
+
+impl DeliverNotifications {
+    pub fn new(batch_size: i64, max_attempts: i32, retry_base: Duration) -> Self {
+        Self {
+            batch_size,
+            max_attempts,
+            retry_base,
+        }
+    }
+}
+
+#[async_trait]
+impl Job for DeliverNotifications {
+    fn name(&self) -> &'static str {
+        "deliver_notifications"
+    }
+
+    async fn run(&self, registry: &AppRegistry, now: DateTime<Utc>) -> AppResult<()> {
+        let repository = registry.notification_repository();
+        let pending = repository.find_pending(now, self.batch_size).await?;
+        for notification in pending {
+            match registry.mailer().send(notification.render()).await {
+                Ok(()) => {
+                    repository
+                        .mark_sent(MarkNotificationSent::new(notification.id, now))
+                        .await?
+                }
+                Err(e) => {
+                    let attempts = notification.attempts + 1;
+                    tracing::warn!(
+                        notification.id = %notification.id,
+                        attempts,
+                        error.message = %e,
+                        "Failed to deliver a notification"
+                    );
+                    repository
+                        .mark_failed(MarkNotificationFailed::new(
+                            notification.id,
+                            now,
+                            e.to_string(),
+                            (attempts < self.max_attempts)
+                                .then(|| now + backoff(self.retry_base, attempts)),
+                        ))
+                        .await?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    use adapter::repository::memory::{
+        InMemoryStore, book::InMemoryBookRepository, notification::InMemoryNotificationRepository,
+    };
+    use kernel::{
+        mailer::MockMailer,
+        model::{
+            book::{BookListOptions, event::CreateBook},
+            notification::{NotificationKind, event::QueueNotification},
+            role::BuiltinRole,
+        },
+        repository::{book::BookRepository, notification::NotificationRepository},
+    };
+    use registry::MockAppRegistryExt;
+    use shared::error::AppError;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn failed_deliveries_are_retried_until_given_up() -> anyhow::Result<()> {
+        let store = InMemoryStore::default();
+        let user_id =
+            store.insert_user("Reader", "reader@example.com", "pass", BuiltinRole::User)?;
+        let books = InMemoryBookRepository::new(store.clone());
+        books
+            .create(
+                CreateBook {
+                    title: "Dune".into(),
+                    author: "Frank Herbert".into(),
+                    isbn: "9780441013593".parse()?,
+                    description: String::new(),
+                    add_copy: false,
+                },
+                user_id,
+            )
+            .await?;
+        let book_id = books
+            .find_all(BookListOptions {
+                limit: 1,
+                ..Default::default()
+            })
+            .await?
+            .items[0]
+            .id;
+
+        let now = "2026-01-05T08:00:00Z".parse::<DateTime<Utc>>()?;
+        let notifications = Arc::new(InMemoryNotificationRepository::new(store));
+        for kind in [NotificationKind::DueSoon, NotificationKind::Overdue] {
+            notifications
+                .queue(QueueNotification::new(
+                    user_id,
+                    kind,
+                    book_id,
+                    Some(now),
+                    None,
+                    now,
+                ))
+                .await?;
+        }
+
+        // the due-soon mail goes through on its second attempt, the overdue one never
+        let sent = Arc::new(AtomicUsize::new(0));
+        let mut mailer = MockMailer::new();
+        let attempts = AtomicUsize::new(0);
+        let counter = sent.clone();
+        mailer.expect_send().returning(move |mail| {
+            assert_eq!(mail.to, "reader@example.com");
+            if mail.subject == "Dune is due soon" && attempts.fetch_add(1, Ordering::SeqCst) > 0 {
+                counter.fetch_add(1, Ordering::SeqCst);
+                return Ok(());
+            }
+            Err(AppError::MailError("mail server down".into()))
+        });
+        let mailer = Arc::new(mailer);
+        let mut registry = MockAppRegistryExt::new();
+        let repository = notifications.clone();
+        registry
+            .expect_notification_repository()
+            .returning(move || repository.clone());
+        registry.expect_mailer().returning(move || mailer.clone());
+        let registry: AppRegistry = Arc::new(registry);
+
+        let retry_base = Duration::minutes(5);
+        let job = DeliverNotifications::new(10, 2, retry_base);
+        job.run(&registry, now).await?;
+        assert!(notifications.find_pending(now, 10).await?.is_empty());
+
+        let retry = now + retry_base;
+        assert_eq!(notifications.find_pending(retry, 10).await?.len(), 2);
+        job.run(&registry, retry).await?;
+        assert_eq!(sent.load(Ordering::SeqCst), 1);
+        assert!(
+            notifications
+                .find_pending(now + Duration::days(1), 10)
+                .await?
+                .is_empty()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,188 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use kernel::model::webhook::event::{MarkDeliveryDelivered, MarkDeliveryFailed};
+use registry::AppRegistry;
+use shared::error::AppResult;
+
+use super::{Job, backoff};
+
+// posts up to `batch_size` webhook deliveries that are due; one that is not
+// answered with a 2xx status is tried again after a growing wait, and becomes
+// a dead letter once `max_attempts` attempts failed
+pub struct DeliverWebhooks {
+    batch_size: i64,
+    max_attempts: i32,
+    retry_base: Duration,
+}
+
+impl DeliverWebhooks {
+    pub fn new(batch_size: i64, max_attempts: i32, retry_base: Duration) -> Self {
+        Self {
+            batch_size,
+            max_attempts,
+            retry_base,
+        }
+    }
+}
+
+#[async_trait]
+impl Job for DeliverWebhooks {
+    fn name(&self) -> &'static str {
+        "deliver_webhooks"
+    }
+
+    async fn run(&self, registry: &AppRegistry, now: DateTime<Utc>) -> AppResult<()> {
+        let repository = registry.webhook_repository();
+        let pending = repository
+            .find_pending_deliveries(now, self.batch_size)
+            .await?;
+        for delivery in pending {
+            let (response_status, error) = match registry.webhook_sender().send(&delivery).await {
+                Ok(status) if (200..300).contains(&status) => {
+                    repository
+                        .mark_delivered(MarkDeliveryDelivered::new(delivery.id, now, status.into()))
+                        .await?;
+                    continue;
+                }
+                Ok(status) => (Some(status.into()), format!("unexpected status {status}")),
+                Err(e) => (None, e.to_string()),
+            };
+            let attempts = delivery.attempts + 1;
+            tracing::warn!(
+                webhook_delivery.id = %delivery.id,
+                attempts,
+                error.message = %error,
+                "Failed to deliver a webhook"
+            );
+            repository
+                .mark_failed(MarkDeliveryFailed::new(
+                    delivery.id,
+                    now,
+                    response_status,
+                    error,
+                    (attempts < self.max_attempts)
+                        .then(|| now + backoff(self.retry_base, attempts)),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use adapter::repository::memory::{
+        InMemoryStore, book::InMemoryBookRepository, webhook::InMemoryWebhookRepository,
+    };
+    use chrono::SubsecRound;
+    use kernel::{
+        model::{
+            book::event::CreateBook,
+            list::CursorListOptions,
+            role::BuiltinRole,
+            webhook::{DeliveryStatus, WebhookEventType, event::CreateWebhook},
+        },
+        repository::{book::BookRepository, webhook::WebhookRepository},
+        webhook::MockWebhookSender,
+    };
+    use registry::MockAppRegistryExt;
+    use shared::error::AppError;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn failed_deliveries_are_retried_until_dead_lettered() -> anyhow::Result<()> {
+        let store = InMemoryStore::default();
+        let user_id =
+            store.insert_user("Admin", "admin@example.com", "pass", BuiltinRole::Admin)?;
+        let webhooks = Arc::new(InMemoryWebhookRepository::new(store.clone()));
+        let mut ids = Vec::new();
+        for url in ["https://up.example.com/", "https://down.example.com/"] {
+            let created = webhooks
+                .create(CreateWebhook::new(
+                    url.into(),
+                    vec![WebhookEventType::BookCreated],
+                    user_id,
+                ))
+                .await?;
+            ids.push(created.webhook.id);
+        }
+        InMemoryBookRepository::new(store)
+            .create(
+                CreateBook {
+                    title: "Dune".into(),
+                    author: "Frank Herbert".into(),
+                    isbn: "9780441013593".parse()?,
+                    description: String::new(),
+                    add_copy: false,
+                },
+                user_id,
+            )
+            .await?;
+
+        // one endpoint fails once and then answers, the other never answers
+        let mut sender = MockWebhookSender::new();
+        let mut failed_once = false;
+        sender.expect_send().returning(move |delivery| {
+            assert!(delivery.payload.contains(r#""type":"book.created""#));
+            match delivery.url.as_str() {
+                "https://up.example.com/" if failed_once => Ok(204),
+                "https://up.example.com/" => {
+                    failed_once = true;
+                    Ok(502)
+                }
+                _ => Err(AppError::WebhookError("connection refused".into())),
+            }
+        });
+        let sender = Arc::new(sender);
+        let mut registry = MockAppRegistryExt::new();
+        let repository = webhooks.clone();
+        registry
+            .expect_webhook_repository()
+            .returning(move || repository.clone());
+        registry
+            .expect_webhook_sender()
+            .returning(move || sender.clone());
+        let registry: AppRegistry = Arc::new(registry);
+
+        // stored times are rounded to milliseconds, so the creation may seem a
+        // little later, and times compared with stored ones must be whole milliseconds
+        let now = (Utc::now() + Duration::seconds(1)).trunc_subsecs(3);
+        assert_eq!(webhooks.find_pending_deliveries(now, 10).await?.len(), 2);
+        let retry_base = Duration::seconds(30);
+        let job = DeliverWebhooks::new(10, 2, retry_base);
+        job.run(&registry, now).await?;
+        assert!(webhooks.find_pending_deliveries(now, 10).await?.is_empty());
+        let retry = now + retry_base;
+        assert_eq!(webhooks.find_pending_deliveries(retry, 10).await?.len(), 2);
+        job.run(&registry, retry).await?;
+
+        let options = || CursorListOptions {
+            limit: 10,
+            cursor: None,
+        };
+        let delivered = webhooks.find_deliveries(ids[0], options()).await?.items;
+        assert_eq!(delivered[0].status, DeliveryStatus::Delivered);
+        assert_eq!(delivered[0].response_status, Some(204));
+        assert_eq!(delivered[0].attempts, 1);
+        let dead = webhooks.find_deliveries(ids[1], options()).await?.items;
+        assert_eq!(dead[0].status, DeliveryStatus::DeadLetter);
+        assert_eq!(dead[0].attempts, 2);
+        assert_eq!(dead[0].response_status, None);
+        assert!(
+            dead[0]
+                .last_error
+                .as_deref()
+                .is_some_and(|e| e.contains("connection refused"))
+        );
+        assert!(
+            webhooks
+                .find_pending_deliveries(now + Duration::days(1), 10)
+                .await?
+                .is_empty()
+        );
+        Ok(())
+    }
+}
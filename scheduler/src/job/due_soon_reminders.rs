@@ -0,0 +1,51 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use kernel::model::notification::{NotificationKind, event::QueueNotification};
+use registry::AppRegistry;
+use shared::error::AppResult;
+
+use super::Job;
+
+// queues a reminder for checkouts due within `within`, once per due date, so
+// a renewed checkout is reminded again before its new one
+pub struct DueSoonReminders {
+    within: Duration,
+}
+
+impl DueSoonReminders {
+    pub fn new(within: Duration) -> Self {
+        Self { within }
+    }
+}
+
+#[async_trait]
+impl Job for DueSoonReminders {
+    fn name(&self) -> &'static str {
+        "due_soon_reminders"
+    }
+
+    async fn run(&self, registry: &AppRegistry, now: DateTime<Utc>) -> AppResult<()> {
+        let checkouts = registry.checkout_repository().find_unreturned_all().await?;
+        for checkout in checkouts
+            .into_iter()
+            .filter(|c| c.due_at > now && c.due_at <= now + self.within)
+        {
+            registry
+                .notification_repository()
+                .queue(QueueNotification::new(
+                    checkout.checked_out_by,
+                    NotificationKind::DueSoon,
+                    checkout.book.book_id,
+                    Some(checkout.due_at),
+                    Some(format!(
+                        "due_soon:{}:{}",
+                        checkout.id,
+                        checkout.due_at.timestamp()
+                    )),
+                    now,
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+}
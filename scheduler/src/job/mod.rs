@@ -0,0 +1,26 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use registry::AppRegistry;
+use shared::error::AppResult;
+
+pub mod deliver_notifications;
+pub mod deliver_webhooks;
+pub mod due_soon_reminders;
+pub mod overdue_reminders;
+pub mod purge;
+
+// work the scheduler runs periodically; a failed run is retried, so a job has
+// to cope with running again after doing part of its work
+#[async_trait]
+pub trait Job: Send + Sync {
+    // the key of the job in the job table
+    fn name(&self) -> &'static str;
+    async fn run(&self, registry: &AppRegistry, now: DateTime<Utc>) -> AppResult<()>;
+}
+
+// the wait after `attempts` failed attempts before the next one: `base` after
+// the first and twice as long after each further one, for failed runs of jobs
+// and for the deliveries they make alike
+pub(crate) fn backoff(base: Duration, attempts: i32) -> Duration {
+    base * (1 << (attempts - 1).clamp(0, 16))
+}
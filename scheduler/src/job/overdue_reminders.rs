@@ -0,0 +1,35 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use kernel::model::notification::{NotificationKind, event::QueueNotification};
+use registry::AppRegistry;
+use shared::error::AppResult;
+
+use super::Job;
+
+// queues a reminder for every overdue checkout, once a day per checkout
+pub struct OverdueReminders;
+
+#[async_trait]
+impl Job for OverdueReminders {
+    fn name(&self) -> &'static str {
+        "overdue_reminders"
+    }
+
+    async fn run(&self, registry: &AppRegistry, now: DateTime<Utc>) -> AppResult<()> {
+        let overdue = registry.checkout_repository().find_overdue_all(now).await?;
+        for checkout in overdue {
+            registry
+                .notification_repository()
+                .queue(QueueNotification::new(
+                    checkout.checked_out_by,
+                    NotificationKind::Overdue,
+                    checkout.book.book_id,
+                    Some(checkout.due_at),
+                    Some(format!("overdue:{}:{}", checkout.id, now.date_naive())),
+                    now,
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+}
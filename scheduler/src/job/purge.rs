@@ -0,0 +1,51 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use registry::AppRegistry;
+use shared::error::AppResult;
+
+use super::Job;
+
+// deletes sign-ups that were never verified or were rejected, revoked API keys,
+// notifications no longer waiting for delivery and delivered webhook deliveries,
+// once they are older than `retention`
+pub struct PurgeStaleData {
+    retention: Duration,
+}
+
+impl PurgeStaleData {
+    pub fn new(retention: Duration) -> Self {
+        Self { retention }
+    }
+}
+
+#[async_trait]
+impl Job for PurgeStaleData {
+    fn name(&self) -> &'static str {
+        "purge_stale_data"
+    }
+
+    async fn run(&self, registry: &AppRegistry, now: DateTime<Utc>) -> AppResult<()> {
+        let cutoff = now - self.retention;
+        let sign_ups = registry
+            .user_repository()
+            .delete_stale_sign_ups(cutoff)
+            .await?;
+        let api_keys = registry.api_key_repository().delete_revoked(cutoff).await?;
+        let notifications = registry
+            .notification_repository()
+            .delete_finished(cutoff)
+            .await?;
+        let webhook_deliveries = registry
+            .webhook_repository()
+            .delete_delivered(cutoff)
+            .await?;
+        tracing::info!(
+            sign_ups,
+            api_keys,
+            notifications,
+            webhook_deliveries,
+            "Purged stale data"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,278 @@
+use std::{str::FromStr, time::Duration as StdDuration};
+
+use anyhow::Context;
+use chrono::{DateTime, Duration, Utc};
+use cron::Schedule;
+use kernel::model::job::{
+    JobLease,
+    event::{CompleteJob, FailJob, ScheduleJob},
+};
+use registry::AppRegistry;
+use shared::{config::SchedulerConfig, error::AppResult};
+use tokio::task::JoinHandle;
+
+use crate::job::{
+    Job, backoff, deliver_notifications::DeliverNotifications, deliver_webhooks::DeliverWebhooks,
+    due_soon_reminders::DueSoonReminders, overdue_reminders::OverdueReminders,
+    purge::PurgeStaleData,
+};
+
+pub mod job;
+
+// runs the jobs of this instance when their cron schedules come due; the job
+// table lets several instances share one schedule, so each run happens once
+pub struct Scheduler {
+    registry: AppRegistry,
+    config: SchedulerConfig,
+    jobs: Vec<ScheduledJob>,
+}
+
+struct ScheduledJob {
+    job: Box<dyn Job>,
+    expression: String,
+    schedule: Schedule,
+}
+
+impl ScheduledJob {
+    fn new(job: impl Job + 'static, expression: &str) -> anyhow::Result<Self> {
+        let schedule = Schedule::from_str(expression)
+            .with_context(|| format!("invalid schedule of job {}: {expression}", job.name()))?;
+        Ok(Self {
+            job: Box::new(job),
+            expression: expression.into(),
+            schedule,
+        })
+    }
+
+    // a schedule without further times, like one for a past year, never runs again
+    fn next_run_after(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        self.schedule
+            .after(&at)
+            .next()
+            .unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+}
+
+impl Scheduler {
+    pub fn new(registry: AppRegistry, config: SchedulerConfig) -> anyhow::Result<Self> {
+        let jobs = vec![
+            ScheduledJob::new(OverdueReminders, &config.overdue_reminders_schedule)?,
+            ScheduledJob::new(
+                DueSoonReminders::new(Duration::days(config.due_soon_days)),
+                &config.due_soon_reminders_schedule,
+            )?,
+            ScheduledJob::new(
+                DeliverNotifications::new(
+                    config.notification_batch_size,
+                    config.notification_max_attempts,
+                    Duration::seconds(config.notification_retry_base_secs as i64),
+                ),
+                &config.notification_delivery_schedule,
+            )?,
+            ScheduledJob::new(
+                DeliverWebhooks::new(
+                    config.webhook_batch_size,
+                    config.webhook_max_attempts,
+                    Duration::seconds(config.webhook_retry_base_secs as i64),
+                ),
+                &config.webhook_delivery_schedule,
+            )?,
+            ScheduledJob::new(
+                PurgeStaleData::new(Duration::days(config.purge_after_days)),
+                &config.purge_schedule,
+            )?,
+        ];
+        Ok(Self {
+            registry,
+            config,
+            jobs,
+        })
+    }
+
+    // adds the jobs to the job table; ones already there keep their next run
+    // unless their schedule changed
+    pub async fn schedule_jobs(&self, now: DateTime<Utc>) -> AppResult<()> {
+        for job in &self.jobs {
+            self.registry
+                .job_repository()
+                .schedule(ScheduleJob::new(
+                    job.job.name().into(),
+                    job.expression.clone(),
+                    job.next_run_after(now),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+
+    // runs the jobs due at `now` one after another, returning how many ran
+    pub async fn run_due(&self, now: DateTime<Utc>) -> AppResult<usize> {
+        let repository = self.registry.job_repository();
+        let lease = Duration::seconds(self.config.lease_secs as i64);
+        let mut ran = 0;
+        while let Some(lease) = repository.lease_due(now, lease).await? {
+            let Some(job) = self.jobs.iter().find(|j| j.job.name() == lease.name) else {
+                // scheduled by another version of the app; its lease lets that one take it
+                tracing::warn!(
+                    job = lease.name,
+                    "Skipped a job this instance does not know"
+                );
+                continue;
+            };
+            match job.job.run(&self.registry, now).await {
+                Ok(()) => {
+                    repository
+                        .complete(CompleteJob::new(
+                            lease.name,
+                            lease.lease_id,
+                            now,
+                            job.next_run_after(now),
+                        ))
+                        .await?
+                }
+                Err(e) => {
+                    tracing::error!(
+                        job = lease.name,
+                        attempts = lease.attempts + 1,
+                        error.cause_chain = ?e,
+                        error.message = %e,
+                        "Job failed"
+                    );
+                    let (attempts, next_run_at) = self.retry(job, &lease, now);
+                    repository
+                        .fail(FailJob::new(
+                            lease.name,
+                            lease.lease_id,
+                            now,
+                            e.to_string(),
+                            attempts,
+                            next_run_at,
+                        ))
+                        .await?
+                }
+            }
+            ran += 1;
+        }
+        Ok(ran)
+    }
+
+    // waits `retry_base_secs` before the first retry and twice as long before
+    // each further one; after `max_retries` the job waits for its next scheduled run
+    fn retry(
+        &self,
+        job: &ScheduledJob,
+        lease: &JobLease,
+        now: DateTime<Utc>,
+    ) -> (i32, DateTime<Utc>) {
+        let attempts = lease.attempts + 1;
+        if attempts > self.config.max_retries {
+            return (0, job.next_run_after(now));
+        }
+        let base = Duration::seconds(self.config.retry_base_secs as i64);
+        (attempts, now + backoff(base, attempts))
+    }
+
+    // polls for due jobs until the process exits
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(StdDuration::from_secs(self.config.poll_interval_secs));
+            let mut scheduled = false;
+            loop {
+                interval.tick().await;
+                let now = Utc::now();
+                // retried on every tick until the job table is reachable
+                if !scheduled {
+                    if let Err(e) = self.schedule_jobs(now).await {
+                        tracing::error!(error.message = %e, "Failed to schedule jobs");
+                        continue;
+                    }
+                    scheduled = true;
+                }
+                if let Err(e) = self.run_due(now).await {
+                    tracing::error!(
+                        error.cause_chain = ?e,
+                        error.message = %e,
+                        "Failed to run due jobs"
+                    );
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    use adapter::repository::memory::{InMemoryStore, job::InMemoryJobRepository};
+    use async_trait::async_trait;
+    use registry::MockAppRegistryExt;
+    use shared::error::AppError;
+
+    use super::*;
+
+    // fails its first `failures` runs
+    struct Flaky {
+        runs: Arc<AtomicUsize>,
+        failures: usize,
+    }
+
+    #[async_trait]
+    impl Job for Flaky {
+        fn name(&self) -> &'static str {
+            "flaky"
+        }
+
+        async fn run(&self, _: &AppRegistry, _: DateTime<Utc>) -> AppResult<()> {
+            if self.runs.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(AppError::MailError("mail server down".into()));
+            }
+            Ok(())
+        }
+    }
+
+    fn scheduler(failures: usize, runs: Arc<AtomicUsize>) -> anyhow::Result<Scheduler> {
+        let jobs = Arc::new(InMemoryJobRepository::new(InMemoryStore::default()));
+        let mut registry = MockAppRegistryExt::new();
+        registry
+            .expect_job_repository()
+            .returning(move || jobs.clone());
+        Ok(Scheduler {
+            registry: Arc::new(registry),
+            config: SchedulerConfig {
+                max_retries: 1,
+                ..Default::default()
+            },
+            jobs: vec![ScheduledJob::new(Flaky { runs, failures }, "0 0 8 * * *")?],
+        })
+    }
+
+    #[tokio::test]
+    async fn failed_jobs_are_retried_with_backoff() -> anyhow::Result<()> {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let scheduler = scheduler(2, runs.clone())?;
+        let eight = "2026-01-05T08:00:00Z".parse::<DateTime<Utc>>()?;
+        let base = Duration::seconds(scheduler.config.retry_base_secs as i64);
+
+        scheduler.schedule_jobs(eight - Duration::hours(1)).await?;
+        assert_eq!(scheduler.run_due(eight - Duration::minutes(1)).await?, 0);
+        assert_eq!(scheduler.run_due(eight).await?, 1);
+
+        // the retry waits for the backoff
+        assert_eq!(scheduler.run_due(eight + base / 2).await?, 0);
+        assert_eq!(scheduler.run_due(eight + base).await?, 1);
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+
+        // out of retries, the job waits for the next day
+        let tomorrow = eight + Duration::days(1);
+        assert_eq!(scheduler.run_due(eight + base * 10).await?, 0);
+        assert_eq!(scheduler.run_due(tomorrow).await?, 1);
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        assert_eq!(scheduler.run_due(tomorrow).await?, 0);
+        Ok(())
+    }
+}
@@ -1,9 +1,30 @@
+use std::net::IpAddr;
+
 use anyhow::Result;
+use strum::EnumString;
 
 pub struct AppConfig {
-    pub database: DatabaseConfig,
-    pub redis: RedisConfig,
+    pub backend: RepositoryBackend,
     pub auth: AuthConfig,
+    pub checkout: CheckoutConfig,
+    pub login_throttle: LoginThrottleConfig,
+    pub proxy: ProxyConfig,
+    pub password_hash: PasswordHashConfig,
+    pub totp: TotpConfig,
+    pub password_reset: PasswordResetConfig,
+    pub registration: RegistrationConfig,
+    pub mail: MailConfig,
+    pub scheduler: SchedulerConfig,
+}
+
+// where the repositories keep their data; `in-memory` needs neither PostgreSQL
+// nor Redis and loses everything when the process exits
+pub enum RepositoryBackend {
+    Postgres {
+        database: DatabaseConfig,
+        redis: RedisConfig,
+    },
+    InMemory,
 }
 
 pub struct DatabaseConfig {
@@ -16,34 +37,482 @@ pub struct DatabaseConfig {
 
 impl AppConfig {
     pub fn new() -> Result<Self> {
-        let database = DatabaseConfig {
-            host: std::env::var("DATABASE_HOST")?,
-            port: std::env::var("DATABASE_PORT")?.parse()?,
-            username: std::env::var("DATABASE_USERNAME")?,
-            password: std::env::var("DATABASE_PASSWORD")?,
-            database: std::env::var("DATABASE_NAME")?,
-        };
-        let redis = RedisConfig {
-            host: std::env::var("REDIS_HOST")?,
-            port: std::env::var("REDIS_PORT")?.parse::<u16>()?,
+        // the connections are only configured for the backend that makes them
+        let backend = match env_or("REPOSITORY_BACKEND", String::from("postgres"))?.as_str() {
+            "postgres" => RepositoryBackend::Postgres {
+                database: DatabaseConfig {
+                    host: std::env::var("DATABASE_HOST")?,
+                    port: std::env::var("DATABASE_PORT")?.parse()?,
+                    username: std::env::var("DATABASE_USERNAME")?,
+                    password: std::env::var("DATABASE_PASSWORD")?,
+                    database: std::env::var("DATABASE_NAME")?,
+                },
+                redis: RedisConfig {
+                    host: std::env::var("REDIS_HOST")?,
+                    port: std::env::var("REDIS_PORT")?.parse::<u16>()?,
+                },
+            },
+            "in-memory" => RepositoryBackend::InMemory,
+            other => {
+                anyhow::bail!("REPOSITORY_BACKEND must be `postgres` or `in-memory`, not `{other}`")
+            }
         };
         let auth = AuthConfig {
             ttl: std::env::var("AUTH_TOKEN_TTL")?.parse::<u64>()?,
+            refresh_ttl: env_or("AUTH_REFRESH_TOKEN_TTL", 30 * 24 * 60 * 60)?,
+        };
+        let default_checkout = CheckoutConfig::default();
+        let checkout = CheckoutConfig {
+            admin_loan_days: env_or("CHECKOUT_ADMIN_LOAN_DAYS", default_checkout.admin_loan_days)?,
+            user_loan_days: env_or("CHECKOUT_USER_LOAN_DAYS", default_checkout.user_loan_days)?,
+            max_renewals: env_or("CHECKOUT_MAX_RENEWALS", default_checkout.max_renewals)?,
+            reservation_claim_hours: env_or(
+                "RESERVATION_CLAIM_HOURS",
+                default_checkout.reservation_claim_hours,
+            )?,
+        };
+
+        let default_throttle = LoginThrottleConfig::default();
+        let login_throttle = LoginThrottleConfig {
+            max_failures: env_or("LOGIN_MAX_FAILURES", default_throttle.max_failures)?,
+            max_failures_per_ip: env_or(
+                "LOGIN_MAX_FAILURES_PER_IP",
+                default_throttle.max_failures_per_ip,
+            )?,
+            lockout_secs: env_or("LOGIN_LOCKOUT_SECS", default_throttle.lockout_secs)?,
+            backoff_base_secs: env_or(
+                "LOGIN_BACKOFF_BASE_SECS",
+                default_throttle.backoff_base_secs,
+            )?,
+        };
+
+        let proxy = ProxyConfig {
+            trusted_proxies: std::env::var("TRUSTED_PROXIES")
+                .map(|proxies| {
+                    proxies
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|p| !p.is_empty())
+                        .map(str::parse)
+                        .collect::<Result<_, _>>()
+                })
+                .unwrap_or(Ok(Vec::new()))?,
+        };
+
+        let password_reset = PasswordResetConfig {
+            ttl: env_or(
+                "PASSWORD_RESET_TOKEN_TTL",
+                PasswordResetConfig::default().ttl,
+            )?,
+            url: std::env::var("PASSWORD_RESET_URL").ok(),
+        };
+        let default_password_hash = PasswordHashConfig::default();
+        let password_hash = PasswordHashConfig {
+            memory_kib: env_or("PASSWORD_HASH_MEMORY_KIB", default_password_hash.memory_kib)?,
+            iterations: env_or("PASSWORD_HASH_ITERATIONS", default_password_hash.iterations)?,
+            parallelism: env_or(
+                "PASSWORD_HASH_PARALLELISM",
+                default_password_hash.parallelism,
+            )?,
+        };
+        let default_totp = TotpConfig::default();
+        let totp = TotpConfig {
+            issuer: std::env::var("TOTP_ISSUER").unwrap_or(default_totp.issuer),
+            required_for_admins: env_or(
+                "TOTP_REQUIRED_FOR_ADMINS",
+                default_totp.required_for_admins,
+            )?,
+            challenge_ttl: env_or("LOGIN_CHALLENGE_TTL", default_totp.challenge_ttl)?,
+            recovery_codes: env_or("TOTP_RECOVERY_CODES", default_totp.recovery_codes)?,
+        };
+        let default_registration = RegistrationConfig::default();
+        let registration = RegistrationConfig {
+            enabled: env_or("REGISTRATION_ENABLED", default_registration.enabled)?,
+            allowed_domains: std::env::var("REGISTRATION_ALLOWED_DOMAINS")
+                .map(|domains| {
+                    domains
+                        .split(',')
+                        .map(|d| d.trim().to_lowercase())
+                        .filter(|d| !d.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            require_verification: env_or(
+                "REGISTRATION_REQUIRE_VERIFICATION",
+                default_registration.require_verification,
+            )?,
+            require_approval: env_or(
+                "REGISTRATION_REQUIRE_APPROVAL",
+                default_registration.require_approval,
+            )?,
+            verification_ttl: env_or(
+                "EMAIL_VERIFICATION_TOKEN_TTL",
+                default_registration.verification_ttl,
+            )?,
+            verification_url: std::env::var("EMAIL_VERIFICATION_URL").ok(),
+        };
+        let default_mail = MailConfig::default();
+        let mail = MailConfig {
+            backend: env_or("MAIL_BACKEND", default_mail.backend)?,
+            from: env_or("MAIL_FROM", default_mail.from)?,
+            outbox_dir: std::env::var("MAIL_OUTBOX_DIR").ok(),
+            spool_dir: env_or("MAIL_SPOOL_DIR", default_mail.spool_dir)?,
+            smtp: SmtpConfig {
+                host: env_or("SMTP_HOST", default_mail.smtp.host)?,
+                port: env_or("SMTP_PORT", default_mail.smtp.port)?,
+                username: std::env::var("SMTP_USERNAME").ok(),
+                password: std::env::var("SMTP_PASSWORD").ok(),
+                starttls: env_or("SMTP_STARTTLS", default_mail.smtp.starttls)?,
+            },
+        };
+
+        let default_scheduler = SchedulerConfig::default();
+        let scheduler = SchedulerConfig {
+            enabled: env_or("SCHEDULER_ENABLED", default_scheduler.enabled)?,
+            poll_interval_secs: env_or(
+                "SCHEDULER_POLL_INTERVAL_SECS",
+                default_scheduler.poll_interval_secs,
+            )?,
+            lease_secs: env_or("JOB_LEASE_SECS", default_scheduler.lease_secs)?,
+            max_retries: env_or("JOB_MAX_RETRIES", default_scheduler.max_retries)?,
+            retry_base_secs: env_or("JOB_RETRY_BASE_SECS", default_scheduler.retry_base_secs)?,
+            overdue_reminders_schedule: env_or(
+                "OVERDUE_REMINDERS_SCHEDULE",
+                default_scheduler.overdue_reminders_schedule,
+            )?,
+            due_soon_reminders_schedule: env_or(
+                "DUE_SOON_REMINDERS_SCHEDULE",
+                default_scheduler.due_soon_reminders_schedule,
+            )?,
+            notification_delivery_schedule: env_or(
+                "NOTIFICATION_DELIVERY_SCHEDULE",
+                default_scheduler.notification_delivery_schedule,
+            )?,
+            webhook_delivery_schedule: env_or(
+                "WEBHOOK_DELIVERY_SCHEDULE",
+                default_scheduler.webhook_delivery_schedule,
+            )?,
+            purge_schedule: env_or("PURGE_SCHEDULE", default_scheduler.purge_schedule)?,
+            due_soon_days: env_or("DUE_SOON_DAYS", default_scheduler.due_soon_days)?,
+            notification_batch_size: env_or(
+                "NOTIFICATION_BATCH_SIZE",
+                default_scheduler.notification_batch_size,
+            )?,
+            notification_max_attempts: env_or(
+                "NOTIFICATION_MAX_ATTEMPTS",
+                default_scheduler.notification_max_attempts,
+            )?,
+            notification_retry_base_secs: env_or(
+                "NOTIFICATION_RETRY_BASE_SECS",
+                default_scheduler.notification_retry_base_secs,
+            )?,
+            webhook_batch_size: env_or("WEBHOOK_BATCH_SIZE", default_scheduler.webhook_batch_size)?,
+            webhook_max_attempts: env_or(
+                "WEBHOOK_MAX_ATTEMPTS",
+                default_scheduler.webhook_max_attempts,
+            )?,
+            webhook_retry_base_secs: env_or(
+                "WEBHOOK_RETRY_BASE_SECS",
+                default_scheduler.webhook_retry_base_secs,
+            )?,
+            purge_after_days: env_or("PURGE_AFTER_DAYS", default_scheduler.purge_after_days)?,
         };
 
         Ok(AppConfig {
-            database,
-            redis,
+            backend,
             auth,
+            checkout,
+            login_throttle,
+            proxy,
+            password_hash,
+            totp,
+            password_reset,
+            registration,
+            mail,
+            scheduler,
         })
     }
 }
 
+fn env_or<T>(key: &str, default: T) -> Result<T>
+where
+    T: std::str::FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    match std::env::var(key) {
+        Ok(value) => Ok(value.parse::<T>()?),
+        Err(_) => Ok(default),
+    }
+}
+
 pub struct RedisConfig {
     pub host: String,
     pub port: u16,
 }
 
 pub struct AuthConfig {
+    // lifetime of access tokens in seconds
+    pub ttl: u64,
+    // lifetime of refresh tokens in seconds; a session ends once it goes unused this long
+    pub refresh_ttl: u64,
+}
+
+#[derive(Clone)]
+pub struct PasswordResetConfig {
+    // lifetime of reset tokens in seconds
     pub ttl: u64,
+    // page of the client where a reset is confirmed; mails link to it with the
+    // token in a `token` query parameter, and only hold the token without it
+    pub url: Option<String>,
+}
+
+impl Default for PasswordResetConfig {
+    fn default() -> Self {
+        Self {
+            ttl: 60 * 60,
+            url: None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct CheckoutConfig {
+    pub admin_loan_days: i64,
+    pub user_loan_days: i64,
+    pub max_renewals: i32,
+    pub reservation_claim_hours: i64,
+}
+
+impl Default for CheckoutConfig {
+    fn default() -> Self {
+        Self {
+            admin_loan_days: 28,
+            user_loan_days: 14,
+            max_renewals: 2,
+            reservation_claim_hours: 72,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct LoginThrottleConfig {
+    // failed logins for one email before the account is locked
+    pub max_failures: u64,
+    // failed logins from one address before it is turned away
+    pub max_failures_per_ip: u64,
+    // how long a lockout lasts, and how long failures are remembered, in seconds
+    pub lockout_secs: u64,
+    // wait after the first failure in seconds; doubles with every further failure
+    pub backoff_base_secs: u64,
+}
+
+impl Default for LoginThrottleConfig {
+    fn default() -> Self {
+        Self {
+            max_failures: 10,
+            max_failures_per_ip: 100,
+            lockout_secs: 15 * 60,
+            backoff_base_secs: 1,
+        }
+    }
+}
+
+// reverse proxies in front of the application; only their `X-Forwarded-For` is
+// believed, since logins are throttled per client address
+#[derive(Debug, Default, Clone)]
+pub struct ProxyConfig {
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+// argon2id cost of new password hashes; stored hashes with other parameters, or
+// from bcrypt, are rehashed on the next successful login
+#[derive(Debug, Clone)]
+pub struct PasswordHashConfig {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for PasswordHashConfig {
+    fn default() -> Self {
+        Self {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+// two-factor authentication with authenticator apps (RFC 6238)
+#[derive(Debug, Clone)]
+pub struct TotpConfig {
+    // the name authenticator apps show next to the account
+    pub issuer: String,
+    // admins without TOTP have to enroll while logging in
+    pub required_for_admins: bool,
+    // seconds a login has to complete the second step after the password
+    pub challenge_ttl: u64,
+    // how many recovery codes a user gets at a time
+    pub recovery_codes: usize,
+}
+
+impl Default for TotpConfig {
+    fn default() -> Self {
+        Self {
+            issuer: "Rusty Book Manager".into(),
+            required_for_admins: false,
+            challenge_ttl: 5 * 60,
+            recovery_codes: 10,
+        }
+    }
+}
+
+// self-service sign-up; users created by admins are not affected
+#[derive(Debug, Clone)]
+pub struct RegistrationConfig {
+    pub enabled: bool,
+    // lowercase domains emails must belong to; any domain when empty
+    pub allowed_domains: Vec<String>,
+    // new users confirm their email with a mailed token before logging in
+    pub require_verification: bool,
+    // new users wait for an admin to approve them before logging in
+    pub require_approval: bool,
+    // lifetime of verification tokens in seconds
+    pub verification_ttl: u64,
+    // page of the client where an email is verified, like `PasswordResetConfig::url`
+    pub verification_url: Option<String>,
+}
+
+impl RegistrationConfig {
+    pub fn allows_email(&self, email: &str) -> bool {
+        let domain = email
+            .rsplit_once('@')
+            .map(|(_, domain)| domain.to_lowercase())
+            .unwrap_or_default();
+        self.allowed_domains.is_empty() || self.allowed_domains.contains(&domain)
+    }
+}
+
+impl Default for RegistrationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allowed_domains: vec![],
+            require_verification: true,
+            require_approval: false,
+            verification_ttl: 24 * 60 * 60,
+            verification_url: None,
+        }
+    }
+}
+
+pub struct MailConfig {
+    pub backend: MailBackend,
+    pub from: String,
+    // where the `log` backend also writes each mail to, as an `.eml` file
+    pub outbox_dir: Option<String>,
+    // where the `file` backend delivers mails to
+    pub spool_dir: String,
+    pub smtp: SmtpConfig,
+}
+
+// how mails leave the application; `log` only logs their recipients and
+// subjects and `file` writes them to a directory, both for local development
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum MailBackend {
+    #[default]
+    Log,
+    File,
+    Smtp,
+}
+
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    // upgrades the connection to TLS before authenticating
+    pub starttls: bool,
+}
+
+impl Default for MailConfig {
+    fn default() -> Self {
+        Self {
+            backend: MailBackend::default(),
+            from: "Library <library@localhost>".into(),
+            outbox_dir: None,
+            spool_dir: "mail-spool".into(),
+            smtp: SmtpConfig {
+                host: "localhost".into(),
+                port: 587,
+                username: None,
+                password: None,
+                starttls: true,
+            },
+        }
+    }
+}
+
+// periodic background jobs; every instance may run the scheduler, and the job
+// table makes sure each run happens on only one of them
+#[derive(Debug, Clone)]
+pub struct SchedulerConfig {
+    pub enabled: bool,
+    // seconds between looking for due jobs
+    pub poll_interval_secs: u64,
+    // seconds an instance holds a job before others may take it over
+    pub lease_secs: u64,
+    // retries of a failed run before the job waits for its next scheduled time
+    pub max_retries: i32,
+    // wait before the first retry in seconds; doubles with every further one
+    pub retry_base_secs: u64,
+    // cron expressions with a seconds field, in UTC
+    pub overdue_reminders_schedule: String,
+    pub due_soon_reminders_schedule: String,
+    pub notification_delivery_schedule: String,
+    pub webhook_delivery_schedule: String,
+    pub purge_schedule: String,
+    // days before its due date a checkout gets a reminder
+    pub due_soon_days: i64,
+    // notifications mailed per delivery run
+    pub notification_batch_size: i64,
+    // deliveries of a notification before it is given up on
+    pub notification_max_attempts: i32,
+    // wait before delivering a notification again in seconds; doubles with every attempt
+    pub notification_retry_base_secs: u64,
+    // webhook deliveries posted per delivery run
+    pub webhook_batch_size: i64,
+    // attempts at a webhook delivery before it becomes a dead letter
+    pub webhook_max_attempts: i32,
+    // wait before posting a webhook delivery again in seconds; doubles with every attempt
+    pub webhook_retry_base_secs: u64,
+    // days unfinished sign-ups, revoked API keys, delivered notifications and
+    // delivered webhook deliveries are kept for
+    pub purge_after_days: i64,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            poll_interval_secs: 30,
+            lease_secs: 10 * 60,
+            max_retries: 5,
+            retry_base_secs: 60,
+            overdue_reminders_schedule: "0 0 8 * * *".into(),
+            due_soon_reminders_schedule: "0 0 8 * * *".into(),
+            notification_delivery_schedule: "0 * * * * *".into(),
+            webhook_delivery_schedule: "0 * * * * *".into(),
+            purge_schedule: "0 30 3 * * *".into(),
+            due_soon_days: 2,
+            notification_batch_size: 100,
+            notification_max_attempts: 5,
+            notification_retry_base_secs: 5 * 60,
+            webhook_batch_size: 100,
+            webhook_max_attempts: 8,
+            webhook_retry_base_secs: 30,
+            purge_after_days: 30,
+        }
+    }
 }
@@ -1,5 +1,14 @@
-use axum::{http::StatusCode, response::IntoResponse};
+use axum::{
+    Json,
+    extract::rejection::{JsonRejection, PathRejection, QueryRejection},
+    http::{StatusCode, header::RETRY_AFTER},
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use utoipa::ToSchema;
+
+use crate::request_id;
 
 #[derive(Error, Debug)]
 pub enum AppError {
@@ -19,8 +28,19 @@ pub enum AppError {
     KeyValueStoreError(#[from] redis::RedisError),
     #[error("{0}")]
     BcryptError(#[from] bcrypt::BcryptError),
+    #[error("failed to hash password")]
+    PasswordHashError(#[source] Box<dyn std::error::Error + Send + Sync>),
     #[error("{0}")]
     ConvertToUuidError(#[from] uuid::Error),
+    // a body, path or query string the extractors could not read
+    #[error("{0}")]
+    InvalidRequest(String),
+    #[error("{0}")]
+    InvalidCursor(String),
+    #[error("{0}")]
+    InvalidIsbn(String),
+    #[error("{0}")]
+    Conflict(String),
     #[error("failed to login")]
     UnauthenticatedError,
     #[error("access infomation is invalid")]
@@ -28,34 +48,168 @@ pub enum AppError {
     #[error("forbidden operation")]
     ForbidenOperation,
     #[error("{0}")]
+    AccountNotActive(String),
+    #[error("too many failed attempts, retry after {retry_after} seconds")]
+    TooManyRequests { retry_after: u64 },
+    #[error("{0}")]
     ConversionEntityError(String),
+    #[error("failed to send mail")]
+    MailError(#[source] Box<dyn std::error::Error + Send + Sync>),
+    #[error("{1}")]
+    BorrowingPolicyViolation(BorrowingRule, String),
+    #[error("failed to deliver webhook: {0}")]
+    WebhookError(String),
 }
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> axum::response::Response {
-        let status_code = match &self {
-            AppError::UnprocessableEntity(_) => StatusCode::UNPROCESSABLE_ENTITY,
-            AppError::EntityNotFound(_) => StatusCode::NOT_FOUND,
-            AppError::ValidationError(_) | AppError::ConvertToUuidError(_) => {
-                StatusCode::BAD_REQUEST
+// the borrowing policy rule a refused checkout broke
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BorrowingRule {
+    LoanLimit,
+    OverdueLoans,
+    OwnBook,
+}
+
+impl AppError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::UnprocessableEntity(_) | AppError::BorrowingPolicyViolation(..) => {
+                StatusCode::UNPROCESSABLE_ENTITY
             }
-            AppError::UnauthenticatedError | AppError::ForbidenOperation => StatusCode::FORBIDDEN,
+            AppError::EntityNotFound(_) => StatusCode::NOT_FOUND,
+            AppError::ValidationError(_)
+            | AppError::ConvertToUuidError(_)
+            | AppError::InvalidRequest(_)
+            | AppError::InvalidCursor(_)
+            | AppError::InvalidIsbn(_) => StatusCode::BAD_REQUEST,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::UnauthenticatedError
+            | AppError::ForbidenOperation
+            | AppError::AccountNotActive(_) => StatusCode::FORBIDDEN,
             AppError::UnauthorizedError => StatusCode::UNAUTHORIZED,
-            e @ (AppError::TransactionError(_)
+            AppError::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AppError::TransactionError(_)
             | AppError::SpecificOperationError(_)
             | AppError::NoRowsAffectedError(_)
             | AppError::KeyValueStoreError(_)
             | AppError::BcryptError(_)
-            | AppError::ConversionEntityError(_)) => {
-                tracing::error!(
-                    error.cause_chain = ?e,
-                    error.message = %e,
-                    "Unexpected error happened"
-                );
-                StatusCode::INTERNAL_SERVER_ERROR
-            }
+            | AppError::PasswordHashError(_)
+            | AppError::ConversionEntityError(_)
+            | AppError::MailError(_)
+            | AppError::WebhookError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    // stable identifier clients can branch on; never reworded once published
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::UnprocessableEntity(_) => "unprocessable_entity",
+            AppError::EntityNotFound(_) => "entity_not_found",
+            AppError::ValidationError(_) => "validation_error",
+            AppError::TransactionError(_) => "transaction_error",
+            AppError::SpecificOperationError(_) => "database_error",
+            AppError::NoRowsAffectedError(_) => "no_rows_affected",
+            AppError::KeyValueStoreError(_) => "key_value_store_error",
+            AppError::BcryptError(_) | AppError::PasswordHashError(_) => "password_hash_error",
+            AppError::ConvertToUuidError(_) => "invalid_uuid",
+            AppError::InvalidRequest(_) => "invalid_request",
+            AppError::InvalidCursor(_) => "invalid_cursor",
+            AppError::InvalidIsbn(_) => "invalid_isbn",
+            AppError::Conflict(_) => "conflict",
+            AppError::UnauthenticatedError => "unauthenticated",
+            AppError::UnauthorizedError => "unauthorized",
+            AppError::ForbidenOperation => "forbidden",
+            AppError::AccountNotActive(_) => "account_not_active",
+            AppError::TooManyRequests { .. } => "too_many_requests",
+            AppError::ConversionEntityError(_) => "conversion_error",
+            AppError::MailError(_) => "mail_error",
+            AppError::WebhookError(_) => "webhook_error",
+            AppError::BorrowingPolicyViolation(BorrowingRule::LoanLimit, _) => "loan_limit_reached",
+            AppError::BorrowingPolicyViolation(BorrowingRule::OverdueLoans, _) => "overdue_loans",
+            AppError::BorrowingPolicyViolation(BorrowingRule::OwnBook, _) => "own_book",
+        }
+    }
+}
+
+impl From<JsonRejection> for AppError {
+    fn from(rejection: JsonRejection) -> Self {
+        AppError::InvalidRequest(rejection.body_text())
+    }
+}
+impl From<PathRejection> for AppError {
+    fn from(rejection: PathRejection) -> Self {
+        AppError::InvalidRequest(rejection.body_text())
+    }
+}
+impl From<QueryRejection> for AppError {
+    fn from(rejection: QueryRejection) -> Self {
+        AppError::InvalidRequest(rejection.body_text())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorResponse {
+    /// Machine-readable error code, e.g. `validation_error` or `conflict`.
+    pub code: String,
+    /// Human-readable description of the error.
+    pub message: String,
+    /// Per-field validation failures; empty for other errors.
+    pub details: Vec<ErrorDetail>,
+    /// Id of the request, also returned in the `x-request-id` header.
+    pub request_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ErrorDetail {
+    /// Path of the offending field, e.g. `isbn`.
+    pub field: String,
+    pub message: String,
+}
+
+impl From<&AppError> for ErrorResponse {
+    fn from(error: &AppError) -> Self {
+        let message = if error.status_code().is_server_error() {
+            // database and store errors may carry query or connection details
+            "Internal server error".to_string()
+        } else {
+            error.to_string()
         };
-        status_code.into_response()
+        let details = match error {
+            AppError::ValidationError(report) => report
+                .iter()
+                .map(|(path, e)| ErrorDetail {
+                    field: path.to_string(),
+                    message: e.message().to_string(),
+                })
+                .collect(),
+            _ => vec![],
+        };
+        ErrorResponse {
+            code: error.code().to_string(),
+            message,
+            details,
+            request_id: request_id::current(),
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> axum::response::Response {
+        let status_code = self.status_code();
+        if status_code.is_server_error() {
+            tracing::error!(
+                error.cause_chain = ?self,
+                error.message = %self,
+                "Unexpected error happened"
+            );
+        }
+        let mut response = (status_code, Json(ErrorResponse::from(&self))).into_response();
+        if let AppError::TooManyRequests { retry_after } = self {
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, retry_after.max(1).into());
+        }
+        response
     }
 }
 
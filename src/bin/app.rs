@@ -1,14 +1,16 @@
 use std::net::{Ipv4Addr, SocketAddr};
 use std::sync::Arc;
 
-use adapter::database::connect_database_with;
-use adapter::redis::RedisClient;
 use anyhow::Context;
 use anyhow::Result;
+use api::middleware::request_id;
+use api::openapi::ApiDoc;
 use api::route::{auth, v1};
-use axum::Router;
 use axum::http::Method;
-use registry::AppRegistryImpl;
+use axum::routing::get;
+use axum::{Json, Router};
+use registry::{AppRegistry, AppRegistryImpl};
+use scheduler::Scheduler;
 use shared::{config::AppConfig, env::which};
 use tokio::net::TcpListener;
 use tower_http::LatencyUnit;
@@ -18,6 +20,8 @@ use tracing::Level;
 use tracing_subscriber::EnvFilter;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
+use utoipa::OpenApi;
+use utoipa_redoc::{Redoc, Servable};
 #[tokio::main]
 async fn main() -> Result<()> {
     init_logger()?;
@@ -52,15 +56,23 @@ fn init_logger() -> Result<()> {
 async fn bootstrap() -> Result<()> {
     let app_config = AppConfig::new()?;
 
-    let pool = connect_database_with(&app_config.database);
+    let scheduler_config = app_config.scheduler.clone();
+    let registry = Arc::new(AppRegistryImpl::from_config(app_config)?);
 
-    let kv = Arc::new(RedisClient::new(&app_config.redis)?);
-
-    let registry = Arc::new(AppRegistryImpl::new(pool, kv, app_config));
+    if scheduler_config.enabled {
+        let registry: AppRegistry = registry.clone();
+        Scheduler::new(registry, scheduler_config)?.spawn();
+    }
 
     let app = Router::new()
         .merge(v1::routes())
         .merge(auth::routes())
+        .merge(Redoc::with_url("/redoc", ApiDoc::openapi()))
+        .route(
+            "/api-docs/openapi.json",
+            get(|| async { Json(ApiDoc::openapi()) }),
+        )
+        .layer(axum::middleware::from_fn(request_id))
         .layer(cors())
         .layer(
             TraceLayer::new_for_http()
@@ -79,14 +91,17 @@ async fn bootstrap() -> Result<()> {
 
     tracing::info!("Listening on http://{}", addr);
 
-    axum::serve(listener, app)
-        .await
-        .context("Failed to start the server")
-        .inspect_err(|e| {
-            tracing::error!(
-                error.cause_chain = ?e,
-                error.message = %e,
-                "Unexpected error"
-            )
-        })
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .context("Failed to start the server")
+    .inspect_err(|e| {
+        tracing::error!(
+            error.cause_chain = ?e,
+            error.message = %e,
+            "Unexpected error"
+        )
+    })
 }